@@ -2,6 +2,16 @@
 /// Manages application settings and preferences
 
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Environment variable that overrides the library (database) path
+pub const DATABASE_PATH_ENV: &str = "NOVEL_WRITER_DB";
+
+/// Command-line flag that overrides the library (database) path
+pub const DATABASE_PATH_ARG: &str = "--library";
+
+/// Maximum number of entries kept in the recent libraries list
+const MAX_RECENT_LIBRARIES: usize = 10;
 
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +25,10 @@ pub struct AppConfig {
     /// Database path
     pub database_path: String,
     
+    /// Recently opened library files, most recent first
+    #[serde(default)]
+    pub recent_libraries: Vec<String>,
+    
     /// Auto-save interval in seconds
     pub auto_save_interval: u64,
     
@@ -23,6 +37,18 @@ pub struct AppConfig {
     
     /// Editor settings
     pub editor: EditorConfig,
+    
+    /// Scheduled backup settings
+    #[serde(default)]
+    pub backup: BackupConfig,
+    
+    /// Startup integrity check settings
+    #[serde(default)]
+    pub integrity: IntegrityConfig,
+    
+    /// Recycle bin settings
+    #[serde(default)]
+    pub trash: TrashConfig,
 }
 
 /// Theme configuration
@@ -54,15 +80,61 @@ pub struct EditorConfig {
     pub word_wrap: bool,
 }
 
+/// Scheduled backup configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// Enable scheduled backups (and the backup on exit)
+    pub enabled: bool,
+    
+    /// Backup interval in minutes
+    pub interval_minutes: u64,
+    
+    /// Backup directory; empty means `backups/` next to the library file
+    pub directory: String,
+    
+    /// Number of hourly backups to keep
+    pub keep_hourly: usize,
+    
+    /// Number of daily backups to keep
+    pub keep_daily: usize,
+    
+    /// Number of weekly backups to keep
+    pub keep_weekly: usize,
+}
+
+/// Database integrity check configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntegrityConfig {
+    /// Check the library each time the application starts
+    pub check_on_startup: bool,
+    
+    /// Repair problems found at startup (a backup is taken first)
+    pub auto_repair: bool,
+}
+
+/// Recycle bin configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrashConfig {
+    /// Days to keep deleted items before purging them at startup; 0 keeps them forever
+    pub retention_days: u32,
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             app_name: "Novel Writer".to_string(),
             version: env!("CARGO_PKG_VERSION").to_string(),
-            database_path: "./novel_writer.db".to_string(),
+            database_path: "data/novels.db".to_string(),
+            recent_libraries: Vec::new(),
             auto_save_interval: 30,
             theme: ThemeConfig::default(),
             editor: EditorConfig::default(),
+            backup: BackupConfig::default(),
+            integrity: IntegrityConfig::default(),
+            trash: TrashConfig::default(),
         }
     }
 }
@@ -88,6 +160,48 @@ impl Default for EditorConfig {
     }
 }
 
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_minutes: 60,
+            directory: String::new(),
+            keep_hourly: 24,
+            keep_daily: 7,
+            keep_weekly: 4,
+        }
+    }
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        Self {
+            check_on_startup: true,
+            auto_repair: true,
+        }
+    }
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+impl BackupConfig {
+    /// Resolve the backup directory for a library file
+    pub fn backup_dir(&self, library: &Path) -> PathBuf {
+        let directory = self.directory.trim();
+        if !directory.is_empty() {
+            return PathBuf::from(directory);
+        }
+        library.parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."))
+            .join("backups")
+    }
+}
+
 impl AppConfig {
     /// Get the default config file path
     pub fn get_config_path() -> String {
@@ -185,6 +299,47 @@ impl AppConfig {
         self.editor.word_wrap = word_wrap;
     }
     
+    /// Resolve the library path to open at startup
+    /// 
+    /// Priority: `--library <path>` argument, then `NOVEL_WRITER_DB`, then `database_path`.
+    pub fn resolve_database_path(&self) -> String {
+        Self::database_path_override(std::env::args().skip(1))
+            .unwrap_or_else(|| self.database_path.clone())
+    }
+    
+    /// Get the library path override from command-line arguments or environment
+    pub fn database_path_override<I>(args: I) -> Option<String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == DATABASE_PATH_ARG {
+                if let Some(path) = args.next() {
+                    return Some(path);
+                }
+            } else if let Some(path) = arg.strip_prefix(&format!("{}=", DATABASE_PATH_ARG)) {
+                return Some(path.to_string());
+            }
+        }
+        
+        std::env::var(DATABASE_PATH_ENV)
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+    }
+    
+    /// Record a library as most recently used
+    pub fn remember_library(&mut self, path: &str) {
+        self.recent_libraries.retain(|p| p != path);
+        self.recent_libraries.insert(0, path.to_string());
+        self.recent_libraries.truncate(MAX_RECENT_LIBRARIES);
+    }
+    
+    /// Remove a library from the recent list
+    pub fn forget_library(&mut self, path: &str) {
+        self.recent_libraries.retain(|p| p != path);
+    }
+    
     /// Update auto-save interval
     pub fn update_auto_save_interval(&mut self, interval: u64) {
         self.auto_save_interval = interval;
@@ -203,6 +358,47 @@ mod tests {
         assert!(config.editor.auto_save);
     }
     
+    #[test]
+    fn test_database_path_override_from_args() {
+        let args = vec!["--library".to_string(), "pen-name.db".to_string()];
+        assert_eq!(AppConfig::database_path_override(args), Some("pen-name.db".to_string()));
+        
+        let args = vec!["--library=other.db".to_string()];
+        assert_eq!(AppConfig::database_path_override(args), Some("other.db".to_string()));
+    }
+    
+    #[test]
+    fn test_remember_library() {
+        let mut config = AppConfig::default();
+        config.remember_library("a.db");
+        config.remember_library("b.db");
+        config.remember_library("a.db");
+        assert_eq!(config.recent_libraries, vec!["a.db".to_string(), "b.db".to_string()]);
+        
+        for i in 0..20 {
+            config.remember_library(&format!("{}.db", i));
+        }
+        assert_eq!(config.recent_libraries.len(), MAX_RECENT_LIBRARIES);
+        assert_eq!(config.recent_libraries[0], "19.db");
+    }
+    
+    #[test]
+    fn test_config_without_recent_libraries_deserializes() {
+        let mut value = serde_json::to_value(AppConfig::default()).unwrap();
+        value.as_object_mut().unwrap().remove("recent_libraries");
+        let config: AppConfig = serde_json::from_value(value).unwrap();
+        assert!(config.recent_libraries.is_empty());
+    }
+    
+    #[test]
+    fn test_config_without_backup_deserializes() {
+        let mut value = serde_json::to_value(AppConfig::default()).unwrap();
+        value.as_object_mut().unwrap().remove("backup");
+        let config: AppConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(config.backup, BackupConfig::default());
+        assert_eq!(config.backup.backup_dir(Path::new("data/novels.db")), Path::new("data/backups"));
+    }
+    
     #[test]
     fn test_config_load() {
         let config = AppConfig::load();
@@ -0,0 +1,217 @@
+use crate::config::AppConfig;
+use crate::core::recovery_journal::{RecoveryEntry, RecoveryJournal};
+use crate::core::version_manager::VersionManager;
+use crate::db::{self, Chapter, ChapterVersion, Database};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+use std::future::Future;
+
+/// 停止输入多久后自动保存（秒）
+pub const AUTO_SAVE_DEBOUNCE_SECS: i64 = 3;
+
+/// 停止输入多久后写入恢复日志（毫秒）
+pub const JOURNAL_DEBOUNCE_MS: i64 = 500;
+
+/// 当前分支最新的版本是不到这么久以前的自动保存版本时，自动保存只更新正文（分钟）
+pub const AUTO_SAVE_VERSION_INTERVAL_MINS: i64 = 10;
+
+/// 自动保存计时
+///
+/// 停止输入 `AUTO_SAVE_DEBOUNCE_SECS` 秒后保存；持续输入时最迟在第一次未保存的修改后
+/// `max_interval`（配置中的 `auto_save_interval`）保存一次。
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoSaveTimer {
+    pub enabled: bool,
+    max_interval: Duration,
+    last_edit: Option<DateTime<Utc>>,
+    dirty_since: Option<DateTime<Utc>>,
+}
+
+impl AutoSaveTimer {
+    pub fn new(enabled: bool, interval_secs: u64) -> Self {
+        Self {
+            enabled,
+            max_interval: Duration::seconds(interval_secs.max(1) as i64),
+            last_edit: None,
+            dirty_since: None,
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(config.editor.auto_save, config.auto_save_interval)
+    }
+
+    pub fn record_edit(&mut self, now: DateTime<Utc>) {
+        self.last_edit = Some(now);
+        self.dirty_since.get_or_insert(now);
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty_since.is_some()
+    }
+
+    /// 现在是否应该自动保存
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        let (Some(last_edit), Some(dirty_since)) = (self.last_edit, self.dirty_since) else {
+            return false;
+        };
+        self.enabled
+            && (now.signed_duration_since(last_edit) >= Duration::seconds(AUTO_SAVE_DEBOUNCE_SECS)
+                || now.signed_duration_since(dirty_since) >= self.max_interval)
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.dirty_since = None;
+    }
+}
+
+/// 自动保存章节内容
+///
+/// 内容与数据库一致时不做任何事并返回 `None`；否则更新正文，并在当前分支最新的版本不是
+/// `AUTO_SAVE_VERSION_INTERVAL_MINS` 分钟内的自动保存版本时创建一个自动保存版本，
+/// 避免持续写作时每次停顿都产生一个版本。
+pub(crate) fn auto_save_chapter_with(db: &Database, chapter_id: i64, content: &str, now: DateTime<Utc>) -> Result<Option<ChapterVersion>> {
+    let version = db.transaction(|tx| {
+        if tx.get_chapter(chapter_id)?.content == content {
+            return Ok(None);
+        }
+        tx.update_chapter_content(chapter_id, content)?;
+        
+        let head = tx.get_current_version_branch(chapter_id)?
+            .and_then(|branch| branch.head_version_id)
+            .map(|id| tx.get_chapter_version(id))
+            .transpose()?;
+        let recent = head.is_some_and(|head| {
+            head.is_auto_save
+                && now.signed_duration_since(head.created_at) < Duration::minutes(AUTO_SAVE_VERSION_INTERVAL_MINS)
+        });
+        if recent {
+            return Ok(None);
+        }
+        VersionManager::create_version_with(tx, chapter_id, content, Some("自动保存"), true).map(Some)
+    })?;
+    if version.is_some() {
+        info!("Auto-saved chapter {}", chapter_id);
+    }
+    Ok(version)
+}
+
+/// 当前书库的恢复日志
+pub fn current_journal() -> Result<RecoveryJournal> {
+    let path = db::current_database_path()
+        .ok_or_else(|| anyhow::anyhow!("数据库尚未初始化"))?;
+    Ok(RecoveryJournal::for_database(&path))
+}
+
+pub struct AutoSaveManager;
+
+impl AutoSaveManager {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 在后台线程自动保存章节，返回保存后的章节；成功后清除与保存内容一致的恢复日志
+    ///
+    /// 保存在调用时就作为独立任务开始，离开编辑器时组件卸载、等待结果的任务被取消也不影响保存。
+    pub fn auto_save(&self, chapter_id: i64, content: String) -> impl Future<Output = Result<Chapter>> {
+        let task = tokio::spawn(async move {
+            let journal = current_journal()?;
+            db::with_database(move |db| {
+                auto_save_chapter_with(db, chapter_id, &content, Utc::now())?;
+                journal.clear_saved(chapter_id, &content)?;
+                db.get_chapter(chapter_id)
+            }).await
+        });
+        async move {
+            task.await.map_err(|e| anyhow::anyhow!("自动保存任务失败: {}", e))?
+        }
+    }
+
+    /// 把未保存的内容写入恢复日志
+    pub fn journal(&self, entry: &RecoveryEntry) -> Result<()> {
+        current_journal()?.write(entry)
+    }
+
+    /// 章节已保存，清除其恢复日志
+    pub fn clear_journal(&self, chapter_id: i64) -> Result<()> {
+        current_journal()?.clear(chapter_id)
+    }
+
+    /// 启动时检查上次运行留下的未保存内容
+    pub async fn pending_recoveries(&self) -> Result<Vec<RecoveryEntry>> {
+        let journal = current_journal()?;
+        db::with_database(move |db| journal.pending_with(db)).await
+    }
+
+    /// 恢复一条未保存的内容
+    pub async fn restore(&self, entry: RecoveryEntry) -> Result<()> {
+        let journal = current_journal()?;
+        db::with_database(move |db| journal.restore_with(db, &entry)).await
+    }
+
+    /// 放弃一条未保存的内容
+    pub fn discard(&self, entry: &RecoveryEntry) -> Result<()> {
+        current_journal()?.clear(entry.chapter_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap() + Duration::seconds(secs)
+    }
+
+    #[test]
+    fn test_timer_debounces_and_caps_interval() {
+        let mut timer = AutoSaveTimer::new(true, 30);
+        assert!(!timer.is_due(at(100)));
+
+        timer.record_edit(at(0));
+        assert!(!timer.is_due(at(1)));
+        assert!(timer.is_due(at(AUTO_SAVE_DEBOUNCE_SECS)));
+
+        // 持续输入时不会一直推迟
+        for secs in 1..=30 {
+            timer.record_edit(at(secs));
+        }
+        assert!(timer.is_due(at(30)));
+
+        timer.mark_saved();
+        assert!(!timer.is_due(at(60)));
+
+        let mut disabled = AutoSaveTimer::new(false, 30);
+        disabled.record_edit(at(0));
+        assert!(!disabled.is_due(at(60)));
+        assert!(disabled.is_dirty());
+    }
+
+    #[test]
+    fn test_auto_save_creates_auto_save_version_only_on_change() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("自动保存").unwrap();
+        let chapter = db.create_chapter(novel.id, "第一章", None).unwrap();
+
+        let version = auto_save_chapter_with(&db, chapter.id, "新写的内容", Utc::now()).unwrap().unwrap();
+        assert!(version.is_auto_save);
+        assert_eq!(db.get_chapter(chapter.id).unwrap().content, "新写的内容");
+
+        assert!(auto_save_chapter_with(&db, chapter.id, "新写的内容", Utc::now()).unwrap().is_none());
+        assert_eq!(db.get_chapter_versions(chapter.id).unwrap().len(), 1);
+
+        // 持续写作时只更新正文，间隔足够久才再保存一个版本
+        assert!(auto_save_chapter_with(&db, chapter.id, "新写的内容，接着写", Utc::now()).unwrap().is_none());
+        assert_eq!(db.get_chapter(chapter.id).unwrap().content, "新写的内容，接着写");
+        assert_eq!(db.get_chapter_versions(chapter.id).unwrap().len(), 1);
+
+        let later = Utc::now() + Duration::minutes(AUTO_SAVE_VERSION_INTERVAL_MINS);
+        let version = auto_save_chapter_with(&db, chapter.id, "新写的内容，接着写完", later).unwrap().unwrap();
+        assert_eq!(VersionManager::reconstruct_content(&db, version.id).unwrap(), "新写的内容，接着写完");
+
+        // 手动保存之后的第一次自动保存总会生成版本
+        VersionManager::create_version_with(&db, chapter.id, "新写的内容，接着写完", Some("手动"), false).unwrap();
+        assert!(auto_save_chapter_with(&db, chapter.id, "再改一点", Utc::now()).unwrap().is_some());
+    }
+}
@@ -0,0 +1,371 @@
+use crate::config::{AppConfig, BackupConfig};
+use crate::db::{self, Database};
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDateTime, SubsecRound, Utc};
+use log::{info, warn};
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 定时备份文件名中的时间格式（UTC）
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// 一份定时备份
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// 备份保留策略
+///
+/// 每个时间段（小时、天、ISO 周）只保留其中最新的一份，分别保留最近 N 个时间段；
+/// 三类保留结果取并集，最新的一份备份总是保留。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetentionPolicy {
+    pub hourly: usize,
+    pub daily: usize,
+    pub weekly: usize,
+}
+
+impl From<&BackupConfig> for RetentionPolicy {
+    fn from(config: &BackupConfig) -> Self {
+        Self {
+            hourly: config.keep_hourly,
+            daily: config.keep_daily,
+            weekly: config.keep_weekly,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// 返回与 `times` 一一对应的保留标记
+    pub fn retained(&self, times: &[DateTime<Utc>]) -> Vec<bool> {
+        let mut order: Vec<usize> = (0..times.len()).collect();
+        order.sort_by(|a, b| times[*b].cmp(&times[*a]));
+
+        let mut keep = vec![false; times.len()];
+        if let Some(&newest) = order.first() {
+            keep[newest] = true;
+        }
+
+        // 按本地时间划分时间段，使“每天”与用户看到的日期一致
+        for (limit, bucket_format) in [(self.hourly, "%Y-%m-%d %H"), (self.daily, "%Y-%m-%d"), (self.weekly, "%G-W%V")] {
+            let mut last_bucket = None;
+            let mut buckets = 0;
+            for &index in &order {
+                if buckets >= limit {
+                    break;
+                }
+                let bucket = times[index].with_timezone(&Local).format(bucket_format).to_string();
+                if last_bucket.as_ref() != Some(&bucket) {
+                    keep[index] = true;
+                    buckets += 1;
+                    last_bucket = Some(bucket);
+                }
+            }
+        }
+
+        keep
+    }
+}
+
+fn library_stem(library: &Path) -> String {
+    library.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "novels".to_string())
+}
+
+/// 定时备份的文件名：`<书库名>-backup-<UTC 时间>.db`
+pub fn backup_file_name(library: &Path, at: DateTime<Utc>) -> String {
+    format!("{}-backup-{}.db", library_stem(library), at.format(TIMESTAMP_FORMAT))
+}
+
+/// 从文件名解析备份时间，不是该书库的定时备份时返回 `None`
+fn parse_backup_file_name(library: &Path, file_name: &str) -> Option<DateTime<Utc>> {
+    let timestamp = file_name
+        .strip_prefix(&format!("{}-backup-", library_stem(library)))?
+        .strip_suffix(".db")?;
+    NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+/// 列出目录中某个书库的定时备份，最新的在前
+///
+/// 迁移前备份和恢复前的安全备份不在其中，也不受保留策略影响。
+pub fn list_backups(dir: &Path, library: &Path) -> Result<Vec<BackupInfo>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if let Some(created_at) = parse_backup_file_name(library, &file_name) {
+            backups.push(BackupInfo {
+                path: entry.path(),
+                created_at,
+                size: entry.metadata()?.len(),
+            });
+        }
+    }
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(backups)
+}
+
+/// 校验备份文件：只读打开，完整性检查通过且包含书库数据表
+pub fn verify_backup(path: &Path) -> Result<()> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| anyhow::anyhow!("无法打开备份 {}: {}", path.display(), e))?;
+
+    let problems: Vec<String> = {
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    if problems != ["ok"] {
+        return Err(anyhow::anyhow!("备份 {} 未通过完整性检查: {}", path.display(), problems.join("; ")));
+    }
+
+    conn.query_row("SELECT COUNT(*) FROM novels", [], |row| row.get::<_, i64>(0))
+        .map_err(|e| anyhow::anyhow!("{} 不是有效的书库备份: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// 备份书库到目录并校验，校验失败的备份会被删除
+pub(crate) fn create_backup_with(db: &Database, dir: &Path) -> Result<BackupInfo> {
+    std::fs::create_dir_all(dir)?;
+    let created_at = Utc::now().trunc_subsecs(0);
+    let path = dir.join(backup_file_name(db.path(), created_at));
+
+    db.backup_to(&path)?;
+    if let Err(e) = verify_backup(&path) {
+        if let Err(remove_error) = std::fs::remove_file(&path) {
+            warn!("Failed to remove invalid backup {}: {}", path.display(), remove_error);
+        }
+        return Err(e);
+    }
+
+    let size = std::fs::metadata(&path)?.len();
+    Ok(BackupInfo { path, created_at, size })
+}
+
+/// 按保留策略删除多余的定时备份，返回被删除的文件
+pub(crate) fn prune_backups(dir: &Path, library: &Path, policy: RetentionPolicy) -> Result<Vec<PathBuf>> {
+    let backups = list_backups(dir, library)?;
+    let times: Vec<DateTime<Utc>> = backups.iter().map(|b| b.created_at).collect();
+    let keep = policy.retained(&times);
+
+    let mut removed = Vec::new();
+    for (backup, keep) in backups.into_iter().zip(keep) {
+        if !keep {
+            std::fs::remove_file(&backup.path)?;
+            removed.push(backup.path);
+        }
+    }
+    Ok(removed)
+}
+
+/// 从备份恢复书库，返回恢复前自动保存的安全备份
+///
+/// 先校验备份，再把当前书库完整备份一份（不受保留策略清理），最后覆盖当前内容。
+pub(crate) fn restore_backup_with(db: &Database, backup: &Path, dir: &Path) -> Result<PathBuf> {
+    verify_backup(backup)?;
+    let safety = safety_backup_with(db, dir, "before-restore")?;
+    db.restore_from(backup)?;
+    Ok(safety)
+}
+
+/// 在有风险的操作前完整备份书库：`<书库名>-<label>-<UTC 时间>.db`
+///
+/// 安全备份不属于定时备份，不会被保留策略清理。
+pub(crate) fn safety_backup_with(db: &Database, dir: &Path, label: &str) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        "{}-{}-{}.db",
+        library_stem(db.path()),
+        label,
+        Utc::now().format(TIMESTAMP_FORMAT)
+    ));
+    db.backup_to(&path)?;
+    Ok(path)
+}
+
+/// 按配置备份当前书库并清理旧备份
+fn run_backup(config: &BackupConfig) -> Result<BackupInfo> {
+    let db = db::get_database()?;
+    let dir = config.backup_dir(db.path());
+    let backup = create_backup_with(&db, &dir)?;
+    let removed = prune_backups(&dir, db.path(), RetentionPolicy::from(config))?;
+    info!(
+        "Backed up library to {} ({} bytes, {} old backups removed)",
+        backup.path.display(), backup.size, removed.len()
+    );
+    Ok(backup)
+}
+
+pub struct BackupManager;
+
+impl BackupManager {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 当前书库使用的备份目录
+    pub fn backup_dir(&self) -> Option<PathBuf> {
+        let library = db::current_database_path()?;
+        Some(AppConfig::load().unwrap_or_default().backup.backup_dir(&library))
+    }
+
+    /// 立即备份当前书库
+    pub async fn backup_now(&self) -> Result<BackupInfo> {
+        self.backup_now_with(AppConfig::load().unwrap_or_default().backup).await
+    }
+
+    /// 退出程序时同步备份一次（已关闭定时备份时跳过）
+    pub fn backup_on_exit(&self) {
+        let config = AppConfig::load().unwrap_or_default().backup;
+        if !config.enabled {
+            return;
+        }
+        if let Err(e) = run_backup(&config) {
+            warn!("Backup on exit failed: {}", e);
+        }
+    }
+
+    /// 后台定时备份，每轮重新读取配置，修改设置后无需重启
+    pub async fn run_schedule(&self) {
+        loop {
+            let interval = AppConfig::load().unwrap_or_default().backup.interval_minutes.max(1);
+            tokio::time::sleep(Duration::from_secs(interval * 60)).await;
+
+            let config = AppConfig::load().unwrap_or_default().backup;
+            if !config.enabled {
+                continue;
+            }
+            if let Err(e) = self.backup_now_with(config).await {
+                warn!("Scheduled backup failed: {}", e);
+            }
+        }
+    }
+
+    async fn backup_now_with(&self, config: BackupConfig) -> Result<BackupInfo> {
+        tokio::task::spawn_blocking(move || run_backup(&config))
+            .await
+            .map_err(|e| anyhow::anyhow!("备份任务失败: {}", e))?
+    }
+
+    /// 列出当前书库的定时备份
+    pub fn list(&self) -> Result<Vec<BackupInfo>> {
+        let library = db::current_database_path()
+            .ok_or_else(|| anyhow::anyhow!("书库尚未打开"))?;
+        let dir = AppConfig::load().unwrap_or_default().backup.backup_dir(&library);
+        list_backups(&dir, &library)
+    }
+
+    /// 从备份恢复当前书库，完成后重新打开书库以运行迁移
+    pub async fn restore(&self, backup: &Path) -> Result<PathBuf> {
+        let backup = backup.to_path_buf();
+        let config = AppConfig::load().unwrap_or_default().backup;
+        let safety = db::with_database(move |db| {
+            let dir = config.backup_dir(db.path());
+            restore_backup_with(db, &backup, &dir)
+        }).await?;
+
+        let library = db::current_database_path()
+            .ok_or_else(|| anyhow::anyhow!("书库尚未打开"))?;
+        db::switch_database(&library.to_string_lossy())?;
+        info!("Restored library {} (previous state saved to {})", library.display(), safety.display());
+        Ok(safety)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("novel-writer-test-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_retention_keeps_newest_per_bucket() {
+        // 十天内每小时一份
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 30, 0).unwrap();
+        let times: Vec<DateTime<Utc>> = (0..240).map(|h| start + chrono::Duration::hours(h)).collect();
+
+        let hourly_only = RetentionPolicy { hourly: 5, daily: 0, weekly: 0 };
+        let keep = hourly_only.retained(&times);
+        assert_eq!(keep.iter().filter(|k| **k).count(), 5);
+        assert!(keep[235..].iter().all(|k| *k));
+
+        let daily_only = RetentionPolicy { hourly: 0, daily: 3, weekly: 0 };
+        let keep = daily_only.retained(&times);
+        assert_eq!(keep.iter().filter(|k| **k).count(), 3);
+        assert!(keep[239], "newest backup is always kept");
+
+        let combined = RetentionPolicy { hourly: 5, daily: 3, weekly: 2 };
+        let keep = combined.retained(&times);
+        let kept = keep.iter().filter(|k| **k).count();
+        assert!(kept > 5 && kept <= 10, "kept {}", kept);
+
+        let nothing = RetentionPolicy { hourly: 0, daily: 0, weekly: 0 };
+        assert_eq!(nothing.retained(&times).iter().filter(|k| **k).count(), 1);
+        assert!(nothing.retained(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_backup_file_names() {
+        let library = Path::new("data/pen-name.db");
+        let at = Utc.with_ymd_and_hms(2024, 3, 1, 8, 5, 9).unwrap();
+        let name = backup_file_name(library, at);
+        assert_eq!(name, "pen-name-backup-20240301-080509.db");
+        assert_eq!(parse_backup_file_name(library, &name), Some(at));
+        assert_eq!(parse_backup_file_name(Path::new("other.db"), &name), None);
+        assert_eq!(parse_backup_file_name(library, "pen-name-before-restore-20240301-080509.db"), None);
+    }
+
+    #[test]
+    fn test_backup_prune_and_restore() {
+        let dir = temp_dir();
+        let db = Database::open(dir.join("novels.db")).unwrap();
+        db.create_novel("原稿").unwrap();
+
+        let backup_dir = dir.join("backups");
+        let backup = create_backup_with(&db, &backup_dir).unwrap();
+        assert!(backup.size > 0);
+        verify_backup(&backup.path).unwrap();
+
+        // 伪造较早的备份，按策略只留下最新一份
+        for hours in 1..4 {
+            let at = backup.created_at - chrono::Duration::hours(hours);
+            std::fs::copy(&backup.path, backup_dir.join(backup_file_name(db.path(), at))).unwrap();
+        }
+        std::fs::write(backup_dir.join("novels-backup-broken.db"), b"not a backup").unwrap();
+        assert_eq!(list_backups(&backup_dir, db.path()).unwrap().len(), 4);
+        let policy = RetentionPolicy { hourly: 1, daily: 0, weekly: 0 };
+        assert_eq!(prune_backups(&backup_dir, db.path(), policy).unwrap().len(), 3);
+        assert_eq!(list_backups(&backup_dir, db.path()).unwrap(), vec![backup.clone()]);
+
+        let broken = backup_dir.join("broken.db");
+        std::fs::write(&broken, vec![0u8; 4096]).unwrap();
+        assert!(verify_backup(&broken).is_err());
+        assert!(restore_backup_with(&db, &broken, &backup_dir).is_err());
+
+        db.create_novel("备份后的新书").unwrap();
+        let safety = restore_backup_with(&db, &backup.path, &backup_dir).unwrap();
+        let titles: Vec<String> = db.get_all_novels().unwrap().into_iter().map(|n| n.title).collect();
+        assert_eq!(titles, vec!["原稿".to_string()]);
+
+        let previous = Connection::open(&safety).unwrap();
+        let count: i64 = previous.query_row("SELECT COUNT(*) FROM novels", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 2);
+
+        drop(db);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
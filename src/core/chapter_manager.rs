@@ -1,7 +1,8 @@
-use crate::db::{Chapter, get_database};
+use crate::db::{self, Chapter, Database, get_database};
+use crate::utils::sort_key;
 use anyhow::Result;
 use std::collections::HashMap;
-use log::{info, warn, error};
+use log::{info, warn};
 
 #[derive(Debug, Clone)]
 pub struct ChapterTree {
@@ -40,8 +41,12 @@ impl ChapterManager {
         info!("Created {} chapter nodes", nodes.len());
         
         // 然后建立父子关系
+        // 按 sort_path 处理，使同级章节保持用户排列的顺序
         let mut node_ids: Vec<i64> = nodes.keys().cloned().collect();
-        node_ids.sort(); // 确保处理顺序一致
+        node_ids.sort_by(|a, b| {
+            let (ca, cb) = (&nodes[a].chapter, &nodes[b].chapter);
+            ca.sort_path.cmp(&cb.sort_path).then(a.cmp(b))
+        });
         
         for &node_id in &node_ids {
             if let Some(node) = nodes.get(&node_id) {
@@ -93,61 +98,34 @@ impl ChapterManager {
         Ok(chapter)
     }
     
+    /// 把章节移动到 `new_parent_id` 下，成为第 `new_position` 个子章节（从 0 开始，不计被移动的章节本身）
+    ///
+    /// 只为被移动的章节生成新的排序键，相邻章节之间没有空隙时才先重新编号目标位置的同级章节。
     pub async fn move_chapter(
         &self,
         chapter_id: i64,
         new_parent_id: Option<i64>,
         new_position: usize
-    ) -> Result<()> {
+    ) -> Result<Chapter> {
         info!("Moving chapter: id={}, new_parent_id={:?}, new_position={}", 
               chapter_id, new_parent_id, new_position);
-        
-        let db = get_database()?;
-        
-        // 获取当前章节
-        let chapter = match db.get_chapter(chapter_id) {
-            Ok(ch) => ch,
-            Err(e) => {
-                error!("Failed to get chapter for moving: id={}, error={}", chapter_id, e);
-                return Err(anyhow::anyhow!("获取章节失败: {}", e));
-            }
-        };
-        
-        let chapters = db.get_chapters_by_novel(chapter.novel_id)?;
-        info!("Retrieved {} chapters for novel_id={} to build chapter tree", chapters.len(), chapter.novel_id);
-        
-        let tree = self.build_chapter_tree(chapters);
-        
-        // 验证移动是否有效（防止循环引用）
-        if let Some(new_parent_id) = new_parent_id {
-            if Self::would_create_cycle(&tree, chapter_id, new_parent_id) {
-                warn!("Move operation rejected: Would create cycle - chapter_id={} cannot be moved under parent_id={}", 
-                      chapter_id, new_parent_id);
-                return Err(anyhow::anyhow!("移动章节会导致循环引用"));
-            }
-        }
-        
-        // 更新父节点和排序路径
-        let new_sort_path = match self.calculate_new_sort_path(&tree, new_parent_id, new_position) {
-            Ok(path) => {
-                info!("Calculated new sort path for chapter {}: {}", chapter_id, path);
-                path
-            },
-            Err(e) => {
-                error!("Failed to calculate new sort path: chapter_id={}, error={}", chapter_id, e);
-                return Err(e);
-            }
-        };
-        
-        // 更新数据库
-        if let Err(e) = db.update_chapter_parent(chapter_id, new_parent_id, &new_sort_path) {
-            error!("Failed to update chapter parent in database: chapter_id={}, error={}", chapter_id, e);
-            return Err(e);
-        }
-        
-        info!("Successfully moved chapter: id={}, new_parent_id={:?}, new_position={}", 
-              chapter_id, new_parent_id, new_position);
-        Ok(())
+        let chapter = db::with_database(move |db| move_chapter_with(db, chapter_id, new_parent_id, new_position)).await?;
+        info!("Successfully moved chapter: id={}, sort_path={}", chapter_id, chapter.sort_path);
+        Ok(chapter)
+    }
+    
+    /// 按当前顺序为同级章节重新生成均匀分布的排序键，返回重新编号的章节数
+    pub async fn renumber_siblings(&self, novel_id: i64, parent_id: Option<i64>) -> Result<usize> {
+        let count = db::with_database(move |db| renumber_siblings_with(db, novel_id, parent_id)).await?;
+        info!("Renumbered {} sibling chapters: novel_id={}, parent_id={:?}", count, novel_id, parent_id);
+        Ok(count)
+    }
+    
+    /// 重新编号整部小说每一组同级章节
+    pub async fn renumber_novel(&self, novel_id: i64) -> Result<usize> {
+        let count = db::with_database(move |db| renumber_novel_with(db, novel_id)).await?;
+        info!("Renumbered {} chapters of novel {}", count, novel_id);
+        Ok(count)
     }
     
     fn would_create_cycle(tree: &ChapterTree, moving_id: i64, new_parent_id: i64) -> bool {
@@ -162,31 +140,6 @@ impl ChapterManager {
         false
     }
     
-    fn calculate_new_sort_path(
-        &self,
-        tree: &ChapterTree,
-        parent_id: Option<i64>,
-        position: usize
-    ) -> Result<String> {
-        let siblings = if let Some(parent_id) = parent_id {
-            tree.nodes.get(&parent_id)
-                .map(|node| &node.children)
-                .unwrap_or(&Vec::new())
-                .clone()
-        } else {
-            tree.root_nodes.clone()
-        };
-        
-        if position > siblings.len() {
-            return Err(anyhow::anyhow!("位置超出范围"));
-        }
-        
-        // 简单的基于位置的排序路径
-        let timestamp = chrono::Utc::now().timestamp_millis();
-        let position_str = format!("{:06}", position);
-        Ok(format!("{}_{}", position_str, timestamp))
-    }
-    
     pub fn flatten_tree(&self, tree: &ChapterTree) -> Vec<Chapter> {
         info!("Flattening chapter tree with {} root nodes", tree.root_nodes.len());
         let mut result = Vec::new();
@@ -197,6 +150,13 @@ impl ChapterManager {
         result
     }
     
+    /// 按树的顺序收集某个节点（通常是卷）及其全部子孙章节
+    pub fn collect_subtree(&self, tree: &ChapterTree, root_id: i64) -> Vec<Chapter> {
+        let mut result = Vec::new();
+        Self::flatten_recursive(tree, root_id, &mut result);
+        result
+    }
+    
     fn flatten_recursive(tree: &ChapterTree, node_id: i64, result: &mut Vec<Chapter>) {
         if let Some(node) = tree.nodes.get(&node_id) {
             result.push(node.chapter.clone());
@@ -205,4 +165,162 @@ impl ChapterManager {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// 移动章节，见 [`ChapterManager::move_chapter`]
+pub(crate) fn move_chapter_with(db: &Database, chapter_id: i64, new_parent_id: Option<i64>, new_position: usize) -> Result<Chapter> {
+    db.transaction(|tx| {
+        let chapter = tx.get_chapter(chapter_id)?;
+        let manager = ChapterManager::new();
+        let tree = manager.build_chapter_tree(tx.get_chapters_by_novel(chapter.novel_id)?);
+        if !tree.nodes.contains_key(&chapter_id) {
+            return Err(anyhow::anyhow!("章节已归档或在回收站中，不能移动"));
+        }
+        if let Some(parent_id) = new_parent_id {
+            if !tree.nodes.contains_key(&parent_id) {
+                return Err(anyhow::anyhow!("目标章节不存在"));
+            }
+            if ChapterManager::would_create_cycle(&tree, chapter_id, parent_id) {
+                warn!("Move operation rejected: chapter_id={} cannot be moved under parent_id={}", chapter_id, parent_id);
+                return Err(anyhow::anyhow!("不能把章节移动到它自己或它的子章节下"));
+            }
+        }
+
+        let sort_path = match sort_path_at(&tree, chapter_id, new_parent_id, new_position)? {
+            Some(sort_path) => sort_path,
+            None => {
+                // 相邻章节的排序键相同（旧数据）或无法识别
+                renumber_siblings_with(tx, chapter.novel_id, new_parent_id)?;
+                let tree = manager.build_chapter_tree(tx.get_chapters_by_novel(chapter.novel_id)?);
+                sort_path_at(&tree, chapter_id, new_parent_id, new_position)?
+                    .ok_or_else(|| anyhow::anyhow!("无法为章节生成排序键"))?
+            }
+        };
+        tx.update_chapter_parent(chapter_id, new_parent_id, &sort_path)?;
+        tx.get_chapter(chapter_id)
+    })
+}
+
+/// 目标位置前后两个同级章节之间的排序键
+fn sort_path_at(tree: &ChapterTree, chapter_id: i64, parent_id: Option<i64>, position: usize) -> Result<Option<String>> {
+    let children = match parent_id {
+        Some(parent_id) => tree.nodes.get(&parent_id).map(|node| node.children.as_slice()).unwrap_or_default(),
+        None => tree.root_nodes.as_slice(),
+    };
+    let siblings: Vec<&Chapter> = children.iter()
+        .filter(|id| **id != chapter_id)
+        .filter_map(|id| tree.nodes.get(id))
+        .map(|node| &node.chapter)
+        .collect();
+    if position > siblings.len() {
+        return Err(anyhow::anyhow!("位置超出范围"));
+    }
+    let lower = position.checked_sub(1).map(|i| siblings[i].sort_path.as_str());
+    let upper = siblings.get(position).map(|c| c.sort_path.as_str());
+    Ok(sort_key::key_between(lower, upper))
+}
+
+/// 重新编号同级章节（包括已归档的章节），见 [`ChapterManager::renumber_siblings`]
+pub(crate) fn renumber_siblings_with(db: &Database, novel_id: i64, parent_id: Option<i64>) -> Result<usize> {
+    db.transaction(|tx| {
+        let mut siblings: Vec<Chapter> = tx.get_all_chapters_by_novel(novel_id)?
+            .into_iter()
+            .filter(|c| c.parent_id == parent_id)
+            .collect();
+        siblings.sort_by(|a, b| a.sort_path.cmp(&b.sort_path).then(a.id.cmp(&b.id)));
+        let keys = sort_key::spread_keys(siblings.len());
+        for (chapter, key) in siblings.iter().zip(&keys) {
+            if chapter.sort_path != *key {
+                tx.set_chapter_sort_path(chapter.id, key)?;
+            }
+        }
+        Ok(siblings.len())
+    })
+}
+
+/// 重新编号整部小说，见 [`ChapterManager::renumber_novel`]
+pub(crate) fn renumber_novel_with(db: &Database, novel_id: i64) -> Result<usize> {
+    db.transaction(|tx| {
+        let chapters = tx.get_all_chapters_by_novel(novel_id)?;
+        let mut parents: Vec<Option<i64>> = chapters.iter().map(|c| c.parent_id).collect();
+        parents.sort();
+        parents.dedup();
+        let mut count = 0;
+        for parent_id in parents {
+            count += renumber_siblings_with(tx, novel_id, parent_id)?;
+        }
+        Ok(count)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(db: &Database, novel_id: i64, parent_id: Option<i64>) -> Vec<String> {
+        let tree = ChapterManager::new().build_chapter_tree(db.get_chapters_by_novel(novel_id).unwrap());
+        let children = match parent_id {
+            Some(id) => tree.nodes[&id].children.clone(),
+            None => tree.root_nodes.clone(),
+        };
+        children.iter().map(|id| tree.nodes[id].chapter.title.clone()).collect()
+    }
+
+    #[test]
+    fn test_move_within_and_across_volumes() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("排序").unwrap();
+        let first = db.create_chapter(novel.id, "第一卷", None).unwrap();
+        let second = db.create_chapter(novel.id, "第二卷", None).unwrap();
+        let a = db.create_chapter(novel.id, "甲", Some(first.id)).unwrap();
+        let b = db.create_chapter(novel.id, "乙", Some(first.id)).unwrap();
+        let c = db.create_chapter(novel.id, "丙", Some(first.id)).unwrap();
+        db.create_chapter(novel.id, "丁", Some(second.id)).unwrap();
+        assert_eq!(titles(&db, novel.id, Some(first.id)), ["甲", "乙", "丙"]);
+
+        // 移到最前、中间，只改动被移动的章节
+        let before = db.get_chapter(b.id).unwrap().sort_path;
+        move_chapter_with(&db, c.id, Some(first.id), 0).unwrap();
+        assert_eq!(titles(&db, novel.id, Some(first.id)), ["丙", "甲", "乙"]);
+        move_chapter_with(&db, a.id, Some(first.id), 2).unwrap();
+        assert_eq!(titles(&db, novel.id, Some(first.id)), ["丙", "乙", "甲"]);
+        assert_eq!(db.get_chapter(b.id).unwrap().sort_path, before);
+
+        // 跨卷移动，以及移到顶层
+        move_chapter_with(&db, b.id, Some(second.id), 0).unwrap();
+        assert_eq!(titles(&db, novel.id, Some(second.id)), ["乙", "丁"]);
+        move_chapter_with(&db, c.id, None, 1).unwrap();
+        assert_eq!(titles(&db, novel.id, None), ["第一卷", "丙", "第二卷"]);
+
+        assert!(move_chapter_with(&db, first.id, Some(a.id), 0).is_err());
+        assert!(move_chapter_with(&db, a.id, Some(second.id), 5).is_err());
+        assert_eq!(db.get_chapter(a.id).unwrap().parent_id, Some(first.id));
+    }
+
+    #[test]
+    fn test_renumber_legacy_sort_paths() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("旧排序").unwrap();
+        let ids: Vec<i64> = ["一", "二", "三"].iter()
+            .map(|title| db.create_chapter(novel.id, title, None).unwrap().id)
+            .collect();
+        // 旧版本在同一毫秒内创建的章节排序键相同
+        for id in &ids {
+            db.update_chapter_parent(*id, None, "00000001700000000000").unwrap();
+        }
+        let updated_at = db.get_chapter(ids[1]).unwrap().updated_at;
+
+        // 两个相同的键之间插入时先重新编号
+        move_chapter_with(&db, ids[2], None, 1).unwrap();
+        assert_eq!(titles(&db, novel.id, None), ["一", "三", "二"]);
+
+        assert_eq!(renumber_novel_with(&db, novel.id).unwrap(), 3);
+        let sort_paths: Vec<String> = ids.iter().map(|id| db.get_chapter(*id).unwrap().sort_path).collect();
+        assert_eq!(sort_paths, ["FV", "kV", "V"]);
+        assert_eq!(titles(&db, novel.id, None), ["一", "三", "二"]);
+        assert_eq!(db.get_chapter(ids[1]).unwrap().updated_at, updated_at);
+
+        let appended = db.create_chapter(novel.id, "四", None).unwrap();
+        assert_eq!(appended.sort_path, "l");
+    }
+}
@@ -0,0 +1,360 @@
+use crate::core::chapter_manager::ChapterNode;
+use crate::core::novel_manager::{NovelExportData, NovelManager};
+use crate::db::ChapterType;
+use crate::utils::export_utils::{escape_xml, paragraphs};
+use anyhow::Result;
+use log::info;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// DOCX 稿件导出选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocxOptions {
+    /// 只导出这些节点（卷或章节）及其子孙，为空时导出全书
+    pub root_ids: Vec<i64>,
+    /// 是否包含已归档的章节
+    pub include_archived: bool,
+    pub font_family: String,
+    /// 字号（磅）
+    pub font_size: f32,
+    /// 行距倍数，投稿格式通常为两倍行距
+    pub line_spacing: f32,
+    /// 页眉中的姓氏，为空时从作者名推断
+    pub surname: String,
+}
+
+impl Default for DocxOptions {
+    fn default() -> Self {
+        Self {
+            root_ids: Vec::new(),
+            include_archived: false,
+            font_family: "宋体".to_string(),
+            font_size: 12.0,
+            line_spacing: 2.0,
+            surname: String::new(),
+        }
+    }
+}
+
+impl DocxOptions {
+    /// 页眉使用的姓氏：西文名取最后一个词，中文名取第一个字
+    fn header_surname(&self, author: &str) -> String {
+        let surname = self.surname.trim();
+        if !surname.is_empty() {
+            return surname.to_string();
+        }
+        let author = author.trim();
+        if let Some(last) = author.split_whitespace().last().filter(|_| author.contains(char::is_whitespace)) {
+            return last.to_string();
+        }
+        match author.chars().next() {
+            Some(c) if !c.is_ascii() => c.to_string(),
+            _ => author.to_string(),
+        }
+    }
+}
+
+/// 投稿稿件的近似字数：取整到百位
+fn approximate_word_count(count: usize) -> usize {
+    if count < 100 { count } else { (count + 50) / 100 * 100 }
+}
+
+pub struct DocxExporter;
+
+impl DocxExporter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 导出小说为 DOCX 文件
+    pub async fn export(&self, novel_id: i64, options: &DocxOptions, output: &Path) -> Result<()> {
+        let data = NovelManager::new().export_novel_data(novel_id).await?;
+        Self::write(&data, options, output)?;
+        info!("Exported DOCX: novel={}, path={}", novel_id, output.display());
+        Ok(())
+    }
+
+    /// 把已准备好的数据（例如按快照重建的全书）写成 DOCX 文件
+    pub fn write(data: &NovelExportData, options: &DocxOptions, output: &Path) -> Result<()> {
+        let bytes = Self::build(data, options)?;
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(output, bytes)?;
+        Ok(())
+    }
+
+    /// 按标准投稿格式生成 DOCX
+    ///
+    /// 第一节是扉页（作者、字数、书名），不带页眉；正文另起一节，页码从 1 开始，
+    /// 页眉为“姓氏 / 书名 / 页码”。卷和章节各自另起一页，场景之间以居中的 `#` 分隔。
+    pub fn build(data: &NovelExportData, options: &DocxOptions) -> Result<Vec<u8>> {
+        let nodes = data.ordered_nodes(&options.root_ids, options.include_archived);
+        if nodes.is_empty() {
+            return Err(anyhow::anyhow!("没有可导出的章节"));
+        }
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let parts = [
+            ("[Content_Types].xml", CONTENT_TYPES_XML.to_string()),
+            ("_rels/.rels", ROOT_RELS_XML.to_string()),
+            ("docProps/core.xml", core_properties(data)),
+            ("word/_rels/document.xml.rels", DOCUMENT_RELS_XML.to_string()),
+            ("word/styles.xml", styles(options)),
+            ("word/header1.xml", header(data, options)),
+            ("word/document.xml", document(data, &nodes, options)),
+        ];
+        for (name, content) in parts {
+            zip.start_file(name, deflated)?;
+            zip.write_all(content.as_bytes())?;
+        }
+
+        let bytes = zip.finish()?.into_inner();
+        info!("Built DOCX for '{}': {} chapters, {} bytes", data.novel.title, nodes.len(), bytes.len());
+        Ok(bytes)
+    }
+}
+
+const WORD_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const REL_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+const CONTENT_TYPES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+  <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+  <Default Extension="xml" ContentType="application/xml"/>
+  <Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+  <Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+  <Override PartName="/word/header1.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.header+xml"/>
+  <Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+</Types>
+"#;
+
+const ROOT_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+  <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+</Relationships>
+"#;
+
+const DOCUMENT_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+  <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/header" Target="header1.xml"/>
+</Relationships>
+"#;
+
+fn core_properties(data: &NovelExportData) -> String {
+    let modified = data.export_time.format("%Y-%m-%dT%H:%M:%SZ");
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <dc:title>{}</dc:title>
+  <dc:creator>{}</dc:creator>
+  <dc:description>{}</dc:description>
+  <dcterms:modified xsi:type="dcterms:W3CDTF">{}</dcterms:modified>
+</cp:coreProperties>
+"#,
+        escape_xml(&data.novel.title),
+        escape_xml(&data.novel.author),
+        escape_xml(&data.novel.description),
+        modified,
+    )
+}
+
+fn styles(options: &DocxOptions) -> String {
+    let font = escape_xml(&options.font_family);
+    // 字号以半磅为单位，行距以 1/240 行为单位
+    let size = (options.font_size * 2.0).round() as u32;
+    let line = (options.line_spacing * 240.0).round() as u32;
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="{WORD_NS}">
+  <w:docDefaults>
+    <w:rPrDefault><w:rPr><w:rFonts w:ascii="{font}" w:hAnsi="{font}" w:eastAsia="{font}" w:cs="{font}"/><w:sz w:val="{size}"/><w:szCs w:val="{size}"/><w:lang w:val="en-US" w:eastAsia="zh-CN"/></w:rPr></w:rPrDefault>
+    <w:pPrDefault><w:pPr><w:spacing w:before="0" w:after="0" w:line="{line}" w:lineRule="auto"/></w:pPr></w:pPrDefault>
+  </w:docDefaults>
+  <w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:pPr><w:ind w:firstLineChars="200" w:firstLine="{indent}"/></w:pPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:pPr><w:jc w:val="center"/><w:ind w:firstLineChars="0" w:firstLine="0"/></w:pPr><w:rPr><w:b/></w:rPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="2880" w:after="480"/><w:jc w:val="center"/><w:ind w:firstLineChars="0" w:firstLine="0"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/></w:rPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="1920" w:after="480"/><w:jc w:val="center"/><w:ind w:firstLineChars="0" w:firstLine="0"/><w:outlineLvl w:val="1"/></w:pPr></w:style>
+  <w:style w:type="paragraph" w:styleId="SceneBreak"><w:name w:val="Scene Break"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:jc w:val="center"/><w:ind w:firstLineChars="0" w:firstLine="0"/></w:pPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Header"><w:name w:val="header"/><w:basedOn w:val="Normal"/><w:pPr><w:jc w:val="right"/><w:spacing w:line="240" w:lineRule="auto"/><w:ind w:firstLineChars="0" w:firstLine="0"/></w:pPr></w:style>
+</w:styles>
+"#,
+        indent = (options.font_size * 40.0).round() as u32,
+    )
+}
+
+fn header(data: &NovelExportData, options: &DocxOptions) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:hdr xmlns:w="{WORD_NS}">
+  <w:p><w:pPr><w:pStyle w:val="Header"/></w:pPr><w:r><w:t xml:space="preserve">{} / {} / </w:t></w:r><w:fldSimple w:instr=" PAGE "><w:r><w:t>1</w:t></w:r></w:fldSimple></w:p>
+</w:hdr>
+"#,
+        escape_xml(&options.header_surname(&data.novel.author)),
+        escape_xml(&data.novel.title),
+    )
+}
+
+fn paragraph(style: Option<&str>, page_break_before: bool, text: &str) -> String {
+    let mut properties = String::new();
+    if let Some(style) = style {
+        properties.push_str(&format!(r#"<w:pStyle w:val="{}"/>"#, style));
+    }
+    if page_break_before {
+        properties.push_str("<w:pageBreakBefore/>");
+    }
+    let properties = if properties.is_empty() { String::new() } else { format!("<w:pPr>{}</w:pPr>", properties) };
+    let run = if text.is_empty() {
+        String::new()
+    } else {
+        format!(r#"<w:r><w:t xml:space="preserve">{}</w:t></w:r>"#, escape_xml(text))
+    };
+    format!("<w:p>{}{}</w:p>", properties, run)
+}
+
+/// 页面设置：A4，四边 1 英寸
+fn section_properties(with_header: bool) -> String {
+    let header = if with_header {
+        format!(r#"<w:headerReference xmlns:r="{REL_NS}" w:type="default" r:id="rId2"/><w:pgNumType w:start="1"/>"#)
+    } else {
+        String::new()
+    };
+    format!(
+        r#"<w:sectPr>{header}<w:pgSz w:w="11906" w:h="16838"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="720" w:footer="720" w:gutter="0"/></w:sectPr>"#
+    )
+}
+
+fn document(data: &NovelExportData, nodes: &[ChapterNode], options: &DocxOptions) -> String {
+    let novel = &data.novel;
+    let mut body = String::new();
+
+    // 扉页：左上作者，右上字数，书名居中
+    let word_count = data.word_count(&options.root_ids, options.include_archived);
+    body.push_str(&format!(
+        r#"<w:p><w:pPr><w:tabs><w:tab w:val="right" w:pos="9026"/></w:tabs><w:ind w:firstLineChars="0" w:firstLine="0"/></w:pPr><w:r><w:t xml:space="preserve">{}</w:t></w:r><w:r><w:tab/><w:t xml:space="preserve">约 {} 字</w:t></w:r></w:p>"#,
+        escape_xml(&novel.author),
+        approximate_word_count(word_count),
+    ));
+    body.push_str(&format!(
+        r#"<w:p><w:pPr><w:pStyle w:val="Title"/><w:spacing w:before="4800"/></w:pPr><w:r><w:t xml:space="preserve">{}</w:t></w:r></w:p>"#,
+        escape_xml(&novel.title),
+    ));
+    if !novel.author.trim().is_empty() {
+        body.push_str(&paragraph(Some("Title"), false, &novel.author));
+    }
+    body.push_str(&format!("<w:p><w:pPr>{}</w:pPr></w:p>", section_properties(false)));
+
+    // 正文
+    let mut first = true;
+    // 上一个节点之后是否已有正文，场景接在正文之后时需要分隔符
+    let mut after_text = false;
+    for node in nodes {
+        let chapter = &node.chapter;
+        match chapter.chapter_type {
+            ChapterType::Volume => body.push_str(&paragraph(Some("Heading1"), !first, &chapter.title)),
+            ChapterType::Chapter => body.push_str(&paragraph(Some("Heading2"), !first, &chapter.title)),
+            ChapterType::Scene if first => body.push_str(&paragraph(Some("Heading2"), false, &chapter.title)),
+            ChapterType::Scene if after_text => body.push_str(&paragraph(Some("SceneBreak"), false, "#")),
+            ChapterType::Scene => {}
+        }
+        let texts = paragraphs(&chapter.content);
+        for text in &texts {
+            body.push_str(&paragraph(None, false, text));
+        }
+        after_text = !texts.is_empty() || (after_text && chapter.chapter_type == ChapterType::Scene);
+        first = false;
+    }
+    body.push_str(&section_properties(true));
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="{WORD_NS}" xmlns:r="{REL_NS}">
+  <w:body>{body}</w:body>
+</w:document>
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::export_utils::{assert_well_formed, read_zip_text, sample_export_data};
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_docx_manuscript_layout() {
+        let data = sample_export_data();
+        let bytes = DocxExporter::build(&data, &DocxOptions::default()).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+        let content_types = read_zip_text(&mut archive, "[Content_Types].xml");
+        for name in ["[Content_Types].xml", "_rels/.rels", "word/_rels/document.xml.rels", "word/styles.xml", "word/header1.xml", "word/document.xml", "docProps/core.xml"] {
+            let xml = read_zip_text(&mut archive, name);
+            assert_well_formed(name, &xml);
+            if name.starts_with("word/") && name.ends_with(".xml") {
+                assert!(content_types.contains(&format!("/{}", name)));
+            }
+        }
+
+        let document = read_zip_text(&mut archive, "word/document.xml");
+        assert!(document.contains("山海 &amp; &lt;归途&gt;"));
+        assert!(document.contains("约 19 字"));
+        assert!(!document.contains("废稿"));
+        // 第一卷在正文第一页，之后的卷和章节另起一页，场景不分页
+        assert_eq!(document.matches("<w:pageBreakBefore/>").count(), 4);
+        assert_eq!(document.matches(r#"<w:pStyle w:val="Heading1"/>"#).count(), 2);
+        // 章节正文与场景一之间、场景一与场景二之间各一个分隔符
+        assert_eq!(document.matches(r#"<w:pStyle w:val="SceneBreak"/>"#).count(), 2);
+        assert_eq!(document.matches("<w:sectPr>").count(), 2);
+        let order: Vec<usize> = ["第一卷", "第一章", "山路很长。", "天黑了。", "天亮了。", "第二章", "第二卷", "第三章"].iter()
+            .map(|t| document.find(t).unwrap())
+            .collect();
+        assert!(order.windows(2).all(|w| w[0] < w[1]));
+
+        let header = read_zip_text(&mut archive, "word/header1.xml");
+        assert!(header.contains("李 / 山海 &amp; &lt;归途&gt; / "));
+        assert!(header.contains(r#"w:instr=" PAGE ""#));
+
+        let styles = read_zip_text(&mut archive, "word/styles.xml");
+        assert!(styles.contains(r#"w:sz w:val="24""#));
+        assert!(styles.contains(r#"w:line="480""#));
+    }
+
+    #[test]
+    fn test_docx_subtree_archived_and_formatting_options() {
+        let data = sample_export_data();
+        let first_chapter = data.chapters.iter().find(|c| c.title == "第一章").unwrap().id;
+        let options = DocxOptions {
+            root_ids: vec![first_chapter],
+            font_family: "Times New Roman".to_string(),
+            font_size: 11.0,
+            line_spacing: 1.5,
+            surname: "Lee".to_string(),
+            ..DocxOptions::default()
+        };
+        let bytes = DocxExporter::build(&data, &options).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let document = read_zip_text(&mut archive, "word/document.xml");
+        assert!(!document.contains("第一卷"));
+        assert!(document.contains("第一章"));
+        assert_eq!(document.matches("<w:pageBreakBefore/>").count(), 0);
+        let styles = read_zip_text(&mut archive, "word/styles.xml");
+        assert!(styles.contains(r#"w:ascii="Times New Roman""#));
+        assert!(styles.contains(r#"w:sz w:val="22""#));
+        assert!(styles.contains(r#"w:line="360""#));
+        assert!(read_zip_text(&mut archive, "word/header1.xml").contains("Lee / "));
+
+        let options = DocxOptions { include_archived: true, ..DocxOptions::default() };
+        let bytes = DocxExporter::build(&data, &options).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        assert!(read_zip_text(&mut archive, "word/document.xml").contains("废稿"));
+
+        assert_eq!(DocxOptions::default().header_surname("Jane Doe"), "Doe");
+        assert_eq!(approximate_word_count(12_345), 12_300);
+    }
+}
@@ -0,0 +1,233 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+/// 每个章节最多保留的撤销步数
+const MAX_UNDO_STEPS: usize = 200;
+
+/// 连续输入间隔不超过该时长（毫秒）时合并为同一个撤销步骤
+const TYPING_COALESCE_MS: i64 = 1500;
+
+/// 编辑操作类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    /// 键盘输入，连续输入会合并
+    Typing,
+    /// 粘贴，单独成为一步
+    Paste,
+    /// 对整章应用格式化，单独成为一步
+    Format,
+    /// 编辑器之外的修改（如查找替换），单独成为一步
+    External,
+}
+
+/// 单个章节的编辑历史
+///
+/// 保存内容快照而非增量，撤销/重做只需要整体替换编辑器内容。
+#[derive(Debug, Clone)]
+pub struct EditHistory {
+    current: String,
+    undo_stack: Vec<String>,
+    redo_stack: Vec<String>,
+    /// 上一次记录的操作，用于判断能否合并
+    last_edit: Option<(EditKind, DateTime<Utc>, i64)>,
+    /// 最近一次保存时的内容
+    saved_content: String,
+}
+
+impl EditHistory {
+    pub fn new(content: &str) -> Self {
+        Self {
+            current: content.to_string(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            last_edit: None,
+            saved_content: content.to_string(),
+        }
+    }
+
+    pub fn current(&self) -> &str {
+        &self.current
+    }
+
+    /// 记录一次修改后的内容
+    pub fn record(&mut self, content: &str, kind: EditKind, now: DateTime<Utc>) {
+        if content == self.current {
+            return;
+        }
+
+        // 输入方向（增加/删除）改变时也断开，避免一次撤销同时撤掉打字和删除
+        let direction = (content.chars().count() as i64 - self.current.chars().count() as i64).signum();
+        let coalesce = match self.last_edit {
+            Some((EditKind::Typing, last_at, last_direction)) => {
+                kind == EditKind::Typing
+                    && last_direction == direction
+                    && now.signed_duration_since(last_at) <= Duration::milliseconds(TYPING_COALESCE_MS)
+            }
+            _ => false,
+        };
+
+        if !coalesce {
+            self.undo_stack.push(std::mem::replace(&mut self.current, content.to_string()));
+            if self.undo_stack.len() > MAX_UNDO_STEPS {
+                self.undo_stack.remove(0);
+            }
+        } else {
+            self.current = content.to_string();
+        }
+
+        self.redo_stack.clear();
+        self.last_edit = Some((kind, now, direction));
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// 撤销一步，返回撤销后的内容
+    pub fn undo(&mut self) -> Option<String> {
+        let previous = self.undo_stack.pop()?;
+        self.redo_stack.push(std::mem::replace(&mut self.current, previous));
+        self.last_edit = None;
+        Some(self.current.clone())
+    }
+
+    /// 重做一步，返回重做后的内容
+    pub fn redo(&mut self) -> Option<String> {
+        let next = self.redo_stack.pop()?;
+        self.undo_stack.push(std::mem::replace(&mut self.current, next));
+        self.last_edit = None;
+        Some(self.current.clone())
+    }
+
+    /// 标记当前内容已保存；保存后的输入从新的撤销步骤开始，撤销可以回到保存时的状态
+    pub fn mark_saved(&mut self) {
+        self.saved_content = self.current.clone();
+        self.last_edit = None;
+    }
+
+    /// 当前内容是否与最近一次保存不同
+    pub fn is_dirty(&self) -> bool {
+        self.current != self.saved_content
+    }
+}
+
+/// 本次运行中打开过的所有章节的编辑历史，切换章节后返回仍可撤销
+#[derive(Debug, Clone, Default)]
+pub struct EditHistoryStore {
+    histories: HashMap<i64, EditHistory>,
+}
+
+impl EditHistoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 打开章节时调用
+    ///
+    /// 已有历史时沿用；若数据库中的内容与历史不一致（例如被查找替换修改过），
+    /// 则把差异记录为一步外部修改。
+    pub fn open(&mut self, chapter_id: i64, content: &str, now: DateTime<Utc>) -> &mut EditHistory {
+        let history = self.histories
+            .entry(chapter_id)
+            .or_insert_with(|| EditHistory::new(content));
+        if history.current() != content {
+            history.record(content, EditKind::External, now);
+            history.mark_saved();
+        }
+        history
+    }
+
+    pub fn get(&self, chapter_id: i64) -> Option<&EditHistory> {
+        self.histories.get(&chapter_id)
+    }
+
+    pub fn get_mut(&mut self, chapter_id: i64) -> Option<&mut EditHistory> {
+        self.histories.get_mut(&chapter_id)
+    }
+
+    /// 章节被删除时丢弃其历史
+    pub fn remove(&mut self, chapter_id: i64) {
+        self.histories.remove(&chapter_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(ms: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap() + Duration::milliseconds(ms)
+    }
+
+    #[test]
+    fn test_typing_runs_are_coalesced() {
+        let mut history = EditHistory::new("");
+        history.record("从", EditKind::Typing, at(0));
+        history.record("从前", EditKind::Typing, at(300));
+        history.record("从前有", EditKind::Typing, at(600));
+        // 停顿后开始新的一步
+        history.record("从前有座山", EditKind::Typing, at(5000));
+
+        assert_eq!(history.undo().as_deref(), Some("从前有"));
+        assert_eq!(history.undo().as_deref(), Some(""));
+        assert!(!history.can_undo());
+        assert_eq!(history.redo().as_deref(), Some("从前有"));
+        assert_eq!(history.redo().as_deref(), Some("从前有座山"));
+    }
+
+    #[test]
+    fn test_paste_and_format_are_discrete_steps() {
+        let mut history = EditHistory::new("开头");
+        history.record("开头。", EditKind::Typing, at(0));
+        history.record("开头。粘贴的段落", EditKind::Paste, at(100));
+        history.record("开头。粘贴的段落。", EditKind::Typing, at(200));
+        history.record("    开头。粘贴的段落。\n", EditKind::Format, at(300));
+
+        assert_eq!(history.undo().as_deref(), Some("开头。粘贴的段落。"));
+        assert_eq!(history.undo().as_deref(), Some("开头。粘贴的段落"));
+        assert_eq!(history.undo().as_deref(), Some("开头。"));
+    }
+
+    #[test]
+    fn test_deleting_after_typing_starts_new_step_and_new_edit_clears_redo() {
+        let mut history = EditHistory::new("");
+        history.record("山中", EditKind::Typing, at(0));
+        history.record("山", EditKind::Typing, at(100));
+        assert_eq!(history.undo().as_deref(), Some("山中"));
+
+        history.record("山中有", EditKind::Typing, at(200));
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_save_breaks_coalescing_and_tracks_dirty_state() {
+        let mut history = EditHistory::new("");
+        history.record("甲", EditKind::Typing, at(0));
+        history.mark_saved();
+        assert!(!history.is_dirty());
+
+        history.record("甲乙", EditKind::Typing, at(100));
+        assert!(history.is_dirty());
+        assert_eq!(history.undo().as_deref(), Some("甲"));
+        assert!(!history.is_dirty());
+    }
+
+    #[test]
+    fn test_store_keeps_history_across_chapter_switches() {
+        let mut store = EditHistoryStore::new();
+        store.open(1, "第一章", at(0)).record("第一章内容", EditKind::Typing, at(10));
+        store.open(2, "第二章", at(20));
+
+        // 回到第一章，数据库中是已保存的内容
+        let history = store.open(1, "第一章内容", at(30));
+        assert_eq!(history.undo().as_deref(), Some("第一章"));
+
+        // 外部修改作为单独一步
+        let history = store.open(2, "第二章（已替换）", at(40));
+        assert_eq!(history.undo().as_deref(), Some("第二章"));
+    }
+}
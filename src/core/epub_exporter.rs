@@ -0,0 +1,430 @@
+use crate::core::chapter_manager::ChapterNode;
+use crate::core::novel_manager::{NovelExportData, NovelManager};
+use crate::db::ChapterType;
+use crate::utils::export_utils::{escape_xml, paragraphs};
+use anyhow::Result;
+use log::info;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// EPUB 导出选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpubOptions {
+    /// 只导出这些卷及其下的章节，为空时导出全书
+    pub volume_ids: Vec<i64>,
+    /// 正文语言标签，如 `zh-CN`、`zh-TW`、`ja`
+    pub language: String,
+    /// 竖排（从右到左翻页）
+    pub vertical_writing: bool,
+    /// 是否包含已归档章节
+    pub include_archived: bool,
+    /// 封面图片文件
+    pub cover_image: Option<PathBuf>,
+}
+
+impl Default for EpubOptions {
+    fn default() -> Self {
+        Self {
+            volume_ids: Vec::new(),
+            language: "zh-CN".to_string(),
+            vertical_writing: false,
+            include_archived: false,
+            cover_image: None,
+        }
+    }
+}
+
+/// 封面图片
+struct Cover {
+    file_name: String,
+    media_type: &'static str,
+    data: Vec<u8>,
+}
+
+impl Cover {
+    fn load(path: &Path) -> Result<Self> {
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_default();
+        let media_type = match extension.as_str() {
+            "jpg" | "jpeg" => "image/jpeg",
+            "png" => "image/png",
+            "gif" => "image/gif",
+            "webp" => "image/webp",
+            "svg" => "image/svg+xml",
+            _ => return Err(anyhow::anyhow!("不支持的封面图片格式: {}", path.display())),
+        };
+        let data = std::fs::read(path)
+            .map_err(|e| anyhow::anyhow!("读取封面图片失败: {}", e))?;
+        Ok(Self { file_name: format!("cover.{}", extension), media_type, data })
+    }
+}
+
+pub struct EpubExporter;
+
+impl EpubExporter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 导出小说为 EPUB 文件
+    pub async fn export(&self, novel_id: i64, options: &EpubOptions, output: &Path) -> Result<()> {
+        let data = NovelManager::new().export_novel_data(novel_id).await?;
+        Self::write(&data, options, output)?;
+        info!("Exported EPUB: novel={}, path={}", novel_id, output.display());
+        Ok(())
+    }
+
+    /// 把已准备好的数据（例如按快照重建的全书）写成 EPUB 文件
+    pub fn write(data: &NovelExportData, options: &EpubOptions, output: &Path) -> Result<()> {
+        let bytes = Self::build(data, options)?;
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(output, bytes)?;
+        Ok(())
+    }
+
+    /// 生成 EPUB 3 文件内容
+    ///
+    /// 卷在目录中作为分组，其下的章节和场景嵌套在分组内；每个章节节点单独生成一个 XHTML 文档。
+    pub fn build(data: &NovelExportData, options: &EpubOptions) -> Result<Vec<u8>> {
+        let nodes = data.ordered_nodes(&options.volume_ids, options.include_archived);
+        if nodes.is_empty() {
+            return Err(anyhow::anyhow!("没有可导出的章节"));
+        }
+        let cover = options.cover_image.as_deref().map(Cover::load).transpose()?;
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        // mimetype 必须是第一个且不压缩
+        zip.start_file("mimetype", stored)?;
+        zip.write_all(b"application/epub+zip")?;
+
+        zip.start_file("META-INF/container.xml", deflated)?;
+        zip.write_all(CONTAINER_XML.as_bytes())?;
+
+        zip.start_file("OEBPS/style.css", deflated)?;
+        zip.write_all(stylesheet(options.vertical_writing).as_bytes())?;
+
+        if let Some(cover) = &cover {
+            zip.start_file(format!("OEBPS/images/{}", cover.file_name), stored)?;
+            zip.write_all(&cover.data)?;
+            zip.start_file("OEBPS/cover.xhtml", deflated)?;
+            zip.write_all(xhtml_document(
+                &options.language,
+                &data.novel.title,
+                "style.css",
+                &format!(r#"<div class="cover"><img src="images/{}" alt="{}"/></div>"#,
+                    cover.file_name, escape_xml(&data.novel.title)),
+            ).as_bytes())?;
+        }
+
+        zip.start_file("OEBPS/title.xhtml", deflated)?;
+        zip.write_all(title_page(data, &options.language).as_bytes())?;
+
+        for node in &nodes {
+            zip.start_file(format!("OEBPS/{}", chapter_href(node)), deflated)?;
+            zip.write_all(chapter_document(node, &options.language).as_bytes())?;
+        }
+
+        zip.start_file("OEBPS/nav.xhtml", deflated)?;
+        zip.write_all(nav_document(data, &nodes, &options.language).as_bytes())?;
+
+        zip.start_file("OEBPS/content.opf", deflated)?;
+        zip.write_all(package_document(data, &nodes, options, cover.as_ref()).as_bytes())?;
+
+        let bytes = zip.finish()?.into_inner();
+        info!("Built EPUB for '{}': {} documents, {} bytes", data.novel.title, nodes.len(), bytes.len());
+        Ok(bytes)
+    }
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+fn stylesheet(vertical_writing: bool) -> String {
+    let mut css = String::from(
+        "body { font-family: serif; line-height: 1.8; }\n\
+         p { text-indent: 2em; margin: 0; }\n\
+         h1, h2, h3 { text-align: center; }\n\
+         .volume-title { margin-top: 30%; }\n\
+         .title-page { text-align: center; }\n\
+         .title-page .author { margin-top: 2em; }\n\
+         .title-page .description { text-align: left; margin-top: 3em; }\n\
+         .cover { text-align: center; }\n\
+         .cover img { max-width: 100%; max-height: 100%; }\n",
+    );
+    if vertical_writing {
+        css.push_str("html { writing-mode: vertical-rl; -epub-writing-mode: vertical-rl; -webkit-writing-mode: vertical-rl; }\n");
+    }
+    css
+}
+
+fn chapter_id(node: &ChapterNode) -> String {
+    format!("c{}", node.chapter.id)
+}
+
+fn chapter_href(node: &ChapterNode) -> String {
+    format!("text/{}.xhtml", chapter_id(node))
+}
+
+/// `stylesheet` 是相对文档的样式表路径，正文位于 `text/` 子目录时为 `../style.css`
+fn xhtml_document(language: &str, title: &str, stylesheet: &str, body: &str) -> String {
+    let language = escape_xml(language);
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{language}" lang="{language}">
+<head>
+  <meta charset="UTF-8"/>
+  <title>{title}</title>
+  <link rel="stylesheet" type="text/css" href="{stylesheet}"/>
+</head>
+<body>
+{body}
+</body>
+</html>
+"#,
+        title = escape_xml(title),
+    )
+}
+
+fn title_page(data: &NovelExportData, language: &str) -> String {
+    let novel = &data.novel;
+    let mut body = format!(r#"<section class="title-page" epub:type="titlepage"><h1>{}</h1>"#, escape_xml(&novel.title));
+    if !novel.author.trim().is_empty() {
+        body.push_str(&format!(r#"<p class="author">{}</p>"#, escape_xml(&novel.author)));
+    }
+    if !novel.description.trim().is_empty() {
+        body.push_str(r#"<div class="description">"#);
+        for paragraph in paragraphs(&novel.description) {
+            body.push_str(&format!("<p>{}</p>", escape_xml(paragraph)));
+        }
+        body.push_str("</div>");
+    }
+    body.push_str("</section>");
+    xhtml_document(language, &novel.title, "style.css", &body)
+}
+
+fn chapter_document(node: &ChapterNode, language: &str) -> String {
+    let chapter = &node.chapter;
+    let title = escape_xml(&chapter.title);
+    let mut body = match chapter.chapter_type {
+        ChapterType::Volume => format!(r#"<section epub:type="part"><h1 class="volume-title">{}</h1>"#, title),
+        ChapterType::Chapter => format!(r#"<section epub:type="chapter"><h2>{}</h2>"#, title),
+        ChapterType::Scene => format!("<section><h3>{}</h3>", title),
+    };
+    for paragraph in paragraphs(&chapter.content) {
+        body.push_str(&format!("<p>{}</p>", escape_xml(paragraph)));
+    }
+    body.push_str("</section>");
+    xhtml_document(language, &chapter.title, "../style.css", &body)
+}
+
+/// 按节点深度生成嵌套的目录列表
+fn nav_document(data: &NovelExportData, nodes: &[ChapterNode], language: &str) -> String {
+    let mut list = String::from("<ol>");
+    let mut open_depth: Option<usize> = None;
+    for node in nodes {
+        // 深度最多比上一项深一层，避免生成空的列表项
+        let depth = open_depth.map_or(0, |d| node.depth.min(d + 1));
+        match open_depth {
+            Some(previous) if depth > previous => list.push_str("<ol>"),
+            Some(previous) => {
+                list.push_str("</li>");
+                for _ in depth..previous {
+                    list.push_str("</ol></li>");
+                }
+            }
+            None => {}
+        }
+        list.push_str(&format!(r#"<li><a href="{}">{}</a>"#, chapter_href(node), escape_xml(&node.chapter.title)));
+        open_depth = Some(depth);
+    }
+    if let Some(depth) = open_depth {
+        list.push_str("</li>");
+        for _ in 0..depth {
+            list.push_str("</ol></li>");
+        }
+    }
+    list.push_str("</ol>");
+
+    let body = format!(
+        r#"<nav epub:type="toc" id="toc"><h1>目录</h1>{}</nav>
+<nav epub:type="landmarks" hidden="hidden"><ol><li><a epub:type="titlepage" href="title.xhtml">{}</a></li><li><a epub:type="bodymatter" href="{}">正文</a></li></ol></nav>"#,
+        list,
+        escape_xml(&data.novel.title),
+        chapter_href(&nodes[0]),
+    );
+    xhtml_document(language, "目录", "style.css", &body)
+}
+
+fn package_document(data: &NovelExportData, nodes: &[ChapterNode], options: &EpubOptions, cover: Option<&Cover>) -> String {
+    let novel = &data.novel;
+    let mut metadata = format!(
+        "    <dc:identifier id=\"book-id\">urn:novel-writer:{}:{}</dc:identifier>\n    <dc:title>{}</dc:title>\n    <dc:language>{}</dc:language>\n    <meta property=\"dcterms:modified\">{}</meta>\n",
+        novel.id,
+        novel.created_at.timestamp(),
+        escape_xml(&novel.title),
+        escape_xml(&options.language),
+        data.export_time.format("%Y-%m-%dT%H:%M:%SZ"),
+    );
+    if !novel.author.trim().is_empty() {
+        metadata.push_str(&format!("    <dc:creator>{}</dc:creator>\n", escape_xml(&novel.author)));
+    }
+    if !novel.description.trim().is_empty() {
+        metadata.push_str(&format!("    <dc:description>{}</dc:description>\n", escape_xml(&novel.description)));
+    }
+    if options.vertical_writing {
+        metadata.push_str("    <meta name=\"primary-writing-mode\" content=\"vertical-rl\"/>\n");
+    }
+
+    let mut manifest = String::from(
+        "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+         \x20   <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n\
+         \x20   <item id=\"title-page\" href=\"title.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+    );
+    let mut spine = String::new();
+    if let Some(cover) = cover {
+        metadata.push_str("    <meta name=\"cover\" content=\"cover-image\"/>\n");
+        manifest.push_str(&format!(
+            "    <item id=\"cover-image\" href=\"images/{}\" media-type=\"{}\" properties=\"cover-image\"/>\n",
+            cover.file_name, cover.media_type
+        ));
+        manifest.push_str("    <item id=\"cover\" href=\"cover.xhtml\" media-type=\"application/xhtml+xml\"/>\n");
+        spine.push_str("    <itemref idref=\"cover\"/>\n");
+    }
+    spine.push_str("    <itemref idref=\"title-page\"/>\n");
+    for node in nodes {
+        manifest.push_str(&format!(
+            "    <item id=\"{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+            chapter_id(node),
+            chapter_href(node)
+        ));
+        spine.push_str(&format!("    <itemref idref=\"{}\"/>\n", chapter_id(node)));
+    }
+
+    let direction = if options.vertical_writing { " page-progression-direction=\"rtl\"" } else { "" };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="{}">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+{}  </metadata>
+  <manifest>
+{}  </manifest>
+  <spine{}>
+{}  </spine>
+</package>
+"#,
+        escape_xml(&options.language),
+        metadata,
+        manifest,
+        direction,
+        spine,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::export_utils::{assert_well_formed, read_zip_text, sample_export_data};
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_epub_structure_is_valid() {
+        let data = sample_export_data();
+        let bytes = EpubExporter::build(&data, &EpubOptions::default()).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+        {
+            let first = archive.by_index(0).unwrap();
+            assert_eq!(first.name(), "mimetype");
+            assert_eq!(first.compression(), CompressionMethod::Stored);
+        }
+        assert_eq!(read_zip_text(&mut archive, "mimetype"), "application/epub+zip");
+        assert!(read_zip_text(&mut archive, "META-INF/container.xml").contains("OEBPS/content.opf"));
+
+        let opf = read_zip_text(&mut archive, "OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>山海 &amp; &lt;归途&gt;</dc:title>"));
+        assert!(opf.contains("<dc:creator>李青山</dc:creator>"));
+        assert!(opf.contains("<dc:language>zh-CN</dc:language>"));
+        let nav = read_zip_text(&mut archive, "OEBPS/nav.xhtml");
+        assert!(!nav.contains("番外草稿") && !nav.contains("废稿"));
+
+        // 清单中的每个文件都存在，书脊引用的都在清单中
+        let item = regex::Regex::new(r#"<item id="([^"]+)" href="([^"]+)""#).unwrap();
+        let ids: Vec<String> = item.captures_iter(&opf).map(|c| c[1].to_string()).collect();
+        for caps in item.captures_iter(&opf) {
+            let path = format!("OEBPS/{}", &caps[2]);
+            let content = read_zip_text(&mut archive, &path);
+            if path.ends_with(".xhtml") {
+                assert!(content.contains(r#"xml:lang="zh-CN""#));
+                assert_well_formed(&path, &content);
+            }
+        }
+        let itemref = regex::Regex::new(r#"<itemref idref="([^"]+)""#).unwrap();
+        let spine: Vec<String> = itemref.captures_iter(&opf).map(|c| c[1].to_string()).collect();
+        assert!(spine.iter().all(|id| ids.contains(id)));
+        assert_eq!(spine.len(), 1 + 7);
+        assert_well_formed("content.opf", &opf);
+
+        // 目录按树结构嵌套，顺序与章节排列一致
+        let titles: Vec<&str> = ["第一卷", "第一章", "场景一", "场景二", "第二章", "第二卷", "第三章"].to_vec();
+        let positions: Vec<usize> = titles.iter().map(|t| nav.find(t).unwrap()).collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert!(nav.contains("第一卷</a><ol><li>"));
+
+        let first_chapter = data.ordered_nodes(&[], false).into_iter().find(|n| n.chapter.title == "第一章").unwrap();
+        let chapter = read_zip_text(&mut archive, &format!("OEBPS/{}", chapter_href(&first_chapter)));
+        assert!(chapter.contains("<p>山路很长。</p><p>他走了很久。</p>"));
+    }
+
+    #[test]
+    fn test_selected_volume_vertical_writing_and_cover() {
+        let data = sample_export_data();
+        let second_volume = data.chapters.iter().find(|c| c.title == "第二卷").unwrap().id;
+
+        let dir = std::env::temp_dir().join(format!("novel-writer-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cover_path = dir.join("cover.PNG");
+        std::fs::write(&cover_path, b"\x89PNG\r\n\x1a\nfake").unwrap();
+
+        let options = EpubOptions {
+            volume_ids: vec![second_volume],
+            language: "ja".to_string(),
+            vertical_writing: true,
+            include_archived: true,
+            cover_image: Some(cover_path),
+        };
+        let bytes = EpubExporter::build(&data, &options).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+        let opf = read_zip_text(&mut archive, "OEBPS/content.opf");
+        assert!(opf.contains(r#"page-progression-direction="rtl""#));
+        assert!(opf.contains(r#"properties="cover-image""#));
+        assert!(opf.contains("<dc:language>ja</dc:language>"));
+        assert!(read_zip_text(&mut archive, "OEBPS/style.css").contains("vertical-rl"));
+        assert!(archive.by_name("OEBPS/images/cover.png").is_ok());
+
+        let nav = read_zip_text(&mut archive, "OEBPS/nav.xhtml");
+        assert!(nav.contains("第三章"));
+        assert!(nav.contains("番外草稿"));
+        assert!(!nav.contains("第一章"));
+        assert_well_formed("nav.xhtml", &nav);
+
+        let options = EpubOptions { cover_image: Some(dir.join("cover.bmp")), ..EpubOptions::default() };
+        assert!(EpubExporter::build(&data, &options).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,402 @@
+use crate::core::chapter_manager::ChapterManager;
+use crate::core::version_manager::VersionManager;
+use crate::db::{self, Chapter, Database};
+use crate::utils::diff_utils::is_cjk;
+use anyhow::Result;
+use log::info;
+use regex::{Regex, RegexBuilder};
+
+/// 预览中命中位置前后展示的字符数
+const PREVIEW_CONTEXT: usize = 20;
+
+/// 匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchMode {
+    /// 普通文本
+    #[default]
+    Plain,
+    /// 正则表达式，替换文本中可用 `$1`、`${name}` 引用捕获组
+    Regex,
+    /// 全词匹配：字母数字开头或结尾的命中，外侧不能紧接着字母数字；中日韩文字没有词间空格，两侧不限制
+    WholeWord,
+}
+
+/// 查找替换参数
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FindOptions {
+    pub pattern: String,
+    /// 普通和全词模式下替换文本按字面处理
+    pub replacement: String,
+    pub mode: MatchMode,
+    pub case_sensitive: bool,
+    /// 只在该章节（通常是卷）及其子章节中查找，`None` 表示整部小说
+    pub scope_chapter_id: Option<i64>,
+}
+
+/// 单个命中
+#[derive(Debug, Clone, PartialEq)]
+pub struct FindMatch {
+    pub chapter_id: i64,
+    pub chapter_title: String,
+    /// 在章节正文中的字节区间
+    pub start: usize,
+    pub end: usize,
+    pub matched: String,
+    /// 替换后的文本（正则模式下已展开捕获组）
+    pub replacement: String,
+    pub context_before: String,
+    pub context_after: String,
+}
+
+/// 替换时为某一章节保存的替换前版本
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplacedChapter {
+    pub chapter_id: i64,
+    pub backup_version_id: i64,
+    pub replacements: usize,
+    /// 替换完成后的章节内容，撤销前用来确认章节没有被再次修改
+    pub replaced_content: String,
+}
+
+/// 一次替换操作的结果，可用于整体撤销
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ReplaceOutcome {
+    pub chapters: Vec<ReplacedChapter>,
+}
+
+impl ReplaceOutcome {
+    pub fn total_replacements(&self) -> usize {
+        self.chapters.iter().map(|c| c.replacements).sum()
+    }
+}
+
+pub struct FindReplaceManager;
+
+impl FindReplaceManager {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 在小说中查找所有命中，用于逐条预览
+    pub async fn find(&self, novel_id: i64, options: &FindOptions) -> Result<Vec<FindMatch>> {
+        let options = options.clone();
+        db::with_database(move |db| Self::find_with(db, novel_id, &options)).await
+    }
+
+    /// 应用选中的替换
+    ///
+    /// 所有章节在同一个事务中修改；每个被修改的章节先保存一个替换前版本，便于撤销。
+    pub async fn replace(&self, novel_id: i64, options: &FindOptions, selected: &[FindMatch]) -> Result<ReplaceOutcome> {
+        let (options, selected) = (options.clone(), selected.to_vec());
+        db::with_database(move |db| Self::replace_with(db, novel_id, &options, &selected)).await
+    }
+
+    /// 撤销一次替换，将涉及的章节恢复到替换前版本
+    pub async fn revert(&self, outcome: &ReplaceOutcome) -> Result<()> {
+        let outcome = outcome.clone();
+        db::with_database(move |db| Self::revert_with(db, &outcome)).await
+    }
+
+    pub(crate) fn find_with(db: &Database, novel_id: i64, options: &FindOptions) -> Result<Vec<FindMatch>> {
+        let regex = Self::build_regex(options)?;
+        let chapters = Self::chapters_in_scope(db, novel_id, options.scope_chapter_id)?;
+
+        let mut matches = Vec::new();
+        for chapter in &chapters {
+            let mut position = 0;
+            while let Some(caps) = regex.captures_at(&chapter.content, position) {
+                let whole = caps.get(0).expect("capture group 0 always exists");
+                if whole.as_str().is_empty()
+                    || (options.mode == MatchMode::WholeWord && !is_whole_word(&chapter.content, whole.start(), whole.end())) {
+                    // 从下一个字符继续查找，不跳过与之重叠的命中
+                    match chapter.content[whole.start()..].chars().next() {
+                        Some(c) => {
+                            position = whole.start() + c.len_utf8();
+                            continue;
+                        }
+                        None => break,
+                    }
+                }
+                position = whole.end();
+
+                let replacement = if options.mode == MatchMode::Regex {
+                    let mut expanded = String::new();
+                    caps.expand(&options.replacement, &mut expanded);
+                    expanded
+                } else {
+                    options.replacement.clone()
+                };
+
+                matches.push(FindMatch {
+                    chapter_id: chapter.id,
+                    chapter_title: chapter.title.clone(),
+                    start: whole.start(),
+                    end: whole.end(),
+                    matched: whole.as_str().to_string(),
+                    replacement,
+                    context_before: tail_chars(&chapter.content[..whole.start()], PREVIEW_CONTEXT),
+                    context_after: chapter.content[whole.end()..].chars().take(PREVIEW_CONTEXT).collect(),
+                });
+            }
+        }
+
+        info!("Find '{}' in novel {}: {} matches in {} chapters", options.pattern, novel_id, matches.len(), chapters.len());
+        Ok(matches)
+    }
+
+    pub(crate) fn replace_with(
+        db: &Database,
+        novel_id: i64,
+        options: &FindOptions,
+        selected: &[FindMatch],
+    ) -> Result<ReplaceOutcome> {
+        let mut chapter_ids: Vec<i64> = Vec::new();
+        for m in selected {
+            if !chapter_ids.contains(&m.chapter_id) {
+                chapter_ids.push(m.chapter_id);
+            }
+        }
+        let message = format!("查找替换前: “{}” → “{}”", options.pattern, options.replacement);
+
+        let outcome = db.transaction(|tx| {
+            let mut outcome = ReplaceOutcome::default();
+
+            for chapter_id in chapter_ids.iter().copied() {
+                let chapter = tx.get_chapter(chapter_id)?;
+                if chapter.novel_id != novel_id {
+                    return Err(anyhow::anyhow!("章节 {} 不属于当前小说", chapter_id));
+                }
+
+                let mut chapter_matches: Vec<&FindMatch> = selected.iter()
+                    .filter(|m| m.chapter_id == chapter_id)
+                    .collect();
+                chapter_matches.sort_by_key(|m| m.start);
+                chapter_matches.dedup_by_key(|m| m.start);
+
+                let mut new_content = String::with_capacity(chapter.content.len());
+                let mut cursor = 0;
+                for m in &chapter_matches {
+                    if m.start < cursor || chapter.content.get(m.start..m.end) != Some(m.matched.as_str()) {
+                        return Err(anyhow::anyhow!("章节《{}》的内容已变化，请重新查找后再替换", chapter.title));
+                    }
+                    new_content.push_str(&chapter.content[cursor..m.start]);
+                    new_content.push_str(&m.replacement);
+                    cursor = m.end;
+                }
+                new_content.push_str(&chapter.content[cursor..]);
+
+                let backup = VersionManager::create_version_with(tx, chapter_id, &chapter.content, Some(&message), false)?;
+                tx.update_chapter_content(chapter_id, &new_content)?;
+
+                outcome.chapters.push(ReplacedChapter {
+                    chapter_id,
+                    backup_version_id: backup.id,
+                    replacements: chapter_matches.len(),
+                    replaced_content: new_content,
+                });
+            }
+
+            Ok(outcome)
+        })?;
+
+        info!("Replaced {} matches in {} chapters", outcome.total_replacements(), outcome.chapters.len());
+        Ok(outcome)
+    }
+
+    pub(crate) fn revert_with(db: &Database, outcome: &ReplaceOutcome) -> Result<()> {
+        db.transaction(|tx| {
+            for replaced in &outcome.chapters {
+                let chapter = tx.get_chapter(replaced.chapter_id)?;
+                if chapter.content != replaced.replaced_content {
+                    return Err(anyhow::anyhow!("章节《{}》在替换后已被修改，无法撤销替换", chapter.title));
+                }
+
+                let content = VersionManager::reconstruct_content(tx, replaced.backup_version_id)?;
+                VersionManager::create_version_with(tx, replaced.chapter_id, &chapter.content, Some("撤销查找替换前"), false)?;
+                tx.update_chapter_content(replaced.chapter_id, &content)?;
+            }
+            Ok(())
+        })?;
+
+        info!("Reverted replacement in {} chapters", outcome.chapters.len());
+        Ok(())
+    }
+
+    fn build_regex(options: &FindOptions) -> Result<Regex> {
+        if options.pattern.is_empty() {
+            return Err(anyhow::anyhow!("查找内容不能为空"));
+        }
+
+        let pattern = match options.mode {
+            MatchMode::Plain => regex::escape(&options.pattern),
+            MatchMode::Regex => options.pattern.clone(),
+            // 边界由 `is_whole_word` 检查，`\b` 会把相连的中文字符当作同一个词
+            MatchMode::WholeWord => regex::escape(&options.pattern),
+        };
+
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .build()
+            .map_err(|e| anyhow::anyhow!("无效的正则表达式: {}", e))
+    }
+
+    /// 按章节树顺序获取查找范围内的章节
+    fn chapters_in_scope(db: &Database, novel_id: i64, scope_chapter_id: Option<i64>) -> Result<Vec<Chapter>> {
+        let manager = ChapterManager::new();
+        let tree = manager.build_chapter_tree(db.get_chapters_by_novel(novel_id)?);
+
+        match scope_chapter_id {
+            Some(root_id) if tree.nodes.contains_key(&root_id) => Ok(manager.collect_subtree(&tree, root_id)),
+            Some(root_id) => Err(anyhow::anyhow!("查找范围章节 {} 不存在", root_id)),
+            None => Ok(manager.flatten_tree(&tree)),
+        }
+    }
+}
+
+/// 命中两端的字母数字外侧是否没有紧接着其他字母数字，中日韩文字不受限制
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    let is_word = |c: char| (c.is_alphanumeric() || c == '_') && !is_cjk(c);
+    let matched = &text[start..end];
+    let open_start = !matched.chars().next().is_some_and(is_word)
+        || !text[..start].chars().next_back().is_some_and(is_word);
+    let open_end = !matched.chars().next_back().is_some_and(is_word)
+        || !text[end..].chars().next().is_some_and(is_word);
+    open_start && open_end
+}
+
+/// 取字符串末尾的若干字符
+fn tail_chars(text: &str, count: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    chars[chars.len().saturating_sub(count)..].iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(db: &Database) -> (i64, i64, i64, i64) {
+        let novel = db.create_novel("测试小说").unwrap();
+        let volume = db.create_chapter(novel.id, "第一卷", None).unwrap();
+        let first = db.create_chapter(novel.id, "第一章", Some(volume.id)).unwrap();
+        db.update_chapter_content(first.id, "李雷推开门，李雷愣住了。Li Lei said hello.").unwrap();
+        let other = db.create_chapter(novel.id, "番外", None).unwrap();
+        db.update_chapter_content(other.id, "李雷和韩梅梅。").unwrap();
+        (novel.id, volume.id, first.id, other.id)
+    }
+
+    fn options(pattern: &str, replacement: &str, mode: MatchMode) -> FindOptions {
+        FindOptions {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            mode,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_plain_and_scoped() {
+        let db = Database::open_in_memory().unwrap();
+        let (novel_id, volume_id, first_id, _) = setup(&db);
+
+        let all = FindReplaceManager::find_with(&db, novel_id, &options("李雷", "王强", MatchMode::Plain)).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[1].context_before, "李雷推开门，");
+
+        let mut scoped = options("李雷", "王强", MatchMode::Plain);
+        scoped.scope_chapter_id = Some(volume_id);
+        let in_volume = FindReplaceManager::find_with(&db, novel_id, &scoped).unwrap();
+        assert_eq!(in_volume.len(), 2);
+        assert!(in_volume.iter().all(|m| m.chapter_id == first_id));
+    }
+
+    #[test]
+    fn test_regex_and_whole_word_modes() {
+        let db = Database::open_in_memory().unwrap();
+        let (novel_id, _, _, _) = setup(&db);
+
+        let regex = FindReplaceManager::find_with(&db, novel_id, &options(r"(\w+) Lei", "$1 Mei", MatchMode::Regex)).unwrap();
+        assert_eq!(regex.len(), 1);
+        assert_eq!(regex[0].replacement, "Li Mei");
+
+        let whole = FindReplaceManager::find_with(&db, novel_id, &options("li", "x", MatchMode::WholeWord)).unwrap();
+        assert_eq!(whole.len(), 1);
+        assert_eq!(whole[0].matched, "Li");
+        assert!(FindReplaceManager::find_with(&db, novel_id, &options("Le", "x", MatchMode::WholeWord)).unwrap().is_empty());
+
+        // 中文人名紧接着其他汉字也算全词
+        let names = FindReplaceManager::find_with(&db, novel_id, &options("李雷", "王强", MatchMode::WholeWord)).unwrap();
+        assert_eq!(names.len(), 3);
+        assert!(names.iter().all(|m| m.matched == "李雷"));
+
+        let mut sensitive = options("li", "x", MatchMode::Plain);
+        sensitive.case_sensitive = true;
+        assert!(FindReplaceManager::find_with(&db, novel_id, &sensitive).unwrap().is_empty());
+
+        assert!(FindReplaceManager::find_with(&db, novel_id, &options("(", "", MatchMode::Regex)).is_err());
+    }
+
+    #[test]
+    fn test_replace_selected_and_revert() {
+        let db = Database::open_in_memory().unwrap();
+        let (novel_id, _, first_id, other_id) = setup(&db);
+        let opts = options("李雷", "王强", MatchMode::Plain);
+
+        let matches = FindReplaceManager::find_with(&db, novel_id, &opts).unwrap();
+        // 跳过第一章中的第二处
+        let selected: Vec<FindMatch> = matches.iter().enumerate()
+            .filter(|(i, _)| *i != 1)
+            .map(|(_, m)| m.clone())
+            .collect();
+
+        let outcome = FindReplaceManager::replace_with(&db, novel_id, &opts, &selected).unwrap();
+        assert_eq!(outcome.total_replacements(), 2);
+        assert_eq!(outcome.chapters.len(), 2);
+        assert_eq!(db.get_chapter(first_id).unwrap().content, "王强推开门，李雷愣住了。Li Lei said hello.");
+        assert_eq!(db.get_chapter(other_id).unwrap().content, "王强和韩梅梅。");
+        assert_eq!(db.get_chapter_versions(first_id).unwrap().len(), 1);
+
+        FindReplaceManager::revert_with(&db, &outcome).unwrap();
+        assert_eq!(db.get_chapter(first_id).unwrap().content, "李雷推开门，李雷愣住了。Li Lei said hello.");
+        assert_eq!(db.get_chapter(other_id).unwrap().content, "李雷和韩梅梅。");
+
+        // 撤销前的内容保存为新版本，撤销本身也可以回退
+        let versions = db.get_chapter_versions(first_id).unwrap();
+        assert_eq!(versions.len(), 2);
+        let latest = versions.iter().max_by_key(|v| v.id).unwrap();
+        assert_eq!(
+            VersionManager::reconstruct_content(&db, latest.id).unwrap(),
+            "王强推开门，李雷愣住了。Li Lei said hello."
+        );
+    }
+
+    #[test]
+    fn test_revert_refuses_when_chapter_edited_after_replace() {
+        let db = Database::open_in_memory().unwrap();
+        let (novel_id, _, first_id, other_id) = setup(&db);
+        let opts = options("李雷", "王强", MatchMode::Plain);
+        let matches = FindReplaceManager::find_with(&db, novel_id, &opts).unwrap();
+        let outcome = FindReplaceManager::replace_with(&db, novel_id, &opts, &matches).unwrap();
+
+        db.update_chapter_content(first_id, "替换后又写了新内容").unwrap();
+
+        assert!(FindReplaceManager::revert_with(&db, &outcome).is_err());
+        // 整个撤销回滚，不会丢失新写的内容，也不会只撤销一部分章节
+        assert_eq!(db.get_chapter(first_id).unwrap().content, "替换后又写了新内容");
+        assert_eq!(db.get_chapter(other_id).unwrap().content, "王强和韩梅梅。");
+        assert_eq!(db.get_chapter_versions(first_id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_replace_rejects_stale_preview_without_partial_changes() {
+        let db = Database::open_in_memory().unwrap();
+        let (novel_id, _, first_id, other_id) = setup(&db);
+        let opts = options("李雷", "王强", MatchMode::Plain);
+        let matches = FindReplaceManager::find_with(&db, novel_id, &opts).unwrap();
+
+        db.update_chapter_content(other_id, "内容已被修改").unwrap();
+
+        assert!(FindReplaceManager::replace_with(&db, novel_id, &opts, &matches).is_err());
+        assert!(db.get_chapter(first_id).unwrap().content.starts_with("李雷"));
+        assert!(db.get_chapter_versions(first_id).unwrap().is_empty());
+    }
+}
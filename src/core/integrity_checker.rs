@@ -0,0 +1,100 @@
+use crate::config::AppConfig;
+use crate::core::backup_manager::safety_backup_with;
+use crate::db::{self, Database, IntegrityReport};
+use anyhow::Result;
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+
+/// 一次完整性检查的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegrityCheck {
+    pub report: IntegrityReport,
+    /// 修复前保存的安全备份
+    pub backup: Option<PathBuf>,
+}
+
+/// 检查书库，需要修复时先把书库完整备份到 `backup_dir`
+pub(crate) fn check_with(db: &Database, repair: bool, backup_dir: &Path) -> Result<IntegrityCheck> {
+    let report = db.check_integrity(false)?;
+    if !repair || report.is_clean() || report.is_corrupted() {
+        return Ok(IntegrityCheck { report, backup: None });
+    }
+
+    let backup = safety_backup_with(db, backup_dir, "before-repair")?;
+    let report = db.check_integrity(true)?;
+    Ok(IntegrityCheck { report, backup: Some(backup) })
+}
+
+pub struct IntegrityChecker;
+
+impl IntegrityChecker {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 检查当前书库，`repair` 为真时修复可修复的问题
+    pub async fn check(&self, repair: bool) -> Result<IntegrityCheck> {
+        let backup_config = AppConfig::load().unwrap_or_default().backup;
+        let result = db::with_database(move |db| {
+            let backup_dir = backup_config.backup_dir(db.path());
+            check_with(db, repair, &backup_dir)
+        }).await?;
+
+        let report = &result.report;
+        if report.is_clean() {
+            info!("Integrity check passed");
+        } else {
+            for issue in &report.issues {
+                warn!("Integrity issue: {}", issue.description());
+            }
+            info!(
+                "Integrity check found {} issues ({} repairable, repaired: {})",
+                report.issues.len(), report.repairable_count(), report.repaired
+            );
+        }
+        Ok(result)
+    }
+
+    /// 按配置在启动时检查，关闭了启动检查时返回 `None`
+    pub async fn startup_check(&self) -> Result<Option<IntegrityCheck>> {
+        let config = AppConfig::load().unwrap_or_default().integrity;
+        if !config.check_on_startup {
+            return Ok(None);
+        }
+        self.check(config.auto_repair).await.map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair_takes_backup_first() {
+        let dir = std::env::temp_dir().join(format!("novel-writer-test-{}", uuid::Uuid::new_v4()));
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("字数").unwrap();
+        let chapter = db.create_chapter(novel.id, "第一章", None).unwrap();
+        db.update_chapter_content(chapter.id, "一二三").unwrap();
+
+        let clean = check_with(&db, true, &dir).unwrap();
+        assert!(clean.report.is_clean());
+        assert!(clean.backup.is_none());
+
+        db.delete_chapter(chapter.id).unwrap();
+        let mut stale = db.get_novel_by_id(novel.id).unwrap().unwrap();
+        stale.word_count = 3;
+        db.update_novel(&stale).unwrap();
+
+        let reported = check_with(&db, false, &dir).unwrap();
+        assert_eq!(reported.report.issues.len(), 1);
+        assert!(!reported.report.repaired);
+
+        let repaired = check_with(&db, true, &dir).unwrap();
+        assert!(repaired.report.repaired);
+        assert!(repaired.backup.as_ref().unwrap().exists());
+        assert_eq!(db.get_novel_by_id(novel.id).unwrap().unwrap().word_count, 0);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
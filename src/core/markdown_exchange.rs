@@ -0,0 +1,729 @@
+use crate::core::chapter_manager::{ChapterManager, ChapterTree};
+use crate::core::novel_manager::{NovelExportData, NovelManager};
+use crate::db::{self, Chapter, ChapterType, Database, NovelStatus};
+use crate::utils::export_utils::safe_file_name;
+use crate::utils::sort_key;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::info;
+use std::path::{Path, PathBuf};
+
+/// 项目文件夹中的文件格式
+///
+/// 两种格式的目录结构和 front matter 相同，Markdown 文件在正文前多一行 `# 标题`。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectFormat {
+    Markdown,
+    PlainText,
+}
+
+impl ProjectFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ProjectFormat::Markdown => "md",
+            ProjectFormat::PlainText => "txt",
+        }
+    }
+
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(ProjectFormat::Markdown),
+            "txt" => Some(ProjectFormat::PlainText),
+            _ => None,
+        }
+    }
+}
+
+/// 小说信息所在文件的文件名（不含扩展名）
+const NOVEL_FILE_STEM: &str = "novel";
+/// 目录自身（卷或含有子章节的章节）的正文文件
+const INDEX_FILE_STEM: &str = "_index";
+
+/// 文件开头的 YAML front matter
+///
+/// 只使用 `键: 值` 形式，字符串值按 JSON 规则加双引号，这也是合法的 YAML。
+#[derive(Debug, Default, Clone, PartialEq)]
+struct FrontMatter {
+    fields: Vec<(String, String)>,
+}
+
+impl FrontMatter {
+    fn push_raw(&mut self, key: &str, value: impl ToString) {
+        self.fields.push((key.to_string(), value.to_string()));
+    }
+
+    fn push_str(&mut self, key: &str, value: &str) {
+        let quoted = serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string());
+        self.push_raw(key, quoted);
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.fields.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn get_time(&self, key: &str) -> Option<DateTime<Utc>> {
+        self.get(key).and_then(|v| db::parse_timestamp(v).ok())
+    }
+
+    fn render(&self) -> String {
+        let mut text = String::from("---\n");
+        for (key, value) in &self.fields {
+            text.push_str(&format!("{}: {}\n", key, value));
+        }
+        text.push_str("---\n");
+        text
+    }
+
+    /// 拆分出 front matter 和正文；没有 front matter 时整个文件都是正文
+    fn parse(text: &str) -> (Self, &str) {
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+            return (Self::default(), text);
+        };
+
+        let mut front_matter = Self::default();
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            offset += line.len();
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line == "---" {
+                return (front_matter, &rest[offset..]);
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                front_matter.fields.push((key.trim().to_string(), Self::parse_value(value.trim())));
+            }
+        }
+        // 没有结束标记，不当作 front matter
+        (Self::default(), text)
+    }
+
+    fn parse_value(value: &str) -> String {
+        if value.starts_with('"') {
+            serde_json::from_str(value).unwrap_or_else(|_| value.trim_matches('"').to_string())
+        } else if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+            value[1..value.len() - 1].replace("''", "'")
+        } else {
+            value.to_string()
+        }
+    }
+}
+
+/// 从项目文件夹读出的小说信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectNovel {
+    pub title: String,
+    pub author: String,
+    pub description: String,
+    pub status: NovelStatus,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// 从项目文件夹读出的章节
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectChapter {
+    pub title: String,
+    pub chapter_type: ChapterType,
+    pub sort_path: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub is_archived: bool,
+    pub content: String,
+    /// 相对项目目录的路径，用于报告冲突
+    pub source: String,
+    pub children: Vec<ProjectChapter>,
+}
+
+/// 读取到内存中的项目文件夹
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    pub novel: ProjectNovel,
+    pub chapters: Vec<ProjectChapter>,
+}
+
+impl Project {
+    /// 章节总数（包括所有层级）
+    pub fn chapter_count(&self) -> usize {
+        fn count(chapters: &[ProjectChapter]) -> usize {
+            chapters.iter().map(|c| 1 + count(&c.children)).sum()
+        }
+        count(&self.chapters)
+    }
+}
+
+/// 导入到已有小说时遇到同名章节的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConflictPolicy {
+    /// 有冲突时不导入任何内容，只返回冲突列表
+    #[default]
+    Abort,
+    /// 同一位置的同名章节视为同一章节，保留已有内容，只导入其下新增的子章节
+    Merge,
+    /// 全部作为新章节导入
+    KeepBoth,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictKind {
+    /// 同一父节点下已有同名章节
+    ExistingChapter { chapter_id: i64 },
+    /// 同一父节点下已有相同的排序键，导入时会改用新的排序键
+    SortPathTaken { chapter_id: i64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportConflict {
+    pub source: String,
+    pub kind: ConflictKind,
+}
+
+impl ImportConflict {
+    pub fn description(&self) -> String {
+        match self.kind {
+            ConflictKind::ExistingChapter { .. } => format!("{}：已存在同名章节", self.source),
+            ConflictKind::SortPathTaken { .. } => format!("{}：排序键与已有章节相同", self.source),
+        }
+    }
+}
+
+/// 导入结果
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ImportReport {
+    /// 导入到的小说；因冲突中止时为 `None`
+    pub novel_id: Option<i64>,
+    pub created_chapters: usize,
+    pub merged_chapters: usize,
+    pub conflicts: Vec<ImportConflict>,
+}
+
+impl ImportReport {
+    pub fn is_aborted(&self) -> bool {
+        self.novel_id.is_none()
+    }
+}
+
+/// 把小说写成项目文件夹，返回创建的目录
+///
+/// 目录结构为 `<标题>/novel.md`，每个卷以及含有子章节的章节是一个目录（自身内容在 `_index.md`），
+/// 其余章节各是一个文件。文件名带三位序号以保持顺序，`data` 中的归档章节也会导出。
+pub(crate) fn write_project(data: &NovelExportData, format: ProjectFormat, dir: &Path) -> Result<PathBuf> {
+    let root = dir.join(safe_file_name(&data.novel.title));
+    if root.exists() && std::fs::read_dir(&root)?.next().is_some() {
+        return Err(anyhow::anyhow!("目录 {} 已存在且不为空", root.display()));
+    }
+    std::fs::create_dir_all(&root)?;
+
+    let novel = &data.novel;
+    let mut front_matter = FrontMatter::default();
+    front_matter.push_str("title", &novel.title);
+    front_matter.push_str("author", &novel.author);
+    front_matter.push_raw("status", novel.status.as_str());
+    front_matter.push_raw("created_at", novel.created_at.to_rfc3339());
+    front_matter.push_raw("updated_at", novel.updated_at.to_rfc3339());
+    front_matter.push_raw("exported_at", data.export_time.to_rfc3339());
+    std::fs::write(
+        root.join(format!("{}.{}", NOVEL_FILE_STEM, format.extension())),
+        render_file(&front_matter, &novel.title, &novel.description, format),
+    )?;
+
+    let tree = ChapterManager::new().build_chapter_tree(data.chapters.clone());
+    write_nodes(&tree, &tree.root_nodes, &root, format)?;
+    Ok(root)
+}
+
+fn write_nodes(tree: &ChapterTree, node_ids: &[i64], dir: &Path, format: ProjectFormat) -> Result<()> {
+    for (index, node_id) in node_ids.iter().enumerate() {
+        let Some(node) = tree.nodes.get(node_id) else { continue };
+        let chapter = &node.chapter;
+        let name = format!("{:03}-{}", index + 1, safe_file_name(&chapter.title));
+        let text = render_file(&chapter_front_matter(chapter), &chapter.title, &chapter.content, format);
+
+        if node.children.is_empty() && chapter.chapter_type != ChapterType::Volume {
+            std::fs::write(dir.join(format!("{}.{}", name, format.extension())), text)?;
+        } else {
+            let child_dir = dir.join(name);
+            std::fs::create_dir_all(&child_dir)?;
+            std::fs::write(child_dir.join(format!("{}.{}", INDEX_FILE_STEM, format.extension())), text)?;
+            write_nodes(tree, &node.children, &child_dir, format)?;
+        }
+    }
+    Ok(())
+}
+
+fn chapter_front_matter(chapter: &Chapter) -> FrontMatter {
+    let mut front_matter = FrontMatter::default();
+    front_matter.push_str("title", &chapter.title);
+    front_matter.push_raw("chapter_type", chapter.chapter_type.as_str());
+    front_matter.push_str("sort_path", &chapter.sort_path);
+    front_matter.push_raw("created_at", chapter.created_at.to_rfc3339());
+    front_matter.push_raw("updated_at", chapter.updated_at.to_rfc3339());
+    front_matter.push_raw("archived", chapter.is_archived);
+    front_matter
+}
+
+/// front matter 后空一行再写正文，正文原样写出
+fn render_file(front_matter: &FrontMatter, title: &str, content: &str, format: ProjectFormat) -> String {
+    let mut text = front_matter.render();
+    text.push('\n');
+    if format == ProjectFormat::Markdown {
+        text.push_str(&format!("# {}\n\n", title.replace(['\r', '\n'], " ")));
+    }
+    text.push_str(content);
+    text
+}
+
+/// 解析单个文件，返回 front matter、Markdown 标题行和正文
+fn parse_file(text: &str, format: ProjectFormat) -> (FrontMatter, Option<String>, String) {
+    let (front_matter, body) = FrontMatter::parse(text);
+    let mut body = body;
+    if !front_matter.fields.is_empty() {
+        body = body.strip_prefix('\n').unwrap_or(body);
+    }
+
+    let mut heading = None;
+    if format == ProjectFormat::Markdown {
+        if let Some(rest) = body.strip_prefix("# ") {
+            let (line, rest) = rest.split_once('\n').unwrap_or((rest, ""));
+            heading = Some(line.trim().to_string());
+            body = rest.strip_prefix('\n').unwrap_or(rest);
+        }
+    }
+    (front_matter, heading, body.to_string())
+}
+
+/// 文件名去掉 `001-` 这样的序号前缀
+fn title_from_name(name: &str) -> String {
+    match name.split_once('-') {
+        Some((prefix, rest)) if !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_digit()) => rest.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// 读取项目文件夹
+pub fn read_project(root: &Path) -> Result<Project> {
+    if !root.is_dir() {
+        return Err(anyhow::anyhow!("{} 不是文件夹", root.display()));
+    }
+    let dir_name = root.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let novel = match find_stem_file(root, NOVEL_FILE_STEM)? {
+        Some((path, format)) => {
+            let (front_matter, heading, description) = parse_file(&std::fs::read_to_string(&path)?, format);
+            ProjectNovel {
+                title: front_matter.get("title").map(str::to_string).or(heading).unwrap_or(dir_name),
+                author: front_matter.get("author").unwrap_or_default().to_string(),
+                description,
+                status: NovelStatus::from_str(front_matter.get("status").unwrap_or_default()),
+                created_at: front_matter.get_time("created_at"),
+                updated_at: front_matter.get_time("updated_at"),
+            }
+        },
+        None => ProjectNovel {
+            title: dir_name,
+            author: String::new(),
+            description: String::new(),
+            status: NovelStatus::Draft,
+            created_at: None,
+            updated_at: None,
+        },
+    };
+
+    let chapters = read_chapters(root, "", true)?;
+    Ok(Project { novel, chapters })
+}
+
+fn find_stem_file(dir: &Path, stem: &str) -> Result<Option<(PathBuf, ProjectFormat)>> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.file_stem().and_then(|s| s.to_str()) == Some(stem) {
+            if let Some(format) = ProjectFormat::from_path(&path) {
+                return Ok(Some((path, format)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn read_chapters(dir: &Path, relative: &str, top_level: bool) -> Result<Vec<ProjectChapter>> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+
+    let mut chapters = Vec::new();
+    for path in entries {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if name.starts_with('.') {
+            continue;
+        }
+        let source = if relative.is_empty() { name.clone() } else { format!("{}/{}", relative, name) };
+
+        if path.is_dir() {
+            let default_type = if top_level { ChapterType::Volume } else { ChapterType::Chapter };
+            let mut chapter = match find_stem_file(&path, INDEX_FILE_STEM)? {
+                Some((index_path, format)) => read_chapter(&index_path, format, &name, default_type, source.clone())?,
+                None => ProjectChapter {
+                    title: title_from_name(&name),
+                    chapter_type: default_type,
+                    sort_path: None,
+                    created_at: None,
+                    updated_at: None,
+                    is_archived: false,
+                    content: String::new(),
+                    source: source.clone(),
+                    children: Vec::new(),
+                },
+            };
+            chapter.children = read_chapters(&path, &source, false)?;
+            chapters.push(chapter);
+        } else if let Some(format) = ProjectFormat::from_path(&path) {
+            let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            if stem == INDEX_FILE_STEM || (top_level && stem == NOVEL_FILE_STEM) {
+                continue;
+            }
+            chapters.push(read_chapter(&path, format, &stem, ChapterType::Chapter, source)?);
+        }
+    }
+    Ok(chapters)
+}
+
+fn read_chapter(path: &Path, format: ProjectFormat, name: &str, default_type: ChapterType, source: String) -> Result<ProjectChapter> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("读取 {} 失败: {}", source, e))?;
+    let (front_matter, heading, content) = parse_file(&text, format);
+    Ok(ProjectChapter {
+        title: front_matter.get("title").map(str::to_string)
+            .or(heading)
+            .unwrap_or_else(|| title_from_name(name)),
+        chapter_type: front_matter.get("chapter_type").map(ChapterType::from_str).unwrap_or(default_type),
+        sort_path: front_matter.get("sort_path").map(str::to_string),
+        created_at: front_matter.get_time("created_at"),
+        updated_at: front_matter.get_time("updated_at"),
+        is_archived: front_matter.get("archived") == Some("true"),
+        content,
+        source,
+        children: Vec::new(),
+    })
+}
+
+/// 检查导入到已有小说时的冲突，不修改数据库
+///
+/// 与已有章节同名的章节按合并处理继续检查其子章节。
+pub(crate) fn find_conflicts(existing: &[Chapter], chapters: &[ProjectChapter]) -> Vec<ImportConflict> {
+    fn walk(existing: &[Chapter], parent_id: Option<i64>, chapters: &[ProjectChapter], conflicts: &mut Vec<ImportConflict>) {
+        let siblings: Vec<&Chapter> = existing.iter().filter(|c| c.parent_id == parent_id).collect();
+        for chapter in chapters {
+            if let Some(same) = siblings.iter().find(|c| c.title == chapter.title) {
+                conflicts.push(ImportConflict {
+                    source: chapter.source.clone(),
+                    kind: ConflictKind::ExistingChapter { chapter_id: same.id },
+                });
+                walk(existing, Some(same.id), &chapter.children, conflicts);
+            } else if let Some(taken) = siblings.iter().find(|c| Some(&c.sort_path) == chapter.sort_path.as_ref()) {
+                conflicts.push(ImportConflict {
+                    source: chapter.source.clone(),
+                    kind: ConflictKind::SortPathTaken { chapter_id: taken.id },
+                });
+            }
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    walk(existing, None, chapters, &mut conflicts);
+    conflicts
+}
+
+/// 把项目导入数据库
+///
+/// `target` 为 `None` 时新建小说；否则导入到已有小说的顶层，冲突按 `policy` 处理。
+/// 整个导入在一个事务中完成。
+pub(crate) fn import_project_with(db: &Database, project: &Project, target: Option<i64>, policy: ConflictPolicy) -> Result<ImportReport> {
+    db.transaction(|tx| {
+        let (novel_id, existing) = match target {
+            Some(novel_id) => {
+                tx.get_novel_by_id(novel_id)?.ok_or_else(|| anyhow::anyhow!("小说不存在"))?;
+                let existing = tx.get_all_chapters_by_novel(novel_id)?;
+                if policy == ConflictPolicy::Abort {
+                    let conflicts = find_conflicts(&existing, &project.chapters);
+                    if !conflicts.is_empty() {
+                        return Ok(ImportReport { conflicts, ..Default::default() });
+                    }
+                }
+                (novel_id, existing)
+            },
+            None => {
+                let mut novel = tx.create_novel(&project.novel.title)?;
+                novel.author = project.novel.author.clone();
+                novel.description = project.novel.description.clone();
+                novel.status = project.novel.status.clone();
+                tx.update_novel(&novel)?;
+                (novel.id, Vec::new())
+            },
+        };
+
+        let mut report = ImportReport { novel_id: Some(novel_id), ..Default::default() };
+        import_chapters(tx, novel_id, None, &project.chapters, &existing, policy, &mut report)?;
+
+        // 章节的改动会经由触发器更新小说，因此小说的时间戳最后设置
+        if target.is_none() {
+            let created_at = project.novel.created_at.unwrap_or_else(Utc::now);
+            let updated_at = project.novel.updated_at.unwrap_or(created_at);
+            tx.set_novel_timestamps(novel_id, created_at, updated_at)?;
+        }
+        Ok(report)
+    })
+}
+
+/// 把 `chapters` 导入到 `parent` 下
+///
+/// 已归档章节下的子章节一并归档，并在子章节之后归档，保证不会有未归档的章节挂在已归档的章节下。
+fn import_chapters(
+    db: &Database,
+    novel_id: i64,
+    parent: Option<&Chapter>,
+    chapters: &[ProjectChapter],
+    existing: &[Chapter],
+    policy: ConflictPolicy,
+    report: &mut ImportReport,
+) -> Result<()> {
+    let parent_id = parent.map(|p| p.id);
+    let parent_archived = parent.is_some_and(|p| p.is_archived);
+    let siblings: Vec<&Chapter> = existing.iter().filter(|c| c.parent_id == parent_id).collect();
+    // 文件顺序与排序键一致时保留原排序键，否则（例如手动调整过文件名）按文件顺序重新生成
+    let keep_sort_paths = chapters.iter().all(|c| c.sort_path.is_some())
+        && chapters.windows(2).all(|w| w[0].sort_path <= w[1].sort_path);
+    let mut previous: Option<String> = None;
+
+    for chapter in chapters {
+        if let Some(same) = siblings.iter().find(|c| c.title == chapter.title) {
+            report.conflicts.push(ImportConflict {
+                source: chapter.source.clone(),
+                kind: ConflictKind::ExistingChapter { chapter_id: same.id },
+            });
+            if policy == ConflictPolicy::Merge {
+                report.merged_chapters += 1;
+                import_chapters(db, novel_id, Some(*same), &chapter.children, existing, policy, report)?;
+                continue;
+            }
+        }
+
+        let created = db.create_chapter(novel_id, &chapter.title, parent_id)?;
+        let mut sort_path = match &chapter.sort_path {
+            Some(sort_path) if keep_sort_paths => sort_path.clone(),
+            _ => next_sort_path(&created.sort_path, previous.as_deref()),
+        };
+        if let Some(taken) = siblings.iter().find(|c| c.sort_path == sort_path) {
+            report.conflicts.push(ImportConflict {
+                source: chapter.source.clone(),
+                kind: ConflictKind::SortPathTaken { chapter_id: taken.id },
+            });
+            while siblings.iter().any(|c| c.sort_path == sort_path) {
+                sort_path = next_sort_path(&created.sort_path, Some(&sort_path));
+            }
+        }
+        db.update_chapter_parent(created.id, parent_id, &sort_path)?;
+        db.update_chapter(&Chapter {
+            content: chapter.content.clone(),
+            word_count: chapter.content.chars().filter(|c| !c.is_whitespace()).count() as i32,
+            chapter_type: chapter.chapter_type.clone(),
+            ..created.clone()
+        })?;
+        report.created_chapters += 1;
+
+        let archived = chapter.is_archived || parent_archived;
+        let created = Chapter { is_archived: archived, ..created };
+        import_chapters(db, novel_id, Some(&created), &chapter.children, &[], policy, report)?;
+        if archived {
+            db.archive_chapter(created.id)?;
+        }
+
+        let created_at = chapter.created_at.unwrap_or(created.created_at);
+        db.set_chapter_timestamps(created.id, created_at, chapter.updated_at.unwrap_or_else(Utc::now))?;
+        previous = Some(sort_path);
+    }
+    Ok(())
+}
+
+/// 生成排在 `previous` 之后的排序键；`assigned` 是新建章节时分配的排序键
+pub(crate) fn next_sort_path(assigned: &str, previous: Option<&str>) -> String {
+    match previous {
+        Some(previous) if assigned <= previous => sort_key::key_after(Some(previous)),
+        _ => assigned.to_string(),
+    }
+}
+
+pub struct MarkdownExchange;
+
+impl MarkdownExchange {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 把小说导出为 `dir` 下的项目文件夹，返回创建的目录
+    ///
+    /// 不包含归档章节时，归档章节及其子孙都不会写出。
+    pub async fn export(&self, novel_id: i64, format: ProjectFormat, include_archived: bool, dir: &Path) -> Result<PathBuf> {
+        let data = NovelManager::new().export_novel_data(novel_id).await?;
+        let root = self.export_data(data, format, include_archived, dir)?;
+        info!("Exported project folder: novel={}, path={}", novel_id, root.display());
+        Ok(root)
+    }
+
+    /// 把已准备好的数据（例如按快照重建的全书）导出为项目文件夹
+    pub fn export_data(&self, mut data: NovelExportData, format: ProjectFormat, include_archived: bool, dir: &Path) -> Result<PathBuf> {
+        if !include_archived {
+            data.chapters.retain(|c| !c.is_archived);
+        }
+        write_project(&data, format, dir)
+    }
+
+    /// 预先检查导入到已有小说时的冲突
+    pub async fn check_conflicts(&self, project: Project, novel_id: i64) -> Result<Vec<ImportConflict>> {
+        db::with_database(move |db| {
+            let existing = db.get_all_chapters_by_novel(novel_id)?;
+            Ok(find_conflicts(&existing, &project.chapters))
+        }).await
+    }
+
+    /// 从项目文件夹导入
+    pub async fn import(&self, project: Project, target: Option<i64>, policy: ConflictPolicy) -> Result<ImportReport> {
+        let report = db::with_database(move |db| import_project_with(db, &project, target, policy)).await?;
+        info!(
+            "Imported project folder: novel={:?}, created={}, merged={}, conflicts={}",
+            report.novel_id, report.created_chapters, report.merged_chapters, report.conflicts.len()
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("novel-writer-test-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn sample_novel(db: &Database) -> i64 {
+        let mut novel = db.create_novel("往返测试").unwrap();
+        novel.author = "作者".to_string();
+        novel.description = "简介第一行\n简介第二行".to_string();
+        novel.status = NovelStatus::Writing;
+        db.update_novel(&novel).unwrap();
+
+        let volume = db.create_chapter(novel.id, "第一卷", None).unwrap();
+        db.update_chapter(&Chapter { chapter_type: ChapterType::Volume, ..volume.clone() }).unwrap();
+        let chapter = db.create_chapter(novel.id, "第一章: \"开端\"", Some(volume.id)).unwrap();
+        db.update_chapter_content(chapter.id, "# 不是标题\n\n　　正文第一段。\n---\n末尾没有换行").unwrap();
+        let scene = db.create_chapter(novel.id, "场景", Some(chapter.id)).unwrap();
+        db.update_chapter(&Chapter { chapter_type: ChapterType::Scene, content: "\n场景开头是空行".to_string(), word_count: 7, ..scene }).unwrap();
+        let archived = db.create_chapter(novel.id, "废稿", Some(volume.id)).unwrap();
+        db.update_chapter_content(archived.id, "不要了").unwrap();
+        db.archive_chapter(archived.id).unwrap();
+        db.create_chapter(novel.id, "尾声", None).unwrap();
+        novel.id
+    }
+
+    /// 按树顺序列出章节的可比较字段，父节点用标题表示
+    fn snapshot(db: &Database, novel_id: i64) -> Vec<(Option<String>, String, String, ChapterType, String, bool, DateTime<Utc>, DateTime<Utc>)> {
+        let chapters = db.get_all_chapters_by_novel(novel_id).unwrap();
+        let data = NovelExportData { novel: db.get_novel_by_id(novel_id).unwrap().unwrap(), chapters: chapters.clone(), export_time: Utc::now() };
+        data.ordered_nodes(&[], true).into_iter()
+            .map(|node| {
+                let c = node.chapter;
+                let parent = c.parent_id.and_then(|id| chapters.iter().find(|p| p.id == id)).map(|p| p.title.clone());
+                (parent, c.title, c.content, c.chapter_type, c.sort_path, c.is_archived, c.created_at, c.updated_at)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_is_lossless() {
+        for format in [ProjectFormat::Markdown, ProjectFormat::PlainText] {
+            let db = Database::open_in_memory().unwrap();
+            let novel_id = sample_novel(&db);
+            let dir = temp_dir();
+
+            let data = NovelManager::export_novel_data_with(&db, novel_id).unwrap();
+            let root = write_project(&data, format, &dir).unwrap();
+            assert!(root.join(format!("001-第一卷/_index.{}", format.extension())).is_file());
+            assert!(root.join(format!("001-第一卷/001-第一章_ _开端_/001-场景.{}", format.extension())).is_file());
+
+            let project = read_project(&root).unwrap();
+            assert_eq!(project.chapter_count(), 5);
+            let report = import_project_with(&db, &project, None, ConflictPolicy::Abort).unwrap();
+            assert_eq!(report.created_chapters, 5);
+            assert!(report.conflicts.is_empty());
+
+            let imported_id = report.novel_id.unwrap();
+            assert_eq!(snapshot(&db, imported_id), snapshot(&db, novel_id));
+
+            let original = db.get_novel_by_id(novel_id).unwrap().unwrap();
+            let imported = db.get_novel_by_id(imported_id).unwrap().unwrap();
+            assert_eq!(
+                (&imported.title, &imported.author, &imported.description, &imported.status, imported.word_count, imported.created_at, imported.updated_at),
+                (&original.title, &original.author, &original.description, &original.status, original.word_count, original.created_at, original.updated_at),
+            );
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_import_into_existing_novel_reports_conflicts() {
+        let db = Database::open_in_memory().unwrap();
+        let novel_id = sample_novel(&db);
+        let dir = temp_dir();
+        let data = NovelManager::export_novel_data_with(&db, novel_id).unwrap();
+        let root = write_project(&data, ProjectFormat::Markdown, &dir).unwrap();
+        // 在导出的第一卷中新增一章
+        std::fs::write(root.join("001-第一卷/009-新章.md"), "# 新章\n\n新内容").unwrap();
+        let project = read_project(&root).unwrap();
+        let before = db.get_all_chapters_by_novel(novel_id).unwrap().len();
+
+        let report = import_project_with(&db, &project, Some(novel_id), ConflictPolicy::Abort).unwrap();
+        assert!(report.is_aborted());
+        assert_eq!(report.conflicts.len(), 5);
+        assert_eq!(db.get_all_chapters_by_novel(novel_id).unwrap().len(), before);
+
+        let report = import_project_with(&db, &project, Some(novel_id), ConflictPolicy::Merge).unwrap();
+        assert_eq!((report.created_chapters, report.merged_chapters), (1, 5));
+        let chapters = db.get_all_chapters_by_novel(novel_id).unwrap();
+        assert_eq!(chapters.len(), before + 1);
+        let added = chapters.iter().find(|c| c.title == "新章").unwrap();
+        let volume = chapters.iter().find(|c| c.title == "第一卷").unwrap();
+        assert_eq!((added.parent_id, added.content.as_str()), (Some(volume.id), "新内容"));
+
+        let report = import_project_with(&db, &project, Some(novel_id), ConflictPolicy::KeepBoth).unwrap();
+        assert_eq!(report.created_chapters, 6);
+        assert_eq!(db.get_all_chapters_by_novel(novel_id).unwrap().len(), before + 7);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_archives_children_of_archived_chapter() {
+        let db = Database::open_in_memory().unwrap();
+        let novel_id = sample_novel(&db);
+        let dir = temp_dir();
+        let data = NovelManager::export_novel_data_with(&db, novel_id).unwrap();
+        let root = write_project(&data, ProjectFormat::Markdown, &dir).unwrap();
+        let mut project = read_project(&root).unwrap();
+        // 手动把第一卷标记为归档，其下的章节仍未归档
+        assert_eq!(project.chapters[0].title, "第一卷");
+        project.chapters[0].is_archived = true;
+
+        let report = import_project_with(&db, &project, None, ConflictPolicy::Abort).unwrap();
+        let imported = snapshot(&db, report.novel_id.unwrap());
+        let original = snapshot(&db, novel_id);
+        for (imported, original) in imported.iter().zip(&original) {
+            let in_volume = imported.1 != "尾声";
+            assert_eq!(imported.5, in_volume, "{}", imported.1);
+            // 归档不会改动导入的时间戳
+            assert_eq!((imported.6, imported.7), (original.6, original.7));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
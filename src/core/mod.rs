@@ -5,6 +5,21 @@ pub mod version_manager;
 pub mod formatter;
 pub mod stats_manager;
 pub mod inspiration_manager;
+pub mod search_manager;
+pub mod find_replace_manager;
+pub mod writing_session;
+pub mod edit_history;
+pub mod recovery_journal;
+pub mod auto_save;
+pub mod epub_exporter;
+pub mod docx_exporter;
+pub mod markdown_exchange;
+pub mod txt_importer;
+pub mod project_bundle;
+pub mod backup_manager;
+pub mod integrity_checker;
+pub mod trash_manager;
+pub mod snapshot_manager;
 
 pub use app_state::AppState;
 pub use chapter_manager::{ChapterManager, ChapterNode};
@@ -12,4 +27,15 @@ pub use novel_manager::*;
 pub use version_manager::*;
 pub use formatter::*;
 pub use stats_manager::*;
-pub use inspiration_manager::*;
\ No newline at end of file
+pub use inspiration_manager::*;
+pub use search_manager::*;
+pub use find_replace_manager::*;
+pub use writing_session::WritingSession;
+pub use edit_history::{EditHistory, EditHistoryStore, EditKind};
+pub use recovery_journal::{RecoveryEntry, RecoveryJournal};
+pub use auto_save::{AutoSaveManager, AutoSaveTimer};
+pub use epub_exporter::{EpubExporter, EpubOptions};
+pub use docx_exporter::{DocxExporter, DocxOptions};
+pub use markdown_exchange::{MarkdownExchange, ProjectFormat, ConflictPolicy, ImportReport};
+pub use txt_importer::{SplitRules, TxtImporter};
+pub use project_bundle::{BundleManager, BundleManifest};
\ No newline at end of file
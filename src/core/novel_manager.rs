@@ -1,4 +1,5 @@
-use crate::db::{Novel, Chapter, get_database};
+use crate::db::{self, Novel, Chapter, Database};
+use crate::core::chapter_manager::{ChapterManager, ChapterNode, ChapterTree};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use log::{info, warn, error};
@@ -13,8 +14,8 @@ impl NovelManager {
     /// 创建新小说
     pub async fn create_novel(&self, title: &str, author: Option<&str>) -> Result<Novel> {
         info!("Creating new novel: title='{}', author={:?}", title, author);
-        let db = get_database()?;
-        let mut novel = db.create_novel(title)?;
+        let owned_title = title.to_string();
+        let mut novel = db::with_database(move |db| db.create_novel(&owned_title)).await?;
         info!("Novel created with ID: {}", novel.id);
         
         if let Some(author_name) = author {
@@ -31,8 +32,7 @@ impl NovelManager {
     /// 获取所有小说
     pub async fn get_all_novels(&self) -> Result<Vec<Novel>> {
         info!("Retrieving all novels from database");
-        let db = get_database()?;
-        let novels = db.get_all_novels()?;
+        let novels = db::with_database(|db| db.get_all_novels()).await?;
         info!("Retrieved {} novels successfully", novels.len());
         Ok(novels)
     }
@@ -55,7 +55,6 @@ impl NovelManager {
     /// 更新小说标题
     pub async fn update_novel_title(&self, novel_id: i64, new_title: &str) -> Result<()> {
         info!("Updating novel title: ID={}, new_title='{}'", novel_id, new_title);
-        let db = get_database()?;
         
         // 由于数据库层没有直接更新标题的方法，我们需要先获取小说，然后更新
         if let Some(mut novel) = self.get_novel_by_id(novel_id).await? {
@@ -64,7 +63,7 @@ impl NovelManager {
             novel.updated_at = Utc::now();
             
             // 更新数据库
-            db.update_novel(&novel)?;
+            db::with_database(move |db| db.update_novel(&novel)).await?;
             info!("Updated novel title: ID={}, old='{}', new='{}'", novel_id, old_title, new_title);
         } else {
             warn!("Failed to update novel title: Novel not found with ID={}", novel_id);
@@ -75,13 +74,11 @@ impl NovelManager {
     
     /// 更新小说作者
     pub async fn update_novel_author(&self, novel_id: i64, author: &str) -> Result<()> {
-        let db = get_database()?;
-        
         if let Some(mut novel) = self.get_novel_by_id(novel_id).await? {
             novel.author = author.to_string();
             novel.updated_at = Utc::now();
             
-            db.update_novel(&novel)?;
+            db::with_database(move |db| db.update_novel(&novel)).await?;
         }
         
         Ok(())
@@ -89,13 +86,11 @@ impl NovelManager {
     
     /// 更新小说描述
     pub async fn update_novel_description(&self, novel_id: i64, description: &str) -> Result<()> {
-        let db = get_database()?;
-        
         if let Some(mut novel) = self.get_novel_by_id(novel_id).await? {
             novel.description = description.to_string();
             novel.updated_at = Utc::now();
             
-            db.update_novel(&novel)?;
+            db::with_database(move |db| db.update_novel(&novel)).await?;
         }
         
         Ok(())
@@ -103,13 +98,11 @@ impl NovelManager {
     
     /// 更新小说状态
     pub async fn update_novel_status(&self, novel_id: i64, status: crate::db::NovelStatus) -> Result<()> {
-        let db = get_database()?;
-        
         if let Some(mut novel) = self.get_novel_by_id(novel_id).await? {
             novel.status = status;
             novel.updated_at = Utc::now();
             
-            db.update_novel(&novel)?;
+            db::with_database(move |db| db.update_novel(&novel)).await?;
         }
         
         Ok(())
@@ -118,7 +111,6 @@ impl NovelManager {
     /// 切换小说置顶状态
     pub async fn toggle_novel_pin(&self, novel_id: i64) -> Result<bool> {
         info!("Toggle novel pin status: ID={}", novel_id);
-        let db = get_database()?;
         
         // 获取要操作的小说
         if let Some(mut novel) = self.get_novel_by_id(novel_id).await? {
@@ -146,17 +138,18 @@ impl NovelManager {
             }
             
             novel.updated_at = Utc::now();
+            let is_pinned = novel.is_pinned;
             
             // 更新数据库
-            db.update_novel(&novel)?;
+            db::with_database(move |db| db.update_novel(&novel)).await?;
             
             // 如果取消置顶，重新计算其他置顶小说的排序号
             if current_pinned {
                 self.reorder_pinned_novels().await?;
             }
             
-            info!("Successfully toggled novel pin status: ID={}, new_status={}", novel_id, novel.is_pinned);
-            Ok(novel.is_pinned)
+            info!("Successfully toggled novel pin status: ID={}, new_status={}", novel_id, is_pinned);
+            Ok(is_pinned)
         } else {
             warn!("Failed to toggle novel pin status: Novel not found with ID={}", novel_id);
             Err(anyhow::anyhow!("小说不存在"))
@@ -166,12 +159,11 @@ impl NovelManager {
     /// 重新排序置顶小说
     async fn reorder_pinned_novels(&self) -> Result<()> {
         info!("Reordering pinned novels");
-        let db = get_database()?;
         
         // 获取所有置顶的小说
-        let mut all_novels = self.get_all_novels().await?;
+        let all_novels = self.get_all_novels().await?;
         let mut pinned_novels: Vec<_> = all_novels
-            .iter_mut()
+            .into_iter()
             .filter(|n| n.is_pinned)
             .collect();
         
@@ -179,27 +171,25 @@ impl NovelManager {
         pinned_novels.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
         
         // 更新排序号
-        for (index, novel) in pinned_novels.iter_mut().enumerate() {
-            novel.pinned_order = Some((index + 1) as i32);
-            db.update_novel(novel)?;
-            info!("Updated pinned order for novel {}: {}", novel.id, index + 1);
-        }
-        
-        Ok(())
+        db::with_database(move |db| {
+            for (index, novel) in pinned_novels.iter_mut().enumerate() {
+                novel.pinned_order = Some((index + 1) as i32);
+                db.update_novel(novel)?;
+                info!("Updated pinned order for novel {}: {}", novel.id, index + 1);
+            }
+            Ok(())
+        }).await
     }
     
-    /// 删除小说
+    /// 删除小说，小说连同章节和灵感移入回收站
     pub async fn delete_novel(&self, novel_id: i64) -> Result<()> {
-        info!("Deleting novel: ID={}", novel_id);
+        info!("Moving novel to trash: ID={}", novel_id);
         
         // 先检查小说是否存在
         if let Some(novel) = self.get_novel_by_id(novel_id).await? {
             info!("Found novel for deletion: ID={}, title='{}'", novel.id, novel.title);
-            let db = get_database()?;
-            
-            // 由于外键约束，删除小说会自动删除相关的章节、节点等
-            db.delete_novel(novel_id)?;
-            info!("Successfully deleted novel and all associated data: ID={}, title='{}'", novel_id, novel.title);
+            db::with_database(move |db| db.trash_novel(novel_id)).await?;
+            info!("Moved novel to trash: ID={}, title='{}'", novel_id, novel.title);
         } else {
             warn!("Failed to delete novel: Novel not found with ID={}", novel_id);
         }
@@ -209,8 +199,7 @@ impl NovelManager {
     
     /// 获取小说的章节统计
     pub async fn get_novel_statistics(&self, novel_id: i64) -> Result<NovelStatistics> {
-        let db = get_database()?;
-        let chapters = db.get_chapters_by_novel(novel_id)?;
+        let chapters = db::with_database(move |db| db.get_chapters_by_novel(novel_id)).await?;
         
         let total_chapters = chapters.len();
         let total_words: i32 = chapters.iter().map(|c| c.word_count).sum();
@@ -270,9 +259,12 @@ impl NovelManager {
     /// 导出小说数据
     pub async fn export_novel_data(&self, novel_id: i64) -> Result<NovelExportData> {
         info!("Exporting novel data: ID={}", novel_id);
-        let db = get_database()?;
-        
-        let novel = match self.get_novel_by_id(novel_id).await? {
+        db::with_database(move |db| Self::export_novel_data_with(db, novel_id)).await
+    }
+    
+    /// 在给定数据库上收集导出数据
+    pub(crate) fn export_novel_data_with(db: &Database, novel_id: i64) -> Result<NovelExportData> {
+        let novel = match db.get_all_novels()?.into_iter().find(|n| n.id == novel_id) {
             Some(n) => {
                 info!("Found novel for export: ID={}, title='{}'", novel_id, n.title);
                 n
@@ -284,7 +276,7 @@ impl NovelManager {
         };
         
         info!("Retrieving chapters for novel: ID={}", novel_id);
-        let chapters = db.get_chapters_by_novel(novel_id)?;
+        let chapters = db.get_all_chapters_by_novel(novel_id)?;
         info!("Retrieved {} chapters for export", chapters.len());
         
         let export_data = NovelExportData {
@@ -322,4 +314,43 @@ pub struct NovelExportData {
     pub novel: Novel,
     pub chapters: Vec<Chapter>,
     pub export_time: DateTime<Utc>,
-}
\ No newline at end of file
+}
+
+impl NovelExportData {
+    /// 按章节树顺序（同级按 `sort_path`）排列的章节节点
+    ///
+    /// `root_ids` 非空时只保留这些节点（通常是卷）及其子孙，深度相对所选节点重新计算。
+    /// 不包含归档章节时，归档章节及其子孙都会被排除。
+    pub fn ordered_nodes(&self, root_ids: &[i64], include_archived: bool) -> Vec<ChapterNode> {
+        let chapters = self.chapters.iter()
+            .filter(|c| include_archived || !c.is_archived)
+            .cloned()
+            .collect();
+        let tree = ChapterManager::new().build_chapter_tree(chapters);
+        let mut result = Vec::new();
+        for &root_id in &tree.root_nodes {
+            Self::walk(&tree, root_id, root_ids, None, &mut result);
+        }
+        result
+    }
+    
+    fn walk(tree: &ChapterTree, node_id: i64, root_ids: &[i64], base_depth: Option<usize>, result: &mut Vec<ChapterNode>) {
+        let Some(node) = tree.nodes.get(&node_id) else { return };
+        let base_depth = base_depth.or_else(|| {
+            (root_ids.is_empty() || root_ids.contains(&node_id)).then_some(node.depth)
+        });
+        if let Some(base) = base_depth {
+            result.push(ChapterNode { depth: node.depth - base, ..node.clone() });
+        }
+        for &child_id in &node.children {
+            Self::walk(tree, child_id, root_ids, base_depth, result);
+        }
+    }
+    
+    /// 全书或所选卷的总字数
+    pub fn word_count(&self, root_ids: &[i64], include_archived: bool) -> usize {
+        self.ordered_nodes(root_ids, include_archived).iter()
+            .map(|node| node.chapter.content.chars().filter(|c| !c.is_whitespace()).count())
+            .sum()
+    }
+}
@@ -0,0 +1,467 @@
+use crate::core::inspiration_manager::Inspiration;
+use crate::db::{self, Chapter, ChapterVersion, Database, Novel, VersionBranch, VersionTag, WritingStatsRecord};
+use crate::utils::export_utils::read_zip_entry;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 项目包文件扩展名
+pub const BUNDLE_EXTENSION: &str = "nwbundle";
+
+const BUNDLE_FORMAT: &str = "novel-writer-bundle";
+/// 当前写出的项目包格式版本，读取时不接受更高的版本
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const NOVEL_FILE: &str = "novel.json";
+const CHAPTERS_FILE: &str = "chapters.json";
+const VERSIONS_FILE: &str = "chapter_versions.json";
+const BRANCHES_FILE: &str = "version_branches.json";
+const TAGS_FILE: &str = "version_tags.json";
+const INSPIRATIONS_FILE: &str = "inspirations.json";
+const STATS_FILE: &str = "writing_stats.json";
+
+/// 项目包中的一个数据文件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundleFile {
+    pub name: String,
+    pub size: u64,
+    /// 文件内容的 SHA-256（十六进制）
+    pub sha256: String,
+}
+
+/// 项目包清单
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundleManifest {
+    pub format: String,
+    pub format_version: u32,
+    /// 导出时数据库的迁移版本
+    pub schema_version: i64,
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+    pub novel_title: String,
+    pub chapter_count: usize,
+    pub version_count: usize,
+    pub inspiration_count: usize,
+    pub files: Vec<BundleFile>,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleImportReport {
+    pub novel_id: i64,
+    pub chapters: usize,
+    pub versions: usize,
+    pub inspirations: usize,
+    pub chapter_links: usize,
+    pub stats_days: usize,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 把一部小说的全部数据打包
+///
+/// 包内每类数据是一个 JSON 文件，保留原始ID；ID 在导入时重新映射。
+pub(crate) fn build_bundle_with(db: &Database, novel_id: i64) -> Result<Vec<u8>> {
+    let novel = db.get_novel_by_id(novel_id)?.ok_or_else(|| anyhow::anyhow!("小说不存在"))?;
+    let chapters = db.get_all_chapters_by_novel(novel_id)?;
+    let versions = db.get_novel_chapter_versions(novel_id)?;
+    let branches = db.get_novel_version_branches(novel_id)?;
+    let tags = db.get_novel_version_tags(novel_id)?;
+    let inspirations = db.get_inspirations_by_novel(novel_id)?;
+    let stats = db.get_writing_stats(novel_id)?;
+
+    let files = vec![
+        (NOVEL_FILE, serde_json::to_vec_pretty(&novel)?),
+        (CHAPTERS_FILE, serde_json::to_vec_pretty(&chapters)?),
+        (VERSIONS_FILE, serde_json::to_vec_pretty(&versions)?),
+        (BRANCHES_FILE, serde_json::to_vec_pretty(&branches)?),
+        (TAGS_FILE, serde_json::to_vec_pretty(&tags)?),
+        (INSPIRATIONS_FILE, serde_json::to_vec_pretty(&inspirations)?),
+        (STATS_FILE, serde_json::to_vec_pretty(&stats)?),
+    ];
+    let manifest = BundleManifest {
+        format: BUNDLE_FORMAT.to_string(),
+        format_version: BUNDLE_FORMAT_VERSION,
+        schema_version: db::schema_version(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Utc::now(),
+        novel_title: novel.title.clone(),
+        chapter_count: chapters.len(),
+        version_count: versions.len(),
+        inspiration_count: inspirations.len(),
+        files: files.iter()
+            .map(|(name, data)| BundleFile {
+                name: name.to_string(),
+                size: data.len() as u64,
+                sha256: sha256_hex(data),
+            })
+            .collect(),
+    };
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(MANIFEST_FILE, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+    for (name, data) in &files {
+        zip.start_file(*name, options)?;
+        zip.write_all(data)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// 已通过校验的项目包内容
+struct BundleReader {
+    archive: ZipArchive<Cursor<Vec<u8>>>,
+    manifest: BundleManifest,
+}
+
+impl BundleReader {
+    fn open(bytes: Vec<u8>) -> Result<Self> {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| anyhow::anyhow!("不是有效的项目包: {}", e))?;
+        let manifest: BundleManifest = serde_json::from_slice(&read_zip_entry(&mut archive, MANIFEST_FILE)?)
+            .map_err(|e| anyhow::anyhow!("项目包清单无法解析: {}", e))?;
+
+        if manifest.format != BUNDLE_FORMAT {
+            return Err(anyhow::anyhow!("不是 Novel Writer 项目包"));
+        }
+        if manifest.format_version > BUNDLE_FORMAT_VERSION || manifest.schema_version > db::schema_version() {
+            return Err(anyhow::anyhow!(
+                "项目包由更新版本的程序创建（数据库版本 {}），请升级程序后再导入",
+                manifest.schema_version
+            ));
+        }
+        for file in &manifest.files {
+            let data = read_zip_entry(&mut archive, &file.name)?;
+            if data.len() as u64 != file.size || sha256_hex(&data) != file.sha256 {
+                return Err(anyhow::anyhow!("项目包中的 {} 校验失败，文件可能已损坏", file.name));
+            }
+        }
+        Ok(Self { archive, manifest })
+    }
+
+    /// 读取清单中列出的数据文件
+    fn read<T: DeserializeOwned>(&mut self, name: &str) -> Result<T> {
+        if !self.manifest.files.iter().any(|f| f.name == name) {
+            return Err(anyhow::anyhow!("项目包缺少 {}", name));
+        }
+        let data = read_zip_entry(&mut self.archive, name)?;
+        serde_json::from_slice(&data).map_err(|e| anyhow::anyhow!("{} 无法解析: {}", name, e))
+    }
+
+    /// 读取较新版本才加入的数据文件，旧项目包中没有时返回空值
+    fn read_optional<T: DeserializeOwned + Default>(&mut self, name: &str) -> Result<T> {
+        if self.manifest.files.iter().any(|f| f.name == name) {
+            self.read(name)
+        } else {
+            Ok(T::default())
+        }
+    }
+}
+
+/// 读取并校验项目包清单
+pub fn read_manifest(bytes: Vec<u8>) -> Result<BundleManifest> {
+    BundleReader::open(bytes).map(|reader| reader.manifest)
+}
+
+/// 把项目包导入为一部新小说
+///
+/// 小说、章节、版本、分支、灵感都会获得新的ID，`parent_id`、`parent_version_id`、
+/// 分支指针和灵感的关联章节按映射改写，不会覆盖书库中已有的数据。
+pub(crate) fn import_bundle_with(db: &Database, bytes: Vec<u8>) -> Result<BundleImportReport> {
+    let mut reader = BundleReader::open(bytes)?;
+    let novel: Novel = reader.read(NOVEL_FILE)?;
+    let chapters: Vec<Chapter> = reader.read(CHAPTERS_FILE)?;
+    let versions: Vec<ChapterVersion> = reader.read(VERSIONS_FILE)?;
+    let branches: Vec<VersionBranch> = reader.read_optional(BRANCHES_FILE)?;
+    let tags: Vec<VersionTag> = reader.read_optional(TAGS_FILE)?;
+    let inspirations: Vec<Inspiration> = reader.read(INSPIRATIONS_FILE)?;
+    let stats: Vec<WritingStatsRecord> = reader.read(STATS_FILE)?;
+
+    db.transaction(|tx| {
+        let mut imported = tx.create_novel(&novel.title)?;
+        imported.author = novel.author.clone();
+        imported.description = novel.description.clone();
+        imported.status = novel.status.clone();
+        tx.update_novel(&imported)?;
+        let novel_id = imported.id;
+
+        let chapter_ids = import_chapters(tx, novel_id, &chapters)?;
+
+        // 分支先不带版本指针写入，版本写入后再补上 head 和基础版本
+        let mut branch_ids: HashMap<i64, i64> = HashMap::new();
+        for branch in &branches {
+            let Some(&chapter_id) = chapter_ids.get(&branch.chapter_id) else { continue };
+            let id = tx.insert_version_branch(&VersionBranch {
+                chapter_id,
+                head_version_id: None,
+                base_version_id: None,
+                ..branch.clone()
+            })?;
+            branch_ids.insert(branch.id, id);
+        }
+
+        let mut version_ids: HashMap<i64, i64> = HashMap::new();
+        for version in &versions {
+            let Some(&chapter_id) = chapter_ids.get(&version.chapter_id) else { continue };
+            let created = tx.create_chapter_version(ChapterVersion {
+                id: 0,
+                chapter_id,
+                parent_version_id: version.parent_version_id.and_then(|id| version_ids.get(&id).copied()),
+                branch_id: version.branch_id.and_then(|id| branch_ids.get(&id).copied()),
+                merge_parent_version_id: version.merge_parent_version_id.and_then(|id| version_ids.get(&id).copied()),
+                ..version.clone()
+            })?;
+            version_ids.insert(version.id, created.id);
+        }
+
+        for branch in &branches {
+            let Some(&branch_id) = branch_ids.get(&branch.id) else { continue };
+            tx.set_version_branch_pointers(
+                branch_id,
+                branch.head_version_id.and_then(|id| version_ids.get(&id).copied()),
+                branch.base_version_id.and_then(|id| version_ids.get(&id).copied()),
+            )?;
+        }
+        for tag in &tags {
+            let (Some(&chapter_id), Some(&version_id)) = (chapter_ids.get(&tag.chapter_id), version_ids.get(&tag.version_id)) else { continue };
+            tx.insert_version_tag(&VersionTag { chapter_id, version_id, ..tag.clone() })?;
+        }
+
+        let mut chapter_links = 0;
+        for inspiration in &inspirations {
+            let inspiration_id = tx.insert_inspiration(novel_id, inspiration)?;
+            tx.add_inspiration_tags(inspiration_id, &inspiration.tags)?;
+            for chapter_id in inspiration.linked_chapters.iter().filter_map(|id| chapter_ids.get(id)) {
+                tx.link_inspiration_to_chapter(inspiration_id, *chapter_id)?;
+                chapter_links += 1;
+            }
+        }
+
+        for record in &stats {
+            tx.insert_writing_stats(novel_id, record)?;
+        }
+
+        // 章节的改动会经由触发器更新小说，因此小说的时间戳最后设置
+        tx.set_novel_timestamps(novel_id, novel.created_at, novel.updated_at)?;
+
+        Ok(BundleImportReport {
+            novel_id,
+            chapters: chapter_ids.len(),
+            versions: version_ids.len(),
+            inspirations: inspirations.len(),
+            chapter_links,
+            stats_days: stats.len(),
+        })
+    })
+}
+
+/// 按父节点在前的顺序创建章节，返回旧ID到新ID的映射
+///
+/// 父章节不在包内的章节作为顶层章节导入。已归档章节下的子章节一并归档，
+/// 归档从最深的章节开始，保证不会有未归档的章节挂在已归档的章节下。
+fn import_chapters(db: &Database, novel_id: i64, chapters: &[Chapter]) -> Result<HashMap<i64, i64>> {
+    let known: HashSet<i64> = chapters.iter().map(|c| c.id).collect();
+    let mut ids: HashMap<i64, i64> = HashMap::new();
+    let mut archived: HashSet<i64> = HashSet::new();
+    let mut created: Vec<(i64, DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    let mut pending: Vec<Chapter> = chapters.iter()
+        .map(|c| Chapter { parent_id: c.parent_id.filter(|id| known.contains(id)), ..c.clone() })
+        .collect();
+
+    while !pending.is_empty() {
+        let (ready, mut waiting): (Vec<Chapter>, Vec<Chapter>) = pending.into_iter()
+            .partition(|c| c.parent_id.map_or(true, |id| ids.contains_key(&id)));
+        if ready.is_empty() {
+            // 剩下的章节互为祖先（数据中有环），断开第一个作为顶层章节
+            waiting[0].parent_id = None;
+        }
+        for chapter in ready {
+            let parent_id = chapter.parent_id.map(|id| ids[&id]);
+            let new_chapter = db.create_chapter(novel_id, &chapter.title, parent_id)?;
+            db.update_chapter_parent(new_chapter.id, parent_id, &chapter.sort_path)?;
+            db.update_chapter(&Chapter { id: new_chapter.id, ..chapter.clone() })?;
+            if chapter.is_archived || parent_id.is_some_and(|id| archived.contains(&id)) {
+                archived.insert(new_chapter.id);
+            }
+            ids.insert(chapter.id, new_chapter.id);
+            created.push((new_chapter.id, chapter.created_at, chapter.updated_at));
+        }
+        pending = waiting;
+    }
+
+    for (id, _, _) in created.iter().rev() {
+        if archived.contains(id) {
+            db.archive_chapter(*id)?;
+        }
+    }
+    // 归档会经由触发器更新章节的时间戳，因此时间戳最后设置
+    for (id, created_at, updated_at) in created {
+        db.set_chapter_timestamps(id, created_at, updated_at)?;
+    }
+    Ok(ids)
+}
+
+pub struct BundleManager;
+
+impl BundleManager {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 把小说导出为项目包
+    pub async fn export(&self, novel_id: i64, output: &Path) -> Result<()> {
+        let bytes = db::with_database(move |db| build_bundle_with(db, novel_id)).await?;
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(output, bytes)?;
+        info!("Exported bundle: novel={}, path={}", novel_id, output.display());
+        Ok(())
+    }
+
+    /// 读取项目包清单用于预览
+    pub fn inspect(&self, path: &Path) -> Result<BundleManifest> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| anyhow::anyhow!("读取 {} 失败: {}", path.display(), e))?;
+        read_manifest(bytes)
+    }
+
+    /// 把项目包导入为新小说
+    pub async fn import(&self, path: &Path) -> Result<BundleImportReport> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| anyhow::anyhow!("读取 {} 失败: {}", path.display(), e))?;
+        let report = db::with_database(move |db| import_bundle_with(db, bytes)).await?;
+        info!(
+            "Imported bundle: novel={}, chapters={}, versions={}, inspirations={}",
+            report.novel_id, report.chapters, report.versions, report.inspirations
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::version_manager::VersionManager;
+    use chrono::NaiveDate;
+
+    fn sample_library() -> (Database, i64) {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("打包").unwrap();
+        let volume = db.create_chapter(novel.id, "第一卷", None).unwrap();
+        let chapter = db.create_chapter(novel.id, "第一章", Some(volume.id)).unwrap();
+        db.update_chapter_content(chapter.id, "初稿").unwrap();
+        let first = VersionManager::create_version_with(&db, chapter.id, "初稿", Some("第一版"), false).unwrap();
+        db.update_chapter_content(chapter.id, "初稿，修改后").unwrap();
+        let second = VersionManager::create_version_with(&db, chapter.id, "初稿，修改后", None, true).unwrap();
+        VersionManager::create_branch_with(&db, first.id, "另一版").unwrap();
+        VersionManager::add_milestone_with(&db, second.id, "定稿", "").unwrap();
+        let archived = db.create_chapter(novel.id, "废稿", Some(volume.id)).unwrap();
+        db.archive_chapter(archived.id).unwrap();
+
+        let inspiration = db.create_inspiration(novel.id, "伏笔", "戒指的来历").unwrap();
+        db.add_inspiration_tags(inspiration.id, &["设定".to_string(), "伏笔".to_string()]).unwrap();
+        db.link_inspiration_to_chapter(inspiration.id, chapter.id).unwrap();
+        db.toggle_inspiration_pin(inspiration.id).unwrap();
+        db.record_writing_session(novel.id, NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(), 120, 20, 600).unwrap();
+        (db, novel.id)
+    }
+
+    #[test]
+    fn test_bundle_round_trip_remaps_ids() {
+        let (source, novel_id) = sample_library();
+        let bytes = build_bundle_with(&source, novel_id).unwrap();
+        assert_eq!(read_manifest(bytes.clone()).unwrap().chapter_count, 3);
+
+        // 目标书库中已有数据，导入后的ID必然与原ID不同
+        let target = Database::open_in_memory().unwrap();
+        let existing = target.create_novel("已有小说").unwrap();
+        for i in 0..5 {
+            target.create_chapter(existing.id, &format!("已有{}", i), None).unwrap();
+        }
+
+        let report = import_bundle_with(&target, bytes).unwrap();
+        assert_eq!((report.chapters, report.versions, report.inspirations, report.chapter_links, report.stats_days), (3, 2, 1, 1, 1));
+        assert_eq!(target.get_all_chapters_by_novel(existing.id).unwrap().len(), 5);
+
+        let original = source.get_novel_by_id(novel_id).unwrap().unwrap();
+        let imported = target.get_novel_by_id(report.novel_id).unwrap().unwrap();
+        assert_eq!((&imported.title, imported.word_count, imported.created_at, imported.updated_at),
+                   (&original.title, original.word_count, original.created_at, original.updated_at));
+
+        let chapters = target.get_all_chapters_by_novel(report.novel_id).unwrap();
+        let volume = chapters.iter().find(|c| c.title == "第一卷").unwrap();
+        let chapter = chapters.iter().find(|c| c.title == "第一章").unwrap();
+        let archived = chapters.iter().find(|c| c.title == "废稿").unwrap();
+        assert_eq!((chapter.parent_id, archived.parent_id), (Some(volume.id), Some(volume.id)));
+        assert!(archived.is_archived);
+        assert_eq!(chapter.content, "初稿，修改后");
+
+        let versions = target.get_chapter_versions(chapter.id).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].parent_version_id, Some(versions[1].id));
+        assert_eq!(VersionManager::reconstruct_content(&target, versions[0].id).unwrap(), "初稿，修改后");
+        let branches = target.get_version_branches(chapter.id).unwrap();
+        assert_eq!(branches.len(), 2);
+        assert_eq!((branches[0].head_version_id, branches[0].is_current), (Some(versions[0].id), true));
+        assert_eq!((branches[1].head_version_id, branches[1].base_version_id), (Some(versions[1].id), Some(versions[1].id)));
+        assert_eq!(versions[0].branch_id, Some(branches[0].id));
+        let milestones = target.get_version_tags(chapter.id).unwrap();
+        assert_eq!((milestones[0].name.as_str(), milestones[0].version_id), ("定稿", versions[0].id));
+
+        let inspirations = target.get_inspirations_by_novel(report.novel_id).unwrap();
+        let mut tags = inspirations[0].tags.clone();
+        tags.sort();
+        assert_eq!(tags, vec!["伏笔".to_string(), "设定".to_string()]);
+        assert_eq!(inspirations[0].linked_chapters, vec![chapter.id]);
+        assert!(inspirations[0].is_pinned);
+        assert_eq!(target.get_writing_stats(report.novel_id).unwrap(), source.get_writing_stats(novel_id).unwrap()
+            .into_iter().map(|r| WritingStatsRecord { novel_id: report.novel_id, ..r }).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_corrupted_or_newer_bundle_is_rejected() {
+        let (source, novel_id) = sample_library();
+        let bytes = build_bundle_with(&source, novel_id).unwrap();
+
+        // 重新打包，替换其中一个文件但保留原清单
+        let rebuild = |replace: &dyn Fn(&str, Vec<u8>) -> Vec<u8>| {
+            let mut archive = ZipArchive::new(Cursor::new(bytes.clone())).unwrap();
+            let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+            let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+            for name in names {
+                let data = read_zip_entry(&mut archive, &name).unwrap();
+                zip.start_file(name.as_str(), SimpleFileOptions::default()).unwrap();
+                zip.write_all(&replace(&name, data)).unwrap();
+            }
+            zip.finish().unwrap().into_inner()
+        };
+
+        let tampered = rebuild(&|name, data| {
+            if name == CHAPTERS_FILE { String::from_utf8(data).unwrap().replace("第一章", "第二章").into_bytes() } else { data }
+        });
+        let error = import_bundle_with(&source, tampered).unwrap_err().to_string();
+        assert!(error.contains("校验失败"), "{}", error);
+
+        let newer = rebuild(&|name, data| {
+            if name == MANIFEST_FILE {
+                let mut manifest: BundleManifest = serde_json::from_slice(&data).unwrap();
+                manifest.schema_version += 1;
+                serde_json::to_vec(&manifest).unwrap()
+            } else { data }
+        });
+        assert!(import_bundle_with(&source, newer).unwrap_err().to_string().contains("升级程序"));
+        assert_eq!(source.get_all_novels().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::core::version_manager::VersionManager;
+use crate::db::Database;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 恢复日志中的一条记录：某个章节尚未保存的正文
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryEntry {
+    pub novel_id: i64,
+    pub chapter_id: i64,
+    pub chapter_title: String,
+    pub content: String,
+    pub written_at: DateTime<Utc>,
+}
+
+impl RecoveryEntry {
+    pub fn word_count(&self) -> usize {
+        self.content.chars().filter(|c| !c.is_whitespace()).count()
+    }
+}
+
+/// 崩溃恢复日志
+///
+/// 编辑器中的未保存内容先写入日志再写入数据库。每个章节一个文件，
+/// 先写临时文件再重命名，保证任意时刻崩溃后日志文件都是完整的。
+/// 正常保存后删除对应记录，下次启动时仍然存在的记录就是上次未保存的内容。
+#[derive(Debug, Clone)]
+pub struct RecoveryJournal {
+    dir: PathBuf,
+}
+
+impl RecoveryJournal {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// 书库对应的日志目录：与数据库文件同目录的 `<文件名>.recovery`
+    pub fn for_database(db_path: &Path) -> Self {
+        let mut name = db_path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+        name.push(".recovery");
+        Self::new(db_path.with_file_name(name))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, chapter_id: i64) -> PathBuf {
+        self.dir.join(format!("chapter-{}.json", chapter_id))
+    }
+
+    /// 写入（覆盖）章节的未保存内容
+    pub fn write(&self, entry: &RecoveryEntry) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.entry_path(entry.chapter_id);
+        let tmp_path = path.with_extension("json.tmp");
+
+        let data = serde_json::to_vec(entry)?;
+        {
+            use std::io::Write;
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(&data)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// 删除章节的日志记录，记录不存在时不报错
+    pub fn clear(&self, chapter_id: i64) -> Result<()> {
+        match fs::remove_file(self.entry_path(chapter_id)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 清除内容与 `saved` 一致的记录，保存期间写入的更新内容保留
+    pub fn clear_saved(&self, chapter_id: i64, saved: &str) -> Result<()> {
+        let current = fs::read(self.entry_path(chapter_id)).ok()
+            .and_then(|data| serde_json::from_slice::<RecoveryEntry>(&data).ok());
+        match current {
+            Some(entry) if entry.content != saved => Ok(()),
+            _ => self.clear(chapter_id),
+        }
+    }
+
+    /// 读取全部记录，按写入时间从新到旧排列；损坏的文件会被跳过
+    pub fn entries(&self) -> Result<Vec<RecoveryEntry>> {
+        let read_dir = match fs::read_dir(&self.dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        for item in read_dir {
+            let path = item?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match fs::read(&path).map_err(anyhow::Error::from)
+                .and_then(|data| serde_json::from_slice::<RecoveryEntry>(&data).map_err(Into::into))
+            {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!("跳过无法读取的恢复日志 {}: {}", path.display(), e),
+            }
+        }
+        entries.sort_by(|a, b| b.written_at.cmp(&a.written_at));
+        Ok(entries)
+    }
+
+    /// 需要提示用户恢复的记录
+    ///
+    /// 章节已被删除或日志内容与数据库一致的记录没有恢复价值，会被顺带清除。
+    /// 读取章节出错（例如数据库被锁定）时保留全部日志并返回错误，下次启动再提示。
+    pub fn pending_with(&self, db: &Database) -> Result<Vec<RecoveryEntry>> {
+        let mut pending = Vec::new();
+        for entry in self.entries()? {
+            match db.get_chapter(entry.chapter_id) {
+                Ok(chapter) if chapter.content != entry.content => pending.push(entry),
+                Ok(_) => self.clear(entry.chapter_id)?,
+                Err(e) if matches!(e.downcast_ref(), Some(rusqlite::Error::QueryReturnedNoRows)) => {
+                    self.clear(entry.chapter_id)?
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(pending)
+    }
+
+    /// 把日志中的内容写回章节，并创建一个版本记录此次恢复
+    pub fn restore_with(&self, db: &Database, entry: &RecoveryEntry) -> Result<()> {
+        db.transaction(|tx| {
+            tx.update_chapter_content(entry.chapter_id, &entry.content)?;
+            VersionManager::create_version_with(
+                tx,
+                entry.chapter_id,
+                &entry.content,
+                Some("崩溃恢复"),
+                false,
+            )?;
+            Ok(())
+        })?;
+        self.clear(entry.chapter_id)?;
+        info!("Recovered unsaved content for chapter {}", entry.chapter_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_journal() -> RecoveryJournal {
+        let dir = std::env::temp_dir().join(format!("novel-writer-test-{}", uuid::Uuid::new_v4()));
+        RecoveryJournal::for_database(&dir.join("novels.db"))
+    }
+
+    fn entry(novel_id: i64, chapter_id: i64, content: &str) -> RecoveryEntry {
+        RecoveryEntry {
+            novel_id,
+            chapter_id,
+            chapter_title: "第一章".to_string(),
+            content: content.to_string(),
+            written_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_write_overwrites_and_clear_removes_entry() {
+        let journal = temp_journal();
+        assert!(journal.dir().ends_with("novels.db.recovery"));
+        assert!(journal.entries().unwrap().is_empty());
+
+        journal.write(&entry(1, 7, "草稿")).unwrap();
+        journal.write(&entry(1, 7, "草稿更新")).unwrap();
+        let entries = journal.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].content, "草稿更新");
+
+        // 损坏的文件不影响其他记录
+        fs::write(journal.dir().join("chapter-8.json"), b"{not json").unwrap();
+        assert_eq!(journal.entries().unwrap().len(), 1);
+
+        journal.clear(7).unwrap();
+        journal.clear(7).unwrap();
+        assert!(journal.entries().unwrap().iter().all(|e| e.chapter_id != 7));
+        fs::remove_dir_all(journal.dir().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_pending_entries_and_restore() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("恢复").unwrap();
+        let saved = db.create_chapter(novel.id, "已保存", None).unwrap();
+        let unsaved = db.create_chapter(novel.id, "未保存", None).unwrap();
+        db.update_chapter_content(saved.id, "一样的内容").unwrap();
+
+        let journal = temp_journal();
+        journal.write(&entry(novel.id, saved.id, "一样的内容")).unwrap();
+        journal.write(&entry(novel.id, unsaved.id, "崩溃前写的字")).unwrap();
+        journal.write(&entry(novel.id, 9999, "章节已删除")).unwrap();
+
+        let pending = journal.pending_with(&db).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].chapter_id, unsaved.id);
+        assert_eq!(journal.entries().unwrap().len(), 1);
+
+        journal.restore_with(&db, &pending[0]).unwrap();
+        assert_eq!(db.get_chapter(unsaved.id).unwrap().content, "崩溃前写的字");
+        let versions = db.get_chapter_versions(unsaved.id).unwrap();
+        assert_eq!(versions[0].commit_message, "崩溃恢复");
+        assert!(journal.entries().unwrap().is_empty());
+        fs::remove_dir_all(journal.dir().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_pending_keeps_entries_when_lookup_fails() {
+        let journal = temp_journal();
+        let path = journal.dir().parent().unwrap().join("novels.db");
+        let db = Database::open(&path).unwrap();
+        let novel = db.create_novel("恢复").unwrap();
+        let chapter = db.create_chapter(novel.id, "第一章", None).unwrap();
+        journal.write(&entry(novel.id, chapter.id, "崩溃前写的字")).unwrap();
+
+        // 章节行无法读取：查询出错而不是章节不存在
+        rusqlite::Connection::open(&path).unwrap()
+            .execute("UPDATE chapters SET updated_at = 'garbage' WHERE id = ?1", [chapter.id])
+            .unwrap();
+        assert!(journal.pending_with(&db).is_err());
+        assert_eq!(journal.entries().unwrap().len(), 1);
+        fs::remove_dir_all(journal.dir().parent().unwrap()).unwrap();
+    }
+}
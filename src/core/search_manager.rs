@@ -0,0 +1,80 @@
+use anyhow::Result;
+use log::info;
+use crate::db::{self, ChapterSearchHit, InspirationSearchHit, Novel};
+use crate::core::novel_manager::NovelManager;
+
+/// 每类结果最多返回的条数
+const MAX_HITS_PER_KIND: usize = 50;
+
+/// 一次检索的全部结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchResults {
+    pub query: String,
+    pub novels: Vec<Novel>,
+    pub chapters: Vec<ChapterSearchHit>,
+    pub inspirations: Vec<InspirationSearchHit>,
+}
+
+impl SearchResults {
+    pub fn is_empty(&self) -> bool {
+        self.novels.is_empty() && self.chapters.is_empty() && self.inspirations.is_empty()
+    }
+
+    pub fn total(&self) -> usize {
+        self.novels.len() + self.chapters.len() + self.inspirations.len()
+    }
+}
+
+pub struct SearchManager;
+
+impl SearchManager {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 检索小说、章节正文和灵感
+    ///
+    /// 指定 `novel_id` 时章节和灵感只在该小说内检索，小说列表始终在整个书库中匹配。
+    pub async fn search(&self, novel_id: Option<i64>, query: &str) -> Result<SearchResults> {
+        let query = query.trim().to_string();
+        if query.is_empty() {
+            return Ok(SearchResults::default());
+        }
+
+        info!("Searching library: novel={:?}, query='{}'", novel_id, query);
+        let novels = NovelManager::new().search_novels(&query).await?;
+
+        let chapter_query = query.clone();
+        let (chapters, inspirations) = db::with_database(move |db| {
+            Ok((
+                db.search_chapters(novel_id, &chapter_query, MAX_HITS_PER_KIND)?,
+                db.search_inspiration_hits(novel_id, &chapter_query, MAX_HITS_PER_KIND)?,
+            ))
+        }).await?;
+
+        let results = SearchResults { query, novels, chapters, inspirations };
+        info!("Search completed: {} results", results.total());
+        Ok(results)
+    }
+}
+
+/// 从检索结果跳转到章节正文中的命中位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchJump {
+    pub novel_id: i64,
+    pub chapter_id: i64,
+    /// 命中位置（按字符计），仅标题命中时为 `None`
+    pub match_offset: Option<usize>,
+    pub match_len: usize,
+}
+
+impl From<&ChapterSearchHit> for SearchJump {
+    fn from(hit: &ChapterSearchHit) -> Self {
+        Self {
+            novel_id: hit.novel_id,
+            chapter_id: hit.chapter_id,
+            match_offset: hit.match_offset,
+            match_len: hit.match_len,
+        }
+    }
+}
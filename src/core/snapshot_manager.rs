@@ -0,0 +1,501 @@
+use crate::core::novel_manager::NovelExportData;
+use crate::core::version_manager::VersionManager;
+use crate::db::{self, Chapter, Database, NovelSnapshot, SnapshotChapter};
+use crate::utils::diff_utils::{ChangeStats, DiffUtils};
+use anyhow::Result;
+use chrono::Utc;
+use log::info;
+use std::collections::{HashMap, HashSet};
+
+/// 快照中章节相对另一个快照的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotChange {
+    Added,
+    Removed,
+    /// 正文有变化
+    Modified,
+    /// 正文相同，只有标题、位置或归档状态变化
+    Unchanged,
+}
+
+/// 两个快照之间一个章节的差异
+#[derive(Debug, Clone)]
+pub struct SnapshotChapterDiff {
+    pub chapter_id: i64,
+    /// 较新快照中的标题（已删除的章节为旧标题）
+    pub title: String,
+    /// 标题改变时的旧标题
+    pub old_title: Option<String>,
+    pub change: SnapshotChange,
+    /// 上级章节或排序位置改变
+    pub moved: bool,
+    pub archived_changed: bool,
+    pub old_word_count: usize,
+    pub new_word_count: usize,
+    /// 在较新快照章节树中的深度（已删除的章节为旧快照中的深度）
+    pub depth: usize,
+    pub statistics: Option<ChangeStats>,
+    /// 正文的逐段（行）差异
+    pub patch: Option<String>,
+}
+
+/// 两个快照之间的差异，只列出有变化的章节
+#[derive(Debug, Clone)]
+pub struct SnapshotDiff {
+    pub old: NovelSnapshot,
+    pub new: NovelSnapshot,
+    pub chapters: Vec<SnapshotChapterDiff>,
+    pub unchanged_count: usize,
+}
+
+/// 恢复快照的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotRestoreReport {
+    /// 恢复前自动拍摄的快照
+    pub safety_snapshot_id: i64,
+    /// 恢复了内容、标题或位置的已有章节
+    pub restored: usize,
+    /// 从回收站中恢复的章节（保留原ID和版本历史）
+    pub untrashed: usize,
+    /// 已被彻底删除、重新创建的章节
+    pub recreated: usize,
+    /// 快照之后新增、被移入回收站的章节（按子树计）
+    pub trashed: usize,
+    /// 版本已被彻底删除、无法恢复正文的章节
+    pub missing_versions: usize,
+}
+
+fn word_count(content: &str) -> usize {
+    content.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// 拍摄全书快照
+///
+/// 每个章节（含已归档、不含回收站中的章节）记录当前分支的 head；
+/// 正文与 head 不一致（或还没有版本）时先创建一个版本。整个过程在一个事务中完成。
+pub(crate) fn create_snapshot_with(db: &Database, novel_id: i64, name: &str, note: &str) -> Result<NovelSnapshot> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("快照名称不能为空"));
+    }
+    db.transaction(|tx| {
+        if tx.get_novel_by_id(novel_id)?.is_none() {
+            return Err(anyhow::anyhow!("小说不存在"));
+        }
+        let message = format!("快照「{}」", name);
+        let mut chapters = Vec::new();
+        for chapter in tx.get_all_chapters_by_novel(novel_id)? {
+            let branch = VersionManager::current_branch_with(tx, chapter.id)?;
+            let head_matches = match branch.head_version_id {
+                Some(head) => VersionManager::reconstruct_content(tx, head)? == chapter.content,
+                None => false,
+            };
+            let version_id = match branch.head_version_id {
+                Some(head) if head_matches => head,
+                _ => VersionManager::create_version_with(tx, chapter.id, &chapter.content, Some(&message), false)?.id,
+            };
+            chapters.push(SnapshotChapter {
+                chapter_id: chapter.id,
+                parent_id: chapter.parent_id,
+                version_id: Some(version_id),
+                title: chapter.title,
+                chapter_type: chapter.chapter_type,
+                sort_path: chapter.sort_path,
+                word_count: chapter.word_count,
+                is_archived: chapter.is_archived,
+            });
+        }
+        let word_count = chapters.iter().filter(|c| !c.is_archived).map(|c| c.word_count).sum();
+        tx.create_novel_snapshot(&NovelSnapshot {
+            id: 0,
+            novel_id,
+            name: name.to_string(),
+            note: note.trim().to_string(),
+            chapter_count: chapters.len(),
+            word_count,
+            created_at: Utc::now(),
+        }, &chapters)
+    })
+}
+
+/// 按快照重建全书，用于比较和导出
+///
+/// 章节保留原来的ID，正文由快照记录的版本重建，版本已被删除的章节正文为空。
+pub(crate) fn snapshot_export_data_with(db: &Database, snapshot_id: i64) -> Result<NovelExportData> {
+    let snapshot = db.get_novel_snapshot(snapshot_id)?;
+    let novel = db.get_novel_by_id(snapshot.novel_id)?.ok_or_else(|| anyhow::anyhow!("小说不存在"))?;
+    let mut chapters = Vec::new();
+    for chapter in db.get_snapshot_chapters(snapshot_id)? {
+        let (content, updated_at) = match chapter.version_id {
+            Some(version_id) => (
+                VersionManager::reconstruct_content(db, version_id)?,
+                db.get_chapter_version(version_id)?.created_at,
+            ),
+            None => (String::new(), snapshot.created_at),
+        };
+        chapters.push(Chapter {
+            id: chapter.chapter_id,
+            novel_id: novel.id,
+            parent_id: chapter.parent_id,
+            title: chapter.title,
+            content,
+            sort_path: chapter.sort_path,
+            word_count: chapter.word_count,
+            created_at: updated_at.min(snapshot.created_at),
+            updated_at,
+            chapter_type: chapter.chapter_type,
+            is_archived: chapter.is_archived,
+        });
+    }
+    Ok(NovelExportData { novel, chapters, export_time: snapshot.created_at })
+}
+
+/// 比较两个快照，`old_id` 通常是较早的快照
+pub(crate) fn diff_snapshots_with(db: &Database, old_id: i64, new_id: i64) -> Result<SnapshotDiff> {
+    let old = snapshot_export_data_with(db, old_id)?;
+    let new = snapshot_export_data_with(db, new_id)?;
+    let old_nodes = old.ordered_nodes(&[], true);
+    let new_nodes = new.ordered_nodes(&[], true);
+    let old_by_id: HashMap<i64, &Chapter> = old_nodes.iter().map(|n| (n.chapter.id, &n.chapter)).collect();
+    let new_ids: HashSet<i64> = new_nodes.iter().map(|n| n.chapter.id).collect();
+
+    let mut chapters = Vec::new();
+    let mut unchanged_count = 0;
+    for node in &new_nodes {
+        let chapter = &node.chapter;
+        let Some(before) = old_by_id.get(&chapter.id) else {
+            chapters.push(SnapshotChapterDiff {
+                chapter_id: chapter.id,
+                title: chapter.title.clone(),
+                old_title: None,
+                change: SnapshotChange::Added,
+                moved: false,
+                archived_changed: false,
+                old_word_count: 0,
+                new_word_count: word_count(&chapter.content),
+                depth: node.depth,
+                statistics: Some(DiffUtils::get_change_statistics("", &chapter.content)),
+                patch: Some(DiffUtils::create_patch("", &chapter.content)),
+            });
+            continue;
+        };
+        let text_changed = before.content != chapter.content;
+        let renamed = before.title != chapter.title;
+        let moved = before.parent_id != chapter.parent_id || before.sort_path != chapter.sort_path;
+        let archived_changed = before.is_archived != chapter.is_archived;
+        if !(text_changed || renamed || moved || archived_changed) {
+            unchanged_count += 1;
+            continue;
+        }
+        chapters.push(SnapshotChapterDiff {
+            chapter_id: chapter.id,
+            title: chapter.title.clone(),
+            old_title: renamed.then(|| before.title.clone()),
+            change: if text_changed { SnapshotChange::Modified } else { SnapshotChange::Unchanged },
+            moved,
+            archived_changed,
+            old_word_count: word_count(&before.content),
+            new_word_count: word_count(&chapter.content),
+            depth: node.depth,
+            statistics: text_changed.then(|| DiffUtils::get_change_statistics(&before.content, &chapter.content)),
+            patch: text_changed.then(|| DiffUtils::create_patch(&before.content, &chapter.content)),
+        });
+    }
+    for node in old_nodes.iter().filter(|n| !new_ids.contains(&n.chapter.id)) {
+        let chapter = &node.chapter;
+        chapters.push(SnapshotChapterDiff {
+            chapter_id: chapter.id,
+            title: chapter.title.clone(),
+            old_title: None,
+            change: SnapshotChange::Removed,
+            moved: false,
+            archived_changed: false,
+            old_word_count: word_count(&chapter.content),
+            new_word_count: 0,
+            depth: node.depth,
+            statistics: Some(DiffUtils::get_change_statistics(&chapter.content, "")),
+            patch: Some(DiffUtils::create_patch(&chapter.content, "")),
+        });
+    }
+
+    Ok(SnapshotDiff {
+        old: db.get_novel_snapshot(old_id)?,
+        new: db.get_novel_snapshot(new_id)?,
+        chapters,
+        unchanged_count,
+    })
+}
+
+/// 把全书恢复到快照时的状态
+///
+/// 恢复前先拍摄一个快照，因此恢复本身也可以撤销。已有章节恢复标题、类型、位置、归档状态和正文
+/// （正文变化时在当前分支上创建版本）；回收站中的章节从回收站恢复，已彻底删除的章节重新创建；
+/// 快照之后新增的章节移入回收站。
+pub(crate) fn restore_snapshot_with(db: &Database, snapshot_id: i64) -> Result<SnapshotRestoreReport> {
+    db.transaction(|tx| {
+        let snapshot = tx.get_novel_snapshot(snapshot_id)?;
+        let safety = create_snapshot_with(tx, snapshot.novel_id, &format!("恢复「{}」前", snapshot.name), "恢复快照前自动创建")?;
+        let data = snapshot_export_data_with(tx, snapshot_id)?;
+        let missing: HashSet<i64> = tx.get_snapshot_chapters(snapshot_id)?
+            .into_iter()
+            .filter(|c| c.version_id.is_none())
+            .map(|c| c.chapter_id)
+            .collect();
+        let mut live: HashMap<i64, Chapter> = tx.get_all_chapters_by_novel(snapshot.novel_id)?
+            .into_iter()
+            .map(|c| (c.id, c))
+            .collect();
+        let message = format!("恢复快照「{}」", snapshot.name);
+        let mut report = SnapshotRestoreReport {
+            safety_snapshot_id: safety.id,
+            restored: 0,
+            untrashed: 0,
+            recreated: 0,
+            trashed: 0,
+            missing_versions: missing.len(),
+        };
+
+        // 按父章节在前的顺序处理，保证设置上级章节时它已经存在且位置已恢复
+        let mut ids: HashMap<i64, i64> = HashMap::new();
+        let mut archive_changes: Vec<(i64, bool)> = Vec::new();
+        let mut untrashed: HashSet<i64> = HashSet::new();
+        for node in data.ordered_nodes(&[], true) {
+            let target = node.chapter;
+            let parent_id = target.parent_id.and_then(|id| ids.get(&id).copied());
+            if !live.contains_key(&target.id) {
+                if let Some(trash_id) = tx.get_chapter_trash_id(target.id)? {
+                    // 整个回收站条目一起恢复，条目中不属于快照的章节随后作为新增章节再移入回收站
+                    tx.restore_trash_entry(trash_id)?;
+                    for chapter in tx.get_all_chapters_by_novel(snapshot.novel_id)? {
+                        if !live.contains_key(&chapter.id) {
+                            untrashed.insert(chapter.id);
+                            live.insert(chapter.id, chapter);
+                        }
+                    }
+                }
+            }
+            let (chapter_id, current) = match live.get(&target.id) {
+                Some(current) => (current.id, current.clone()),
+                None => {
+                    let created = tx.create_chapter(snapshot.novel_id, &target.title, parent_id)?;
+                    report.recreated += 1;
+                    (created.id, created)
+                }
+            };
+            ids.insert(target.id, chapter_id);
+
+            let content = if missing.contains(&target.id) { current.content.clone() } else { target.content.clone() };
+            let mut changed = false;
+            if current.title != target.title || current.chapter_type != target.chapter_type {
+                tx.update_chapter(&Chapter {
+                    title: target.title.clone(),
+                    chapter_type: target.chapter_type.clone(),
+                    ..current.clone()
+                })?;
+                changed = true;
+            }
+            if current.content != content {
+                tx.update_chapter_content(chapter_id, &content)?;
+                VersionManager::create_version_with(tx, chapter_id, &content, Some(&message), false)?;
+                changed = true;
+            }
+            if current.parent_id != parent_id || current.sort_path != target.sort_path {
+                tx.update_chapter_parent(chapter_id, parent_id, &target.sort_path)?;
+                changed = true;
+            }
+            if current.is_archived != target.is_archived {
+                archive_changes.push((chapter_id, target.is_archived));
+                changed = true;
+            }
+            if untrashed.contains(&target.id) {
+                report.untrashed += 1;
+            } else if changed && live.contains_key(&target.id) {
+                report.restored += 1;
+            }
+        }
+
+        // 快照之后新增的章节：只需把最上层的移入回收站，子孙随之一起
+        let extras: HashSet<i64> = live.keys().filter(|id| !ids.contains_key(id)).copied().collect();
+        for id in &extras {
+            if live[id].parent_id.map_or(true, |parent| !extras.contains(&parent)) {
+                tx.trash_chapter(*id)?;
+                report.trashed += 1;
+            }
+        }
+
+        // 章节树恢复后再调整归档状态：先取消归档，再从最深的章节开始归档
+        for (id, _) in archive_changes.iter().filter(|(_, archived)| !archived) {
+            tx.unarchive_chapter(*id)?;
+        }
+        for (id, _) in archive_changes.iter().rev().filter(|(_, archived)| *archived) {
+            tx.archive_chapter(*id)?;
+        }
+        Ok(report)
+    })
+}
+
+pub struct SnapshotManager;
+
+impl SnapshotManager {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 拍摄全书快照
+    pub async fn create(&self, novel_id: i64, name: &str, note: &str) -> Result<NovelSnapshot> {
+        let (name, note) = (name.to_string(), note.to_string());
+        let snapshot = db::with_database(move |db| create_snapshot_with(db, novel_id, &name, &note)).await?;
+        info!("Created snapshot {} of novel {}: {} chapters", snapshot.id, novel_id, snapshot.chapter_count);
+        Ok(snapshot)
+    }
+
+    /// 小说的全部快照，最新的在前
+    pub async fn list(&self, novel_id: i64) -> Result<Vec<NovelSnapshot>> {
+        db::with_database(move |db| db.get_novel_snapshots(novel_id)).await
+    }
+
+    /// 比较两个快照
+    pub async fn diff(&self, old_id: i64, new_id: i64) -> Result<SnapshotDiff> {
+        db::with_database(move |db| diff_snapshots_with(db, old_id, new_id)).await
+    }
+
+    /// 按快照重建的全书数据，可交给各导出器
+    pub async fn export_data(&self, snapshot_id: i64) -> Result<NovelExportData> {
+        db::with_database(move |db| snapshot_export_data_with(db, snapshot_id)).await
+    }
+
+    /// 把全书恢复到快照时的状态
+    pub async fn restore(&self, snapshot_id: i64) -> Result<SnapshotRestoreReport> {
+        let report = db::with_database(move |db| restore_snapshot_with(db, snapshot_id)).await?;
+        info!(
+            "Restored snapshot {}: restored={}, recreated={}, trashed={}",
+            snapshot_id, report.restored, report.recreated, report.trashed
+        );
+        Ok(report)
+    }
+
+    /// 删除快照，不影响章节和版本
+    pub async fn delete(&self, snapshot_id: i64) -> Result<()> {
+        db::with_database(move |db| db.delete_novel_snapshot(snapshot_id)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_novel(db: &Database) -> (i64, i64, i64, i64) {
+        let novel = db.create_novel("快照").unwrap();
+        let volume = db.create_chapter(novel.id, "第一卷", None).unwrap();
+        let first = db.create_chapter(novel.id, "第一章", Some(volume.id)).unwrap();
+        let second = db.create_chapter(novel.id, "第二章", Some(volume.id)).unwrap();
+        db.update_chapter_content(first.id, "清晨。\n她出门了。").unwrap();
+        db.update_chapter_content(second.id, "夜里下起了雨。").unwrap();
+        (novel.id, volume.id, first.id, second.id)
+    }
+
+    #[test]
+    fn test_snapshot_records_versions_and_diffs() {
+        let db = Database::open_in_memory().unwrap();
+        let (novel_id, volume_id, first_id, second_id) = sample_novel(&db);
+
+        let sent = create_snapshot_with(&db, novel_id, "交给编辑", "3 月 3 日").unwrap();
+        assert_eq!((sent.chapter_count, sent.word_count), (3, 15));
+        // 正文与当前分支 head 一致时不再创建版本
+        let again = create_snapshot_with(&db, novel_id, "再拍一次", "").unwrap();
+        assert_eq!(db.get_chapter_versions(first_id).unwrap().len(), 1);
+        assert_eq!(db.get_snapshot_chapters(again.id).unwrap(), db.get_snapshot_chapters(sent.id).unwrap());
+
+        db.update_chapter_content(first_id, "清晨。\n他出门了。").unwrap();
+        let chapter = db.get_chapter(second_id).unwrap();
+        db.update_chapter(&Chapter { title: "第二章 雨".to_string(), ..chapter }).unwrap();
+        let third = db.create_chapter(novel_id, "第三章", Some(volume_id)).unwrap();
+        db.update_chapter_content(third.id, "天亮了。").unwrap();
+        let revised = create_snapshot_with(&db, novel_id, "修改稿", "").unwrap();
+
+        let diff = diff_snapshots_with(&db, sent.id, revised.id).unwrap();
+        assert_eq!(diff.unchanged_count, 1);
+        let changes: Vec<(i64, SnapshotChange)> = diff.chapters.iter().map(|c| (c.chapter_id, c.change)).collect();
+        assert_eq!(changes, vec![
+            (first_id, SnapshotChange::Modified),
+            (second_id, SnapshotChange::Unchanged),
+            (third.id, SnapshotChange::Added),
+        ]);
+        let modified = &diff.chapters[0];
+        assert!(modified.patch.as_deref().unwrap().contains("-她出门了。"));
+        assert!(modified.patch.as_deref().unwrap().contains("+他出门了。"));
+        assert_eq!(diff.chapters[1].old_title.as_deref(), Some("第二章"));
+
+        // 导出时正文按快照时的版本重建
+        let data = snapshot_export_data_with(&db, sent.id).unwrap();
+        let first = data.chapters.iter().find(|c| c.id == first_id).unwrap();
+        assert_eq!(first.content, "清晨。\n她出门了。");
+        assert_eq!(data.ordered_nodes(&[], false).len(), 3);
+    }
+
+    #[test]
+    fn test_restore_snapshot_is_reversible() {
+        let db = Database::open_in_memory().unwrap();
+        let (novel_id, volume_id, first_id, second_id) = sample_novel(&db);
+        let sent = create_snapshot_with(&db, novel_id, "交给编辑", "").unwrap();
+
+        // 快照之后：改正文、移动、删除一章、新增一章
+        db.update_chapter_content(first_id, "完全重写。").unwrap();
+        db.update_chapter_parent(first_id, None, "z").unwrap();
+        db.trash_chapter(second_id).unwrap();
+        let extra = db.create_chapter(novel_id, "新章", Some(volume_id)).unwrap();
+        db.create_chapter(novel_id, "新场景", Some(extra.id)).unwrap();
+
+        let report = restore_snapshot_with(&db, sent.id).unwrap();
+        assert_eq!(
+            (report.restored, report.untrashed, report.recreated, report.trashed, report.missing_versions),
+            (1, 1, 0, 1, 0)
+        );
+
+        let chapters = db.get_all_chapters_by_novel(novel_id).unwrap();
+        assert_eq!(chapters.len(), 3);
+        let first = db.get_chapter(first_id).unwrap();
+        assert_eq!((first.content.as_str(), first.parent_id), ("清晨。\n她出门了。", Some(volume_id)));
+        // 回收站中的章节原样恢复，保留ID和版本历史，回收站条目随之移除
+        let untrashed = chapters.iter().find(|c| c.title == "第二章").unwrap();
+        assert_eq!(untrashed.id, second_id);
+        assert_eq!((untrashed.content.as_str(), untrashed.parent_id), ("夜里下起了雨。", Some(volume_id)));
+        assert_eq!(db.get_chapter_versions(second_id).unwrap().len(), 1);
+        assert!(db.get_trash_entries().unwrap().iter().all(|e| e.item_id != second_id));
+        assert_eq!(db.get_novel_by_id(novel_id).unwrap().unwrap().word_count, 15);
+        // 重写的内容保留在版本历史中，恢复前的快照可以再恢复回去
+        assert_eq!(db.get_chapter_versions(first_id).unwrap().len(), 3);
+        let safety = db.get_novel_snapshot(report.safety_snapshot_id).unwrap();
+        assert_eq!(safety.chapter_count, 4);
+        restore_snapshot_with(&db, safety.id).unwrap();
+        assert_eq!(db.get_chapter(first_id).unwrap().content, "完全重写。");
+
+        // 再次恢复前的状态中第二章在回收站里，彻底删除后才重新创建
+        let trash_id = db.get_chapter_trash_id(second_id).unwrap().unwrap();
+        db.purge_trash_entry(trash_id).unwrap();
+        let report = restore_snapshot_with(&db, sent.id).unwrap();
+        assert_eq!((report.untrashed, report.recreated, report.missing_versions), (0, 1, 1));
+        let recreated = db.get_all_chapters_by_novel(novel_id).unwrap().into_iter().find(|c| c.title == "第二章").unwrap();
+        assert_ne!(recreated.id, second_id);
+        // 版本随章节一起被彻底删除，只能恢复标题和位置
+        assert_eq!((recreated.content.as_str(), recreated.parent_id), ("", Some(volume_id)));
+    }
+
+    #[test]
+    fn test_restore_snapshot_keeps_archive_rule() {
+        let db = Database::open_in_memory().unwrap();
+        let (novel_id, volume_id, first_id, second_id) = sample_novel(&db);
+        db.archive_subtree(volume_id).unwrap();
+        let archived = create_snapshot_with(&db, novel_id, "整卷归档", "").unwrap();
+        db.unarchive_subtree(volume_id).unwrap();
+        let live = create_snapshot_with(&db, novel_id, "取消归档", "").unwrap();
+
+        // 上级章节先于子章节处理，归档必须等子章节都归档后进行
+        let report = restore_snapshot_with(&db, archived.id).unwrap();
+        assert_eq!(report.restored, 3);
+        assert!([volume_id, first_id, second_id].iter().all(|id| db.get_chapter(*id).unwrap().is_archived));
+
+        restore_snapshot_with(&db, live.id).unwrap();
+        assert!([volume_id, first_id, second_id].iter().all(|id| !db.get_chapter(*id).unwrap().is_archived));
+    }
+}
@@ -1,7 +1,6 @@
-use crate::db::{get_database, Novel};
+use crate::db::{self, ChapterType, Database, GoalStatus, GoalType, Novel, WritingGoalRecord};
 use anyhow::Result;
-use chrono::{DateTime, Duration, Utc, NaiveDate};
-use std::collections::HashMap;
+use chrono::{DateTime, Duration, Local, Utc, NaiveDate};
 
 pub struct StatsManager;
 
@@ -12,17 +11,18 @@ impl StatsManager {
     
     /// 获取小说统计信息
     pub async fn get_novel_stats(&self, novel_id: i64) -> Result<NovelStats> {
-        let db = get_database()?;
-        let novel = db.get_novel_by_id(novel_id)?
-            .ok_or_else(|| anyhow::anyhow!("小说不存在"))?;
-        
-        let chapters = db.get_chapters_by_novel(novel_id)?;
+        let (novel, chapters, progress_percentage) = db::with_database(move |db| {
+            let novel = db.get_novel_by_id(novel_id)?
+                .ok_or_else(|| anyhow::anyhow!("小说不存在"))?;
+            let chapters = db.get_chapters_by_novel(novel_id)?;
+            let progress_percentage = StatsManager.calculate_progress_percentage(db, &novel)?;
+            Ok((novel, chapters, progress_percentage))
+        }).await?;
         let writing_stats = self.get_writing_stats(novel_id).await?;
         
-        let current_streak = self.calculate_current_streak(&writing_stats);
+        let current_streak = self.calculate_current_streak(&writing_stats, Local::now().date_naive());
         let longest_streak = self.calculate_longest_streak(&writing_stats);
         let average_daily_words = self.calculate_average_daily_words(&writing_stats);
-        let progress_percentage = self.calculate_progress_percentage(&novel);
         let last_updated = novel.updated_at;
         let total_words = novel.word_count;
         
@@ -39,44 +39,40 @@ impl StatsManager {
         })
     }
     
-    /// 获取写作统计记录
+    /// 获取写作统计记录（来自编辑器写作会话，按日期倒序）
     pub async fn get_writing_stats(&self, novel_id: i64) -> Result<Vec<DailyWritingStats>> {
-        let db = get_database()?;
-        let mut stats_map: HashMap<NaiveDate, DailyWritingStats> = HashMap::new();
-        
-        // 从数据库获取写作统计（这里需要实现对应的数据库方法）
-        // 暂时使用章节更新日期来模拟写作统计
-        let chapters = db.get_chapters_by_novel(novel_id)?;
-        
-        for chapter in chapters {
-            let date = chapter.updated_at.date_naive();
-            let entry = stats_map.entry(date).or_insert(DailyWritingStats {
-                date,
-                word_count: 0,
-                writing_time: 0,
-                session_count: 0,
-            });
-            
-            entry.word_count += chapter.word_count as u32;
-            entry.session_count += 1;
-        }
-        
-        let mut stats: Vec<DailyWritingStats> = stats_map.into_values().collect();
-        stats.sort_by(|a, b| b.date.cmp(&a.date)); // 按日期倒序排列
+        let stats = db::with_database(move |db| db.get_writing_stats(novel_id)).await?
+            .into_iter()
+            .map(|record| DailyWritingStats {
+                date: record.date,
+                word_count: record.word_count.max(0) as u32,
+                words_added: record.words_added.max(0) as u32,
+                words_deleted: record.words_deleted.max(0) as u32,
+                writing_time: record.writing_time.max(0) as u32,
+                session_count: record.session_count.max(0) as u32,
+            })
+            .filter(|s| s.is_writing_day())
+            .collect();
         
         Ok(stats)
     }
     
     /// 计算当前连续写作天数
-    fn calculate_current_streak(&self, stats: &[DailyWritingStats]) -> u32 {
+    ///
+    /// 今天还没写不会中断连续记录，从昨天开始往前数。
+    fn calculate_current_streak(&self, stats: &[DailyWritingStats], today: NaiveDate) -> u32 {
         let mut current_streak = 0;
-        let mut current_date = Utc::now().date_naive();
+        let mut expected_date = match stats.first() {
+            Some(latest) if latest.date == today => today,
+            Some(latest) if Some(latest.date) == today.pred_opt() => latest.date,
+            _ => return 0,
+        };
         
         for stat in stats {
-            if stat.date == current_date {
+            if stat.date == expected_date {
                 current_streak += 1;
-                current_date = current_date.pred_opt().unwrap_or(current_date);
-            } else if stat.date < current_date {
+                expected_date = expected_date.pred_opt().unwrap_or(expected_date);
+            } else if stat.date < expected_date {
                 // 日期不连续，终止统计
                 break;
             }
@@ -95,8 +91,8 @@ impl StatsManager {
         let mut current_streak = 1;
         let mut prev_date = stats[0].date;
         
-        for i in 1..stats.len() {
-            let current_date = stats[i].date;
+        for stat in stats.iter().skip(1) {
+            let current_date = stat.date;
             let gap = prev_date.signed_duration_since(current_date).num_days();
             
             if gap == 1 {
@@ -124,22 +120,25 @@ impl StatsManager {
         total_words as f64 / stats.len() as f64
     }
     
-    /// 计算进度百分比
-    fn calculate_progress_percentage(&self, novel: &Novel) -> f64 {
-        // 这里可以根据目标字数计算进度
-        // 暂时使用一个简单的逻辑
-        let target_words = 50000; // 5万字目标
+    /// 计算进度百分比（相对进行中的总字数目标，没有目标时为 0）
+    fn calculate_progress_percentage(&self, db: &Database, novel: &Novel) -> Result<f64> {
+        let target_words = db.get_active_writing_goals(novel.id)?
+            .into_iter()
+            .find(|g| g.goal_type == GoalType::TotalWords)
+            .map(|g| g.target)
+            .unwrap_or(0);
+        
         if target_words > 0 {
-            (novel.word_count as f64 / target_words as f64 * 100.0).min(100.0)
+            Ok((novel.word_count as f64 / target_words as f64 * 100.0).min(100.0))
         } else {
-            0.0
+            Ok(0.0)
         }
     }
     
     /// 获取写作趋势分析
     pub async fn get_writing_trends(&self, novel_id: i64, period_days: i64) -> Result<WritingTrends> {
         let stats = self.get_writing_stats(novel_id).await?;
-        let cutoff_date = Utc::now().date_naive() - Duration::days(period_days);
+        let cutoff_date = Local::now().date_naive() - Duration::days(period_days);
         
         let recent_stats: Vec<&DailyWritingStats> = stats.iter()
             .filter(|s| s.date >= cutoff_date)
@@ -149,11 +148,11 @@ impl StatsManager {
         
         Ok(WritingTrends {
             period_days,
+            recent_days: recent_stats.iter().map(|s| (*s).clone()).collect(),
             recent_daily_average: self.calculate_period_average(&recent_stats),
             total_daily_average: self.calculate_period_average(&total_stats),
             best_day: self.find_best_day(&stats),
-            most_productive_time: self.analyze_productive_times(&stats),
-            consistency_score: self.calculate_consistency_score(&stats),
+            consistency_score: self.calculate_consistency_score(&stats, Local::now().date_naive()),
         })
     }
     
@@ -177,57 +176,175 @@ impl StatsManager {
             })
     }
     
-    /// 分析高效写作时间段（简化实现）
-    fn analyze_productive_times(&self, _stats: &[DailyWritingStats]) -> String {
-        // 这里可以分析一天中哪个时间段写作效率最高
-        // 暂时返回一个固定值
-        "上午 9-11 点".to_string()
-    }
-    
-    /// 计算写作一致性分数
-    fn calculate_consistency_score(&self, stats: &[DailyWritingStats]) -> f64 {
-        if stats.len() < 2 {
+    /// 计算写作一致性分数：从第一次写作到今天，有写作记录的天数占比
+    fn calculate_consistency_score(&self, stats: &[DailyWritingStats], today: NaiveDate) -> f64 {
+        let Some(first_date) = stats.iter().map(|s| s.date).min() else {
             return 0.0;
-        }
+        };
         
-        let total_days = stats.len() as f64;
-        let writing_days = stats.iter()
-            .filter(|s| s.word_count > 0)
-            .count() as f64;
+        let total_days = (today.signed_duration_since(first_date).num_days() + 1).max(1) as f64;
+        let writing_days = stats.len() as f64;
         
         (writing_days / total_days * 100.0).min(100.0)
     }
     
-    /// 设置写作目标
-    pub async fn set_writing_goal(&self, _novel_id: i64, _goal: WritingGoal) -> Result<()> {
-        // 这里可以实现目标设置逻辑
-        // 暂时只是保存目标信息
-        Ok(())
+    /// 设置写作目标，替换同类型的进行中目标
+    pub async fn set_writing_goal(&self, novel_id: i64, goal: WritingGoal) -> Result<WritingGoalRecord> {
+        if goal.target == 0 {
+            return Err(anyhow::anyhow!("目标必须大于0"));
+        }
+        if let Some(deadline) = goal.deadline {
+            if deadline < Local::now().date_naive() {
+                return Err(anyhow::anyhow!("截止日期不能早于今天"));
+            }
+        }
+        
+        db::with_database(move |db| {
+            let start_value = match goal.goal_type {
+                GoalType::DailyWords => 0,
+                goal_type => Self::current_goal_value(db, novel_id, goal_type)? as i64,
+            };
+            db.create_writing_goal(novel_id, goal.goal_type, goal.target as i64, start_value, goal.deadline)
+        }).await
     }
     
-    /// 获取目标进度
-    pub async fn get_goal_progress(&self, novel_id: i64) -> Result<GoalProgress> {
-        let novel_stats = self.get_novel_stats(novel_id).await?;
+    /// 放弃一个进行中的目标
+    pub async fn abandon_writing_goal(&self, goal_id: i64) -> Result<()> {
+        db::with_database(move |db| db.update_writing_goal_status(goal_id, GoalStatus::Abandoned)).await
+    }
+    
+    /// 获取目标历史
+    pub async fn get_goal_history(&self, novel_id: i64) -> Result<Vec<WritingGoalRecord>> {
+        db::with_database(move |db| db.get_writing_goal_history(novel_id)).await
+    }
+    
+    /// 获取所有进行中目标的进度，新达成的目标会被标记为已达成
+    pub async fn get_goal_progress(&self, novel_id: i64) -> Result<Vec<GoalProgress>> {
+        let goals = db::with_database(move |db| {
+            db.get_active_writing_goals(novel_id)?
+                .into_iter()
+                .map(|goal| Ok((Self::current_goal_value(db, novel_id, goal.goal_type)?, goal)))
+                .collect::<Result<Vec<_>>>()
+        }).await?;
+        let writing_stats = self.get_writing_stats(novel_id).await?;
+        let today = Local::now().date_naive();
         
-        Ok(GoalProgress {
-            current_words: novel_stats.total_words as u32,
-            target_words: 50000, // 默认5万字目标
-            deadline: None, // 可以添加截止日期功能
-            days_remaining: None,
-            progress_percentage: novel_stats.progress_percentage,
-            estimated_completion_date: self.estimate_completion_date(&novel_stats),
+        let mut progress = Vec::new();
+        let mut achieved = Vec::new();
+        for (current, goal) in goals {
+            let daily_rate = match goal.goal_type {
+                GoalType::TotalWords => self.calculate_average_daily_words(&writing_stats),
+                // 按设定目标以来的实际速度估算
+                _ => {
+                    let elapsed = (today.signed_duration_since(goal.start_date).num_days() + 1).max(1);
+                    (current as f64 - goal.start_value as f64).max(0.0) / elapsed as f64
+                }
+            };
+            
+            let goal_progress = self.calculate_goal_progress(goal, current, daily_rate, today);
+            if goal_progress.pace == GoalPace::Achieved && goal_progress.goal.goal_type != GoalType::DailyWords {
+                achieved.push(goal_progress.goal.id);
+            }
+            progress.push(goal_progress);
+        }
+        
+        if !achieved.is_empty() {
+            db::with_database(move |db| {
+                achieved.into_iter().try_for_each(|id| db.update_writing_goal_status(id, GoalStatus::Achieved))
+            }).await?;
+        }
+        Ok(progress)
+    }
+    
+    /// 目标当前完成的数量：总字数、章节数或今天的净增字数
+    fn current_goal_value(db: &Database, novel_id: i64, goal_type: GoalType) -> Result<u32> {
+        Ok(match goal_type {
+            GoalType::TotalWords => db.get_novel_by_id(novel_id)?
+                .map(|n| n.word_count.max(0) as u32)
+                .unwrap_or(0),
+            GoalType::ChapterCount => db.get_chapters_by_novel(novel_id)?
+                .iter()
+                .filter(|c| c.chapter_type == ChapterType::Chapter)
+                .count() as u32,
+            GoalType::DailyWords => {
+                let today = Local::now().date_naive();
+                db.get_writing_stats(novel_id)?
+                    .into_iter()
+                    .find(|s| s.date == today)
+                    .map(|s| s.word_count.max(0) as u32)
+                    .unwrap_or(0)
+            }
         })
     }
     
-    /// 估算完成日期
-    fn estimate_completion_date(&self, stats: &NovelStats) -> Option<NaiveDate> {
-        let remaining_words = 50000u32.saturating_sub(stats.total_words as u32);
+    /// 计算单个目标的进度、所需日均速度和是否按计划推进
+    ///
+    /// 有截止日期时，按设定目标当天到截止日期线性分配应完成的量，实际进度不低于应有进度即视为按计划。
+    fn calculate_goal_progress(&self, goal: WritingGoalRecord, current: u32, daily_rate: f64, today: NaiveDate) -> GoalProgress {
+        let target = goal.target.max(0) as u32;
+        let progress_percentage = if target > 0 {
+            (current as f64 / target as f64 * 100.0).min(100.0)
+        } else {
+            0.0
+        };
+        let remaining = target.saturating_sub(current);
         
-        if stats.average_daily_words > 0.0 {
-            let days_needed = (remaining_words as f64 / stats.average_daily_words).ceil() as i64;
-            Some(Utc::now().date_naive() + Duration::days(days_needed))
+        // 每日目标只看今天
+        if goal.goal_type == GoalType::DailyWords {
+            return GoalProgress {
+                deadline: None,
+                days_remaining: None,
+                progress_percentage,
+                estimated_completion_date: None,
+                required_daily_pace: Some(remaining as f64),
+                pace: if remaining == 0 { GoalPace::Achieved } else { GoalPace::Behind },
+                current,
+                target,
+                goal,
+            };
+        }
+        
+        let days_remaining = goal.deadline.map(|d| d.signed_duration_since(today).num_days() + 1);
+        let required_daily_pace = match days_remaining {
+            Some(days) if days > 0 && remaining > 0 => Some(remaining as f64 / days as f64),
+            _ => None,
+        };
+        
+        let pace = if remaining == 0 {
+            GoalPace::Achieved
+        } else {
+            match (goal.deadline, days_remaining) {
+                (Some(_), Some(days)) if days <= 0 => GoalPace::Overdue,
+                (Some(deadline), _) => {
+                    let total_days = (deadline.signed_duration_since(goal.start_date).num_days() + 1).max(1);
+                    let elapsed_days = today.signed_duration_since(goal.start_date).num_days().clamp(0, total_days);
+                    let expected = goal.start_value as f64
+                        + (goal.target - goal.start_value) as f64 * elapsed_days as f64 / total_days as f64;
+                    if current as f64 >= expected { GoalPace::OnTrack } else { GoalPace::Behind }
+                }
+                _ => GoalPace::NoDeadline,
+            }
+        };
+        
+        let estimated_completion_date = if remaining == 0 {
+            None
+        } else if daily_rate > 0.0 {
+            let days_needed = (remaining as f64 / daily_rate).ceil() as i64;
+            Some(today + Duration::days(days_needed))
         } else {
             None
+        };
+        
+        GoalProgress {
+            deadline: goal.deadline,
+            days_remaining,
+            progress_percentage,
+            estimated_completion_date,
+            required_daily_pace,
+            pace,
+            current,
+            target,
+            goal,
         }
     }
     
@@ -236,19 +353,21 @@ impl StatsManager {
         let novel_stats = self.get_novel_stats(novel_id).await?;
         let trends = self.get_writing_trends(novel_id, 30).await?;
         let goal_progress = self.get_goal_progress(novel_id).await?;
+        let goal_history = self.get_goal_history(novel_id).await?;
         
-        let recommendations = self.generate_recommendations(&novel_stats, &trends);
+        let recommendations = self.generate_recommendations(&novel_stats, &trends, &goal_progress);
         
         Ok(WritingReport {
             novel_stats,
             trends,
             goal_progress,
+            goal_history,
             recommendations,
         })
     }
     
     /// 生成改进建议
-    fn generate_recommendations(&self, stats: &NovelStats, trends: &WritingTrends) -> Vec<String> {
+    fn generate_recommendations(&self, stats: &NovelStats, trends: &WritingTrends, goals: &[GoalProgress]) -> Vec<String> {
         let mut recommendations = Vec::new();
         
         if stats.current_streak == 0 {
@@ -259,8 +378,25 @@ impl StatsManager {
             recommendations.push("尝试提高每日写作量，目标500字以上".to_string());
         }
         
-        if stats.progress_percentage < 50.0 {
-            recommendations.push("加快写作进度，保持稳定的创作节奏".to_string());
+        if goals.is_empty() {
+            recommendations.push("设定一个写作目标，让进度更有方向".to_string());
+        }
+        
+        for goal in goals.iter().filter(|g| matches!(g.pace, GoalPace::Behind | GoalPace::Overdue)) {
+            match (goal.goal.goal_type, goal.required_daily_pace) {
+                (GoalType::DailyWords, Some(pace)) => {
+                    recommendations.push(format!("今天还差 {:.0} 字完成每日目标", pace));
+                }
+                (GoalType::TotalWords, Some(pace)) => {
+                    recommendations.push(format!("总字数目标进度落后，需要每天写 {:.0} 字才能按期完成", pace));
+                }
+                (GoalType::ChapterCount, Some(pace)) => {
+                    recommendations.push(format!("章节目标进度落后，需要每天完成 {:.1} 章才能按期完成", pace));
+                }
+                (goal_type, None) => {
+                    recommendations.push(format!("{}目标已超过截止日期，考虑调整目标或截止日期", goal_type.display_name()));
+                }
+            }
         }
         
         if trends.consistency_score < 70.0 {
@@ -287,18 +423,27 @@ pub struct NovelStats {
 #[derive(Debug, Clone)]
 pub struct DailyWritingStats {
     pub date: NaiveDate,
-    pub word_count: u32,
+    pub word_count: u32, // 净增字数
+    pub words_added: u32,
+    pub words_deleted: u32,
     pub writing_time: u32, // 秒
     pub session_count: u32,
 }
 
+impl DailyWritingStats {
+    /// 当天是否真正写过（有新增字数或有效写作时间）
+    pub fn is_writing_day(&self) -> bool {
+        self.words_added > 0 || self.writing_time > 0
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct WritingTrends {
     pub period_days: i64,
+    pub recent_days: Vec<DailyWritingStats>, // 统计周期内有写作的日期，按日期倒序
     pub recent_daily_average: f64,
     pub total_daily_average: f64,
     pub best_day: Option<BestDay>,
-    pub most_productive_time: String,
     pub consistency_score: f64,
 }
 
@@ -310,25 +455,173 @@ pub struct BestDay {
 
 #[derive(Debug, Clone)]
 pub struct WritingGoal {
-    pub target_words: u32,
+    pub goal_type: GoalType,
+    pub target: u32,
     pub deadline: Option<NaiveDate>,
-    pub daily_target: u32,
+}
+
+/// 目标推进情况
+#[derive(Debug, Clone, PartialEq)]
+pub enum GoalPace {
+    Achieved,
+    OnTrack,
+    Behind,
+    /// 已过截止日期仍未达成
+    Overdue,
+    /// 没有截止日期，无法判断快慢
+    NoDeadline,
 }
 
 #[derive(Debug, Clone)]
 pub struct GoalProgress {
-    pub current_words: u32,
-    pub target_words: u32,
+    pub goal: WritingGoalRecord,
+    pub current: u32,
+    pub target: u32,
     pub deadline: Option<NaiveDate>,
     pub days_remaining: Option<i64>,
     pub progress_percentage: f64,
     pub estimated_completion_date: Option<NaiveDate>,
+    /// 按期完成所需的日均字数（章节目标为日均章数）
+    pub required_daily_pace: Option<f64>,
+    pub pace: GoalPace,
 }
 
 #[derive(Debug, Clone)]
 pub struct WritingReport {
     pub novel_stats: NovelStats,
     pub trends: WritingTrends,
-    pub goal_progress: GoalProgress,
+    pub goal_progress: Vec<GoalProgress>,
+    pub goal_history: Vec<WritingGoalRecord>,
     pub recommendations: Vec<String>,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn day(date: NaiveDate, words: u32) -> DailyWritingStats {
+        DailyWritingStats {
+            date,
+            word_count: words,
+            words_added: words,
+            words_deleted: 0,
+            writing_time: 60,
+            session_count: 1,
+        }
+    }
+    
+    fn days_ago(today: NaiveDate, n: i64) -> NaiveDate {
+        today - Duration::days(n)
+    }
+    
+    #[test]
+    fn test_current_streak_tolerates_not_yet_writing_today() {
+        let manager = StatsManager::new();
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        
+        let through_today = vec![day(today, 100), day(days_ago(today, 1), 50), day(days_ago(today, 3), 80)];
+        assert_eq!(manager.calculate_current_streak(&through_today, today), 2);
+        
+        let through_yesterday = vec![day(days_ago(today, 1), 50), day(days_ago(today, 2), 80)];
+        assert_eq!(manager.calculate_current_streak(&through_yesterday, today), 2);
+        
+        let broken = vec![day(days_ago(today, 2), 50)];
+        assert_eq!(manager.calculate_current_streak(&broken, today), 0);
+    }
+    
+    #[test]
+    fn test_longest_streak_and_consistency() {
+        let manager = StatsManager::new();
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let stats = vec![
+            day(today, 10),
+            day(days_ago(today, 3), 10),
+            day(days_ago(today, 4), 10),
+            day(days_ago(today, 5), 10),
+            day(days_ago(today, 9), 10),
+        ];
+        
+        assert_eq!(manager.calculate_longest_streak(&stats), 3);
+        // 10 天中写了 5 天
+        assert_eq!(manager.calculate_consistency_score(&stats, today), 50.0);
+        assert_eq!(manager.calculate_consistency_score(&[], today), 0.0);
+    }
+    
+    fn goal(goal_type: GoalType, target: i64, start_value: i64, start_date: NaiveDate, deadline: Option<NaiveDate>) -> WritingGoalRecord {
+        WritingGoalRecord {
+            id: 1,
+            novel_id: 1,
+            goal_type,
+            target,
+            start_value,
+            start_date,
+            deadline,
+            status: GoalStatus::Active,
+            created_at: Utc::now(),
+            completed_at: None,
+        }
+    }
+    
+    #[test]
+    fn test_goal_pace_against_deadline() {
+        let manager = StatsManager::new();
+        let start = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let deadline = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 3, 6).unwrap();
+        let total = goal(GoalType::TotalWords, 20000, 10000, start, Some(deadline));
+        
+        // 10 天写 1 万字，第 6 天开始时应有 1.5 万字
+        let behind = manager.calculate_goal_progress(total.clone(), 14000, 800.0, today);
+        assert_eq!(behind.pace, GoalPace::Behind);
+        assert_eq!(behind.days_remaining, Some(5));
+        assert_eq!(behind.required_daily_pace, Some(1200.0));
+        assert_eq!(behind.estimated_completion_date, Some(today + Duration::days(8)));
+        
+        let on_track = manager.calculate_goal_progress(total.clone(), 15000, 800.0, today);
+        assert_eq!(on_track.pace, GoalPace::OnTrack);
+        
+        let achieved = manager.calculate_goal_progress(total.clone(), 20500, 800.0, today);
+        assert_eq!(achieved.pace, GoalPace::Achieved);
+        assert_eq!(achieved.progress_percentage, 100.0);
+        
+        let late = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        let overdue = manager.calculate_goal_progress(total, 19000, 800.0, late);
+        assert_eq!(overdue.pace, GoalPace::Overdue);
+        assert_eq!(overdue.required_daily_pace, None);
+    }
+    
+    #[test]
+    fn test_daily_and_open_ended_goals() {
+        let manager = StatsManager::new();
+        let today = NaiveDate::from_ymd_opt(2024, 3, 6).unwrap();
+        
+        let daily = manager.calculate_goal_progress(goal(GoalType::DailyWords, 1000, 0, today, None), 400, 0.0, today);
+        assert_eq!(daily.pace, GoalPace::Behind);
+        assert_eq!(daily.required_daily_pace, Some(600.0));
+        
+        let chapters = manager.calculate_goal_progress(goal(GoalType::ChapterCount, 30, 10, today, None), 12, 0.0, today);
+        assert_eq!(chapters.pace, GoalPace::NoDeadline);
+        assert_eq!(chapters.estimated_completion_date, None);
+    }
+    
+    #[test]
+    fn test_new_goal_replaces_active_goal_of_same_type() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("目标").unwrap();
+        let deadline = NaiveDate::from_ymd_opt(2099, 1, 1);
+        
+        let first = db.create_writing_goal(novel.id, GoalType::TotalWords, 50000, 0, deadline).unwrap();
+        db.create_writing_goal(novel.id, GoalType::DailyWords, 1000, 0, None).unwrap();
+        let second = db.create_writing_goal(novel.id, GoalType::TotalWords, 80000, 0, None).unwrap();
+        
+        let active = db.get_active_writing_goals(novel.id).unwrap();
+        assert_eq!(active.len(), 2);
+        assert!(active.iter().any(|g| g.id == second.id && g.deadline.is_none()));
+        
+        let history = db.get_writing_goal_history(novel.id).unwrap();
+        assert_eq!(history.len(), 3);
+        let replaced = history.iter().find(|g| g.id == first.id).unwrap();
+        assert_eq!(replaced.status, GoalStatus::Abandoned);
+        assert_eq!(replaced.deadline, deadline);
+        assert!(replaced.completed_at.is_some());
+    }
+}
@@ -0,0 +1,57 @@
+use crate::config::AppConfig;
+use crate::db::{self, TrashEntry};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+
+/// 保留天数对应的清理截止时间，`0` 表示永不自动清理
+pub fn purge_cutoff(retention_days: u32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    (retention_days > 0).then(|| now - Duration::days(retention_days as i64))
+}
+
+pub struct TrashManager;
+
+impl TrashManager {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 回收站中的全部条目，最近删除的在前
+    pub async fn list(&self) -> Result<Vec<TrashEntry>> {
+        db::with_database(|db| db.get_trash_entries()).await
+    }
+
+    /// 恢复条目到原来的位置
+    pub async fn restore(&self, entry_id: i64) -> Result<TrashEntry> {
+        let entry = db::with_database(move |db| db.restore_trash_entry(entry_id)).await?;
+        info!("Restored {} from trash: id={}, title='{}'", entry.item_type.as_str(), entry.item_id, entry.title);
+        Ok(entry)
+    }
+
+    /// 彻底删除条目
+    pub async fn purge(&self, entry_id: i64) -> Result<()> {
+        db::with_database(move |db| db.purge_trash_entry(entry_id)).await?;
+        info!("Purged trash entry {}", entry_id);
+        Ok(())
+    }
+
+    /// 清空回收站，返回删除的条目数
+    pub async fn empty(&self) -> Result<usize> {
+        let purged = db::with_database(|db| db.purge_trash_before(Utc::now() + Duration::seconds(1))).await?;
+        info!("Emptied trash: {} entries purged", purged);
+        Ok(purged)
+    }
+
+    /// 按配置的保留天数清理过期条目
+    pub async fn auto_purge(&self) -> Result<usize> {
+        let retention_days = AppConfig::load().unwrap_or_default().trash.retention_days;
+        let Some(cutoff) = purge_cutoff(retention_days, Utc::now()) else {
+            return Ok(0);
+        };
+        let purged = db::with_database(move |db| db.purge_trash_before(cutoff)).await?;
+        if purged > 0 {
+            info!("Purged {} trash entries older than {} days", purged, retention_days);
+        }
+        Ok(purged)
+    }
+}
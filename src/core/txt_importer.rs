@@ -0,0 +1,538 @@
+use crate::core::markdown_exchange::{next_sort_path, ImportReport};
+use crate::db::{self, Chapter, ChapterType, Database};
+use anyhow::Result;
+use log::info;
+use regex::Regex;
+use std::path::Path;
+
+/// 识别出的文本编码
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextEncoding {
+    Utf8,
+    Gbk,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl TextEncoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Gbk => "GBK",
+            TextEncoding::Utf16Le => "UTF-16LE",
+            TextEncoding::Utf16Be => "UTF-16BE",
+        }
+    }
+
+    fn encoding(&self) -> &'static encoding_rs::Encoding {
+        match self {
+            TextEncoding::Utf8 => encoding_rs::UTF_8,
+            // GB18030 兼容 GBK，同时能解码 GBK 之外的字符
+            TextEncoding::Gbk => encoding_rs::GB18030,
+            TextEncoding::Utf16Le => encoding_rs::UTF_16LE,
+            TextEncoding::Utf16Be => encoding_rs::UTF_16BE,
+        }
+    }
+}
+
+/// 识别编码并解码文本
+///
+/// 依次检查 BOM、是否为合法的 UTF-8、是否为不带 BOM 的 UTF-16（根据换行符的字节排列），
+/// 都不是时按 GBK 解码。换行统一为 `\n`。
+pub fn decode_text(bytes: &[u8]) -> (String, TextEncoding) {
+    let (encoding, body) = if let Some((bom_encoding, bom_len)) = encoding_rs::Encoding::for_bom(bytes) {
+        let encoding = if bom_encoding == encoding_rs::UTF_16LE {
+            TextEncoding::Utf16Le
+        } else if bom_encoding == encoding_rs::UTF_16BE {
+            TextEncoding::Utf16Be
+        } else {
+            TextEncoding::Utf8
+        };
+        (encoding, &bytes[bom_len..])
+    } else if std::str::from_utf8(bytes).is_ok() {
+        (TextEncoding::Utf8, bytes)
+    } else {
+        (guess_utf16(bytes).unwrap_or(TextEncoding::Gbk), bytes)
+    };
+
+    let (text, _) = encoding.encoding().decode_without_bom_handling(body);
+    (text.replace("\r\n", "\n").replace('\r', "\n"), encoding)
+}
+
+/// 不带 BOM 的 UTF-16：换行符的两个字节中有一个是 0，且按该字节序解码没有错误
+fn guess_utf16(bytes: &[u8]) -> Option<TextEncoding> {
+    if bytes.len() < 2 || bytes.len() % 2 != 0 {
+        return None;
+    }
+    let pairs = bytes.chunks_exact(2);
+    let little_endian = pairs.clone().filter(|p| p == &[b'\n', 0]).count();
+    let big_endian = pairs.filter(|p| p == &[0, b'\n']).count();
+    let candidate = match (little_endian, big_endian) {
+        (0, 0) => return None,
+        (le, be) if le >= be => TextEncoding::Utf16Le,
+        _ => TextEncoding::Utf16Be,
+    };
+    let (_, had_errors) = candidate.encoding().decode_without_bom_handling(bytes);
+    (!had_errors).then_some(candidate)
+}
+
+/// 解析章节编号：阿拉伯数字（含全角）、中文数字或罗马数字
+pub fn parse_numeral(text: &str) -> Option<u32> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    if text.chars().all(|c| c.is_ascii_digit() || ('０'..='９').contains(&c)) {
+        let ascii: String = text.chars()
+            .map(|c| if c.is_ascii_digit() { c } else { char::from(b'0' + (c as u32 - '０' as u32) as u8) })
+            .collect();
+        return ascii.parse().ok();
+    }
+
+    if text.chars().all(|c| "IVXLCDMivxlcdm".contains(c)) {
+        return parse_roman(text);
+    }
+
+    let mut total = 0u32;
+    let mut section = 0u32;
+    let mut digit = 0u32;
+    for c in text.chars() {
+        match c {
+            '零' | '〇' => digit = 0,
+            '一' => digit = 1,
+            '二' | '两' => digit = 2,
+            '三' => digit = 3,
+            '四' => digit = 4,
+            '五' => digit = 5,
+            '六' => digit = 6,
+            '七' => digit = 7,
+            '八' => digit = 8,
+            '九' => digit = 9,
+            '十' | '百' | '千' => {
+                let unit = match c { '十' => 10, '百' => 100, _ => 1000 };
+                // “十二”中的“十”前面省略了“一”
+                section += digit.max(1) * unit;
+                digit = 0;
+            },
+            '万' => {
+                total += (section + digit).max(1) * 10_000;
+                section = 0;
+                digit = 0;
+            },
+            _ => return None,
+        }
+    }
+    Some(total + section + digit)
+}
+
+/// 罗马数字，只接受标准写法（如 `XIV`），`IIIIX`、`IXM` 之类的写法返回 `None`
+fn parse_roman(text: &str) -> Option<u32> {
+    let text = text.to_ascii_uppercase();
+    let mut total: u32 = 0;
+    let mut previous = 0;
+    for c in text.chars().rev() {
+        let value = match c {
+            'I' => 1,
+            'V' => 5,
+            'X' => 10,
+            'L' => 50,
+            'C' => 100,
+            'D' => 500,
+            'M' => 1000,
+            _ => return None,
+        };
+        if value < previous {
+            total = total.checked_sub(value)?;
+        } else {
+            total = total.checked_add(value)?;
+            previous = value;
+        }
+    }
+    (total > 0 && to_roman(total) == text).then_some(total)
+}
+
+fn to_roman(mut value: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"),
+        (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I"),
+    ];
+    let mut roman = String::new();
+    for (unit, symbol) in NUMERALS {
+        while value >= unit {
+            roman.push_str(symbol);
+            value -= unit;
+        }
+    }
+    roman
+}
+
+/// 拆分章节的规则
+///
+/// 每条规则是一个正则表达式，对去掉首尾空白的行匹配；可以用命名分组 `num` 标出编号，
+/// 用于检查编号是否连续。
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitRules {
+    pub volume_patterns: Vec<String>,
+    pub chapter_patterns: Vec<String>,
+    /// 序章、楔子、尾声等没有编号的章节
+    pub special_patterns: Vec<String>,
+    /// 超过这个字数的行不作为标题
+    pub max_heading_chars: usize,
+}
+
+const NUMERALS: &str = "[0-9０-９零〇一二两三四五六七八九十百千万]+";
+
+impl Default for SplitRules {
+    fn default() -> Self {
+        Self {
+            volume_patterns: vec![
+                format!(r"^第\s*(?P<num>{})\s*[卷部]", NUMERALS),
+                r"(?i)^(?:volume|book)\s+(?P<num>\d+|[ivxlcdm]+)\b".to_string(),
+            ],
+            chapter_patterns: vec![
+                format!(r"^第\s*(?P<num>{})\s*[章回节]", NUMERALS),
+                r"(?i)^chapter\s+(?P<num>\d+|[ivxlcdm]+)\b".to_string(),
+            ],
+            special_patterns: vec![
+                r"^(?:序章|序幕|序言|序|楔子|引子|前言|尾声|后记|番外)(?:$|[\s:：、.．·—-])".to_string(),
+                r"(?i)^(?:prologue|epilogue)\b".to_string(),
+            ],
+            max_heading_chars: 40,
+        }
+    }
+}
+
+struct CompiledRules {
+    volume: Vec<Regex>,
+    chapter: Vec<Regex>,
+    special: Vec<Regex>,
+    max_heading_chars: usize,
+}
+
+impl SplitRules {
+    fn compile(&self) -> Result<CompiledRules> {
+        fn compile_all(patterns: &[String], kind: &str) -> Result<Vec<Regex>> {
+            patterns.iter()
+                .filter(|p| !p.trim().is_empty())
+                .map(|p| Regex::new(p).map_err(|e| anyhow::anyhow!("{}正则表达式无效: {}", kind, e)))
+                .collect()
+        }
+        Ok(CompiledRules {
+            volume: compile_all(&self.volume_patterns, "卷标题")?,
+            chapter: compile_all(&self.chapter_patterns, "章节标题")?,
+            special: compile_all(&self.special_patterns, "特殊章节")?,
+            max_heading_chars: self.max_heading_chars,
+        })
+    }
+}
+
+impl CompiledRules {
+    /// 判断一行是否为标题，返回章节类型和编号
+    fn classify(&self, line: &str) -> Option<(ChapterType, Option<u32>)> {
+        if line.is_empty() || line.chars().count() > self.max_heading_chars {
+            return None;
+        }
+        // 以句末标点结尾的是正文
+        if line.ends_with(['。', '！', '？', '，', '；', '…', '”', ',', ';']) {
+            return None;
+        }
+        let find = |patterns: &[Regex]| {
+            patterns.iter().find_map(|re| re.captures(line))
+                .map(|caps| caps.name("num").and_then(|m| parse_numeral(m.as_str())))
+        };
+        if let Some(number) = find(&self.volume) {
+            return Some((ChapterType::Volume, number));
+        }
+        if let Some(number) = find(&self.chapter) {
+            return Some((ChapterType::Chapter, number));
+        }
+        find(&self.special).map(|_| (ChapterType::Chapter, None))
+    }
+}
+
+/// 拆分得到的章节
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProposedChapter {
+    pub title: String,
+    pub chapter_type: ChapterType,
+    pub number: Option<u32>,
+    /// 标题所在的行号（从 1 开始）
+    pub line: usize,
+    pub content: String,
+    pub children: Vec<ProposedChapter>,
+}
+
+impl ProposedChapter {
+    fn new(title: &str, chapter_type: ChapterType, number: Option<u32>, line: usize) -> Self {
+        Self { title: title.to_string(), chapter_type, number, line, content: String::new(), children: Vec::new() }
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.content.chars().filter(|c| !c.is_whitespace()).count()
+    }
+
+    /// 包括子章节在内的总字数
+    pub fn total_word_count(&self) -> usize {
+        self.word_count() + self.children.iter().map(|c| c.total_word_count()).sum::<usize>()
+    }
+}
+
+/// 导入前的拆分预览
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitPreview {
+    pub encoding: TextEncoding,
+    pub chapters: Vec<ProposedChapter>,
+    /// 编号不连续等可能拆分有误的地方
+    pub warnings: Vec<String>,
+}
+
+impl SplitPreview {
+    pub fn chapter_count(&self) -> usize {
+        fn count(chapters: &[ProposedChapter]) -> usize {
+            chapters.iter().map(|c| 1 + count(&c.children)).sum()
+        }
+        count(&self.chapters)
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.chapters.iter().map(|c| c.total_word_count()).sum()
+    }
+}
+
+/// 按规则把全文拆分成卷和章节
+///
+/// 卷标题之后的章节归入该卷，卷标题和第一个章节之间的文字作为卷的正文；
+/// 第一个标题之前的文字单独成为“前言”，全文没有任何标题时整体作为一章。
+pub fn split_text(text: &str, encoding: TextEncoding, rules: &SplitRules) -> Result<SplitPreview> {
+    let rules = rules.compile()?;
+    let mut chapters: Vec<ProposedChapter> = Vec::new();
+    let mut preamble = String::new();
+    let mut in_volume = false;
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = raw_line.trim();
+        match rules.classify(line) {
+            Some((ChapterType::Volume, number)) => {
+                chapters.push(ProposedChapter::new(line, ChapterType::Volume, number, index + 1));
+                in_volume = true;
+            },
+            Some((chapter_type, number)) => {
+                let chapter = ProposedChapter::new(line, chapter_type, number, index + 1);
+                match chapters.last_mut() {
+                    Some(volume) if in_volume => volume.children.push(chapter),
+                    _ => chapters.push(chapter),
+                }
+            },
+            None => {
+                let current = match chapters.last_mut() {
+                    Some(volume) if in_volume && !volume.children.is_empty() => {
+                        volume.children.last_mut().expect("卷下至少有一章")
+                    },
+                    Some(chapter) => chapter,
+                    None => {
+                        push_line(&mut preamble, raw_line);
+                        continue;
+                    },
+                };
+                push_line(&mut current.content, raw_line);
+            },
+        }
+    }
+
+    let preamble = trim_blank_lines(&preamble);
+    if !preamble.trim().is_empty() {
+        let title = if chapters.is_empty() { "正文" } else { "前言" };
+        let mut chapter = ProposedChapter::new(title, ChapterType::Chapter, None, 1);
+        chapter.content = preamble;
+        chapters.insert(0, chapter);
+    }
+    finish(&mut chapters);
+
+    let mut warnings = Vec::new();
+    check_numbering(&chapters, &mut warnings);
+    Ok(SplitPreview { encoding, chapters, warnings })
+}
+
+fn push_line(content: &mut String, line: &str) {
+    content.push_str(line);
+    content.push('\n');
+}
+
+/// 去掉首尾的空行，保留行内缩进
+fn trim_blank_lines(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.iter().position(|l| !l.trim().is_empty()).unwrap_or(lines.len());
+    let end = lines.iter().rposition(|l| !l.trim().is_empty()).map(|i| i + 1).unwrap_or(start);
+    lines[start..end].join("\n")
+}
+
+fn finish(chapters: &mut [ProposedChapter]) {
+    for chapter in chapters {
+        chapter.content = trim_blank_lines(&chapter.content);
+        finish(&mut chapter.children);
+    }
+}
+
+fn check_numbering(chapters: &[ProposedChapter], warnings: &mut Vec<String>) {
+    let mut previous: Option<(ChapterType, u32)> = None;
+    for chapter in chapters {
+        if let Some(number) = chapter.number {
+            if let Some((kind, prev)) = &previous {
+                if *kind == chapter.chapter_type && number != prev + 1 {
+                    warnings.push(format!(
+                        "第 {} 行「{}」的编号 {} 与前一个编号 {} 不连续",
+                        chapter.line, chapter.title, number, prev
+                    ));
+                }
+            }
+            previous = Some((chapter.chapter_type.clone(), number));
+        }
+        check_numbering(&chapter.children, warnings);
+    }
+}
+
+/// 按预览创建卷和章节
+///
+/// `target` 为 `None` 时以 `novel_title` 新建小说，否则追加到已有小说的末尾。
+pub(crate) fn import_preview_with(db: &Database, preview: &SplitPreview, target: Option<i64>, novel_title: &str) -> Result<ImportReport> {
+    db.transaction(|tx| {
+        let (novel_id, last_root) = match target {
+            Some(novel_id) => {
+                tx.get_novel_by_id(novel_id)?.ok_or_else(|| anyhow::anyhow!("小说不存在"))?;
+                let last_root = tx.get_all_chapters_by_novel(novel_id)?.into_iter()
+                    .filter(|c| c.parent_id.is_none())
+                    .map(|c| c.sort_path)
+                    .max();
+                (novel_id, last_root)
+            },
+            None => (tx.create_novel(novel_title)?.id, None),
+        };
+
+        let mut report = ImportReport { novel_id: Some(novel_id), ..Default::default() };
+        create_chapters(tx, novel_id, None, &preview.chapters, last_root, &mut report)?;
+        Ok(report)
+    })
+}
+
+fn create_chapters(
+    db: &Database,
+    novel_id: i64,
+    parent_id: Option<i64>,
+    chapters: &[ProposedChapter],
+    mut previous: Option<String>,
+    report: &mut ImportReport,
+) -> Result<()> {
+    for proposed in chapters {
+        let created = db.create_chapter(novel_id, &proposed.title, parent_id)?;
+        // 保证按原文顺序排在前一个章节之后
+        let sort_path = next_sort_path(&created.sort_path, previous.as_deref());
+        db.update_chapter_parent(created.id, parent_id, &sort_path)?;
+        db.update_chapter(&Chapter {
+            content: proposed.content.clone(),
+            word_count: proposed.word_count() as i32,
+            chapter_type: proposed.chapter_type.clone(),
+            ..created.clone()
+        })?;
+        report.created_chapters += 1;
+        create_chapters(db, novel_id, Some(created.id), &proposed.children, None, report)?;
+        previous = Some(sort_path);
+    }
+    Ok(())
+}
+
+pub struct TxtImporter;
+
+impl TxtImporter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 读取文件并按规则拆分，不修改数据库
+    pub fn preview(&self, path: &Path, rules: &SplitRules) -> Result<SplitPreview> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| anyhow::anyhow!("读取 {} 失败: {}", path.display(), e))?;
+        let (text, encoding) = decode_text(&bytes);
+        split_text(&text, encoding, rules)
+    }
+
+    /// 按预览导入
+    pub async fn import(&self, preview: SplitPreview, target: Option<i64>, novel_title: String) -> Result<ImportReport> {
+        let report = db::with_database(move |db| import_preview_with(db, &preview, target, &novel_title)).await?;
+        info!("Imported TXT manuscript: novel={:?}, chapters={}", report.novel_id, report.created_chapters);
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\u{3000}\u{3000}作者的话。\n\n第一卷 风起\n卷首语\n第一章 开端\n\u{3000}\u{3000}第一段。\n\n\u{3000}\u{3000}第二段。\n第二章：相遇\n正文\n第四章 跳号\n第一章就写到这里，这一行不是标题。\n第二卷 云涌\n第1章 新卷\n内容\n尾声\n完。\n";
+
+    #[test]
+    fn test_decode_detects_encodings() {
+        let text = "第一章 开端\n正文\n";
+        assert_eq!(decode_text(text.as_bytes()), (text.to_string(), TextEncoding::Utf8));
+
+        let (gbk, _, _) = encoding_rs::GBK.encode(text);
+        assert_eq!(decode_text(&gbk), (text.to_string(), TextEncoding::Gbk));
+
+        let utf16le: Vec<u8> = text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        assert_eq!(decode_text(&utf16le), (text.to_string(), TextEncoding::Utf16Le));
+
+        let mut utf16be = vec![0xFE, 0xFF];
+        utf16be.extend(text.replace('\n', "\r\n").encode_utf16().flat_map(|u| u.to_be_bytes()));
+        assert_eq!(decode_text(&utf16be), (text.to_string(), TextEncoding::Utf16Be));
+    }
+
+    #[test]
+    fn test_parse_numerals() {
+        assert_eq!(parse_numeral("十"), Some(10));
+        assert_eq!(parse_numeral("十二"), Some(12));
+        assert_eq!(parse_numeral("一百零五"), Some(105));
+        assert_eq!(parse_numeral("两千三百四十"), Some(2340));
+        assert_eq!(parse_numeral("１２"), Some(12));
+        assert_eq!(parse_numeral("XIV"), Some(14));
+        assert_eq!(parse_numeral("mcmxciv"), Some(1994));
+        assert_eq!(parse_numeral("章"), None);
+
+        // 不规范的罗马数字
+        assert_eq!(parse_numeral("IIIIIIIIIIIX"), None);
+        assert_eq!(parse_numeral("IXM"), None);
+        assert_eq!(parse_numeral("IIII"), None);
+        let preview = split_text("Chapter I\n开头。\nChapter IIIIIIIIIIIX\n结尾。", TextEncoding::Utf8, &SplitRules::default()).unwrap();
+        assert_eq!(preview.chapter_count(), 2);
+    }
+
+    #[test]
+    fn test_split_and_import_tree() {
+        let preview = split_text(SAMPLE, TextEncoding::Utf8, &SplitRules::default()).unwrap();
+        let titles: Vec<(&str, Vec<&str>)> = preview.chapters.iter()
+            .map(|c| (c.title.as_str(), c.children.iter().map(|c| c.title.as_str()).collect()))
+            .collect();
+        assert_eq!(titles, vec![
+            ("前言", vec![]),
+            ("第一卷 风起", vec!["第一章 开端", "第二章：相遇", "第四章 跳号"]),
+            ("第二卷 云涌", vec!["第1章 新卷", "尾声"]),
+        ]);
+        assert_eq!(preview.chapters[1].content, "卷首语");
+        assert_eq!(preview.chapters[1].children[0].content, "\u{3000}\u{3000}第一段。\n\n\u{3000}\u{3000}第二段。");
+        assert_eq!(preview.warnings.len(), 1);
+        assert!(preview.warnings[0].contains("第四章 跳号"));
+
+        let db = Database::open_in_memory().unwrap();
+        let report = import_preview_with(&db, &preview, None, "旧稿").unwrap();
+        assert_eq!(report.created_chapters, preview.chapter_count());
+        let novel = db.get_novel_by_id(report.novel_id.unwrap()).unwrap().unwrap();
+        assert_eq!(novel.word_count as usize, preview.word_count());
+
+        let data = crate::core::NovelManager::export_novel_data_with(&db, novel.id).unwrap();
+        let ordered: Vec<(usize, String, ChapterType)> = data.ordered_nodes(&[], true).into_iter()
+            .map(|n| (n.depth, n.chapter.title, n.chapter.chapter_type))
+            .collect();
+        assert_eq!(ordered[1], (0, "第一卷 风起".to_string(), ChapterType::Volume));
+        assert_eq!(ordered[2], (1, "第一章 开端".to_string(), ChapterType::Chapter));
+        assert_eq!(ordered.last().unwrap(), &(1, "尾声".to_string(), ChapterType::Chapter));
+    }
+}
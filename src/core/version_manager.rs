@@ -1,7 +1,16 @@
-use crate::db::{ChapterVersion, VersionType, get_database};
-use crate::utils::diff_utils::DiffUtils;
+use crate::db::{self, Chapter, ChapterVersion, Database, VersionBranch, VersionTag, VersionType};
+use crate::utils::diff_utils::{DiffGranularity, DiffUtils, StructuredDiff};
+use crate::utils::merge::{self, MergeResult};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// 每隔多少个版本保存一次完整快照
+const SNAPSHOT_INTERVAL: usize = 10;
+
+/// 章节第一次保存版本时自动创建的分支
+pub const MAIN_BRANCH_NAME: &str = "主线";
 
 pub struct VersionManager;
 
@@ -18,144 +27,181 @@ impl VersionManager {
         commit_message: Option<&str>,
         is_auto_save: bool
     ) -> Result<ChapterVersion> {
-        let db = get_database()?;
-        
-        // 获取上一个版本
-        let previous_versions = db.get_chapter_versions(chapter_id)?;
-        let parent_version = previous_versions.first().map(|v| v.id);
-        
-        let version_type = if previous_versions.len() % 10 == 0 {
-            VersionType::Snapshot
-        } else {
-            VersionType::Diff
-        };
-        
-        let diff_data = if let Some(previous_version) = previous_versions.first() {
-            if version_type == VersionType::Diff {
-                Some(DiffUtils::calculate_diff(&previous_version.content, content))
-            } else {
-                None
+        let content = content.to_string();
+        let commit_message = commit_message.map(str::to_string);
+        db::with_database(move |db| {
+            Self::create_version_with(db, chapter_id, &content, commit_message.as_deref(), is_auto_save)
+        }).await
+    }
+    
+    /// 在给定数据库上创建新版本
+    ///
+    /// 新版本接在章节当前分支的 head 之后并成为新的 head。
+    /// 版本链上每 10 个版本保存一次完整快照，其余版本只在 `diff_data` 中保存相对父版本的增量，
+    /// `content` 字段留空以避免重复存储全文。
+    pub(crate) fn create_version_with(
+        db: &Database,
+        chapter_id: i64,
+        content: &str,
+        commit_message: Option<&str>,
+        is_auto_save: bool
+    ) -> Result<ChapterVersion> {
+        db.transaction(|tx| {
+            let branch = Self::current_branch_with(tx, chapter_id)?;
+            let previous_version = branch.head_version_id
+                .map(|id| tx.get_chapter_version(id))
+                .transpose()?;
+            
+            let (version_type, stored_content, diff_data) = match &previous_version {
+                Some(previous) if Self::diffs_since_snapshot(tx, previous)? + 1 < SNAPSHOT_INTERVAL => {
+                    let previous_content = Self::reconstruct_content(tx, previous.id)?;
+                    (
+                        VersionType::Diff,
+                        String::new(),
+                        Some(DiffUtils::create_delta(&previous_content, content)),
+                    )
+                }
+                _ => (VersionType::Snapshot, content.to_string(), None),
+            };
+            
+            let version = tx.create_chapter_version(ChapterVersion {
+                id: 0,
+                chapter_id,
+                parent_version_id: previous_version.map(|v| v.id),
+                version_type,
+                content: stored_content,
+                diff_data,
+                // 改进的字数统计方法：统计所有非空白字符，对中英文都更准确
+                word_count: content.chars().filter(|c| !c.is_whitespace()).count() as i32,
+                created_at: Utc::now(),
+                commit_message: commit_message.unwrap_or("").to_string(),
+                is_auto_save,
+                branch_id: Some(branch.id),
+                merge_parent_version_id: None,
+            })?;
+            tx.set_version_branch_head(branch.id, version.id)?;
+            Ok(version)
+        })
+    }
+    
+    /// 从 `version` 沿父版本回溯到最近的完整内容之前经过的增量版本数（最多数到快照间隔）
+    fn diffs_since_snapshot(db: &Database, version: &ChapterVersion) -> Result<usize> {
+        let mut count = 0;
+        let mut current = version.clone();
+        while count < SNAPSHOT_INTERVAL
+            && current.version_type == VersionType::Diff
+            && current.diff_data.as_deref().is_some_and(DiffUtils::is_delta)
+        {
+            count += 1;
+            match current.parent_version_id {
+                Some(id) => current = db.get_chapter_version(id)?,
+                None => break,
+            }
+        }
+        Ok(count)
+    }
+    
+    /// 章节的当前分支，还没有分支时创建主线
+    ///
+    /// 没有分支记录的版本历史（例如从旧格式项目包导入的版本）以最新的版本作为主线的 head。
+    pub(crate) fn current_branch_with(db: &Database, chapter_id: i64) -> Result<VersionBranch> {
+        if let Some(branch) = db.get_current_version_branch(chapter_id)? {
+            return Ok(branch);
+        }
+        let existing = db.get_version_branches(chapter_id)?
+            .into_iter()
+            .find(|b| b.name == MAIN_BRANCH_NAME);
+        let branch = match existing {
+            Some(branch) => branch,
+            None => {
+                let head = db.get_chapter_versions(chapter_id)?.first().map(|v| v.id);
+                let branch = db.create_version_branch(chapter_id, MAIN_BRANCH_NAME, None)?;
+                db.set_version_branch_pointers(branch.id, head, None)?;
+                VersionBranch { head_version_id: head, ..branch }
             }
-        } else {
-            None
-        };
-        
-        let version = ChapterVersion {
-            id: 0,
-            chapter_id,
-            parent_version_id: parent_version,
-            version_type,
-            content: content.to_string(),
-            diff_data,
-            // 改进的字数统计方法：统计所有非空白字符，对中英文都更准确
-            word_count: content.chars().filter(|c| !c.is_whitespace()).count() as i32,
-            created_at: Utc::now(),
-            commit_message: commit_message.unwrap_or("").to_string(),
-            is_auto_save,
         };
-        
-        db.create_chapter_version(version)
+        db.set_current_version_branch(branch.id)?;
+        Ok(VersionBranch { is_current: true, ..branch })
     }
     
     /// 获取章节的所有版本
     pub async fn get_versions(&self, chapter_id: i64) -> Result<Vec<ChapterVersion>> {
-        let db = get_database()?;
-        db.get_chapter_versions(chapter_id)
+        db::with_database(move |db| db.get_chapter_versions(chapter_id)).await
     }
     
     /// 获取特定版本
     pub async fn get_version(&self, version_id: i64) -> Result<ChapterVersion> {
-        let db = get_database()?;
-        db.get_chapter_version(version_id)
+        db::with_database(move |db| db.get_chapter_version(version_id)).await
     }
     
     /// 恢复到特定版本
     pub async fn restore_to_version(&self, version_id: i64) -> Result<String> {
-        let version = self.get_version(version_id).await?;
-        
-        // 如果是快照版本，直接返回内容
-        if version.version_type == VersionType::Snapshot {
-            return Ok(version.content);
-        }
-        
-        // 如果是差异版本，需要重建内容
-        self.reconstruct_content_from_diff(version_id).await
+        db::with_database(move |db| Self::reconstruct_content(db, version_id)).await
     }
     
-    /// 从差异版本重建内容
-    async fn reconstruct_content_from_diff(&self, version_id: i64) -> Result<String> {
-        let mut current_version_id = version_id;
+    /// 重建特定版本的完整内容
+    ///
+    /// 沿 `parent_version_id` 回溯到最近的快照（或保存了全文的旧版差异记录），
+    /// 再按时间顺序依次应用增量。
+    pub(crate) fn reconstruct_content(db: &Database, version_id: i64) -> Result<String> {
         let mut versions_to_apply = Vec::new();
+        let mut current_version_id = Some(version_id);
         
         // 收集需要应用的版本链
-        while let Ok(version) = self.get_version(current_version_id).await {
-            versions_to_apply.push(version.clone());
+        let mut base_content = loop {
+            let Some(id) = current_version_id else {
+                return Err(anyhow::anyhow!("版本 {} 的版本链中缺少快照", version_id));
+            };
+            let version = db.get_chapter_version(id)?;
             
-            if let Some(parent_id) = version.parent_version_id {
-                current_version_id = parent_id;
-            } else {
-                break;
-            }
-        }
-        
-        // 反转版本链，从最早的快照开始应用差异
-        versions_to_apply.reverse();
-        
-        let mut current_content = String::new();
-        
-        for version in versions_to_apply {
             if version.version_type == VersionType::Snapshot {
-                current_content = version.content;
-            } else {
-                // 应用差异（这里需要实现差异应用逻辑）
-                current_content = self.apply_diff(&current_content, &version.diff_data.unwrap_or_default())?;
+                break version.content;
             }
-        }
-        
-        Ok(current_content)
-    }
-    
-    /// 应用差异到内容（简化实现）
-    fn apply_diff(&self, base_content: &str, diff_data: &str) -> Result<String> {
-        // 这里应该实现完整的差异应用逻辑
-        // 目前简化处理：如果差异数据包含完整内容，则使用差异数据
-        if diff_data.contains("[+") && diff_data.contains("[-") {
-            // 简单的差异应用：提取所有插入的内容
-            let mut result = String::new();
-            let mut in_insert = false;
-            let mut insert_content = String::new();
             
-            for c in diff_data.chars() {
-                if c == '+' {
-                    in_insert = true;
-                    insert_content.clear();
-                } else if c == ']' && in_insert {
-                    in_insert = false;
-                    result.push_str(&insert_content);
-                } else if in_insert {
-                    insert_content.push(c);
+            match version.diff_data.as_deref() {
+                Some(diff_data) if DiffUtils::is_delta(diff_data) => {
+                    current_version_id = version.parent_version_id;
+                    versions_to_apply.push(version);
                 }
+                // 旧格式的差异记录同时保存了完整内容，可直接作为基础
+                _ => break version.content,
             }
-            
-            Ok(result)
-        } else {
-            // 如果没有有效的差异数据，返回基础内容
-            Ok(base_content.to_string())
+        };
+        
+        // 从最早的基础版本开始依次应用差异
+        for version in versions_to_apply.iter().rev() {
+            base_content = Self::apply_diff(&base_content, version.diff_data.as_deref().unwrap_or_default())
+                .map_err(|e| anyhow::anyhow!("应用版本 {} 的差异失败: {}", version.id, e))?;
         }
+        
+        Ok(base_content)
+    }
+    
+    /// 应用差异到内容
+    fn apply_diff(base_content: &str, diff_data: &str) -> Result<String> {
+        DiffUtils::apply_delta(base_content, diff_data)
     }
     
-    /// 比较两个版本
-    pub async fn compare_versions(&self, version1_id: i64, version2_id: i64) -> Result<VersionComparison> {
-        let version1 = self.get_version(version1_id).await?;
-        let version2 = self.get_version(version2_id).await?;
+    /// 比较两个版本，`version1_id` 为旧版本
+    pub async fn compare_versions(&self, version1_id: i64, version2_id: i64, granularity: DiffGranularity) -> Result<VersionComparison> {
+        db::with_database(move |db| Self::compare_versions_with(db, version1_id, version2_id, granularity)).await
+    }
+    
+    pub(crate) fn compare_versions_with(db: &Database, version1_id: i64, version2_id: i64, granularity: DiffGranularity) -> Result<VersionComparison> {
+        let version1 = db.get_chapter_version(version1_id)?;
+        let version2 = db.get_chapter_version(version2_id)?;
+        let content1 = Self::reconstruct_content(db, version1_id)?;
+        let content2 = Self::reconstruct_content(db, version2_id)?;
         
-        let diff = DiffUtils::calculate_diff(&version1.content, &version2.content);
-        let stats = DiffUtils::get_change_statistics(&version1.content, &version2.content);
-        let similar_chunks = DiffUtils::find_similar_chunks(&version1.content, &version2.content, 10);
+        let diff = DiffUtils::structured_diff(&content1, &content2, granularity);
+        let stats = DiffUtils::get_change_statistics(&content1, &content2);
+        let similar_chunks = DiffUtils::find_similar_chunks(&content1, &content2, 10);
         
         Ok(VersionComparison {
             version1,
             version2,
+            content1,
+            content2,
             diff,
             statistics: stats,
             similar_chunks,
@@ -164,22 +210,62 @@ impl VersionManager {
     
     /// 获取版本时间线
     pub async fn get_version_timeline(&self, chapter_id: i64) -> Result<Vec<VersionTimelineEntry>> {
-        let versions = self.get_versions(chapter_id).await?;
+        db::with_database(move |db| Self::get_version_timeline_with(db, chapter_id)).await
+    }
+    
+    /// 按时间倒序列出版本，并为分支图分配泳道
+    ///
+    /// 按分支创建顺序，每个分支从 head 沿父版本回溯，认领尚未归属其他泳道的版本，
+    /// 因此主线占第 0 道，分支只占分叉点之后的版本。分支已删除的版本各自按版本链另占泳道。
+    pub(crate) fn get_version_timeline_with(db: &Database, chapter_id: i64) -> Result<Vec<VersionTimelineEntry>> {
+        let versions = db.get_chapter_versions(chapter_id)?;
+        let branches = db.get_version_branches(chapter_id)?;
+        let tags = db.get_version_tags(chapter_id)?;
         
-        let mut timeline = Vec::new();
+        let parents: HashMap<i64, Option<i64>> = versions.iter()
+            .map(|v| (v.id, v.parent_version_id))
+            .collect();
+        let mut lanes: HashMap<i64, usize> = HashMap::new();
+        let mut lane_count = 0;
+        let heads = branches.iter()
+            .filter_map(|b| b.head_version_id)
+            .chain(versions.iter().map(|v| v.id));
+        for head in heads {
+            if lanes.contains_key(&head) || !parents.contains_key(&head) {
+                continue;
+            }
+            let mut current = Some(head);
+            while let Some(id) = current.filter(|id| parents.contains_key(id) && !lanes.contains_key(id)) {
+                lanes.insert(id, lane_count);
+                current = parents[&id];
+            }
+            lane_count += 1;
+        }
         
-        for version in versions {
-            let entry = VersionTimelineEntry {
+        let mut timeline: Vec<VersionTimelineEntry> = versions.into_iter()
+            .map(|version| VersionTimelineEntry {
                 version_id: version.id,
+                parent_version_id: version.parent_version_id,
+                merge_parent_version_id: version.merge_parent_version_id,
                 created_at: version.created_at,
                 commit_message: version.commit_message.clone(),
                 version_type: version.version_type.clone(),
                 word_count: version.word_count,
                 is_auto_save: version.is_auto_save,
-            };
-            
-            timeline.push(entry);
-        }
+                branch_name: version.branch_id
+                    .and_then(|id| branches.iter().find(|b| b.id == id))
+                    .map(|b| b.name.clone()),
+                head_of: branches.iter()
+                    .filter(|b| b.head_version_id == Some(version.id))
+                    .cloned()
+                    .collect(),
+                milestones: tags.iter()
+                    .filter(|t| t.version_id == version.id)
+                    .cloned()
+                    .collect(),
+                lane: lanes.get(&version.id).copied().unwrap_or(0),
+            })
+            .collect();
         
         // 按时间倒序排列
         timeline.sort_by(|a, b| b.created_at.cmp(&a.created_at));
@@ -187,30 +273,263 @@ impl VersionManager {
         Ok(timeline)
     }
     
-    /// 清理过期的自动保存版本
-    pub async fn cleanup_auto_save_versions(&self, chapter_id: i64, keep_count: usize) -> Result<usize> {
-        let versions = self.get_versions(chapter_id).await?;
-        
-        let auto_save_versions: Vec<_> = versions.iter()
-            .filter(|v| v.is_auto_save)
-            .collect();
+    /// 获取章节的全部分支
+    pub async fn list_branches(&self, chapter_id: i64) -> Result<Vec<VersionBranch>> {
+        db::with_database(move |db| db.get_version_branches(chapter_id)).await
+    }
+    
+    /// 从某个版本创建新分支，不切换当前分支
+    pub async fn create_branch(&self, version_id: i64, name: &str) -> Result<VersionBranch> {
+        let name = name.to_string();
+        db::with_database(move |db| Self::create_branch_with(db, version_id, &name)).await
+    }
+    
+    pub(crate) fn create_branch_with(db: &Database, version_id: i64, name: &str) -> Result<VersionBranch> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("分支名称不能为空"));
+        }
+        db.transaction(|tx| {
+            let version = tx.get_chapter_version(version_id)?;
+            // 先确定当前分支，保证已有的历史归入主线
+            Self::current_branch_with(tx, version.chapter_id)?;
+            tx.create_version_branch(version.chapter_id, name, Some(version_id))
+        })
+    }
+    
+    /// 把章节正文切换到另一个分支，返回切换后的章节
+    pub async fn switch_branch(&self, branch_id: i64) -> Result<Chapter> {
+        db::with_database(move |db| Self::switch_branch_with(db, branch_id)).await
+    }
+    
+    /// 切换分支
+    ///
+    /// 正文与当前分支 head 不一致时，先在当前分支上保存一个自动版本，切换回来时不会丢失修改；
+    /// 然后把目标分支 head 的内容写入章节正文。
+    pub(crate) fn switch_branch_with(db: &Database, branch_id: i64) -> Result<Chapter> {
+        db.transaction(|tx| {
+            let target = tx.get_version_branch(branch_id)?;
+            let chapter = tx.get_chapter(target.chapter_id)?;
+            let current = Self::current_branch_with(tx, chapter.id)?;
+            if current.id == target.id {
+                return Ok(chapter);
+            }
             
-        if auto_save_versions.len() <= keep_count {
-            return Ok(0);
+            let saved = match current.head_version_id {
+                Some(head) => Self::reconstruct_content(tx, head)?,
+                None => String::new(),
+            };
+            if saved != chapter.content {
+                Self::create_version_with(tx, chapter.id, &chapter.content, Some("切换分支前保存"), true)?;
+            }
+            
+            tx.set_current_version_branch(target.id)?;
+            if let Some(head) = target.head_version_id {
+                let content = Self::reconstruct_content(tx, head)?;
+                if content != chapter.content {
+                    tx.update_chapter_content(chapter.id, &content)?;
+                }
+            }
+            tx.get_chapter(chapter.id)
+        })
+    }
+    
+    /// 重命名分支
+    pub async fn rename_branch(&self, branch_id: i64, name: &str) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("分支名称不能为空"));
+        }
+        let name = name.to_string();
+        db::with_database(move |db| db.rename_version_branch(branch_id, &name)).await
+    }
+    
+    /// 删除分支，分支上的版本仍保留在时间线中
+    pub async fn delete_branch(&self, branch_id: i64) -> Result<()> {
+        db::with_database(move |db| Self::delete_branch_with(db, branch_id)).await
+    }
+    
+    pub(crate) fn delete_branch_with(db: &Database, branch_id: i64) -> Result<()> {
+        if db.get_version_branch(branch_id)?.is_current {
+            return Err(anyhow::anyhow!("不能删除当前分支，请先切换到其他分支"));
+        }
+        db.delete_version_branch(branch_id)
+    }
+    
+    /// 在版本上标记里程碑
+    pub async fn add_milestone(&self, version_id: i64, name: &str, note: &str) -> Result<VersionTag> {
+        let (name, note) = (name.to_string(), note.to_string());
+        db::with_database(move |db| Self::add_milestone_with(db, version_id, &name, &note)).await
+    }
+    
+    pub(crate) fn add_milestone_with(db: &Database, version_id: i64, name: &str, note: &str) -> Result<VersionTag> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("里程碑名称不能为空"));
+        }
+        db.create_version_tag(version_id, name, note.trim())
+    }
+    
+    /// 删除里程碑，不影响版本本身
+    pub async fn remove_milestone(&self, tag_id: i64) -> Result<()> {
+        db::with_database(move |db| db.delete_version_tag(tag_id)).await
+    }
+    
+    /// 预览把某个版本合并到章节当前分支的结果
+    pub async fn preview_merge(&self, chapter_id: i64, theirs_version_id: i64) -> Result<VersionMerge> {
+        db::with_database(move |db| Self::preview_merge_with(db, chapter_id, theirs_version_id)).await
+    }
+    
+    /// 三方合并章节正文（我方）和 `theirs_version_id`（对方）
+    ///
+    /// 共同祖先是当前分支 head 与对方版本最近的共同祖先；没有共同祖先时（例如合作者的独立副本）
+    /// 以双方共有的段落作为祖先。对方版本可以属于其他章节。
+    pub(crate) fn preview_merge_with(db: &Database, chapter_id: i64, theirs_version_id: i64) -> Result<VersionMerge> {
+        let chapter = db.get_chapter(chapter_id)?;
+        let branch = Self::current_branch_with(db, chapter_id)?;
+        if branch.head_version_id == Some(theirs_version_id) {
+            return Err(anyhow::anyhow!("不能与当前分支的最新版本合并"));
+        }
+        let base_version_id = match branch.head_version_id {
+            Some(head) => Self::common_ancestor_with(db, head, theirs_version_id)?,
+            None => None,
+        };
+        if base_version_id == Some(theirs_version_id) {
+            return Err(anyhow::anyhow!("该版本已经包含在当前分支中"));
+        }
+        
+        let theirs = Self::reconstruct_content(db, theirs_version_id)?;
+        let base = match base_version_id {
+            Some(id) => Self::reconstruct_content(db, id)?,
+            None => merge::common_paragraphs(&chapter.content, &theirs),
+        };
+        Ok(VersionMerge {
+            chapter_id,
+            branch,
+            theirs_version_id,
+            theirs_label: Self::merge_source_label(db, theirs_version_id)?,
+            base_version_id,
+            result: merge::merge_paragraphs(&base, &chapter.content, &theirs),
+        })
+    }
+    
+    /// 提交合并结果
+    pub async fn commit_merge(&self, chapter_id: i64, theirs_version_id: i64, content: &str) -> Result<ChapterVersion> {
+        let content = content.to_string();
+        db::with_database(move |db| Self::commit_merge_with(db, chapter_id, theirs_version_id, &content)).await
+    }
+    
+    /// 把合并后的正文写入章节，并在当前分支上保存一个记录了合并来源的版本
+    pub(crate) fn commit_merge_with(db: &Database, chapter_id: i64, theirs_version_id: i64, content: &str) -> Result<ChapterVersion> {
+        db.transaction(|tx| {
+            let chapter = tx.get_chapter(chapter_id)?;
+            let message = format!("合并{}", Self::merge_source_label(tx, theirs_version_id)?);
+            if chapter.content != content {
+                tx.update_chapter_content(chapter_id, content)?;
+            }
+            let version = Self::create_version_with(tx, chapter_id, content, Some(&message), false)?;
+            tx.set_version_merge_parent(version.id, theirs_version_id)?;
+            Ok(ChapterVersion { merge_parent_version_id: Some(theirs_version_id), ..version })
+        })
+    }
+    
+    /// 合并来源的名称：以该版本为 head 的分支，或版本号
+    fn merge_source_label(db: &Database, version_id: i64) -> Result<String> {
+        let version = db.get_chapter_version(version_id)?;
+        let branch = db.get_version_branches(version.chapter_id)?
+            .into_iter()
+            .find(|b| b.head_version_id == Some(version_id));
+        Ok(match branch {
+            Some(branch) => format!("分支「{}」", branch.name),
+            None => format!("版本 #{}", version_id),
+        })
+    }
+    
+    /// 两个版本最近的共同祖先
+    ///
+    /// 沿父版本和合并来源回溯：先收集 `a` 的全部祖先（含自身），再从 `b` 开始由近到远查找。
+    pub(crate) fn common_ancestor_with(db: &Database, a: i64, b: i64) -> Result<Option<i64>> {
+        let mut ancestors = HashSet::new();
+        let mut queue = VecDeque::from([a]);
+        while let Some(id) = queue.pop_front() {
+            if ancestors.insert(id) {
+                let version = db.get_chapter_version(id)?;
+                queue.extend(version.parent_version_id.into_iter().chain(version.merge_parent_version_id));
+            }
         }
         
-        let versions_to_delete = &auto_save_versions[keep_count..];
-        let _db = get_database()?;
-        let mut deleted_count = 0;
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([b]);
+        while let Some(id) = queue.pop_front() {
+            if ancestors.contains(&id) {
+                return Ok(Some(id));
+            }
+            if seen.insert(id) {
+                let version = db.get_chapter_version(id)?;
+                queue.extend(version.parent_version_id.into_iter().chain(version.merge_parent_version_id));
+            }
+        }
+        Ok(None)
+    }
+    
+    /// 标注正文每一段最后由哪个版本修改
+    pub async fn annotate_paragraphs(&self, chapter_id: i64, content: &str) -> Result<Vec<ParagraphAnnotation>> {
+        let content = content.to_string();
+        db::with_database(move |db| Self::annotate_paragraphs_with(db, chapter_id, &content)).await
+    }
+    
+    /// 沿当前分支 head 的父版本链从最早的版本开始逐个比较段落，未变的段落沿用原来的来源，
+    /// 新增或改写的段落归于该版本；最后与 `content`（编辑器中的正文）比较，尚未保存为版本的段落没有来源。
+    /// 合并进来的段落归于合并版本。
+    pub(crate) fn annotate_paragraphs_with(db: &Database, chapter_id: i64, content: &str) -> Result<Vec<ParagraphAnnotation>> {
+        let mut chain = Vec::new();
+        let mut current = db.get_current_version_branch(chapter_id)?.and_then(|b| b.head_version_id);
+        while let Some(id) = current {
+            let version = db.get_chapter_version(id)?;
+            current = version.parent_version_id;
+            chain.push(version);
+        }
+        chain.reverse();
         
-        for _version in versions_to_delete {
-            // 注意：这里需要实现删除版本的方法
-            // 由于数据库层没有直接删除版本的方法，这里暂时跳过
-            // db.delete_version(version.id)?;
-            deleted_count += 1;
+        // 逐个版本重建全文，记录每一段来自链上的第几个版本
+        let mut text = String::new();
+        let mut origins: Vec<Option<usize>> = Vec::new();
+        for (index, version) in chain.iter().enumerate() {
+            let next = match version.diff_data.as_deref() {
+                Some(delta) if version.version_type == VersionType::Diff && DiffUtils::is_delta(delta) => {
+                    Self::apply_diff(&text, delta)
+                        .map_err(|e| anyhow::anyhow!("应用版本 {} 的差异失败: {}", version.id, e))?
+                }
+                _ => version.content.clone(),
+            };
+            origins = if index == 0 {
+                vec![Some(0); next.split('\n').count()]
+            } else {
+                carry_origins(&text, &next, &origins, Some(index))
+            };
+            text = next;
         }
+        let origins = if chain.is_empty() {
+            vec![None; content.split('\n').count()]
+        } else {
+            carry_origins(&text, content, &origins, None)
+        };
         
-        Ok(deleted_count)
+        Ok(content.split('\n')
+            .zip(origins)
+            .map(|(paragraph, origin)| ParagraphAnnotation {
+                text: paragraph.to_string(),
+                origin: origin.map(|index| {
+                    let version = &chain[index];
+                    ParagraphOrigin {
+                        version_id: version.id,
+                        created_at: version.created_at,
+                        commit_message: version.commit_message.clone(),
+                        is_auto_save: version.is_auto_save,
+                    }
+                }),
+            })
+            .collect())
     }
     
     /// 分析版本历史模式
@@ -248,11 +567,26 @@ impl VersionManager {
     }
 }
 
+/// 按段落比较 `old` 和 `new`：未变的段落沿用 `origins` 中的来源，其余段落记为 `changed`
+fn carry_origins(old: &str, new: &str, origins: &[Option<usize>], changed: Option<usize>) -> Vec<Option<usize>> {
+    let old_paragraphs: Vec<&str> = old.split('\n').collect();
+    let new_paragraphs: Vec<&str> = new.split('\n').collect();
+    let mut result = vec![changed; new_paragraphs.len()];
+    for op in capture_diff_slices(Algorithm::Myers, &old_paragraphs, &new_paragraphs) {
+        if let DiffOp::Equal { old_index, new_index, len } = op {
+            result[new_index..new_index + len].copy_from_slice(&origins[old_index..old_index + len]);
+        }
+    }
+    result
+}
+
 #[derive(Debug, Clone)]
 pub struct VersionComparison {
     pub version1: ChapterVersion,
     pub version2: ChapterVersion,
-    pub diff: String,
+    pub content1: String,
+    pub content2: String,
+    pub diff: StructuredDiff,
     pub statistics: crate::utils::diff_utils::ChangeStats,
     pub similar_chunks: Vec<crate::utils::diff_utils::SimilarChunk>,
 }
@@ -260,11 +594,52 @@ pub struct VersionComparison {
 #[derive(Debug, Clone)]
 pub struct VersionTimelineEntry {
     pub version_id: i64,
+    pub parent_version_id: Option<i64>,
+    /// 合并产生的版本所合并进来的版本
+    pub merge_parent_version_id: Option<i64>,
     pub created_at: DateTime<Utc>,
     pub commit_message: String,
     pub version_type: VersionType,
     pub word_count: i32,
     pub is_auto_save: bool,
+    /// 创建版本时所在的分支，分支已删除时为 `None`
+    pub branch_name: Option<String>,
+    /// 以此版本为 head 的分支
+    pub head_of: Vec<VersionBranch>,
+    pub milestones: Vec<VersionTag>,
+    /// 分支图中的泳道，0 为主线
+    pub lane: usize,
+}
+
+/// 引入某一段的版本
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParagraphOrigin {
+    pub version_id: i64,
+    pub created_at: DateTime<Utc>,
+    pub commit_message: String,
+    pub is_auto_save: bool,
+}
+
+/// 正文中的一段及其来源
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParagraphAnnotation {
+    pub text: String,
+    /// 最后修改这一段的版本，尚未保存为版本的段落为 `None`
+    pub origin: Option<ParagraphOrigin>,
+}
+
+/// 把某个版本合并到章节当前分支的预览
+#[derive(Debug, Clone)]
+pub struct VersionMerge {
+    pub chapter_id: i64,
+    /// 合并结果保存到的分支
+    pub branch: VersionBranch,
+    pub theirs_version_id: i64,
+    /// 对方版本的名称，例如 分支「结局B」 或 版本 #12
+    pub theirs_label: String,
+    /// 共同祖先，没有时以双方共有的段落作为祖先
+    pub base_version_id: Option<i64>,
+    pub result: MergeResult,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -275,4 +650,255 @@ pub struct VersionPatterns {
     pub average_time_between_saves: i64,
     pub first_version_date: DateTime<Utc>,
     pub last_version_date: DateTime<Utc>,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::merge::ConflictResolution;
+    
+    fn setup_chapter(db: &Database) -> i64 {
+        let novel = db.create_novel("测试小说").unwrap();
+        db.create_chapter(novel.id, "第一章", None).unwrap().id
+    }
+    
+    #[test]
+    fn test_restore_every_version_in_chain() {
+        let db = Database::open_in_memory().unwrap();
+        let chapter_id = setup_chapter(&db);
+        
+        let mut expected = Vec::new();
+        let mut text = String::new();
+        for i in 0..25 {
+            match i % 3 {
+                0 => text.push_str(&format!("第{}段：夜色渐深，他推开门。\n", i)),
+                1 => text = text.replacen("他", "她", 1),
+                _ => text = text.chars().skip(3).collect(),
+            }
+            let version = VersionManager::create_version_with(&db, chapter_id, &text, None, i % 2 == 0).unwrap();
+            expected.push((version.id, text.clone()));
+        }
+        
+        for (version_id, content) in &expected {
+            let restored = VersionManager::reconstruct_content(&db, *version_id).unwrap();
+            assert_eq!(restored.as_bytes(), content.as_bytes());
+        }
+    }
+    
+    #[test]
+    fn test_diff_versions_do_not_store_full_content() {
+        let db = Database::open_in_memory().unwrap();
+        let chapter_id = setup_chapter(&db);
+        
+        let first = VersionManager::create_version_with(&db, chapter_id, "初稿内容", None, false).unwrap();
+        let second = VersionManager::create_version_with(&db, chapter_id, "修改后的初稿内容", None, false).unwrap();
+        
+        assert_eq!(first.version_type, VersionType::Snapshot);
+        assert_eq!(first.content, "初稿内容");
+        assert_eq!(second.version_type, VersionType::Diff);
+        assert!(second.content.is_empty());
+        assert_eq!(second.parent_version_id, Some(first.id));
+        assert!(DiffUtils::is_delta(second.diff_data.as_deref().unwrap()));
+    }
+    
+    #[test]
+    fn test_legacy_diff_rows_fall_back_to_stored_content() {
+        let db = Database::open_in_memory().unwrap();
+        let chapter_id = setup_chapter(&db);
+        
+        let snapshot = VersionManager::create_version_with(&db, chapter_id, "旧版本", None, false).unwrap();
+        let legacy = db.create_chapter_version(ChapterVersion {
+            id: 0,
+            chapter_id,
+            parent_version_id: Some(snapshot.id),
+            version_type: VersionType::Diff,
+            content: "旧格式保存的全文".to_string(),
+            diff_data: Some(DiffUtils::calculate_diff("旧版本", "旧格式保存的全文")),
+            word_count: 8,
+            created_at: Utc::now(),
+            commit_message: String::new(),
+            is_auto_save: false,
+            branch_id: snapshot.branch_id,
+            merge_parent_version_id: None,
+        }).unwrap();
+        // 迁移后旧版本历史中最新的版本是主线的 head
+        db.set_version_branch_head(snapshot.branch_id.unwrap(), legacy.id).unwrap();
+        let next = VersionManager::create_version_with(&db, chapter_id, "旧格式保存的全文，继续写", None, false).unwrap();
+        
+        assert_eq!(VersionManager::reconstruct_content(&db, legacy.id).unwrap(), "旧格式保存的全文");
+        assert_eq!(VersionManager::reconstruct_content(&db, next.id).unwrap(), "旧格式保存的全文，继续写");
+        assert_eq!(next.parent_version_id, Some(legacy.id));
+    }
+    
+    /// 保存正文并在当前分支上创建版本
+    fn commit(db: &Database, chapter_id: i64, content: &str) -> ChapterVersion {
+        db.update_chapter_content(chapter_id, content).unwrap();
+        VersionManager::create_version_with(db, chapter_id, content, None, false).unwrap()
+    }
+    
+    #[test]
+    fn test_branches_fork_and_switch_working_copy() {
+        let db = Database::open_in_memory().unwrap();
+        let chapter_id = setup_chapter(&db);
+        
+        let opening = commit(&db, chapter_id, "开头。");
+        let ending_a = commit(&db, chapter_id, "开头。结局A。");
+        let main = db.get_current_version_branch(chapter_id).unwrap().unwrap();
+        assert_eq!((main.name.as_str(), main.head_version_id), (MAIN_BRANCH_NAME, Some(ending_a.id)));
+        
+        // 从开头分叉，切换后正文回到分叉点
+        let branch = VersionManager::create_branch_with(&db, opening.id, " 结局B ").unwrap();
+        assert_eq!(branch.name, "结局B");
+        let chapter = VersionManager::switch_branch_with(&db, branch.id).unwrap();
+        assert_eq!(chapter.content, "开头。");
+        let ending_b = commit(&db, chapter_id, "开头。结局B。");
+        assert_eq!(ending_b.parent_version_id, Some(opening.id));
+        assert_eq!(ending_b.branch_id, Some(branch.id));
+        
+        // 切换回主线前，未保存为版本的修改记在结局B上
+        db.update_chapter_content(chapter_id, "开头。结局B，改。").unwrap();
+        let chapter = VersionManager::switch_branch_with(&db, main.id).unwrap();
+        assert_eq!(chapter.content, "开头。结局A。");
+        let branch = db.get_version_branch(branch.id).unwrap();
+        assert_eq!(VersionManager::reconstruct_content(&db, branch.head_version_id.unwrap()).unwrap(), "开头。结局B，改。");
+        assert!(!branch.is_current);
+        assert_eq!(VersionManager::switch_branch_with(&db, branch.id).unwrap().content, "开头。结局B，改。");
+        
+        let timeline = VersionManager::get_version_timeline_with(&db, chapter_id).unwrap();
+        let lane = |id: i64| timeline.iter().find(|e| e.version_id == id).unwrap().lane;
+        assert_eq!((lane(opening.id), lane(ending_a.id), lane(ending_b.id)), (0, 0, 1));
+        let tip = timeline.iter().find(|e| e.version_id == branch.head_version_id.unwrap()).unwrap();
+        assert_eq!(tip.head_of.len(), 1);
+        assert_eq!(tip.branch_name.as_deref(), Some("结局B"));
+    }
+    
+    #[test]
+    fn test_milestones_and_branch_rules() {
+        let db = Database::open_in_memory().unwrap();
+        let chapter_id = setup_chapter(&db);
+        
+        // 分支上的版本链同样按间隔保存快照
+        let mut versions = Vec::new();
+        for i in 0..12 {
+            versions.push(commit(&db, chapter_id, &format!("第{}稿", i)));
+        }
+        assert_eq!(versions[10].version_type, VersionType::Snapshot);
+        let branch = VersionManager::create_branch_with(&db, versions[3].id, "改写").unwrap();
+        VersionManager::switch_branch_with(&db, branch.id).unwrap();
+        let forked = commit(&db, chapter_id, "改写稿");
+        assert_eq!(forked.version_type, VersionType::Diff);
+        assert_eq!(VersionManager::reconstruct_content(&db, forked.id).unwrap(), "改写稿");
+        
+        assert!(VersionManager::create_branch_with(&db, versions[5].id, "改写").is_err());
+        assert!(VersionManager::create_branch_with(&db, versions[5].id, "  ").is_err());
+        assert!(VersionManager::delete_branch_with(&db, branch.id).is_err());
+        
+        let milestone = VersionManager::add_milestone_with(&db, versions[3].id, "初稿完成", "交给编辑").unwrap();
+        assert!(VersionManager::add_milestone_with(&db, versions[4].id, "初稿完成", "").is_err());
+        let timeline = VersionManager::get_version_timeline_with(&db, chapter_id).unwrap();
+        let entry = timeline.iter().find(|e| e.version_id == versions[3].id).unwrap();
+        assert_eq!(entry.milestones, vec![milestone.clone()]);
+        
+        // 删除分支后版本仍在时间线上，另占泳道
+        let main = db.get_version_branches(chapter_id).unwrap().into_iter().find(|b| b.name == MAIN_BRANCH_NAME).unwrap();
+        VersionManager::switch_branch_with(&db, main.id).unwrap();
+        VersionManager::delete_branch_with(&db, branch.id).unwrap();
+        let timeline = VersionManager::get_version_timeline_with(&db, chapter_id).unwrap();
+        let orphan = timeline.iter().find(|e| e.version_id == forked.id).unwrap();
+        assert_eq!((orphan.branch_name.as_deref(), orphan.lane), (None, 1));
+        assert_eq!(timeline.len(), 13);
+        
+        db.delete_version_tag(milestone.id).unwrap();
+        assert!(db.get_version_tags(chapter_id).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_merge_branch_into_current() {
+        let db = Database::open_in_memory().unwrap();
+        let chapter_id = setup_chapter(&db);
+        let base = commit(&db, chapter_id, "甲。\n乙。\n丙。");
+        let branch = VersionManager::create_branch_with(&db, base.id, "改稿").unwrap();
+        commit(&db, chapter_id, "甲，主线改。\n乙。\n丙。");
+        
+        VersionManager::switch_branch_with(&db, branch.id).unwrap();
+        let revised = commit(&db, chapter_id, "甲。\n乙。\n丙，分支改。");
+        let main = db.get_version_branches(chapter_id).unwrap().into_iter().find(|b| b.name == MAIN_BRANCH_NAME).unwrap();
+        VersionManager::switch_branch_with(&db, main.id).unwrap();
+        
+        let merge = VersionManager::preview_merge_with(&db, chapter_id, revised.id).unwrap();
+        assert_eq!((merge.base_version_id, merge.theirs_label.as_str()), (Some(base.id), "分支「改稿」"));
+        assert_eq!(merge.result.conflict_count(), 0);
+        let merged = merge.result.resolve(&[]).unwrap();
+        assert_eq!(merged, "甲，主线改。\n乙。\n丙，分支改。");
+        let version = VersionManager::commit_merge_with(&db, chapter_id, revised.id, &merged).unwrap();
+        assert_eq!(db.get_chapter(chapter_id).unwrap().content, merged);
+        assert_eq!(db.get_chapter_version(version.id).unwrap().merge_parent_version_id, Some(revised.id));
+        assert_eq!(version.commit_message, "合并分支「改稿」");
+        assert_eq!(db.get_current_version_branch(chapter_id).unwrap().unwrap().head_version_id, Some(version.id));
+        
+        // 已合并的版本不能再次合并；分支继续修改后，共同祖先变为上次合并进来的版本
+        assert!(VersionManager::preview_merge_with(&db, chapter_id, revised.id).is_err());
+        VersionManager::switch_branch_with(&db, branch.id).unwrap();
+        let again = commit(&db, chapter_id, "甲。\n乙，分支再改。\n丙，分支改。");
+        VersionManager::switch_branch_with(&db, main.id).unwrap();
+        let merge = VersionManager::preview_merge_with(&db, chapter_id, again.id).unwrap();
+        assert_eq!(merge.base_version_id, Some(revised.id));
+        assert_eq!(merge.result.resolve(&[]).unwrap(), "甲，主线改。\n乙，分支再改。\n丙，分支改。");
+        let timeline = VersionManager::get_version_timeline_with(&db, chapter_id).unwrap();
+        let entry = timeline.iter().find(|e| e.version_id == version.id).unwrap();
+        assert_eq!(entry.merge_parent_version_id, Some(revised.id));
+    }
+    
+    #[test]
+    fn test_annotate_paragraphs_by_version() {
+        let db = Database::open_in_memory().unwrap();
+        let chapter_id = setup_chapter(&db);
+        assert_eq!(
+            VersionManager::annotate_paragraphs_with(&db, chapter_id, "未保存").unwrap(),
+            vec![ParagraphAnnotation { text: "未保存".to_string(), origin: None }]
+        );
+        
+        let first = commit(&db, chapter_id, "甲。\n\n乙。\n丙。");
+        let second = commit(&db, chapter_id, "甲。\n\n乙，改写。\n丙。");
+        // 中间隔着快照间隔之外的多个增量版本，来源仍能正确传递
+        for i in 0..12 {
+            commit(&db, chapter_id, &format!("甲。\n\n乙，改写。\n丙。\n草稿{}", i));
+        }
+        let last = commit(&db, chapter_id, "甲。\n\n乙，改写。\n丙。\n结尾。");
+        
+        let annotations = VersionManager::annotate_paragraphs_with(&db, chapter_id, "甲。\n\n乙，改写。\n丙。\n结尾。\n新写的。").unwrap();
+        let origins: Vec<Option<i64>> = annotations.iter().map(|a| a.origin.as_ref().map(|o| o.version_id)).collect();
+        assert_eq!(origins, vec![Some(first.id), Some(first.id), Some(second.id), Some(first.id), Some(last.id), None]);
+        assert_eq!(annotations[5].text, "新写的。");
+        assert!(!annotations[2].origin.as_ref().unwrap().is_auto_save);
+    }
+    
+    #[test]
+    fn test_merge_conflicts_and_unrelated_copies() {
+        let db = Database::open_in_memory().unwrap();
+        let chapter_id = setup_chapter(&db);
+        let base = commit(&db, chapter_id, "甲。\n乙。\n丙。");
+        commit(&db, chapter_id, "甲。\n乙，我方。\n丙。");
+        // 恢复旧版本后的另一条修改线
+        let branch = VersionManager::create_branch_with(&db, base.id, "旧稿").unwrap();
+        VersionManager::switch_branch_with(&db, branch.id).unwrap();
+        let theirs = commit(&db, chapter_id, "甲。\n乙，对方。\n丙。");
+        let main = db.get_version_branches(chapter_id).unwrap().into_iter().find(|b| b.name == MAIN_BRANCH_NAME).unwrap();
+        VersionManager::switch_branch_with(&db, main.id).unwrap();
+        
+        let merge = VersionManager::preview_merge_with(&db, chapter_id, theirs.id).unwrap();
+        assert_eq!(merge.result.conflict_count(), 1);
+        assert_eq!(
+            merge.result.resolve(&[ConflictResolution::Both]).unwrap(),
+            "甲。\n乙，我方。\n乙，对方。\n丙。"
+        );
+        
+        // 其他章节中的独立副本没有共同祖先，以共有段落为祖先
+        let novel_id = db.get_chapter(chapter_id).unwrap().novel_id;
+        let copy = db.create_chapter(novel_id, "合作者副本", None).unwrap();
+        let copied = commit(&db, copy.id, "甲。\n乙，我方。\n丙。\n丁，合作者加的。");
+        let merge = VersionManager::preview_merge_with(&db, chapter_id, copied.id).unwrap();
+        assert_eq!((merge.base_version_id, merge.result.conflict_count()), (None, 0));
+        assert_eq!(merge.result.resolve(&[]).unwrap(), "甲。\n乙，我方。\n丙。\n丁，合作者加的。");
+    }
+}
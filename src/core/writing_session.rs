@@ -0,0 +1,137 @@
+use crate::db::Database;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, Utc};
+use log::info;
+
+/// 两次输入间隔超过该时长视为空闲，空闲时间不计入写作时间
+pub const IDLE_TIMEOUT_SECS: i64 = 60;
+
+/// 编辑器中的一次写作会话
+///
+/// 打开章节时开始，切换章节、保存或离开编辑器时结束并写入 `writing_stats`。
+#[derive(Debug, Clone, PartialEq)]
+pub struct WritingSession {
+    pub novel_id: i64,
+    pub chapter_id: i64,
+    pub started_at: DateTime<Utc>,
+    last_activity: Option<DateTime<Utc>>,
+    last_word_count: i64,
+    pub words_added: i64,
+    pub words_deleted: i64,
+    /// 有效写作时间（秒）
+    pub active_seconds: i64,
+}
+
+impl WritingSession {
+    pub fn start(novel_id: i64, chapter_id: i64, content: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            novel_id,
+            chapter_id,
+            started_at: now,
+            last_activity: None,
+            last_word_count: count_words(content),
+            words_added: 0,
+            words_deleted: 0,
+            active_seconds: 0,
+        }
+    }
+
+    /// 记录一次编辑，累计字数变化和有效写作时间
+    pub fn record_edit(&mut self, content: &str, now: DateTime<Utc>) {
+        let word_count = count_words(content);
+        let delta = word_count - self.last_word_count;
+        if delta > 0 {
+            self.words_added += delta;
+        } else {
+            self.words_deleted -= delta;
+        }
+        self.last_word_count = word_count;
+
+        if let Some(last) = self.last_activity {
+            let gap = now.signed_duration_since(last);
+            if gap > Duration::zero() && gap <= Duration::seconds(IDLE_TIMEOUT_SECS) {
+                self.active_seconds += gap.num_seconds();
+            }
+        }
+        self.last_activity = Some(now);
+    }
+
+    /// 会话期间是否有过输入
+    pub fn has_activity(&self) -> bool {
+        self.last_activity.is_some()
+    }
+
+    pub fn net_words(&self) -> i64 {
+        self.words_added - self.words_deleted
+    }
+
+    /// 结束会话并累加到会话开始当天（本地日期）的写作统计，没有输入的会话不记录
+    pub fn finish(self, db: &Database) -> Result<()> {
+        if !self.has_activity() {
+            return Ok(());
+        }
+
+        let date = self.started_at.with_timezone(&Local).date_naive();
+        db.record_writing_session(self.novel_id, date, self.words_added, self.words_deleted, self.active_seconds)?;
+        info!(
+            "Writing session recorded: novel={}, chapter={}, +{} -{} words, {}s",
+            self.novel_id, self.chapter_id, self.words_added, self.words_deleted, self.active_seconds
+        );
+        Ok(())
+    }
+}
+
+/// 与编辑器一致的字数统计：所有非空白字符
+fn count_words(content: &str) -> i64 {
+    content.chars().filter(|c| !c.is_whitespace()).count() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracks_added_deleted_and_active_time() {
+        let start = Utc::now();
+        let mut session = WritingSession::start(1, 1, "开头", start);
+
+        session.record_edit("开头很好", start + Duration::seconds(5));
+        session.record_edit("开头很好的故事", start + Duration::seconds(20));
+        // 空闲十分钟后继续，空闲时间不计入
+        session.record_edit("开头", start + Duration::seconds(620));
+        session.record_edit("开头。", start + Duration::seconds(630));
+
+        assert_eq!(session.words_added, 6);
+        assert_eq!(session.words_deleted, 5);
+        assert_eq!(session.net_words(), 1);
+        assert_eq!(session.active_seconds, 25);
+    }
+
+    #[test]
+    fn test_finish_upserts_daily_stats() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("统计").unwrap();
+        let now = Utc::now();
+
+        let mut first = WritingSession::start(novel.id, 1, "", now);
+        first.record_edit("一二三", now);
+        first.record_edit("一二三四五", now + Duration::seconds(10));
+        first.finish(&db).unwrap();
+
+        let mut second = WritingSession::start(novel.id, 2, "甲乙丙", now);
+        second.record_edit("甲", now + Duration::seconds(30));
+        second.finish(&db).unwrap();
+
+        // 没有输入的会话不产生记录
+        WritingSession::start(novel.id, 3, "", now).finish(&db).unwrap();
+
+        let stats = db.get_writing_stats(novel.id).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].date, now.with_timezone(&Local).date_naive());
+        assert_eq!(stats[0].words_added, 5);
+        assert_eq!(stats[0].words_deleted, 2);
+        assert_eq!(stats[0].word_count, 3);
+        assert_eq!(stats[0].writing_time, 10);
+        assert_eq!(stats[0].session_count, 2);
+    }
+}
@@ -0,0 +1,189 @@
+// 章节归档
+//
+// 归档章节保留在书库中但不出现在章节树、搜索和小说字数里（字数触发器只统计 `is_archived = 0` 的章节）。
+// 未归档的章节不会挂在已归档的章节下：归档单个章节要求它没有未归档的子章节，
+// 取消归档时会一并取消已归档的上级章节。
+
+use super::{Chapter, Database};
+use super::trash::live_subtree;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+impl Database {
+    /// 获取小说中已归档的章节（不含回收站中的章节），按 `sort_path` 排序
+    pub fn get_archived_chapters(&self, novel_id: i64) -> Result<Vec<Chapter>> {
+        Ok(self.query_novel_chapters(novel_id, true)?
+            .into_iter()
+            .filter(|c| c.is_archived)
+            .collect())
+    }
+
+    /// 归档单个章节，返回归档的章节数
+    ///
+    /// 章节下还有未归档的子章节时返回错误，这种情况应归档整个子树。
+    pub fn archive_chapter(&self, chapter_id: i64) -> Result<usize> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            live_chapter(&conn, chapter_id)?;
+            let active_children: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM chapters WHERE parent_id = ?1 AND is_archived = 0 AND trash_id IS NULL",
+                [chapter_id],
+                |row| row.get(0),
+            )?;
+            if active_children > 0 {
+                return Err(anyhow::anyhow!("章节下还有 {} 个未归档的子章节，请归档整个子树", active_children));
+            }
+            set_archived(&conn, &[chapter_id], true)
+        })
+    }
+
+    /// 归档章节及其全部子章节，返回新归档的章节数
+    pub fn archive_subtree(&self, chapter_id: i64) -> Result<usize> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            live_chapter(&conn, chapter_id)?;
+            let ids: Vec<i64> = live_subtree(&conn, chapter_id)?.into_iter().map(|(id, _)| id).collect();
+            set_archived(&conn, &ids, true)
+        })
+    }
+
+    /// 取消归档单个章节，已归档的上级章节一并取消归档，返回取消归档的章节数
+    pub fn unarchive_chapter(&self, chapter_id: i64) -> Result<usize> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            let parent_id = live_chapter(&conn, chapter_id)?;
+            let mut ids = archived_ancestors(&conn, parent_id)?;
+            ids.push(chapter_id);
+            set_archived(&conn, &ids, false)
+        })
+    }
+
+    /// 取消归档章节及其全部子章节，已归档的上级章节一并取消归档，返回取消归档的章节数
+    pub fn unarchive_subtree(&self, chapter_id: i64) -> Result<usize> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            let parent_id = live_chapter(&conn, chapter_id)?;
+            let mut ids = archived_ancestors(&conn, parent_id)?;
+            ids.extend(live_subtree(&conn, chapter_id)?.into_iter().map(|(id, _)| id));
+            set_archived(&conn, &ids, false)
+        })
+    }
+}
+
+/// 确认章节存在且不在回收站中，返回它的上级章节
+fn live_chapter(conn: &Connection, chapter_id: i64) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT parent_id FROM chapters WHERE id = ?1 AND trash_id IS NULL",
+        [chapter_id],
+        |row| row.get(0),
+    ).optional()?.ok_or_else(|| anyhow::anyhow!("章节不存在或已在回收站中: {}", chapter_id))
+}
+
+/// 沿上级链收集已归档的章节
+fn archived_ancestors(conn: &Connection, mut parent_id: Option<i64>) -> Result<Vec<i64>> {
+    let mut ids = Vec::new();
+    while let Some(id) = parent_id {
+        if ids.contains(&id) {
+            break;
+        }
+        let row: Option<(Option<i64>, bool)> = conn.query_row(
+            "SELECT parent_id, is_archived FROM chapters WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional()?;
+        match row {
+            Some((grandparent, true)) => {
+                ids.push(id);
+                parent_id = grandparent;
+            },
+            _ => break,
+        }
+    }
+    Ok(ids)
+}
+
+/// 设置归档状态，返回状态实际改变的章节数
+fn set_archived(conn: &Connection, ids: &[i64], archived: bool) -> Result<usize> {
+    let mut changed = 0;
+    for id in ids {
+        changed += conn.execute(
+            "UPDATE chapters SET is_archived = ?1 WHERE id = ?2 AND is_archived != ?1",
+            params![archived, id],
+        )?;
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn novel_word_count(db: &Database, novel_id: i64) -> i32 {
+        db.get_novel_by_id(novel_id).unwrap().unwrap().word_count
+    }
+
+    #[test]
+    fn test_archive_excludes_word_count() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("归档").unwrap();
+        let first = db.create_chapter(novel.id, "第一章", None).unwrap();
+        let draft = db.create_chapter(novel.id, "废稿", None).unwrap();
+        db.update_chapter_content(first.id, "一二三").unwrap();
+        db.update_chapter_content(draft.id, "四五").unwrap();
+        assert_eq!(novel_word_count(&db, novel.id), 5);
+
+        assert_eq!(db.archive_chapter(draft.id).unwrap(), 1);
+        assert_eq!(novel_word_count(&db, novel.id), 3);
+        assert_eq!(db.archive_chapter(draft.id).unwrap(), 0);
+
+        // 编辑、新建和删除其他章节时，触发器同样不计入归档章节
+        db.update_chapter_content(draft.id, "四五六七").unwrap();
+        assert_eq!(novel_word_count(&db, novel.id), 3);
+        let second = db.create_chapter(novel.id, "第二章", None).unwrap();
+        assert_eq!(novel_word_count(&db, novel.id), 3);
+        db.delete_chapter(second.id).unwrap();
+        assert_eq!(novel_word_count(&db, novel.id), 3);
+
+        assert!(db.get_chapters_by_novel(novel.id).unwrap().iter().all(|c| c.id != draft.id));
+        let archived = db.get_archived_chapters(novel.id).unwrap();
+        assert_eq!(archived.iter().map(|c| c.id).collect::<Vec<_>>(), vec![draft.id]);
+
+        assert_eq!(db.unarchive_chapter(draft.id).unwrap(), 1);
+        assert_eq!(novel_word_count(&db, novel.id), 7);
+        assert!(db.get_archived_chapters(novel.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_archive_subtrees() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("归档子树").unwrap();
+        let volume = db.create_chapter(novel.id, "第一卷", None).unwrap();
+        let chapter = db.create_chapter(novel.id, "第一章", Some(volume.id)).unwrap();
+        let scene = db.create_chapter(novel.id, "场景", Some(chapter.id)).unwrap();
+        db.update_chapter_content(chapter.id, "一二三").unwrap();
+        db.update_chapter_content(scene.id, "四五").unwrap();
+
+        let error = db.archive_chapter(chapter.id).unwrap_err();
+        assert!(error.to_string().contains("请归档整个子树"));
+
+        assert_eq!(db.archive_subtree(volume.id).unwrap(), 3);
+        assert!(db.get_chapters_by_novel(novel.id).unwrap().is_empty());
+        assert_eq!(novel_word_count(&db, novel.id), 0);
+
+        // 取消归档场景时，它的上级章节和卷也恢复显示，第一章的其余子章节保持归档
+        assert_eq!(db.unarchive_chapter(scene.id).unwrap(), 3);
+        assert_eq!(novel_word_count(&db, novel.id), 5);
+        db.archive_chapter(scene.id).unwrap();
+        assert_eq!(novel_word_count(&db, novel.id), 3);
+
+        db.archive_subtree(volume.id).unwrap();
+        assert_eq!(db.unarchive_subtree(chapter.id).unwrap(), 3);
+        assert_eq!(db.get_chapters_by_novel(novel.id).unwrap().len(), 3);
+
+        // 回收站中的章节不能归档，也不算作已归档
+        db.archive_chapter(scene.id).unwrap();
+        db.trash_chapter(scene.id).unwrap();
+        assert!(db.get_archived_chapters(novel.id).unwrap().is_empty());
+        assert!(db.archive_subtree(scene.id).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+// 版本分支与里程碑
+//
+// 每个章节的版本历史由若干分支组成（见迁移 0009），新版本接在当前分支的 head 之后，
+// 同一章节同一时刻只有一个当前分支。里程碑是附加在版本上的命名标记，名称在章节内唯一。
+
+use super::{parse_timestamp, Database, VersionBranch, VersionTag};
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+const BRANCH_COLUMNS: &str = "id, chapter_id, name, head_version_id, base_version_id, is_current, created_at";
+const TAG_COLUMNS: &str = "id, chapter_id, version_id, name, note, created_at";
+
+impl Database {
+    /// 获取章节的全部分支，按创建顺序排列
+    pub fn get_version_branches(&self, chapter_id: i64) -> Result<Vec<VersionBranch>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM version_branches WHERE chapter_id = ?1 ORDER BY id",
+            BRANCH_COLUMNS
+        ))?;
+        let branches = stmt.query_map([chapter_id], branch_from_row)?;
+        branches.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+    }
+
+    pub fn get_version_branch(&self, branch_id: i64) -> Result<VersionBranch> {
+        let conn = self.conn()?;
+        get_branch(&conn, branch_id)
+    }
+
+    /// 获取章节的当前分支，章节还没有任何版本时为 `None`
+    pub fn get_current_version_branch(&self, chapter_id: i64) -> Result<Option<VersionBranch>> {
+        let conn = self.conn()?;
+        conn.query_row(
+            &format!("SELECT {} FROM version_branches WHERE chapter_id = ?1 AND is_current = 1", BRANCH_COLUMNS),
+            [chapter_id],
+            branch_from_row,
+        ).optional().map_err(|e| e.into())
+    }
+
+    /// 从 `base_version_id` 创建新分支，新分支的 head 即为该版本
+    pub fn create_version_branch(&self, chapter_id: i64, name: &str, base_version_id: Option<i64>) -> Result<VersionBranch> {
+        let mut branch = VersionBranch {
+            id: 0,
+            chapter_id,
+            name: name.to_string(),
+            head_version_id: base_version_id,
+            base_version_id,
+            is_current: false,
+            created_at: chrono::Utc::now(),
+        };
+        branch.id = self.insert_version_branch(&branch)?;
+        Ok(branch)
+    }
+
+    /// 按原样写入分支记录（导入项目包时使用），返回新分支的ID
+    pub fn insert_version_branch(&self, branch: &VersionBranch) -> Result<i64> {
+        let conn = self.conn()?;
+        ensure_unique_name(&conn, "version_branches", branch.chapter_id, &branch.name, "分支")?;
+        conn.execute(
+            "INSERT INTO version_branches (chapter_id, name, head_version_id, base_version_id, is_current, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                branch.chapter_id,
+                branch.name,
+                branch.head_version_id,
+                branch.base_version_id,
+                branch.is_current,
+                branch.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn set_version_branch_head(&self, branch_id: i64, version_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("UPDATE version_branches SET head_version_id = ?2 WHERE id = ?1", params![branch_id, version_id])?;
+        Ok(())
+    }
+
+    /// 同时设置分支的 head 和基础版本（导入项目包时在版本写入后使用）
+    pub fn set_version_branch_pointers(&self, branch_id: i64, head_version_id: Option<i64>, base_version_id: Option<i64>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE version_branches SET head_version_id = ?2, base_version_id = ?3 WHERE id = ?1",
+            params![branch_id, head_version_id, base_version_id],
+        )?;
+        Ok(())
+    }
+
+    /// 把分支设为所属章节的当前分支
+    pub fn set_current_version_branch(&self, branch_id: i64) -> Result<()> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            let branch = get_branch(&conn, branch_id)?;
+            conn.execute(
+                "UPDATE version_branches SET is_current = (id = ?2) WHERE chapter_id = ?1",
+                params![branch.chapter_id, branch_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn rename_version_branch(&self, branch_id: i64, name: &str) -> Result<()> {
+        let conn = self.conn()?;
+        let branch = get_branch(&conn, branch_id)?;
+        if branch.name != name {
+            ensure_unique_name(&conn, "version_branches", branch.chapter_id, name, "分支")?;
+        }
+        conn.execute("UPDATE version_branches SET name = ?2 WHERE id = ?1", params![branch_id, name])?;
+        Ok(())
+    }
+
+    /// 删除分支记录，分支上的版本保留在版本历史中（`branch_id` 置空）
+    pub fn delete_version_branch(&self, branch_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM version_branches WHERE id = ?1", [branch_id])?;
+        Ok(())
+    }
+
+    /// 获取小说所有章节的分支（不含回收站中的章节），按ID升序
+    pub fn get_novel_version_branches(&self, novel_id: i64) -> Result<Vec<VersionBranch>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT b.id, b.chapter_id, b.name, b.head_version_id, b.base_version_id, b.is_current, b.created_at
+             FROM version_branches b JOIN chapters c ON c.id = b.chapter_id
+             WHERE c.novel_id = ?1 AND c.trash_id IS NULL ORDER BY b.id"
+        )?;
+        let branches = stmt.query_map([novel_id], branch_from_row)?;
+        branches.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+    }
+
+    /// 获取章节的全部里程碑，按创建时间排列
+    pub fn get_version_tags(&self, chapter_id: i64) -> Result<Vec<VersionTag>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM version_tags WHERE chapter_id = ?1 ORDER BY created_at, id",
+            TAG_COLUMNS
+        ))?;
+        let tags = stmt.query_map([chapter_id], tag_from_row)?;
+        tags.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+    }
+
+    /// 在版本上添加里程碑
+    pub fn create_version_tag(&self, version_id: i64, name: &str, note: &str) -> Result<VersionTag> {
+        let chapter_id: i64 = {
+            let conn = self.conn()?;
+            conn.query_row("SELECT chapter_id FROM chapter_versions WHERE id = ?1", [version_id], |row| row.get(0))
+                .optional()?
+                .ok_or_else(|| anyhow::anyhow!("版本不存在: {}", version_id))?
+        };
+        let mut tag = VersionTag {
+            id: 0,
+            chapter_id,
+            version_id,
+            name: name.to_string(),
+            note: note.to_string(),
+            created_at: chrono::Utc::now(),
+        };
+        tag.id = self.insert_version_tag(&tag)?;
+        Ok(tag)
+    }
+
+    /// 按原样写入里程碑记录（导入项目包时使用），返回新里程碑的ID
+    pub fn insert_version_tag(&self, tag: &VersionTag) -> Result<i64> {
+        let conn = self.conn()?;
+        ensure_unique_name(&conn, "version_tags", tag.chapter_id, &tag.name, "里程碑")?;
+        conn.execute(
+            "INSERT INTO version_tags (chapter_id, version_id, name, note, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![tag.chapter_id, tag.version_id, tag.name, tag.note, tag.created_at.to_rfc3339()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn delete_version_tag(&self, tag_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM version_tags WHERE id = ?1", [tag_id])?;
+        Ok(())
+    }
+
+    /// 获取小说所有章节的里程碑（不含回收站中的章节），按ID升序
+    pub fn get_novel_version_tags(&self, novel_id: i64) -> Result<Vec<VersionTag>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.chapter_id, t.version_id, t.name, t.note, t.created_at
+             FROM version_tags t JOIN chapters c ON c.id = t.chapter_id
+             WHERE c.novel_id = ?1 AND c.trash_id IS NULL ORDER BY t.id"
+        )?;
+        let tags = stmt.query_map([novel_id], tag_from_row)?;
+        tags.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+    }
+}
+
+fn get_branch(conn: &Connection, branch_id: i64) -> Result<VersionBranch> {
+    conn.query_row(
+        &format!("SELECT {} FROM version_branches WHERE id = ?1", BRANCH_COLUMNS),
+        [branch_id],
+        branch_from_row,
+    ).optional()?.ok_or_else(|| anyhow::anyhow!("分支不存在: {}", branch_id))
+}
+
+/// 分支和里程碑的名称在章节内唯一
+fn ensure_unique_name(conn: &Connection, table: &str, chapter_id: i64, name: &str, kind: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS(SELECT 1 FROM {} WHERE chapter_id = ?1 AND name = ?2)", table),
+        params![chapter_id, name],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Err(anyhow::anyhow!("{}「{}」已存在", kind, name));
+    }
+    Ok(())
+}
+
+fn branch_from_row(row: &rusqlite::Row) -> rusqlite::Result<VersionBranch> {
+    Ok(VersionBranch {
+        id: row.get(0)?,
+        chapter_id: row.get(1)?,
+        name: row.get(2)?,
+        head_version_id: row.get(3)?,
+        base_version_id: row.get(4)?,
+        is_current: row.get(5)?,
+        created_at: parse_timestamp(&row.get::<_, String>(6)?)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e)))?,
+    })
+}
+
+fn tag_from_row(row: &rusqlite::Row) -> rusqlite::Result<VersionTag> {
+    Ok(VersionTag {
+        id: row.get(0)?,
+        chapter_id: row.get(1)?,
+        version_id: row.get(2)?,
+        name: row.get(3)?,
+        note: row.get(4)?,
+        created_at: parse_timestamp(&row.get::<_, String>(5)?)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, Box::new(e)))?,
+    })
+}
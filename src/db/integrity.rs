@@ -0,0 +1,491 @@
+use super::{parse_timestamp, Database};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use std::collections::{HashMap, HashSet};
+
+/// 需要检查的时间戳列（表名、列）
+const TIMESTAMP_COLUMNS: &[(&str, &[&str])] = &[
+    ("novels", &["created_at", "updated_at"]),
+    ("chapters", &["created_at", "updated_at"]),
+    ("chapter_versions", &["created_at"]),
+    ("version_branches", &["created_at"]),
+    ("version_tags", &["created_at"]),
+    ("novel_snapshots", &["created_at"]),
+    ("inspirations", &["created_at", "updated_at"]),
+    ("writing_goals", &["created_at", "completed_at"]),
+    ("trash", &["deleted_at"]),
+];
+
+/// 完整性检查发现的问题
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntegrityIssue {
+    /// `PRAGMA integrity_check` 报告的文件损坏，无法自动修复
+    Corruption(String),
+    /// 外键指向不存在的记录
+    ForeignKeyViolation { table: String, rowid: i64, parent: String },
+    /// 无法解析的时间戳
+    InvalidTimestamp { table: String, id: i64, column: String, value: String },
+    /// `YYYY-MM-DD HH:MM:SS` 旧格式的时间戳（按表和列汇总）
+    LegacyTimestamps { table: String, column: String, count: usize },
+    /// 章节的 `parent_id` 指向另一部小说的章节
+    CrossNovelParent { chapter_id: i64, novel_id: i64, parent_id: i64, parent_novel_id: i64 },
+    /// 章节层级中的环
+    HierarchyCycle { chapter_ids: Vec<i64> },
+    /// `novels.word_count` 与未归档、未删除章节的字数之和不一致
+    StaleWordCount { novel_id: i64, stored: i64, actual: i64 },
+}
+
+impl IntegrityIssue {
+    pub fn description(&self) -> String {
+        match self {
+            IntegrityIssue::Corruption(message) => format!("数据库文件损坏: {}", message),
+            IntegrityIssue::ForeignKeyViolation { table, rowid, parent } => {
+                format!("{} 中的记录 {} 引用了 {} 中不存在的记录", table, rowid, parent)
+            },
+            IntegrityIssue::InvalidTimestamp { table, id, column, value } => {
+                format!("{} 中的记录 {} 的 {} 无法解析: \"{}\"", table, id, column, value)
+            },
+            IntegrityIssue::LegacyTimestamps { table, column, count } => {
+                format!("{} 中有 {} 条记录的 {} 是旧的时间格式", table, count, column)
+            },
+            IntegrityIssue::CrossNovelParent { chapter_id, novel_id, parent_id, parent_novel_id } => {
+                format!(
+                    "章节 {}（小说 {}）的上级章节 {} 属于另一部小说 {}",
+                    chapter_id, novel_id, parent_id, parent_novel_id
+                )
+            },
+            IntegrityIssue::HierarchyCycle { chapter_ids } => {
+                let ids: Vec<String> = chapter_ids.iter().map(|id| id.to_string()).collect();
+                format!("章节层级存在循环: {}", ids.join(" → "))
+            },
+            IntegrityIssue::StaleWordCount { novel_id, stored, actual } => {
+                format!("小说 {} 记录的字数为 {}，实际为 {}", novel_id, stored, actual)
+            },
+        }
+    }
+
+    /// 是否可以自动修复
+    pub fn is_repairable(&self) -> bool {
+        !matches!(self, IntegrityIssue::Corruption(_))
+    }
+}
+
+/// 完整性检查结果
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct IntegrityReport {
+    pub issues: Vec<IntegrityIssue>,
+    /// 是否已经修复了其中可修复的问题
+    pub repaired: bool,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// 数据库文件是否损坏（损坏时不做任何自动修复，应从备份恢复）
+    pub fn is_corrupted(&self) -> bool {
+        self.issues.iter().any(|issue| matches!(issue, IntegrityIssue::Corruption(_)))
+    }
+
+    pub fn repairable_count(&self) -> usize {
+        self.issues.iter().filter(|issue| issue.is_repairable()).count()
+    }
+}
+
+/// 一行中需要规范化的时间戳，`values` 与 `columns` 一一对应
+struct TimestampRow {
+    table: &'static str,
+    id: i64,
+    columns: &'static [&'static str],
+    values: Vec<Option<String>>,
+}
+
+impl TimestampRow {
+    fn needs_repair(&self) -> bool {
+        self.values.iter().flatten().any(|value| !is_canonical(value))
+    }
+
+    /// 规范化为 RFC 3339；无法解析的值用同一行中其他可解析的时间代替，都不可解析时用当前时间
+    fn normalized(&self) -> Vec<Option<String>> {
+        let fallback = self.values.iter()
+            .flatten()
+            .find_map(|value| parse_timestamp(value).ok())
+            .unwrap_or_else(Utc::now);
+        self.values.iter()
+            .map(|value| value.as_ref().map(|v| normalize_timestamp(v).unwrap_or(fallback).to_rfc3339()))
+            .collect()
+    }
+}
+
+fn is_canonical(value: &str) -> bool {
+    DateTime::parse_from_rfc3339(value).is_ok()
+}
+
+fn normalize_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    parse_timestamp(value).ok()
+}
+
+impl Database {
+    /// 检查数据库完整性，`repair` 为真时在单个事务中修复可修复的问题
+    ///
+    /// 文件损坏时只报告不修复。修复不会改变小说和章节原有的更新时间。
+    pub fn check_integrity(&self, repair: bool) -> Result<IntegrityReport> {
+        let mut issues = {
+            let conn = self.conn()?;
+            let mut issues = check_file(&conn)?;
+            if issues.is_empty() {
+                issues.extend(check_foreign_keys(&conn)?);
+                issues.extend(check_hierarchy(&conn)?);
+                issues.extend(check_timestamps(&conn)?);
+                issues.extend(check_word_counts(&conn)?);
+            }
+            issues
+        };
+
+        let mut report = IntegrityReport { issues: Vec::new(), repaired: false };
+        if repair && !issues.is_empty() && issues.iter().all(IntegrityIssue::is_repairable) {
+            self.transaction(|tx| {
+                let conn = tx.conn()?;
+                repair_issues(&conn, &issues)
+            })?;
+            report.repaired = true;
+        }
+        report.issues.append(&mut issues);
+        Ok(report)
+    }
+}
+
+fn check_file(conn: &Connection) -> Result<Vec<IntegrityIssue>> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let messages = stmt.query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(messages.into_iter()
+        .filter(|message| message != "ok")
+        .map(IntegrityIssue::Corruption)
+        .collect())
+}
+
+fn check_foreign_keys(conn: &Connection) -> Result<Vec<IntegrityIssue>> {
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(IntegrityIssue::ForeignKeyViolation {
+            table: row.get(0)?,
+            rowid: row.get(1)?,
+            parent: row.get(2)?,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// 读取全部章节的 (id, novel_id, parent_id)
+fn chapter_links(conn: &Connection) -> Result<Vec<(i64, i64, Option<i64>)>> {
+    let mut stmt = conn.prepare("SELECT id, novel_id, parent_id FROM chapters ORDER BY id")?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+fn check_hierarchy(conn: &Connection) -> Result<Vec<IntegrityIssue>> {
+    let chapters = chapter_links(conn)?;
+    let novel_of: HashMap<i64, i64> = chapters.iter().map(|(id, novel_id, _)| (*id, *novel_id)).collect();
+
+    let mut issues = Vec::new();
+    // 跨小说的上级关系会被修复为顶层，不参与环检测
+    let mut parent_of = HashMap::new();
+    for (id, novel_id, parent_id) in &chapters {
+        let Some(parent_id) = parent_id else { continue };
+        match novel_of.get(parent_id) {
+            Some(parent_novel_id) if parent_novel_id != novel_id => {
+                issues.push(IntegrityIssue::CrossNovelParent {
+                    chapter_id: *id,
+                    novel_id: *novel_id,
+                    parent_id: *parent_id,
+                    parent_novel_id: *parent_novel_id,
+                });
+            },
+            Some(_) => {
+                parent_of.insert(*id, *parent_id);
+            },
+            // 指向不存在章节的情况由外键检查报告
+            None => {},
+        }
+    }
+
+    issues.extend(find_cycles(&chapters.iter().map(|(id, _, _)| *id).collect::<Vec<_>>(), &parent_of)
+        .into_iter()
+        .map(|chapter_ids| IntegrityIssue::HierarchyCycle { chapter_ids }));
+    Ok(issues)
+}
+
+/// 沿上级链查找环，每个环从其中最小的ID开始列出
+fn find_cycles(ids: &[i64], parent_of: &HashMap<i64, i64>) -> Vec<Vec<i64>> {
+    let mut finished = HashSet::new();
+    let mut cycles = Vec::new();
+
+    for &start in ids {
+        let mut path = Vec::new();
+        let mut on_path = HashSet::new();
+        let mut current = Some(start);
+        while let Some(id) = current {
+            if finished.contains(&id) {
+                break;
+            }
+            if on_path.contains(&id) {
+                let position = path.iter().position(|p| *p == id).unwrap_or(0);
+                let mut cycle = path[position..].to_vec();
+                let min_index = cycle.iter().enumerate().min_by_key(|(_, id)| **id).map(|(i, _)| i).unwrap_or(0);
+                cycle.rotate_left(min_index);
+                cycles.push(cycle);
+                break;
+            }
+            on_path.insert(id);
+            path.push(id);
+            current = parent_of.get(&id).copied();
+        }
+        finished.extend(path);
+    }
+    cycles
+}
+
+fn timestamp_rows(conn: &Connection) -> Result<Vec<TimestampRow>> {
+    let mut rows = Vec::new();
+    for (table, columns) in TIMESTAMP_COLUMNS {
+        let mut stmt = conn.prepare(&format!("SELECT id, {} FROM {}", columns.join(", "), table))?;
+        let table_rows = stmt.query_map([], |row| {
+            let values = (0..columns.len())
+                .map(|i| row.get::<_, Option<String>>(i + 1))
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(TimestampRow { table, id: row.get(0)?, columns, values })
+        })?;
+        for row in table_rows {
+            let row = row?;
+            if row.needs_repair() {
+                rows.push(row);
+            }
+        }
+    }
+    Ok(rows)
+}
+
+fn check_timestamps(conn: &Connection) -> Result<Vec<IntegrityIssue>> {
+    let mut issues = Vec::new();
+    let mut legacy: Vec<((&str, &str), usize)> = Vec::new();
+
+    for row in timestamp_rows(conn)? {
+        for (column, value) in row.columns.iter().zip(&row.values) {
+            let Some(value) = value else { continue };
+            if is_canonical(value) {
+                continue;
+            }
+            if normalize_timestamp(value).is_some() {
+                match legacy.iter_mut().find(|(key, _)| *key == (row.table, *column)) {
+                    Some((_, count)) => *count += 1,
+                    None => legacy.push(((row.table, column), 1)),
+                }
+            } else {
+                issues.push(IntegrityIssue::InvalidTimestamp {
+                    table: row.table.to_string(),
+                    id: row.id,
+                    column: column.to_string(),
+                    value: value.clone(),
+                });
+            }
+        }
+    }
+
+    issues.extend(legacy.into_iter().map(|((table, column), count)| IntegrityIssue::LegacyTimestamps {
+        table: table.to_string(),
+        column: column.to_string(),
+        count,
+    }));
+    Ok(issues)
+}
+
+fn check_word_counts(conn: &Connection) -> Result<Vec<IntegrityIssue>> {
+    let mut stmt = conn.prepare(
+        "SELECT n.id, COALESCE(n.word_count, 0),
+                (SELECT COALESCE(SUM(c.word_count), 0) FROM chapters c WHERE c.novel_id = n.id AND c.is_archived = 0 AND c.trash_id IS NULL)
+         FROM novels n"
+    )?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)))?;
+
+    let mut issues = Vec::new();
+    for row in rows {
+        let (novel_id, stored, actual) = row?;
+        if stored != actual {
+            issues.push(IntegrityIssue::StaleWordCount { novel_id, stored, actual });
+        }
+    }
+    Ok(issues)
+}
+
+/// 读取 (id, updated_at) 并规范化，用于修复后还原更新时间
+fn updated_at_snapshot(conn: &Connection, table: &str) -> Result<Vec<(i64, String)>> {
+    let mut stmt = conn.prepare(&format!("SELECT id, created_at, updated_at FROM {}", table))?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, Option<String>>(2)?))
+    })?;
+
+    let mut snapshot = Vec::new();
+    for row in rows {
+        let (id, created_at, updated_at) = row?;
+        let updated_at = updated_at.as_deref().and_then(normalize_timestamp)
+            .or_else(|| created_at.as_deref().and_then(normalize_timestamp))
+            .unwrap_or_else(Utc::now);
+        snapshot.push((id, updated_at.to_rfc3339()));
+    }
+    Ok(snapshot)
+}
+
+fn repair_issues(conn: &Connection, issues: &[IntegrityIssue]) -> Result<()> {
+    // 修复中的 UPDATE 会触发时间戳和字数触发器，最后统一还原更新时间
+    let chapter_times = updated_at_snapshot(conn, "chapters")?;
+    let novel_times = updated_at_snapshot(conn, "novels")?;
+
+    for issue in issues {
+        match issue {
+            IntegrityIssue::ForeignKeyViolation { table, rowid, .. } => repair_foreign_key(conn, table, *rowid)?,
+            IntegrityIssue::CrossNovelParent { chapter_id, .. } => {
+                conn.execute("UPDATE chapters SET parent_id = NULL WHERE id = ?1", [chapter_id])?;
+            },
+            IntegrityIssue::HierarchyCycle { chapter_ids } => {
+                if let Some(first) = chapter_ids.first() {
+                    conn.execute("UPDATE chapters SET parent_id = NULL WHERE id = ?1", [first])?;
+                }
+            },
+            _ => {},
+        }
+    }
+    // 外键修复可能删除了章节，其余问题重新计算后再修复
+    conn.execute(
+        "UPDATE novels SET word_count = (
+            SELECT COALESCE(SUM(c.word_count), 0) FROM chapters c WHERE c.novel_id = novels.id AND c.is_archived = 0 AND c.trash_id IS NULL
+         )
+         WHERE word_count IS NOT (
+            SELECT COALESCE(SUM(c.word_count), 0) FROM chapters c WHERE c.novel_id = novels.id AND c.is_archived = 0 AND c.trash_id IS NULL
+         )",
+        [],
+    )?;
+    for row in timestamp_rows(conn)? {
+        let assignments: Vec<String> = row.columns.iter()
+            .enumerate()
+            .map(|(i, column)| format!("{} = ?{}", column, i + 1))
+            .collect();
+        let sql = format!("UPDATE {} SET {} WHERE id = ?{}", row.table, assignments.join(", "), row.columns.len() + 1);
+        let mut values: Vec<rusqlite::types::Value> = row.normalized().into_iter()
+            .map(|value| value.map(rusqlite::types::Value::Text).unwrap_or(rusqlite::types::Value::Null))
+            .collect();
+        values.push(rusqlite::types::Value::Integer(row.id));
+        conn.execute(&sql, rusqlite::params_from_iter(values))?;
+    }
+
+    // 章节的更新会通过字数触发器改动小说，所以先还原章节再还原小说
+    for (table, times) in [("chapters", chapter_times), ("novels", novel_times)] {
+        let sql = format!("UPDATE {} SET updated_at = ?1 WHERE id = ?2 AND updated_at IS NOT ?1", table);
+        for (id, updated_at) in times {
+            conn.execute(&sql, params![updated_at, id])?;
+        }
+    }
+    Ok(())
+}
+
+/// 按外键声明的删除行为修复：`ON DELETE SET NULL` 的置空，其余删除该记录
+fn repair_foreign_key(conn: &Connection, table: &str, rowid: i64) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA foreign_key_list({})", table))?;
+    let keys = stmt.query_map([], |row| Ok((row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(6)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    for (parent, column, on_delete) in keys {
+        let dangling: bool = conn.query_row(
+            &format!(
+                "SELECT {column} IS NOT NULL AND NOT EXISTS (SELECT 1 FROM {parent} WHERE id = t.{column})
+                 FROM {table} t WHERE rowid = ?1"
+            ),
+            [rowid],
+            |row| row.get(0),
+        ).unwrap_or(false);
+        if !dangling {
+            continue;
+        }
+        if on_delete.eq_ignore_ascii_case("SET NULL") {
+            conn.execute(&format!("UPDATE {} SET {} = NULL WHERE rowid = ?1", table, column), [rowid])?;
+        } else {
+            conn.execute(&format!("DELETE FROM {} WHERE rowid = ?1", table), [rowid])?;
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_cycles() {
+        let parent_of: HashMap<i64, i64> = [(2, 3), (3, 4), (4, 2), (5, 1), (6, 6)].into_iter().collect();
+        let cycles = find_cycles(&[1, 2, 3, 4, 5, 6], &parent_of);
+        assert_eq!(cycles, vec![vec![2, 3, 4], vec![6]]);
+    }
+
+    #[test]
+    fn test_check_and_repair_integrity() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("主线").unwrap();
+        let other = db.create_novel("外传").unwrap();
+        let volume = db.create_chapter(novel.id, "第一卷", None).unwrap();
+        let first = db.create_chapter(novel.id, "第一章", Some(volume.id)).unwrap();
+        let second = db.create_chapter(novel.id, "第二章", Some(volume.id)).unwrap();
+        let stray = db.create_chapter(other.id, "番外", None).unwrap();
+        db.update_chapter_content(first.id, "一二三四五").unwrap();
+        assert!(db.check_integrity(false).unwrap().is_clean());
+
+        let conn = db.conn().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = OFF").unwrap();
+        conn.execute("UPDATE chapters SET created_at = '2024-01-01 08:00:00', updated_at = 'garbage' WHERE id = ?1", [first.id]).unwrap();
+        conn.execute("UPDATE chapters SET parent_id = ?1 WHERE id = ?2", [first.id, volume.id]).unwrap();
+        conn.execute("UPDATE chapters SET parent_id = ?1 WHERE id = ?2", [volume.id, stray.id]).unwrap();
+        conn.execute("UPDATE chapters SET parent_id = 999 WHERE id = ?1", [second.id]).unwrap();
+        conn.execute(
+            "INSERT INTO chapter_versions (chapter_id, content, created_at) VALUES (998, '', ?1)",
+            [Utc::now().to_rfc3339()],
+        ).unwrap();
+        // 上面的章节更新会经触发器改写小说字数，所以最后再制造字数不一致
+        let novel_updated = "2024-01-02T03:04:05+00:00";
+        conn.execute("UPDATE novels SET updated_at = ?1, word_count = 99 WHERE id = ?2", params![novel_updated, novel.id]).unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+        drop(conn);
+
+        let report = db.check_integrity(false).unwrap();
+        assert!(!report.repaired);
+        let issues = &report.issues;
+        assert_eq!(issues.iter().filter(|i| matches!(i, IntegrityIssue::ForeignKeyViolation { .. })).count(), 2);
+        assert!(issues.contains(&IntegrityIssue::CrossNovelParent {
+            chapter_id: stray.id, novel_id: other.id, parent_id: volume.id, parent_novel_id: novel.id,
+        }));
+        assert!(issues.contains(&IntegrityIssue::InvalidTimestamp {
+            table: "chapters".to_string(), id: first.id, column: "updated_at".to_string(), value: "garbage".to_string(),
+        }));
+        assert!(issues.contains(&IntegrityIssue::LegacyTimestamps {
+            table: "chapters".to_string(), column: "created_at".to_string(), count: 1,
+        }));
+        assert!(issues.contains(&IntegrityIssue::HierarchyCycle { chapter_ids: vec![volume.id, first.id] }));
+        assert!(issues.contains(&IntegrityIssue::StaleWordCount { novel_id: novel.id, stored: 99, actual: 5 }));
+
+        let report = db.check_integrity(true).unwrap();
+        assert!(report.repaired);
+        assert!(db.check_integrity(false).unwrap().is_clean(), "{:?}", db.check_integrity(false).unwrap());
+
+        let repaired_first = db.get_chapter(first.id).unwrap();
+        assert_eq!(repaired_first.created_at.to_rfc3339(), "2024-01-01T08:00:00+00:00");
+        assert_eq!(repaired_first.updated_at, repaired_first.created_at);
+        assert_eq!(db.get_chapter(volume.id).unwrap().parent_id, None);
+        assert_eq!(repaired_first.parent_id, Some(volume.id));
+        assert_eq!(db.get_chapter(second.id).unwrap().parent_id, None);
+        assert_eq!(db.get_chapter(stray.id).unwrap().parent_id, None);
+        let repaired_novel = db.get_novel_by_id(novel.id).unwrap().unwrap();
+        assert_eq!(repaired_novel.word_count, 5);
+        assert_eq!(repaired_novel.updated_at.to_rfc3339(), novel_updated);
+    }
+}
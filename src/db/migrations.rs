@@ -1,14 +1,407 @@
-use rusqlite::Connection;
+use rusqlite::{Connection, params};
 use anyhow::Result;
+use log::{info, warn};
+use std::path::{Path, PathBuf};
 
-pub fn run_migrations(conn: &Connection) -> Result<()> {
+/// 单个编号迁移
+///
+/// 迁移一旦发布就不应再修改；表结构变更需要追加新的编号迁移。
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+/// 按版本号升序排列的全部迁移
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "initial_schema", apply: migrate_initial_schema },
+    Migration { version: 2, name: "novel_pinning", apply: migrate_novel_pinning },
+    Migration { version: 3, name: "full_text_search", apply: migrate_full_text_search },
+    Migration { version: 4, name: "writing_session_stats", apply: migrate_writing_session_stats },
+    Migration { version: 5, name: "writing_goals", apply: migrate_writing_goals },
+    Migration { version: 6, name: "explicit_timestamps", apply: migrate_explicit_timestamps },
+    Migration { version: 7, name: "rfc3339_triggers", apply: migrate_rfc3339_triggers },
+    Migration { version: 8, name: "recycle_bin", apply: migrate_recycle_bin },
+    Migration { version: 9, name: "version_branches", apply: migrate_version_branches },
+    Migration { version: 10, name: "novel_snapshots", apply: migrate_novel_snapshots },
+    Migration { version: 11, name: "version_merges", apply: migrate_version_merges },
+];
+
+/// 当前代码期望的数据库版本
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+fn migrate_initial_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(include_str!("migrations/0001_initial_schema.sql"))?;
+    Ok(())
+}
+
+fn migrate_novel_pinning(conn: &Connection) -> Result<()> {
+    // 早期未记录版本的数据库可能已经通过旧版schema.sql拥有这两列
+    add_column_if_missing(conn, "novels", "is_pinned", "BOOLEAN DEFAULT 0")?;
+    add_column_if_missing(conn, "novels", "pinned_order", "INTEGER DEFAULT NULL")?;
+    Ok(())
+}
+
+fn migrate_full_text_search(conn: &Connection) -> Result<()> {
+    conn.execute_batch(include_str!("migrations/0003_full_text_search.sql"))?;
+    Ok(())
+}
+
+fn migrate_writing_session_stats(conn: &Connection) -> Result<()> {
+    // word_count 改为记录当天的净增字数，另外分别记录新增和删除的字数
+    add_column_if_missing(conn, "writing_stats", "words_added", "INTEGER DEFAULT 0")?;
+    add_column_if_missing(conn, "writing_stats", "words_deleted", "INTEGER DEFAULT 0")?;
+    Ok(())
+}
+
+fn migrate_writing_goals(conn: &Connection) -> Result<()> {
+    conn.execute_batch(include_str!("migrations/0005_writing_goals.sql"))?;
+    Ok(())
+}
+
+fn migrate_explicit_timestamps(conn: &Connection) -> Result<()> {
+    conn.execute_batch(include_str!("migrations/0006_explicit_timestamps.sql"))?;
+    Ok(())
+}
+
+fn migrate_rfc3339_triggers(conn: &Connection) -> Result<()> {
+    conn.execute_batch(include_str!("migrations/0007_rfc3339_triggers.sql"))?;
+    Ok(())
+}
+
+fn migrate_recycle_bin(conn: &Connection) -> Result<()> {
+    conn.execute_batch(include_str!("migrations/0008_recycle_bin.sql"))?;
+    Ok(())
+}
+
+fn migrate_version_branches(conn: &Connection) -> Result<()> {
+    conn.execute_batch(include_str!("migrations/0009_version_branches.sql"))?;
+    Ok(())
+}
+
+fn migrate_novel_snapshots(conn: &Connection) -> Result<()> {
+    conn.execute_batch(include_str!("migrations/0010_novel_snapshots.sql"))?;
+    Ok(())
+}
+
+fn migrate_version_merges(conn: &Connection) -> Result<()> {
+    conn.execute_batch(include_str!("migrations/0011_version_merges.sql"))?;
+    Ok(())
+}
+
+/// 仅当列不存在时添加列
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    if !column_exists(conn, table, column)? {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))?;
+    }
+    Ok(())
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in columns {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn ensure_ledger(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        );"
+    )?;
+    Ok(())
+}
+
+/// 获取已应用的最高迁移版本
+pub fn current_version(conn: &Connection) -> Result<i64> {
+    ensure_ledger(conn)?;
+    let version: Option<i64> = conn.query_row(
+        "SELECT MAX(version) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(version.unwrap_or(0))
+}
+
+/// 数据库中是否已有业务数据表（用于判断是否需要迁移前备份）
+fn has_user_tables(conn: &Connection) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master
+         WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != 'schema_migrations'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// 设置每个连接都需要的PRAGMA
+pub fn configure_connection(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         "PRAGMA foreign_keys = ON;
          PRAGMA journal_mode = WAL;
-         PRAGMA synchronous = NORMAL;"
+         PRAGMA synchronous = NORMAL;
+         PRAGMA busy_timeout = 5000;"
     )?;
-    
-    conn.execute_batch(include_str!("schema.sql"))?;
-    
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 运行所有未应用的迁移，每个迁移在独立事务中执行并记入 `schema_migrations`
+///
+/// 若提供了 `backup_dir` 且数据库中已有数据，则在迁移前先将整个数据库备份到该目录。
+/// 返回本次应用的迁移数量。
+pub fn run_migrations(conn: &Connection, backup_dir: Option<&Path>) -> Result<usize> {
+    configure_connection(conn)?;
+
+    let current = current_version(conn)?;
+    if current > latest_version() {
+        return Err(anyhow::anyhow!(
+            "数据库版本 ({}) 高于当前程序支持的版本 ({})，请升级程序",
+            current, latest_version()
+        ));
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter()
+        .filter(|m| m.version > current)
+        .collect();
+
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    if let Some(dir) = backup_dir {
+        if has_user_tables(conn)? {
+            let backup_path = backup_before_migration(conn, dir, current)?;
+            info!("迁移前已备份数据库到: {}", backup_path.display());
+        }
+    }
+
+    for migration in &pending {
+        info!("Applying migration {:04}_{}", migration.version, migration.name);
+        let tx = conn.unchecked_transaction()?;
+        (migration.apply)(&tx).map_err(|e| {
+            warn!("迁移 {:04}_{} 失败: {}", migration.version, migration.name, e);
+            anyhow::anyhow!("迁移 {:04}_{} 失败: {}", migration.version, migration.name, e)
+        })?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?1, ?2, ?3)",
+            params![migration.version, migration.name, chrono::Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+    }
+
+    Ok(pending.len())
+}
+
+/// 使用 `VACUUM INTO` 生成一份一致的数据库副本
+fn backup_before_migration(conn: &Connection, backup_dir: &Path, from_version: i64) -> Result<PathBuf> {
+    std::fs::create_dir_all(backup_dir)?;
+    let file_name = format!(
+        "novels-v{}-before-migration-{}.db",
+        from_version,
+        chrono::Utc::now().format("%Y%m%d%H%M%S%3f")
+    );
+    let backup_path = backup_dir.join(file_name);
+    conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy().as_ref()])?;
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 描述表结构（列定义、索引和触发器），用于比较不同升级路径的结果
+    fn schema_fingerprint(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn.prepare(
+            "SELECT type, name, tbl_name FROM sqlite_master
+             WHERE name NOT LIKE 'sqlite_%' ORDER BY type, name"
+        ).unwrap();
+        let objects: Vec<(String, String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let mut fingerprint = Vec::new();
+        for (kind, name, table) in objects {
+            fingerprint.push(format!("{} {} on {}", kind, name, table));
+            if kind == "table" {
+                let mut columns = conn.prepare(&format!("PRAGMA table_info({})", name)).unwrap();
+                let mut cols: Vec<String> = columns
+                    .query_map([], |row| Ok(format!("  {} {}", row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+                    .unwrap()
+                    .collect::<Result<_, _>>()
+                    .unwrap();
+                cols.sort();
+                fingerprint.extend(cols);
+            }
+        }
+        fingerprint
+    }
+
+    fn fresh_schema() -> Vec<String> {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn, None).unwrap();
+        schema_fingerprint(&conn)
+    }
+
+    fn insert_sample_novel(conn: &Connection) {
+        conn.execute(
+            "INSERT INTO novels (title, created_at, updated_at) VALUES ('旧小说', ?1, ?1)",
+            [chrono::Utc::now().to_rfc3339()],
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_fresh_database_reaches_latest_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        let applied = run_migrations(&conn, None).unwrap();
+
+        assert_eq!(applied, MIGRATIONS.len());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        assert!(column_exists(&conn, "novels", "is_pinned").unwrap());
+
+        // 再次运行不会重复应用
+        assert_eq!(run_migrations(&conn, None).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_upgrade_from_every_recorded_version() {
+        let expected = fresh_schema();
+
+        for start in 1..=latest_version() {
+            let conn = Connection::open_in_memory().unwrap();
+            ensure_ledger(&conn).unwrap();
+            for migration in MIGRATIONS.iter().filter(|m| m.version <= start) {
+                (migration.apply)(&conn).unwrap();
+                conn.execute(
+                    "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?1, ?2, '')",
+                    params![migration.version, migration.name],
+                ).unwrap();
+            }
+            insert_sample_novel(&conn);
+
+            run_migrations(&conn, None).unwrap();
+
+            assert_eq!(current_version(&conn).unwrap(), latest_version(), "start version {}", start);
+            assert_eq!(schema_fingerprint(&conn), expected, "start version {}", start);
+            let count: i64 = conn.query_row("SELECT COUNT(*) FROM novels", [], |r| r.get(0)).unwrap();
+            assert_eq!(count, 1);
+        }
+    }
+
+    #[test]
+    fn test_existing_versions_become_main_branch() {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_ledger(&conn).unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version < 9) {
+            (migration.apply)(&conn).unwrap();
+            conn.execute(
+                "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?1, ?2, '')",
+                params![migration.version, migration.name],
+            ).unwrap();
+        }
+        insert_sample_novel(&conn);
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO chapters (novel_id, title, sort_path, created_at, updated_at) VALUES (1, '第一章', 'V', ?1, ?1)",
+            [&now],
+        ).unwrap();
+        for parent in [None, Some(1)] {
+            conn.execute(
+                "INSERT INTO chapter_versions (chapter_id, parent_version_id, content, created_at) VALUES (1, ?1, '', ?2)",
+                params![parent, now],
+            ).unwrap();
+        }
+
+        run_migrations(&conn, None).unwrap();
+
+        let (name, head, is_current): (String, i64, bool) = conn.query_row(
+            "SELECT name, head_version_id, is_current FROM version_branches WHERE chapter_id = 1",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        ).unwrap();
+        assert_eq!((name.as_str(), head, is_current), ("主线", 2, true));
+        let unassigned: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM chapter_versions WHERE branch_id IS NULL",
+            [],
+            |r| r.get(0),
+        ).unwrap();
+        assert_eq!(unassigned, 0);
+    }
+
+    #[test]
+    fn test_upgrade_unversioned_legacy_databases() {
+        let expected = fresh_schema();
+
+        // 未引入台账前的两种历史形态：置顶列加入之前和之后
+        let legacy_fixtures = [
+            include_str!("migrations/0001_initial_schema.sql").to_string(),
+            format!(
+                "{}\nALTER TABLE novels ADD COLUMN is_pinned BOOLEAN DEFAULT 0;
+                 ALTER TABLE novels ADD COLUMN pinned_order INTEGER DEFAULT NULL;",
+                include_str!("migrations/0001_initial_schema.sql")
+            ),
+        ];
+
+        for fixture in legacy_fixtures {
+            let conn = Connection::open_in_memory().unwrap();
+            conn.execute_batch(&fixture).unwrap();
+            insert_sample_novel(&conn);
+
+            run_migrations(&conn, None).unwrap();
+
+            assert_eq!(current_version(&conn).unwrap(), latest_version());
+            assert_eq!(schema_fingerprint(&conn), expected);
+            let pinned: bool = conn.query_row("SELECT is_pinned FROM novels", [], |r| r.get(0)).unwrap();
+            assert!(!pinned);
+        }
+    }
+
+    #[test]
+    fn test_rejects_newer_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn, None).unwrap();
+        conn.execute(
+            "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?1, 'future', '')",
+            [latest_version() + 1],
+        ).unwrap();
+
+        assert!(run_migrations(&conn, None).is_err());
+    }
+
+    #[test]
+    fn test_backup_created_before_migrating_existing_data() {
+        let dir = std::env::temp_dir().join(format!("novel-writer-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("novels.db");
+        let backup_dir = dir.join("backups");
+
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch(include_str!("migrations/0001_initial_schema.sql")).unwrap();
+            insert_sample_novel(&conn);
+            run_migrations(&conn, Some(&backup_dir)).unwrap();
+
+            // 已是最新版本时不再备份
+            run_migrations(&conn, Some(&backup_dir)).unwrap();
+        }
+
+        let backups: Vec<_> = std::fs::read_dir(&backup_dir).unwrap().collect();
+        assert_eq!(backups.len(), 1);
+        let backup = Connection::open(backups[0].as_ref().unwrap().path()).unwrap();
+        assert_eq!(current_version(&backup).unwrap(), 0);
+        let count: i64 = backup.query_row("SELECT COUNT(*) FROM novels", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
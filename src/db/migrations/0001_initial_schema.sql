@@ -1,4 +1,5 @@
 -- 小说写作工具数据库Schema
+-- 迁移 0001：初始表结构（后续的列变更请新增编号迁移，不要修改本文件）
 
 -- 小说表
 CREATE TABLE IF NOT EXISTS novels (
@@ -9,9 +10,7 @@ CREATE TABLE IF NOT EXISTS novels (
     created_at TEXT NOT NULL,
     updated_at TEXT NOT NULL,
     word_count INTEGER DEFAULT 0,
-    status TEXT DEFAULT 'draft' CHECK(status IN ('draft', 'writing', 'completed', 'abandoned')),
-    is_pinned BOOLEAN DEFAULT 0,
-    pinned_order INTEGER DEFAULT NULL
+    status TEXT DEFAULT 'draft' CHECK(status IN ('draft', 'writing', 'completed', 'abandoned'))
 );
 
 -- 章节表（使用路径枚举法实现树状结构）
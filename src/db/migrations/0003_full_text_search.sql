@@ -0,0 +1,63 @@
+-- 迁移 0003：章节与灵感的全文索引
+-- 使用 trigram 分词器：按三字符滑动窗口建立索引，中文无需分词即可检索；
+-- 少于三个字符的检索词由应用层回退到 LIKE 查询。
+
+CREATE VIRTUAL TABLE IF NOT EXISTS chapters_fts USING fts5(
+    title,
+    content,
+    content = 'chapters',
+    content_rowid = 'id',
+    tokenize = 'trigram'
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS inspirations_fts USING fts5(
+    title,
+    content,
+    content = 'inspirations',
+    content_rowid = 'id',
+    tokenize = 'trigram'
+);
+
+-- 保持章节索引同步
+CREATE TRIGGER IF NOT EXISTS chapters_fts_insert
+AFTER INSERT ON chapters
+BEGIN
+    INSERT INTO chapters_fts (rowid, title, content) VALUES (NEW.id, NEW.title, COALESCE(NEW.content, ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS chapters_fts_delete
+AFTER DELETE ON chapters
+BEGIN
+    INSERT INTO chapters_fts (chapters_fts, rowid, title, content) VALUES ('delete', OLD.id, OLD.title, COALESCE(OLD.content, ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS chapters_fts_update
+AFTER UPDATE OF title, content ON chapters
+BEGIN
+    INSERT INTO chapters_fts (chapters_fts, rowid, title, content) VALUES ('delete', OLD.id, OLD.title, COALESCE(OLD.content, ''));
+    INSERT INTO chapters_fts (rowid, title, content) VALUES (NEW.id, NEW.title, COALESCE(NEW.content, ''));
+END;
+
+-- 保持灵感索引同步
+CREATE TRIGGER IF NOT EXISTS inspirations_fts_insert
+AFTER INSERT ON inspirations
+BEGIN
+    INSERT INTO inspirations_fts (rowid, title, content) VALUES (NEW.id, NEW.title, NEW.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS inspirations_fts_delete
+AFTER DELETE ON inspirations
+BEGIN
+    INSERT INTO inspirations_fts (inspirations_fts, rowid, title, content) VALUES ('delete', OLD.id, OLD.title, OLD.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS inspirations_fts_update
+AFTER UPDATE OF title, content ON inspirations
+BEGIN
+    INSERT INTO inspirations_fts (inspirations_fts, rowid, title, content) VALUES ('delete', OLD.id, OLD.title, OLD.content);
+    INSERT INTO inspirations_fts (rowid, title, content) VALUES (NEW.id, NEW.title, NEW.content);
+END;
+
+-- 为已有数据建立索引
+INSERT INTO chapters_fts (chapters_fts) VALUES ('rebuild');
+INSERT INTO inspirations_fts (inspirations_fts) VALUES ('rebuild');
@@ -0,0 +1,20 @@
+-- 迁移 0005：写作目标
+-- 每部小说每种类型同时只有一个进行中的目标；设定新目标时旧目标标记为 abandoned，
+-- 全部记录保留作为目标历史。
+
+CREATE TABLE IF NOT EXISTS writing_goals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    novel_id INTEGER NOT NULL,
+    goal_type TEXT NOT NULL,              -- total_words / chapter_count / daily_words
+    target INTEGER NOT NULL,
+    start_value INTEGER NOT NULL DEFAULT 0, -- 设定目标时的字数或章节数，用于计算应有进度
+    start_date TEXT NOT NULL,             -- YYYY-MM-DD格式
+    deadline TEXT,                        -- YYYY-MM-DD格式，可为空
+    status TEXT NOT NULL DEFAULT 'active', -- active / achieved / abandoned
+    created_at TEXT NOT NULL,
+    completed_at TEXT,
+
+    FOREIGN KEY (novel_id) REFERENCES novels(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_writing_goals_novel_status ON writing_goals(novel_id, status);
@@ -0,0 +1,18 @@
+-- 迁移 0006：更新时间戳触发器只在语句没有自己设置 updated_at 时生效
+-- 这样导入时可以保留原有的时间戳，也不会用 datetime('now') 覆盖应用写入的 RFC 3339 时间。
+
+DROP TRIGGER IF EXISTS update_novel_timestamp;
+CREATE TRIGGER update_novel_timestamp
+AFTER UPDATE ON novels
+WHEN NEW.updated_at IS OLD.updated_at
+BEGIN
+    UPDATE novels SET updated_at = datetime('now') WHERE id = NEW.id;
+END;
+
+DROP TRIGGER IF EXISTS update_chapter_timestamp;
+CREATE TRIGGER update_chapter_timestamp
+AFTER UPDATE ON chapters
+WHEN NEW.updated_at IS OLD.updated_at
+BEGIN
+    UPDATE chapters SET updated_at = datetime('now') WHERE id = NEW.id;
+END;
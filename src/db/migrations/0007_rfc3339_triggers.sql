@@ -0,0 +1,31 @@
+-- 迁移 0007：触发器写入 RFC 3339 时间戳，删除章节时同步小说字数
+-- datetime('now') 的 `YYYY-MM-DD HH:MM:SS` 格式与应用写入的时间戳混在一起时无法正确排序，
+-- 已有的旧格式时间戳由启动时的完整性检查修复。
+
+DROP TRIGGER IF EXISTS update_novel_timestamp;
+CREATE TRIGGER update_novel_timestamp
+AFTER UPDATE ON novels
+WHEN NEW.updated_at IS OLD.updated_at
+BEGIN
+    UPDATE novels SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = NEW.id;
+END;
+
+DROP TRIGGER IF EXISTS update_chapter_timestamp;
+CREATE TRIGGER update_chapter_timestamp
+AFTER UPDATE ON chapters
+WHEN NEW.updated_at IS OLD.updated_at
+BEGIN
+    UPDATE chapters SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS delete_novel_word_count
+AFTER DELETE ON chapters
+BEGIN
+    UPDATE novels
+    SET word_count = (
+        SELECT COALESCE(SUM(word_count), 0)
+        FROM chapters
+        WHERE novel_id = OLD.novel_id AND is_archived = 0
+    )
+    WHERE id = OLD.novel_id;
+END;
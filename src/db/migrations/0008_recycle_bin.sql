@@ -0,0 +1,60 @@
+-- 迁移 0008：回收站
+-- 删除操作只把记录标记为属于某个回收站条目（trash_id），同一次删除的章节子树共用一个条目；
+-- 恢复时清除标记，彻底删除时才真正删除记录（连同版本历史）。
+
+CREATE TABLE IF NOT EXISTS trash (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_type TEXT NOT NULL CHECK(item_type IN ('novel', 'chapter', 'inspiration')),
+    item_id INTEGER NOT NULL,     -- 被删除的小说、子树根章节或灵感
+    novel_id INTEGER NOT NULL,    -- 所属小说（小说条目即其本身）
+    title TEXT NOT NULL,
+    summary TEXT NOT NULL DEFAULT '',  -- 删除时包含的内容概要
+    deleted_at TEXT NOT NULL
+);
+
+ALTER TABLE novels ADD COLUMN trash_id INTEGER DEFAULT NULL REFERENCES trash(id) ON DELETE SET NULL;
+ALTER TABLE chapters ADD COLUMN trash_id INTEGER DEFAULT NULL REFERENCES trash(id) ON DELETE SET NULL;
+ALTER TABLE inspirations ADD COLUMN trash_id INTEGER DEFAULT NULL REFERENCES trash(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_chapters_trash ON chapters(trash_id);
+CREATE INDEX IF NOT EXISTS idx_trash_novel ON trash(novel_id);
+
+-- 小说字数不计回收站中的章节
+DROP TRIGGER IF EXISTS update_novel_word_count;
+CREATE TRIGGER update_novel_word_count
+AFTER UPDATE ON chapters
+BEGIN
+    UPDATE novels
+    SET word_count = (
+        SELECT COALESCE(SUM(word_count), 0)
+        FROM chapters
+        WHERE novel_id = NEW.novel_id AND is_archived = 0 AND trash_id IS NULL
+    )
+    WHERE id = NEW.novel_id;
+END;
+
+DROP TRIGGER IF EXISTS insert_novel_word_count;
+CREATE TRIGGER insert_novel_word_count
+AFTER INSERT ON chapters
+BEGIN
+    UPDATE novels
+    SET word_count = (
+        SELECT COALESCE(SUM(word_count), 0)
+        FROM chapters
+        WHERE novel_id = NEW.novel_id AND is_archived = 0 AND trash_id IS NULL
+    )
+    WHERE id = NEW.novel_id;
+END;
+
+DROP TRIGGER IF EXISTS delete_novel_word_count;
+CREATE TRIGGER delete_novel_word_count
+AFTER DELETE ON chapters
+BEGIN
+    UPDATE novels
+    SET word_count = (
+        SELECT COALESCE(SUM(word_count), 0)
+        FROM chapters
+        WHERE novel_id = OLD.novel_id AND is_archived = 0 AND trash_id IS NULL
+    )
+    WHERE id = OLD.novel_id;
+END;
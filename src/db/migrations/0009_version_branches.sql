@@ -0,0 +1,46 @@
+-- 迁移 0009：版本分支与里程碑
+-- 每个章节的版本历史可以有多个分支，新版本接在当前分支的最新版本（head）之后；
+-- 里程碑是附加在某个版本上的命名标记。已有的版本历史归入各章节的“主线”分支。
+
+CREATE TABLE IF NOT EXISTS version_branches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chapter_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    head_version_id INTEGER,      -- 分支上最新的版本
+    base_version_id INTEGER,      -- 分支创建时所基于的版本，主线为空
+    is_current BOOLEAN NOT NULL DEFAULT 0,  -- 编辑器中的正文属于哪个分支
+    created_at TEXT NOT NULL,
+
+    FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE,
+    FOREIGN KEY (head_version_id) REFERENCES chapter_versions(id) ON DELETE SET NULL,
+    FOREIGN KEY (base_version_id) REFERENCES chapter_versions(id) ON DELETE SET NULL,
+    UNIQUE(chapter_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS version_tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chapter_id INTEGER NOT NULL,
+    version_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    note TEXT NOT NULL DEFAULT '',
+    created_at TEXT NOT NULL,
+
+    FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE,
+    FOREIGN KEY (version_id) REFERENCES chapter_versions(id) ON DELETE CASCADE,
+    UNIQUE(chapter_id, name)
+);
+
+ALTER TABLE chapter_versions ADD COLUMN branch_id INTEGER DEFAULT NULL REFERENCES version_branches(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_version_branches_chapter ON version_branches(chapter_id);
+CREATE INDEX IF NOT EXISTS idx_version_tags_version ON version_tags(version_id);
+CREATE INDEX IF NOT EXISTS idx_chapter_versions_branch ON chapter_versions(branch_id);
+
+-- 已有的线性历史成为主线，最新的版本是主线的 head
+INSERT INTO version_branches (chapter_id, name, head_version_id, base_version_id, is_current, created_at)
+SELECT chapter_id, '主线', MAX(id), NULL, 1, MIN(created_at)
+FROM chapter_versions
+GROUP BY chapter_id;
+
+UPDATE chapter_versions
+SET branch_id = (SELECT b.id FROM version_branches b WHERE b.chapter_id = chapter_versions.chapter_id);
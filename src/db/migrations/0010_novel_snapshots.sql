@@ -0,0 +1,34 @@
+-- 迁移 0010：全书快照
+-- 快照在同一事务中记录小说每个章节当时的版本ID、标题、章节类型和在章节树中的位置。
+-- 章节ID不设外键：章节之后被删除时快照仍保留它的标题和位置；
+-- 章节被彻底删除时其版本随之删除，快照中的 version_id 置空。
+
+CREATE TABLE IF NOT EXISTS novel_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    novel_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    note TEXT NOT NULL DEFAULT '',
+    word_count INTEGER NOT NULL DEFAULT 0,  -- 未归档章节的字数之和
+    created_at TEXT NOT NULL,
+
+    FOREIGN KEY (novel_id) REFERENCES novels(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS novel_snapshot_chapters (
+    snapshot_id INTEGER NOT NULL,
+    chapter_id INTEGER NOT NULL,
+    parent_id INTEGER,
+    version_id INTEGER,
+    title TEXT NOT NULL,
+    chapter_type TEXT NOT NULL DEFAULT 'chapter',
+    sort_path TEXT NOT NULL,
+    word_count INTEGER NOT NULL DEFAULT 0,
+    is_archived BOOLEAN NOT NULL DEFAULT 0,
+
+    PRIMARY KEY (snapshot_id, chapter_id),
+    FOREIGN KEY (snapshot_id) REFERENCES novel_snapshots(id) ON DELETE CASCADE,
+    FOREIGN KEY (version_id) REFERENCES chapter_versions(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_novel_snapshots_novel ON novel_snapshots(novel_id);
+CREATE INDEX IF NOT EXISTS idx_novel_snapshot_chapters_version ON novel_snapshot_chapters(version_id);
@@ -0,0 +1,7 @@
+-- 迁移 0011：版本合并
+-- 合并产生的版本除了 `parent_version_id`（合并时当前分支的 head）之外，
+-- 还记录被合并进来的版本，之后再次合并时可以找到更近的共同祖先。
+
+ALTER TABLE chapter_versions ADD COLUMN merge_parent_version_id INTEGER DEFAULT NULL REFERENCES chapter_versions(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_chapter_versions_merge_parent ON chapter_versions(merge_parent_version_id);
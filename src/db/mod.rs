@@ -1,50 +1,185 @@
 mod models;
 mod migrations;
+mod pool;
+mod search;
+mod integrity;
+mod trash;
+mod archive;
+mod branches;
+mod snapshots;
 
 use anyhow::Result;
 use rusqlite::{Connection, params};
 pub use models::*;
+pub use integrity::{IntegrityIssue, IntegrityReport};
 use chrono::{DateTime, Utc};
+use crate::utils::sort_key;
 use crate::core::inspiration_manager::Inspiration;
 use log::{info, warn, error};
 
-use std::sync::Mutex;
+use pool::{ConnectionPool, PooledConnection};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use lazy_static::lazy_static;
 
 lazy_static! {
     static ref DB: Mutex<Option<Database>> = Mutex::new(None);
 }
 
-pub struct Database {
-    conn: Connection,
+/// 书库连接池中的连接数量
+const POOL_SIZE: usize = 4;
+
+/// 事务占用的连接
+/// 
+/// 离开作用域时（包括闭包出错或 panic）未提交的事务回滚，连接总是归还连接池。
+/// 连接从事务句柄的私有连接池中取出，闭包留下的句柄克隆此后无法再使用它。
+struct TransactionGuard<'a> {
+    pool: &'a ConnectionPool,
+    scoped: Arc<ConnectionPool>,
+    committed: bool,
+}
+
+impl TransactionGuard<'_> {
+    fn commit(&mut self) -> Result<()> {
+        self.scoped.get()?.execute_batch("COMMIT")?;
+        self.committed = true;
+        Ok(())
+    }
 }
 
-impl Clone for Database {
-    fn clone(&self) -> Self {
-        // 重新创建数据库连接而不是克隆
-        Database::new().expect("Failed to clone database connection")
+impl Drop for TransactionGuard<'_> {
+    fn drop(&mut self) {
+        let Some(conn) = self.scoped.try_take() else {
+            error!("事务连接仍被占用，无法归还连接池");
+            return;
+        };
+        if !self.committed {
+            if let Err(e) = conn.execute_batch("ROLLBACK") {
+                error!("事务回滚失败: {}", e);
+            }
+        }
+        self.pool.put(conn);
     }
 }
 
+/// 数据库访问句柄
+/// 
+/// 克隆只复制共享连接池的引用，不会重新打开文件或重复运行迁移，
+/// 因此可以自由地在组件和后台线程之间传递。
+#[derive(Clone)]
+pub struct Database {
+    pool: Arc<ConnectionPool>,
+    path: PathBuf,
+    in_transaction: bool,
+}
+
 impl Database {
-    pub fn new() -> Result<Self> {
-        info!("Creating data directory if it doesn't exist...");
-        std::fs::create_dir_all("data")?;
-        info!("Data directory created successfully");
+    /// 打开（必要时创建）指定路径的书库文件，并运行迁移
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let data_dir = path.parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
         
-        info!("Opening database connection to data/novels.db");
-        let conn = Connection::open("data/novels.db")?;
+        info!("Creating data directory if it doesn't exist: {}", data_dir.display());
+        std::fs::create_dir_all(data_dir)?;
+        
+        info!("Opening database connection to {}", path.display());
+        let conn = Connection::open(&path)?;
         info!("Database connection established successfully");
         
         info!("Running database migrations...");
-        migrations::run_migrations(&conn)?;
-        info!("Database migrations completed successfully");
+        let applied = migrations::run_migrations(&conn, Some(&data_dir.join("backups")))?;
+        info!("Database migrations completed successfully ({} applied)", applied);
+        
+        let mut connections = vec![conn];
+        for _ in 1..POOL_SIZE {
+            let conn = Connection::open(&path)?;
+            migrations::configure_connection(&conn)?;
+            connections.push(conn);
+        }
         
-        Ok(Self { conn })
+        Ok(Self {
+            pool: Arc::new(ConnectionPool::new(connections)),
+            path,
+            in_transaction: false,
+        })
+    }
+    
+    /// 当前书库文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    /// 创建内存数据库（用于测试）
+    #[cfg(test)]
+    pub(crate) fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        migrations::run_migrations(&conn, None)?;
+        Ok(Self {
+            pool: Arc::new(ConnectionPool::new(vec![conn])),
+            path: PathBuf::from(":memory:"),
+            in_transaction: false,
+        })
+    }
+    
+    /// 从连接池借出一个连接
+    fn conn(&self) -> Result<PooledConnection<'_>> {
+        self.pool.get()
+    }
+    
+    /// 在单个事务中执行一组数据库操作
+    /// 
+    /// 闭包收到的 `Database` 绑定在同一个连接上，其上的所有方法都属于该事务；
+    /// 闭包返回错误时整个事务回滚。在事务内部再次调用时直接复用外层事务。
+    pub fn transaction<T>(&self, f: impl FnOnce(&Database) -> Result<T>) -> Result<T> {
+        if self.in_transaction {
+            return f(self);
+        }
+        
+        let conn = self.pool.take()?;
+        if let Err(e) = conn.execute_batch("BEGIN IMMEDIATE") {
+            self.pool.put(conn);
+            return Err(e.into());
+        }
+        
+        let mut guard = TransactionGuard {
+            pool: &self.pool,
+            scoped: Arc::new(ConnectionPool::new(vec![conn])),
+            committed: false,
+        };
+        let scoped = Database {
+            pool: guard.scoped.clone(),
+            path: self.path.clone(),
+            in_transaction: true,
+        };
+        let value = f(&scoped)?;
+        guard.commit()?;
+        Ok(value)
+    }
+    
+    /// 使用 SQLite 在线备份接口把书库复制到 `dest`
+    /// 
+    /// 备份期间其他连接仍可读写，复制结果是一致的快照。
+    pub fn backup_to(&self, dest: &Path) -> Result<()> {
+        let conn = self.conn()?;
+        conn.backup(rusqlite::MAIN_DB, dest, None)?;
+        Ok(())
+    }
+    
+    /// 用备份文件覆盖当前书库的内容
+    /// 
+    /// 只替换数据，不运行迁移；旧版本的备份需要重新打开书库完成迁移。
+    pub fn restore_from(&self, src: &Path) -> Result<()> {
+        let mut conn = self.pool.take()?;
+        let result = conn.restore(rusqlite::MAIN_DB, src, None::<fn(rusqlite::backup::Progress)>);
+        self.pool.put(conn);
+        result.map_err(Into::into)
     }
     
     // 小说操作
     pub fn create_novel(&self, title: &str) -> Result<Novel> {
+        let conn = self.conn()?;
         let novel = Novel {
             id: 0,
             title: title.to_string(),
@@ -58,9 +193,9 @@ impl Database {
             pinned_order: None,
         };
         
-        self.conn.execute(
-            "INSERT INTO novels (title, author, description, created_at, updated_at, word_count, status) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        conn.execute(
+            "INSERT INTO novels (title, author, description, created_at, updated_at, word_count, status, is_pinned, pinned_order) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             params![
                 novel.title,
                 novel.author,
@@ -69,18 +204,21 @@ impl Database {
                 novel.updated_at.to_rfc3339(),
                 novel.word_count,
                 novel.status.as_str(),
+                novel.is_pinned,
+                novel.pinned_order,
             ],
         )?;
         
-        let id = self.conn.last_insert_rowid();
+        let id = conn.last_insert_rowid();
         Ok(Novel { id, ..novel })
     }
     
     pub fn get_all_novels(&self) -> Result<Vec<Novel>> {
+        let conn = self.conn()?;
         log::debug!("查询所有小说数据");
-        let mut stmt = self.conn.prepare(
-            "SELECT id, title, author, description, created_at, updated_at, word_count, status 
-             FROM novels ORDER BY updated_at DESC"
+        let mut stmt = conn.prepare(
+            "SELECT id, title, author, description, created_at, updated_at, word_count, status, is_pinned, pinned_order 
+             FROM novels WHERE trash_id IS NULL ORDER BY updated_at DESC"
         )?;
         
         let novel_iter = stmt.query_map([], |row| {
@@ -99,8 +237,8 @@ impl Database {
                 }
             };
             
-            let created_at = match DateTime::parse_from_rfc3339(&created_at_str) {
-                Ok(dt) => dt.with_timezone(&Utc),
+            let created_at = match parse_timestamp(&created_at_str) {
+                Ok(dt) => dt,
                 Err(e) => {
                     log::warn!("解析created_at时间失败: {}，格式: {}, 使用当前时间", e, created_at_str);
                     Utc::now()
@@ -116,8 +254,8 @@ impl Database {
                 }
             };
             
-            let updated_at = match DateTime::parse_from_rfc3339(&updated_at_str) {
-                Ok(dt) => dt.with_timezone(&Utc),
+            let updated_at = match parse_timestamp(&updated_at_str) {
+                Ok(dt) => dt,
                 Err(e) => {
                     log::warn!("解析updated_at时间失败: {}，格式: {}, 使用当前时间", e, updated_at_str);
                     Utc::now()
@@ -142,8 +280,8 @@ impl Database {
                 updated_at,
                 word_count,
                 status: NovelStatus::from_str(&status_str),
-                is_pinned: false,
-                pinned_order: None,
+                is_pinned: row.get::<_, Option<bool>>(8)?.unwrap_or(false),
+                pinned_order: row.get(9)?,
             })
         })?;
         
@@ -161,8 +299,9 @@ impl Database {
     }
     
     pub fn update_novel(&self, novel: &Novel) -> Result<()> {
-        self.conn.execute(
-            "UPDATE novels SET title = ?1, author = ?2, description = ?3, updated_at = ?4, word_count = ?5, status = ?6 WHERE id = ?7",
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE novels SET title = ?1, author = ?2, description = ?3, updated_at = ?4, word_count = ?5, status = ?6, is_pinned = ?7, pinned_order = ?8 WHERE id = ?9",
             params![
                 novel.title,
                 novel.author,
@@ -170,20 +309,34 @@ impl Database {
                 novel.updated_at.to_rfc3339(),
                 novel.word_count,
                 novel.status.as_str(),
+                novel.is_pinned,
+                novel.pinned_order,
                 novel.id,
             ],
         )?;
         Ok(())
     }
     
+    /// 直接设置小说的创建和更新时间（用于导入时保留原有时间戳）
+    pub fn set_novel_timestamps(&self, novel_id: i64, created_at: DateTime<Utc>, updated_at: DateTime<Utc>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE novels SET created_at = ?1, updated_at = ?2 WHERE id = ?3",
+            params![created_at.to_rfc3339(), updated_at.to_rfc3339(), novel_id],
+        )?;
+        Ok(())
+    }
+    
     pub fn delete_novel(&self, novel_id: i64) -> Result<()> {
-        self.conn.execute("DELETE FROM novels WHERE id = ?1", [novel_id])?;
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM novels WHERE id = ?1", [novel_id])?;
         Ok(())
     }
     
     // 章节操作
     pub fn create_chapter(&self, novel_id: i64, title: &str, parent_id: Option<i64>) -> Result<Chapter> {
         let sort_path = self.calculate_next_sort_path(novel_id, parent_id)?;
+        let conn = self.conn()?;
         
         // 即使是空内容，也使用统一的字数统计方法
         let content = "".to_string();
@@ -203,7 +356,7 @@ impl Database {
             is_archived: false,
         };
         
-        self.conn.execute(
+        conn.execute(
             "INSERT INTO chapters (novel_id, parent_id, title, content, sort_path, word_count, created_at, updated_at, chapter_type, is_archived) 
              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
             params![
@@ -220,18 +373,28 @@ impl Database {
             ],
         )?;
         
-        let id = self.conn.last_insert_rowid();
+        let id = conn.last_insert_rowid();
         Ok(Chapter { id, ..chapter })
     }
     
     pub fn get_chapters_by_novel(&self, novel_id: i64) -> Result<Vec<Chapter>> {
+        self.query_novel_chapters(novel_id, false)
+    }
+    
+    /// 获取小说的全部章节，包括已归档的章节
+    pub fn get_all_chapters_by_novel(&self, novel_id: i64) -> Result<Vec<Chapter>> {
+        self.query_novel_chapters(novel_id, true)
+    }
+    
+    fn query_novel_chapters(&self, novel_id: i64, include_archived: bool) -> Result<Vec<Chapter>> {
+        let conn = self.conn()?;
         log::debug!("查询小说 {} 的章节数据", novel_id);
-        let mut stmt = self.conn.prepare(
+        let mut stmt = conn.prepare(
             "SELECT id, novel_id, parent_id, title, content, sort_path, word_count, created_at, updated_at, chapter_type, is_archived 
-             FROM chapters WHERE novel_id = ?1 AND is_archived = 0 ORDER BY sort_path"
+             FROM chapters WHERE novel_id = ?1 AND trash_id IS NULL AND (?2 OR is_archived = 0) ORDER BY sort_path"
         )?;
         
-        let chapter_iter = stmt.query_map([novel_id], |row| {
+        let chapter_iter = stmt.query_map(params![novel_id, include_archived], |row| {
             // 获取字段
             let id = row.get(0)?;
             let novel_id = row.get(1)?;
@@ -250,8 +413,8 @@ impl Database {
                 }
             };
             
-            let created_at = match DateTime::parse_from_rfc3339(&created_at_str) {
-                Ok(dt) => dt.with_timezone(&Utc),
+            let created_at = match parse_timestamp(&created_at_str) {
+                Ok(dt) => dt,
                 Err(e) => {
                     log::warn!("解析chapter created_at时间失败: {}，格式: {}, 使用当前时间", e, created_at_str);
                     Utc::now()
@@ -267,8 +430,8 @@ impl Database {
                 }
             };
             
-            let updated_at = match DateTime::parse_from_rfc3339(&updated_at_str) {
-                Ok(dt) => dt.with_timezone(&Utc),
+            let updated_at = match parse_timestamp(&updated_at_str) {
+                Ok(dt) => dt,
                 Err(e) => {
                     log::warn!("解析chapter updated_at时间失败: {}，格式: {}, 使用当前时间", e, updated_at_str);
                     Utc::now()
@@ -317,10 +480,11 @@ impl Database {
     }
     
     pub fn update_chapter_content(&self, chapter_id: i64, content: &str) -> Result<()> {
+        let conn = self.conn()?;
         // 改进的字数统计方法：统计所有非空白字符，对中英文都更准确
         let word_count = content.chars().filter(|c| !c.is_whitespace()).count() as i32;
         
-        self.conn.execute(
+        conn.execute(
             "UPDATE chapters SET content = ?1, word_count = ?2, updated_at = ?3 WHERE id = ?4",
             params![
                 content,
@@ -335,14 +499,21 @@ impl Database {
     
     // 移除思维导图相关功能
     
-    fn calculate_next_sort_path(&self, _novel_id: i64, _parent_id: Option<i64>) -> Result<String> {
-        let timestamp = chrono::Utc::now().timestamp_millis();
-        Ok(format!("{:020}", timestamp))
+    /// 新章节排在同级章节（包括已归档和回收站中的章节）的最后
+    fn calculate_next_sort_path(&self, novel_id: i64, parent_id: Option<i64>) -> Result<String> {
+        let conn = self.conn()?;
+        let last: Option<String> = conn.query_row(
+            "SELECT MAX(sort_path) FROM chapters WHERE novel_id = ?1 AND parent_id IS ?2",
+            params![novel_id, parent_id],
+            |row| row.get(0),
+        )?;
+        Ok(sort_key::key_after(last.as_deref()))
     }
 
 
     pub fn get_chapter(&self, chapter_id: i64) -> Result<Chapter> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
             "SELECT id, novel_id, parent_id, title, content, sort_path, word_count, created_at, updated_at, chapter_type, is_archived 
              FROM chapters WHERE id = ?1"
         )?;
@@ -356,12 +527,10 @@ impl Database {
                 content: row.get(4)?,
                 sort_path: row.get(5)?,
                 word_count: row.get(6)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
-                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(7, rusqlite::types::Type::Text, Box::new(e)))?
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
-                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(8, rusqlite::types::Type::Text, Box::new(e)))?
-                    .with_timezone(&Utc),
+                created_at: parse_timestamp(&row.get::<_, String>(7)?)
+                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(7, rusqlite::types::Type::Text, Box::new(e)))?,
+                updated_at: parse_timestamp(&row.get::<_, String>(8)?)
+                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(8, rusqlite::types::Type::Text, Box::new(e)))?,
                 chapter_type: ChapterType::from_str(&row.get::<_, String>(9)?),
                 is_archived: row.get(10)?,
             })
@@ -369,7 +538,8 @@ impl Database {
     }
     
     pub fn update_chapter_parent(&self, chapter_id: i64, parent_id: Option<i64>, sort_path: &str) -> Result<()> {
-        self.conn.execute(
+        let conn = self.conn()?;
+        conn.execute(
             "UPDATE chapters SET parent_id = ?1, sort_path = ?2, updated_at = ?3 WHERE id = ?4",
             params![parent_id, sort_path, chrono::Utc::now().to_rfc3339(), chapter_id],
         )?;
@@ -377,7 +547,8 @@ impl Database {
     }
     
     pub fn update_chapter(&self, chapter: &Chapter) -> Result<()> {
-        self.conn.execute(
+        let conn = self.conn()?;
+        conn.execute(
             "UPDATE chapters SET title = ?1, content = ?2, word_count = ?3, chapter_type = ?4, updated_at = ?5 WHERE id = ?6",
             params![
                 chapter.title,
@@ -391,15 +562,39 @@ impl Database {
         Ok(())
     }
     
+    /// 只修改章节的排序键，不改变更新时间（用于重新编号）
+    pub fn set_chapter_sort_path(&self, chapter_id: i64, sort_path: &str) -> Result<()> {
+        let conn = self.conn()?;
+        let updated_at: String = conn.query_row("SELECT updated_at FROM chapters WHERE id = ?1", [chapter_id], |row| row.get(0))?;
+        conn.execute("UPDATE chapters SET sort_path = ?1 WHERE id = ?2", params![sort_path, chapter_id])?;
+        conn.execute(
+            "UPDATE chapters SET updated_at = ?1 WHERE id = ?2 AND updated_at IS NOT ?1",
+            params![updated_at, chapter_id],
+        )?;
+        Ok(())
+    }
+    
+    /// 直接设置章节的创建和更新时间（用于导入时保留原有时间戳）
+    pub fn set_chapter_timestamps(&self, chapter_id: i64, created_at: DateTime<Utc>, updated_at: DateTime<Utc>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE chapters SET created_at = ?1, updated_at = ?2 WHERE id = ?3",
+            params![created_at.to_rfc3339(), updated_at.to_rfc3339(), chapter_id],
+        )?;
+        Ok(())
+    }
+    
     pub fn delete_chapter(&self, chapter_id: i64) -> Result<()> {
-        self.conn.execute("DELETE FROM chapters WHERE id = ?1", [chapter_id])?;
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM chapters WHERE id = ?1", [chapter_id])?;
         Ok(())
     }
     
     pub fn create_chapter_version(&self, version: ChapterVersion) -> Result<ChapterVersion> {
-        self.conn.execute(
-            "INSERT INTO chapter_versions (chapter_id, parent_version_id, version_type, content, diff_data, word_count, created_at, commit_message, is_auto_save) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO chapter_versions (chapter_id, parent_version_id, version_type, content, diff_data, word_count, created_at, commit_message, is_auto_save, branch_id, merge_parent_version_id) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 version.chapter_id,
                 version.parent_version_id,
@@ -410,35 +605,33 @@ impl Database {
                 version.created_at.to_rfc3339(),
                 version.commit_message,
                 version.is_auto_save,
+                version.branch_id,
+                version.merge_parent_version_id,
             ],
         )?;
         
-        let id = self.conn.last_insert_rowid();
+        let id = conn.last_insert_rowid();
         Ok(ChapterVersion { id, ..version })
     }
     
+    /// 记录合并版本所合并进来的版本
+    pub fn set_version_merge_parent(&self, version_id: i64, merge_parent_version_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE chapter_versions SET merge_parent_version_id = ?2 WHERE id = ?1",
+            params![version_id, merge_parent_version_id],
+        )?;
+        Ok(())
+    }
+    
     pub fn get_chapter_versions(&self, chapter_id: i64) -> Result<Vec<ChapterVersion>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, chapter_id, parent_version_id, version_type, content, diff_data, word_count, created_at, commit_message, is_auto_save 
-             FROM chapter_versions WHERE chapter_id = ?1 ORDER BY created_at DESC"
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, chapter_id, parent_version_id, version_type, content, diff_data, word_count, created_at, commit_message, is_auto_save, branch_id, merge_parent_version_id 
+             FROM chapter_versions WHERE chapter_id = ?1 ORDER BY created_at DESC, id DESC"
         )?;
         
-        let version_iter = stmt.query_map([chapter_id], |row| {
-            Ok(ChapterVersion {
-                id: row.get(0)?,
-                chapter_id: row.get(1)?,
-                parent_version_id: row.get(2)?,
-                version_type: VersionType::from_str(&row.get::<_, String>(3)?),
-                content: row.get(4)?,
-                diff_data: row.get(5)?,
-                word_count: row.get(6)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
-                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(7, rusqlite::types::Type::Text, Box::new(e)))?
-                    .with_timezone(&Utc),
-                commit_message: row.get(8)?,
-                is_auto_save: row.get(9)?,
-            })
-        })?;
+        let version_iter = stmt.query_map([chapter_id], chapter_version_from_row)?;
         
         let mut versions = Vec::new();
         for version in version_iter {
@@ -449,33 +642,51 @@ impl Database {
     }
     
     pub fn get_chapter_version(&self, version_id: i64) -> Result<ChapterVersion> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, chapter_id, parent_version_id, version_type, content, diff_data, word_count, created_at, commit_message, is_auto_save 
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, chapter_id, parent_version_id, version_type, content, diff_data, word_count, created_at, commit_message, is_auto_save, branch_id, merge_parent_version_id 
              FROM chapter_versions WHERE id = ?1"
         )?;
         
-        stmt.query_row([version_id], |row| {
-            Ok(ChapterVersion {
-                id: row.get(0)?,
-                chapter_id: row.get(1)?,
-                parent_version_id: row.get(2)?,
-                version_type: VersionType::from_str(&row.get::<_, String>(3)?),
-                content: row.get(4)?,
-                diff_data: row.get(5)?,
-                word_count: row.get(6)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
-                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(7, rusqlite::types::Type::Text, Box::new(e)))?
-                    .with_timezone(&Utc),
-                commit_message: row.get(8)?,
-                is_auto_save: row.get(9)?,
-            })
-        }).map_err(|e| e.into())
+        stmt.query_row([version_id], chapter_version_from_row).map_err(|e| e.into())
+    }
+    
+    /// 获取小说所有章节的全部版本，按ID升序（父版本总在子版本之前）
+    pub fn get_novel_chapter_versions(&self, novel_id: i64) -> Result<Vec<ChapterVersion>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT v.id, v.chapter_id, v.parent_version_id, v.version_type, v.content, v.diff_data, v.word_count, v.created_at, v.commit_message, v.is_auto_save, v.branch_id, v.merge_parent_version_id 
+             FROM chapter_versions v JOIN chapters c ON c.id = v.chapter_id
+             WHERE c.novel_id = ?1 AND c.trash_id IS NULL ORDER BY v.id"
+        )?;
+        
+        let versions = stmt.query_map([novel_id], chapter_version_from_row)?;
+        versions.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
     }
 }
 
-pub fn init_database() -> Result<()> {
-    info!("Initializing database...");
-    let db = Database::new()?;
+fn chapter_version_from_row(row: &rusqlite::Row) -> rusqlite::Result<ChapterVersion> {
+    Ok(ChapterVersion {
+        id: row.get(0)?,
+        chapter_id: row.get(1)?,
+        parent_version_id: row.get(2)?,
+        version_type: VersionType::from_str(&row.get::<_, String>(3)?),
+        content: row.get(4)?,
+        diff_data: row.get(5)?,
+        word_count: row.get(6)?,
+        created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(7, rusqlite::types::Type::Text, Box::new(e)))?
+            .with_timezone(&Utc),
+        commit_message: row.get(8)?,
+        is_auto_save: row.get(9)?,
+        branch_id: row.get(10)?,
+        merge_parent_version_id: row.get(11)?,
+    })
+}
+
+pub fn init_database(path: &str) -> Result<()> {
+    info!("Initializing database at {}...", path);
+    let db = Database::open(path)?;
     info!("Database instance created successfully");
     
     info!("Locking database mutex...");
@@ -492,15 +703,41 @@ pub fn init_database() -> Result<()> {
     }
 }
 
+/// 切换到另一个书库文件
+/// 
+/// 新书库打开并迁移成功后才会替换当前连接，失败时保持原书库不变。
+pub fn switch_database(path: &str) -> Result<()> {
+    info!("Switching library to {}", path);
+    let db = Database::open(path)?;
+    
+    match DB.lock() {
+        Ok(mut guard) => {
+            *guard = Some(db);
+            info!("Library switched to {}", path);
+            Ok(())
+        },
+        Err(e) => {
+            error!("Failed to lock database mutex: {}", e);
+            Err(anyhow::anyhow!("Failed to lock database mutex: {}", e))
+        }
+    }
+}
+
+/// 当前程序期望的数据库结构版本（最新迁移的编号）
+pub fn schema_version() -> i64 {
+    migrations::latest_version()
+}
+
+/// 当前打开的书库文件路径
+pub fn current_database_path() -> Option<PathBuf> {
+    DB.lock().ok()?.as_ref().map(|db| db.path.clone())
+}
+
 pub fn get_database() -> Result<Database> {
-    info!("Getting database connection...");
     match DB.lock() {
         Ok(guard) => {
             match guard.as_ref().cloned() {
-                Some(db) => {
-                    info!("Database connection retrieved successfully");
-                    Ok(db)
-                },
+                Some(db) => Ok(db),
                 None => {
                     error!("Attempted to get database before initialization");
                     Err(anyhow::anyhow!("Database not initialized"))
@@ -514,17 +751,30 @@ pub fn get_database() -> Result<Database> {
     }
 }
 
+/// 在后台线程上执行数据库操作，避免阻塞UI线程
+pub async fn with_database<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&Database) -> Result<T> + Send + 'static,
+{
+    let db = get_database()?;
+    tokio::task::spawn_blocking(move || f(&db))
+        .await
+        .map_err(|e| anyhow::anyhow!("数据库后台任务失败: {}", e))?
+}
+
 // 灵感相关操作方法
 impl Database {
     pub fn create_inspiration(&self, novel_id: i64, title: &str, content: &str) -> Result<Inspiration> {
+        let conn = self.conn()?;
         let now = current_timestamp();
-        let mut stmt = self.conn.prepare(
+        let mut stmt = conn.prepare(
             "INSERT INTO inspirations (novel_id, title, content, created_at, updated_at) 
              VALUES (?1, ?2, ?3, ?4, ?4)"
         )?;
         
         stmt.execute(params![novel_id, title, content, now])?;
-        let id = self.conn.last_insert_rowid();
+        let id = conn.last_insert_rowid();
         
         Ok(Inspiration {
             id,
@@ -539,10 +789,29 @@ impl Database {
         })
     }
     
+    /// 按给定的时间戳和置顶状态插入灵感（用于导入），返回新ID；标签和关联章节需另行添加
+    pub fn insert_inspiration(&self, novel_id: i64, inspiration: &Inspiration) -> Result<i64> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO inspirations (novel_id, title, content, created_at, updated_at, is_pinned) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                novel_id,
+                inspiration.title,
+                inspiration.content,
+                inspiration.created_at,
+                inspiration.updated_at,
+                inspiration.is_pinned,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+    
     pub fn get_inspirations_by_novel(&self, novel_id: i64) -> Result<Vec<Inspiration>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
             "SELECT id, novel_id, title, content, created_at, updated_at, is_pinned 
-             FROM inspirations WHERE novel_id = ?1 ORDER BY is_pinned DESC, updated_at DESC"
+             FROM inspirations WHERE novel_id = ?1 AND trash_id IS NULL ORDER BY is_pinned DESC, updated_at DESC"
         )?;
         
         let rows = stmt.query_map(params![novel_id], |row| {
@@ -563,15 +832,16 @@ impl Database {
         
         // 加载标签和关联章节
         for insp in &mut inspirations {
-            insp.tags = self.get_inspiration_tags(insp.id)?;
-            insp.linked_chapters = self.get_inspiration_linked_chapters(insp.id)?;
+            insp.tags = Self::load_inspiration_tags(&conn, insp.id)?;
+            insp.linked_chapters = Self::load_inspiration_linked_chapters(&conn, insp.id)?;
         }
 
         Ok(inspirations)
     }
     
     pub fn get_inspiration(&self, inspiration_id: i64) -> Result<Option<Inspiration>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
             "SELECT id, novel_id, title, content, created_at, updated_at, is_pinned 
              FROM inspirations WHERE id = ?1"
         )?;
@@ -592,8 +862,8 @@ impl Database {
 
         if let Some(row) = rows.next() {
             let mut inspiration = row?;
-            inspiration.tags = self.get_inspiration_tags(inspiration.id)?;
-            inspiration.linked_chapters = self.get_inspiration_linked_chapters(inspiration.id)?;
+            inspiration.tags = Self::load_inspiration_tags(&conn, inspiration.id)?;
+            inspiration.linked_chapters = Self::load_inspiration_linked_chapters(&conn, inspiration.id)?;
             Ok(Some(inspiration))
         } else {
             Ok(None)
@@ -601,8 +871,9 @@ impl Database {
     }
     
     pub fn update_inspiration(&self, inspiration_id: i64, title: &str, content: &str) -> Result<()> {
+        let conn = self.conn()?;
         let now = current_timestamp();
-        self.conn.execute(
+        conn.execute(
             "UPDATE inspirations SET title = ?1, content = ?2, updated_at = ?3 WHERE id = ?4",
             params![title, content, now, inspiration_id],
         )?;
@@ -610,19 +881,21 @@ impl Database {
     }
     
     pub fn delete_inspiration(&self, inspiration_id: i64) -> Result<()> {
-        self.conn.execute("DELETE FROM inspirations WHERE id = ?1", params![inspiration_id])?;
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM inspirations WHERE id = ?1", params![inspiration_id])?;
         Ok(())
     }
     
     pub fn toggle_inspiration_pin(&self, inspiration_id: i64) -> Result<bool> {
-        let current: bool = self.conn.query_row(
+        let conn = self.conn()?;
+        let current: bool = conn.query_row(
             "SELECT is_pinned FROM inspirations WHERE id = ?1",
             params![inspiration_id],
             |row| row.get(0),
         )?;
         
         let new_value = !current;
-        self.conn.execute(
+        conn.execute(
             "UPDATE inspirations SET is_pinned = ?1, updated_at = ?2 WHERE id = ?3",
             params![new_value, current_timestamp(), inspiration_id],
         )?;
@@ -631,9 +904,10 @@ impl Database {
     }
     
     pub fn add_inspiration_tags(&self, inspiration_id: i64, tags: &[String]) -> Result<()> {
+        let conn = self.conn()?;
         for tag in tags {
             if !tag.trim().is_empty() {
-                self.conn.execute(
+                conn.execute(
                     "INSERT OR IGNORE INTO inspiration_tags (inspiration_id, tag) VALUES (?1, ?2)",
                     params![inspiration_id, tag.trim()],
                 )?;
@@ -643,7 +917,8 @@ impl Database {
     }
     
     pub fn remove_inspiration_tag(&self, inspiration_id: i64, tag: &str) -> Result<()> {
-        self.conn.execute(
+        let conn = self.conn()?;
+        conn.execute(
             "DELETE FROM inspiration_tags WHERE inspiration_id = ?1 AND tag = ?2",
             params![inspiration_id, tag],
         )?;
@@ -651,7 +926,8 @@ impl Database {
     }
     
     pub fn link_inspiration_to_chapter(&self, inspiration_id: i64, chapter_id: i64) -> Result<()> {
-        self.conn.execute(
+        let conn = self.conn()?;
+        conn.execute(
             "INSERT OR IGNORE INTO inspiration_chapter_links (inspiration_id, chapter_id) VALUES (?1, ?2)",
             params![inspiration_id, chapter_id],
         )?;
@@ -659,7 +935,8 @@ impl Database {
     }
     
     pub fn unlink_inspiration_from_chapter(&self, inspiration_id: i64, chapter_id: i64) -> Result<()> {
-        self.conn.execute(
+        let conn = self.conn()?;
+        conn.execute(
             "DELETE FROM inspiration_chapter_links WHERE inspiration_id = ?1 AND chapter_id = ?2",
             params![inspiration_id, chapter_id],
         )?;
@@ -667,40 +944,22 @@ impl Database {
     }
     
     pub fn search_inspirations(&self, novel_id: i64, query: &str) -> Result<Vec<Inspiration>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, novel_id, title, content, created_at, updated_at, is_pinned 
-             FROM inspirations 
-             WHERE novel_id = ?1 AND (title LIKE ?2 OR content LIKE ?2)
-             ORDER BY is_pinned DESC, updated_at DESC"
-        )?;
-        
-        let search_pattern = format!("%{}%", query);
-        let rows = stmt.query_map(params![novel_id, search_pattern], |row| {
-            Ok(Inspiration {
-                id: row.get(0)?,
-                novel_id: row.get(1)?,
-                title: row.get(2)?,
-                content: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                is_pinned: row.get(6)?,
-                tags: Vec::new(),
-                linked_chapters: Vec::new(),
-            })
-        })?;
+        let hits = self.search_inspiration_hits(Some(novel_id), query, i64::MAX as usize)?;
 
-        let mut inspirations: Vec<Inspiration> = rows.collect::<Result<_, _>>()?;
-        
-        for insp in &mut inspirations {
-            insp.tags = self.get_inspiration_tags(insp.id)?;
-            insp.linked_chapters = self.get_inspiration_linked_chapters(insp.id)?;
+        let mut inspirations = Vec::with_capacity(hits.len());
+        for hit in hits {
+            if let Some(inspiration) = self.get_inspiration(hit.inspiration_id)? {
+                inspirations.push(inspiration);
+            }
         }
 
+        // 置顶的灵感优先，其余保持相关度顺序
+        inspirations.sort_by_key(|insp| !insp.is_pinned);
         Ok(inspirations)
     }
     
-    fn get_inspiration_tags(&self, inspiration_id: i64) -> Result<Vec<String>> {
-        let mut stmt = self.conn.prepare(
+    fn load_inspiration_tags(conn: &Connection, inspiration_id: i64) -> Result<Vec<String>> {
+        let mut stmt = conn.prepare(
             "SELECT tag FROM inspiration_tags WHERE inspiration_id = ?1",
         )?;
         
@@ -708,8 +967,8 @@ impl Database {
         tags.collect::<Result<Vec<String>, _>>().map_err(|e| e.into())
     }
     
-    fn get_inspiration_linked_chapters(&self, inspiration_id: i64) -> Result<Vec<i64>> {
-        let mut stmt = self.conn.prepare(
+    fn load_inspiration_linked_chapters(conn: &Connection, inspiration_id: i64) -> Result<Vec<i64>> {
+        let mut stmt = conn.prepare(
             "SELECT chapter_id FROM inspiration_chapter_links WHERE inspiration_id = ?1",
         )?;
         
@@ -718,8 +977,302 @@ impl Database {
     }
 }
 
+// 写作统计相关操作方法
+impl Database {
+    /// 将一次写作会话累加到当天的 `writing_stats` 记录
+    pub fn record_writing_session(
+        &self,
+        novel_id: i64,
+        date: chrono::NaiveDate,
+        words_added: i64,
+        words_deleted: i64,
+        writing_time: i64,
+    ) -> Result<()> {
+        self.insert_writing_stats(novel_id, &WritingStatsRecord {
+            novel_id,
+            date,
+            word_count: words_added - words_deleted,
+            words_added,
+            words_deleted,
+            writing_time,
+            session_count: 1,
+        })
+    }
+    
+    /// 写入一整条写作记录，同一天已有记录时累加
+    pub fn insert_writing_stats(&self, novel_id: i64, record: &WritingStatsRecord) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO writing_stats (novel_id, date, word_count, words_added, words_deleted, writing_time, session_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(novel_id, date) DO UPDATE SET
+                word_count = COALESCE(word_count, 0) + excluded.word_count,
+                words_added = COALESCE(words_added, 0) + excluded.words_added,
+                words_deleted = COALESCE(words_deleted, 0) + excluded.words_deleted,
+                writing_time = COALESCE(writing_time, 0) + excluded.writing_time,
+                session_count = COALESCE(session_count, 0) + excluded.session_count",
+            params![
+                novel_id,
+                record.date.format("%Y-%m-%d").to_string(),
+                record.word_count,
+                record.words_added,
+                record.words_deleted,
+                record.writing_time,
+                record.session_count,
+            ],
+        )?;
+        Ok(())
+    }
+    
+    /// 获取小说的每日写作记录，按日期倒序
+    pub fn get_writing_stats(&self, novel_id: i64) -> Result<Vec<WritingStatsRecord>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT novel_id, date, COALESCE(word_count, 0), COALESCE(words_added, 0), COALESCE(words_deleted, 0),
+                    COALESCE(writing_time, 0), COALESCE(session_count, 0)
+             FROM writing_stats WHERE novel_id = ?1 ORDER BY date DESC"
+        )?;
+        
+        let rows = stmt.query_map([novel_id], |row| {
+            let date: String = row.get(1)?;
+            Ok(WritingStatsRecord {
+                novel_id: row.get(0)?,
+                date: chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e)))?,
+                word_count: row.get(2)?,
+                words_added: row.get(3)?,
+                words_deleted: row.get(4)?,
+                writing_time: row.get(5)?,
+                session_count: row.get(6)?,
+            })
+        })?;
+        
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+    }
+    
+    /// 设定新的写作目标，同类型的进行中目标会被标记为放弃
+    pub fn create_writing_goal(
+        &self,
+        novel_id: i64,
+        goal_type: GoalType,
+        target: i64,
+        start_value: i64,
+        deadline: Option<chrono::NaiveDate>,
+    ) -> Result<WritingGoalRecord> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            let now = Utc::now();
+            
+            conn.execute(
+                "UPDATE writing_goals SET status = ?1, completed_at = ?2
+                 WHERE novel_id = ?3 AND goal_type = ?4 AND status = ?5",
+                params![GoalStatus::Abandoned, now.to_rfc3339(), novel_id, goal_type, GoalStatus::Active],
+            )?;
+            
+            conn.execute(
+                "INSERT INTO writing_goals (novel_id, goal_type, target, start_value, start_date, deadline, status, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    novel_id,
+                    goal_type,
+                    target,
+                    start_value,
+                    chrono::Local::now().date_naive().format("%Y-%m-%d").to_string(),
+                    deadline.map(|d| d.format("%Y-%m-%d").to_string()),
+                    GoalStatus::Active,
+                    now.to_rfc3339(),
+                ],
+            )?;
+            let id = conn.last_insert_rowid();
+            drop(conn);
+            
+            tx.get_writing_goal(id)
+        })
+    }
+    
+    pub fn get_writing_goal(&self, goal_id: i64) -> Result<WritingGoalRecord> {
+        let conn = self.conn()?;
+        conn.query_row(
+            &format!("{} WHERE id = ?1", WRITING_GOAL_SELECT),
+            [goal_id],
+            writing_goal_from_row,
+        ).map_err(|e| e.into())
+    }
+    
+    /// 获取小说进行中的写作目标
+    pub fn get_active_writing_goals(&self, novel_id: i64) -> Result<Vec<WritingGoalRecord>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE novel_id = ?1 AND status = ?2 ORDER BY created_at",
+            WRITING_GOAL_SELECT
+        ))?;
+        let rows = stmt.query_map(params![novel_id, GoalStatus::Active], writing_goal_from_row)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+    }
+    
+    /// 获取小说的全部写作目标（目标历史），按设定时间倒序
+    pub fn get_writing_goal_history(&self, novel_id: i64) -> Result<Vec<WritingGoalRecord>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE novel_id = ?1 ORDER BY created_at DESC, id DESC",
+            WRITING_GOAL_SELECT
+        ))?;
+        let rows = stmt.query_map([novel_id], writing_goal_from_row)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+    }
+    
+    /// 更新目标状态，结束状态会记录完成时间
+    pub fn update_writing_goal_status(&self, goal_id: i64, status: GoalStatus) -> Result<()> {
+        let conn = self.conn()?;
+        let completed_at = (status != GoalStatus::Active).then(|| Utc::now().to_rfc3339());
+        conn.execute(
+            "UPDATE writing_goals SET status = ?1, completed_at = ?2 WHERE id = ?3",
+            params![status, completed_at, goal_id],
+        )?;
+        Ok(())
+    }
+}
+
+const WRITING_GOAL_SELECT: &str =
+    "SELECT id, novel_id, goal_type, target, start_value, start_date, deadline, status, created_at, completed_at
+     FROM writing_goals";
+
+fn writing_goal_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<WritingGoalRecord> {
+    let parse_date = |index: usize, value: String| {
+        chrono::NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+    };
+    let parse_time = |index: usize, value: String| {
+        parse_timestamp(&value)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+    };
+    
+    Ok(WritingGoalRecord {
+        id: row.get(0)?,
+        novel_id: row.get(1)?,
+        goal_type: GoalType::from_str(&row.get::<_, String>(2)?),
+        target: row.get(3)?,
+        start_value: row.get(4)?,
+        start_date: parse_date(5, row.get(5)?)?,
+        deadline: row.get::<_, Option<String>>(6)?.map(|d| parse_date(6, d)).transpose()?,
+        status: GoalStatus::from_str(&row.get::<_, String>(7)?),
+        created_at: parse_time(8, row.get(8)?)?,
+        completed_at: row.get::<_, Option<String>>(9)?.map(|t| parse_time(9, t)).transpose()?,
+    })
+}
+
+/// 解析数据库中的时间戳
+///
+/// 应用写入的是 RFC 3339 格式，而更新时间戳的触发器写入的是 SQLite `datetime('now')`
+/// 的 `YYYY-MM-DD HH:MM:SS` 格式（UTC），两种都需要支持。
+pub fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|e| {
+            chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+                .map(|naive| naive.and_utc())
+                .map_err(|_| e)
+        })
+}
+
 pub fn current_timestamp() -> String {
     chrono::Utc::now().to_rfc3339()
 }
 
-
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_get_chapter_after_trigger_updated_timestamp() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("时间戳").unwrap();
+        let chapter = db.create_chapter(novel.id, "第一章", None).unwrap();
+        
+        // 旧版本的更新触发器以 datetime('now') 格式写入 updated_at
+        db.update_chapter_content(chapter.id, "新内容").unwrap();
+        
+        assert_eq!(db.get_chapter(chapter.id).unwrap().content, "新内容");
+        assert!(parse_timestamp("2024-05-01 08:30:00").is_ok());
+        assert!(parse_timestamp("not a time").is_err());
+    }
+    
+    #[test]
+    fn test_clones_share_connection_pool() {
+        let db = Database::open_in_memory().unwrap();
+        let clone = db.clone();
+        let novel = clone.create_novel("共享连接").unwrap();
+        
+        assert!(Arc::ptr_eq(&db.pool, &clone.pool));
+        assert_eq!(db.get_novel_by_id(novel.id).unwrap().unwrap().title, "共享连接");
+    }
+    
+    #[test]
+    fn test_transaction_commits_and_rolls_back() {
+        let db = Database::open_in_memory().unwrap();
+        
+        let novel = db.transaction(|tx| {
+            let novel = tx.create_novel("事务内创建")?;
+            tx.create_chapter(novel.id, "第一章", None)?;
+            Ok(novel)
+        }).unwrap();
+        assert_eq!(db.get_chapters_by_novel(novel.id).unwrap().len(), 1);
+        
+        let result: Result<()> = db.transaction(|tx| {
+            tx.create_chapter(novel.id, "第二章", None)?;
+            // 嵌套调用复用外层事务
+            tx.transaction(|inner| inner.create_chapter(novel.id, "第三章", None))?;
+            Err(anyhow::anyhow!("中途失败"))
+        });
+        assert!(result.is_err());
+        assert_eq!(db.get_chapters_by_novel(novel.id).unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn test_transaction_panic_returns_connection() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("事务").unwrap();
+        
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _ = db.transaction(|tx| -> Result<()> {
+                tx.create_chapter(novel.id, "第一章", None)?;
+                panic!("闭包中途崩溃");
+            });
+        }));
+        assert!(panicked.is_err());
+        
+        // 闭包留下的句柄克隆不妨碍提交，也无法在事务结束后继续使用连接
+        let mut leaked = None;
+        db.transaction(|tx| {
+            leaked = Some(tx.clone());
+            tx.create_chapter(novel.id, "第二章", None).map(|_| ())
+        }).unwrap();
+        
+        // 内存数据库只有一个连接：回滚后已归还，后续操作可以正常进行
+        let chapters = db.get_chapters_by_novel(novel.id).unwrap();
+        assert_eq!(chapters.iter().map(|c| c.title.as_str()).collect::<Vec<_>>(), vec!["第二章"]);
+        assert!(leaked.unwrap().pool.try_take().is_none());
+    }
+    
+    #[test]
+    fn test_database_usable_across_threads() {
+        let dir = std::env::temp_dir().join(format!("novel-writer-test-{}", uuid::Uuid::new_v4()));
+        let db = Database::open(dir.join("novels.db")).unwrap();
+        let novel = db.create_novel("多线程").unwrap();
+        
+        let handles: Vec<_> = (0..8).map(|i| {
+            let db = db.clone();
+            std::thread::spawn(move || {
+                db.create_chapter(novel.id, &format!("第{}章", i), None).unwrap();
+                db.get_chapters_by_novel(novel.id).unwrap().len()
+            })
+        }).collect();
+        for handle in handles {
+            assert!(handle.join().unwrap() >= 1);
+        }
+        
+        assert_eq!(db.get_chapters_by_novel(novel.id).unwrap().len(), 8);
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use rusqlite::types::ToSql;
 
@@ -110,6 +110,12 @@ pub struct ChapterVersion {
     pub created_at: DateTime<Utc>,
     pub commit_message: String,
     pub is_auto_save: bool,
+    /// 版本所在的分支，分支被删除后为空
+    #[serde(default)]
+    pub branch_id: Option<i64>,
+    /// 合并产生的版本所合并进来的版本
+    #[serde(default)]
+    pub merge_parent_version_id: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -138,4 +144,249 @@ impl ToSql for VersionType {
     fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
         Ok(self.as_str().into())
     }
-}
\ No newline at end of file
+}
+
+/// 回收站条目类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TrashItemType {
+    Novel,
+    /// 章节及删除时它的全部子章节
+    Chapter,
+    Inspiration,
+}
+
+impl TrashItemType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Novel => "novel",
+            Self::Chapter => "chapter",
+            Self::Inspiration => "inspiration",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "novel" => Self::Novel,
+            "inspiration" => Self::Inspiration,
+            _ => Self::Chapter,
+        }
+    }
+    
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Novel => "小说",
+            Self::Chapter => "章节",
+            Self::Inspiration => "灵感",
+        }
+    }
+}
+
+impl ToSql for TrashItemType {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+/// 章节版本历史中的一个分支
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VersionBranch {
+    pub id: i64,
+    pub chapter_id: i64,
+    pub name: String,
+    /// 分支上最新的版本，新版本以它为父版本
+    pub head_version_id: Option<i64>,
+    /// 分支创建时所基于的版本
+    pub base_version_id: Option<i64>,
+    /// 编辑器中的正文是否属于这个分支
+    pub is_current: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 附加在版本上的命名里程碑
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VersionTag {
+    pub id: i64,
+    pub chapter_id: i64,
+    pub version_id: i64,
+    pub name: String,
+    pub note: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 全书快照
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NovelSnapshot {
+    pub id: i64,
+    pub novel_id: i64,
+    pub name: String,
+    pub note: String,
+    pub chapter_count: usize,
+    /// 拍摄快照时未归档章节的字数之和
+    pub word_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 快照中记录的一个章节
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotChapter {
+    pub chapter_id: i64,
+    pub parent_id: Option<i64>,
+    /// 章节当时内容对应的版本，版本已被彻底删除时为 `None`
+    pub version_id: Option<i64>,
+    pub title: String,
+    pub chapter_type: ChapterType,
+    pub sort_path: String,
+    pub word_count: i32,
+    pub is_archived: bool,
+}
+
+/// `trash` 表中的一个回收站条目
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrashEntry {
+    pub id: i64,
+    pub item_type: TrashItemType,
+    pub item_id: i64,
+    pub novel_id: i64,
+    pub title: String,
+    /// 删除时包含的内容概要
+    pub summary: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// 检索结果摘要中的一段文本
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnippetSegment {
+    pub text: String,
+    pub highlighted: bool,
+}
+
+/// 章节全文检索命中
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChapterSearchHit {
+    pub chapter_id: i64,
+    pub novel_id: i64,
+    pub title: String,
+    pub snippet: Vec<SnippetSegment>,
+    /// 相关度得分，越小越相关（与 FTS5 的 bm25 一致）
+    pub rank: f64,
+    /// 第一个命中在正文中的字符偏移，用于在编辑器中定位
+    pub match_offset: Option<usize>,
+    /// 命中文本的字符长度
+    pub match_len: usize,
+}
+
+/// 灵感全文检索命中
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InspirationSearchHit {
+    pub inspiration_id: i64,
+    pub novel_id: i64,
+    pub title: String,
+    pub snippet: Vec<SnippetSegment>,
+    pub rank: f64,
+}
+
+/// `writing_stats` 表中某部小说某一天的写作记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WritingStatsRecord {
+    pub novel_id: i64,
+    pub date: NaiveDate,
+    /// 当天净增字数（删除多于新增时为负数）
+    pub word_count: i64,
+    pub words_added: i64,
+    pub words_deleted: i64,
+    /// 有效写作时间（秒），不含空闲时间
+    pub writing_time: i64,
+    pub session_count: i64,
+}
+
+/// 写作目标类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GoalType {
+    /// 全书总字数
+    TotalWords,
+    /// 章节数
+    ChapterCount,
+    /// 每日净增字数
+    DailyWords,
+}
+
+impl GoalType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TotalWords => "total_words",
+            Self::ChapterCount => "chapter_count",
+            Self::DailyWords => "daily_words",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "chapter_count" => Self::ChapterCount,
+            "daily_words" => Self::DailyWords,
+            _ => Self::TotalWords,
+        }
+    }
+    
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::TotalWords => "总字数",
+            Self::ChapterCount => "章节数",
+            Self::DailyWords => "每日字数",
+        }
+    }
+}
+
+impl ToSql for GoalType {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+/// 写作目标状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GoalStatus {
+    Active,
+    Achieved,
+    /// 被新目标替换或手动放弃
+    Abandoned,
+}
+
+impl GoalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Achieved => "achieved",
+            Self::Abandoned => "abandoned",
+        }
+    }
+    
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "achieved" => Self::Achieved,
+            "abandoned" => Self::Abandoned,
+            _ => Self::Active,
+        }
+    }
+}
+
+impl ToSql for GoalStatus {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+/// `writing_goals` 表中的一条写作目标
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WritingGoalRecord {
+    pub id: i64,
+    pub novel_id: i64,
+    pub goal_type: GoalType,
+    pub target: i64,
+    /// 设定目标时已有的字数或章节数
+    pub start_value: i64,
+    pub start_date: NaiveDate,
+    pub deadline: Option<NaiveDate>,
+    pub status: GoalStatus,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use rusqlite::Connection;
+use std::ops::Deref;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+/// 等待空闲连接的最长时间，超时视为死锁或长事务
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 固定大小的SQLite连接池
+///
+/// 连接在打开书库时一次性创建并完成迁移，之后在各线程间复用。
+pub(crate) struct ConnectionPool {
+    idle: Mutex<Vec<Connection>>,
+    available: Condvar,
+}
+
+impl ConnectionPool {
+    pub fn new(connections: Vec<Connection>) -> Self {
+        Self {
+            idle: Mutex::new(connections),
+            available: Condvar::new(),
+        }
+    }
+
+    /// 借出一个连接，归还由守卫在析构时完成
+    pub fn get(&self) -> Result<PooledConnection<'_>> {
+        Ok(PooledConnection {
+            pool: self,
+            conn: Some(self.take()?),
+        })
+    }
+
+    /// 取出一个连接的所有权，使用后必须调用 `put` 归还
+    pub fn take(&self) -> Result<Connection> {
+        let mut idle = self.idle.lock()
+            .map_err(|e| anyhow::anyhow!("数据库连接池已损坏: {}", e))?;
+
+        loop {
+            if let Some(conn) = idle.pop() {
+                return Ok(conn);
+            }
+
+            let (guard, timeout) = self.available.wait_timeout(idle, ACQUIRE_TIMEOUT)
+                .map_err(|e| anyhow::anyhow!("数据库连接池已损坏: {}", e))?;
+            idle = guard;
+
+            if timeout.timed_out() && idle.is_empty() {
+                return Err(anyhow::anyhow!("等待数据库连接超时"));
+            }
+        }
+    }
+
+    /// 不等待地取出一个空闲连接
+    pub fn try_take(&self) -> Option<Connection> {
+        self.idle.lock().ok()?.pop()
+    }
+
+    /// 归还连接
+    pub fn put(&self, conn: Connection) {
+        match self.idle.lock() {
+            Ok(mut idle) => {
+                idle.push(conn);
+                self.available.notify_one();
+            },
+            Err(e) => log::error!("归还数据库连接失败: {}", e),
+        }
+    }
+}
+
+/// 借出的连接，离开作用域时自动归还连接池
+pub(crate) struct PooledConnection<'a> {
+    pool: &'a ConnectionPool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("pooled connection already returned")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.put(conn);
+        }
+    }
+}
@@ -0,0 +1,395 @@
+// 全文检索
+//
+// 章节和灵感各有一张 FTS5 外部内容表（trigram 分词，见迁移 0003）。
+// 所有检索词都不少于三个字符时走索引并按 bm25 排序；否则回退到 LIKE 扫描，
+// 按命中次数排序，摘要由应用层生成。
+
+use super::{ChapterSearchHit, Database, InspirationSearchHit, SnippetSegment};
+use anyhow::Result;
+use rusqlite::{Connection, types::Value};
+
+/// FTS5 snippet() 使用的高亮标记（Unicode 私用区字符，不会出现在正文中）
+const HIGHLIGHT_OPEN: char = '\u{E000}';
+const HIGHLIGHT_CLOSE: char = '\u{E001}';
+
+/// 摘要中命中位置前后保留的字符数
+const SNIPPET_CONTEXT: usize = 24;
+
+/// trigram 分词器能够利用索引的最短检索词长度
+const MIN_INDEXED_TERM_LEN: usize = 3;
+
+/// 参与检索的表
+struct SearchTable {
+    base: &'static str,
+    fts: &'static str,
+    extra_filter: &'static str,
+}
+
+const CHAPTERS: SearchTable = SearchTable {
+    base: "chapters",
+    fts: "chapters_fts",
+    extra_filter: "AND b.is_archived = 0 AND b.trash_id IS NULL
+                   AND b.novel_id IN (SELECT id FROM novels WHERE trash_id IS NULL)",
+};
+
+const INSPIRATIONS: SearchTable = SearchTable {
+    base: "inspirations",
+    fts: "inspirations_fts",
+    extra_filter: "AND b.trash_id IS NULL AND b.novel_id IN (SELECT id FROM novels WHERE trash_id IS NULL)",
+};
+
+/// 检索到的原始记录
+struct RawHit {
+    id: i64,
+    novel_id: i64,
+    title: String,
+    content: String,
+    snippet: Vec<SnippetSegment>,
+    rank: f64,
+}
+
+impl Database {
+    /// 全文检索章节标题和正文（不含已归档和回收站中的章节）
+    ///
+    /// `novel_id` 为 `None` 时检索整个书库。
+    pub fn search_chapters(&self, novel_id: Option<i64>, query: &str, limit: usize) -> Result<Vec<ChapterSearchHit>> {
+        let terms = parse_terms(query);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.conn()?;
+        let hits = search_table(&conn, &CHAPTERS, novel_id, &terms, limit)?;
+
+        Ok(hits.into_iter().map(|hit| {
+            let found = find_first_term(&hit.content, &terms);
+            ChapterSearchHit {
+                chapter_id: hit.id,
+                novel_id: hit.novel_id,
+                title: hit.title,
+                snippet: hit.snippet,
+                rank: hit.rank,
+                match_offset: found.map(|(offset, _)| offset),
+                match_len: found.map(|(_, len)| len).unwrap_or(0),
+            }
+        }).collect())
+    }
+
+    /// 全文检索灵感标题和内容
+    pub fn search_inspiration_hits(&self, novel_id: Option<i64>, query: &str, limit: usize) -> Result<Vec<InspirationSearchHit>> {
+        let terms = parse_terms(query);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.conn()?;
+        let hits = search_table(&conn, &INSPIRATIONS, novel_id, &terms, limit)?;
+
+        Ok(hits.into_iter().map(|hit| InspirationSearchHit {
+            inspiration_id: hit.id,
+            novel_id: hit.novel_id,
+            title: hit.title,
+            snippet: hit.snippet,
+            rank: hit.rank,
+        }).collect())
+    }
+}
+
+/// 按空白拆分检索词
+pub(crate) fn parse_terms(query: &str) -> Vec<String> {
+    query.split_whitespace().map(|t| t.to_string()).collect()
+}
+
+fn search_table(
+    conn: &Connection,
+    table: &SearchTable,
+    novel_id: Option<i64>,
+    terms: &[String],
+    limit: usize,
+) -> Result<Vec<RawHit>> {
+    if terms.iter().all(|t| t.chars().count() >= MIN_INDEXED_TERM_LEN) {
+        search_with_index(conn, table, novel_id, terms, limit)
+    } else {
+        search_with_scan(conn, table, novel_id, terms, limit)
+    }
+}
+
+fn search_with_index(
+    conn: &Connection,
+    table: &SearchTable,
+    novel_id: Option<i64>,
+    terms: &[String],
+    limit: usize,
+) -> Result<Vec<RawHit>> {
+    let sql = format!(
+        "SELECT b.id, b.novel_id, b.title, COALESCE(b.content, ''),
+                snippet({fts}, -1, ?4, ?5, '…', 16),
+                bm25({fts}, 5.0, 1.0)
+         FROM {fts} JOIN {base} b ON b.id = {fts}.rowid
+         WHERE {fts} MATCH ?1 AND (?2 IS NULL OR b.novel_id = ?2) {filter}
+         ORDER BY bm25({fts}, 5.0, 1.0)
+         LIMIT ?3",
+        fts = table.fts,
+        base = table.base,
+        filter = table.extra_filter,
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(
+        rusqlite::params![
+            fts_match_expression(terms),
+            novel_id,
+            limit as i64,
+            HIGHLIGHT_OPEN.to_string(),
+            HIGHLIGHT_CLOSE.to_string(),
+        ],
+        |row| {
+            Ok(RawHit {
+                id: row.get(0)?,
+                novel_id: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                snippet: parse_marked_snippet(&row.get::<_, String>(4)?),
+                rank: row.get(5)?,
+            })
+        },
+    )?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+}
+
+fn search_with_scan(
+    conn: &Connection,
+    table: &SearchTable,
+    novel_id: Option<i64>,
+    terms: &[String],
+    limit: usize,
+) -> Result<Vec<RawHit>> {
+    let mut conditions = Vec::new();
+    let mut params: Vec<Value> = vec![novel_id.map(Value::Integer).unwrap_or(Value::Null)];
+    for term in terms {
+        params.push(Value::Text(format!("%{}%", escape_like(term))));
+        let index = params.len();
+        conditions.push(format!(
+            "(b.title LIKE ?{i} ESCAPE '\\' OR b.content LIKE ?{i} ESCAPE '\\')",
+            i = index
+        ));
+    }
+
+    let sql = format!(
+        "SELECT b.id, b.novel_id, b.title, COALESCE(b.content, '')
+         FROM {base} b
+         WHERE (?1 IS NULL OR b.novel_id = ?1) {filter} AND {conditions}",
+        base = table.base,
+        filter = table.extra_filter,
+        conditions = conditions.join(" AND "),
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+    })?;
+
+    let mut hits = Vec::new();
+    for row in rows {
+        let (id, novel_id, title, content) = row?;
+        let occurrences = count_occurrences(&title, terms) * 5 + count_occurrences(&content, terms);
+        let snippet = build_snippet(&content, terms)
+            .unwrap_or_else(|| build_snippet(&title, terms).unwrap_or_default());
+        hits.push(RawHit {
+            id,
+            novel_id,
+            title,
+            content,
+            snippet,
+            rank: -(occurrences as f64),
+        });
+    }
+
+    hits.sort_by(|a, b| a.rank.partial_cmp(&b.rank).unwrap_or(std::cmp::Ordering::Equal));
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+/// 将检索词转换为 FTS5 查询表达式：每个词作为短语，词之间为 AND
+fn fts_match_expression(terms: &[String]) -> String {
+    terms.iter()
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// 解析 snippet() 输出中的高亮标记
+fn parse_marked_snippet(marked: &str) -> Vec<SnippetSegment> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut highlighted = false;
+
+    for c in marked.chars() {
+        if c == HIGHLIGHT_OPEN || c == HIGHLIGHT_CLOSE {
+            if !current.is_empty() {
+                segments.push(SnippetSegment { text: std::mem::take(&mut current), highlighted });
+            }
+            highlighted = c == HIGHLIGHT_OPEN;
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        segments.push(SnippetSegment { text: current, highlighted });
+    }
+
+    segments
+}
+
+fn chars_eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// 在字符序列中查找所有出现位置（字符偏移）
+fn find_all(haystack: &[char], needle: &[char]) -> Vec<usize> {
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return Vec::new();
+    }
+    (0..=haystack.len() - needle.len())
+        .filter(|&start| {
+            haystack[start..start + needle.len()].iter()
+                .zip(needle)
+                .all(|(a, b)| chars_eq_ignore_case(*a, *b))
+        })
+        .collect()
+}
+
+/// 查找最早出现的检索词，返回 (字符偏移, 字符长度)
+pub(crate) fn find_first_term(text: &str, terms: &[String]) -> Option<(usize, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    terms.iter()
+        .filter_map(|term| {
+            let needle: Vec<char> = term.chars().collect();
+            find_all(&chars, &needle).first().map(|&offset| (offset, needle.len()))
+        })
+        .min_by_key(|(offset, _)| *offset)
+}
+
+fn count_occurrences(text: &str, terms: &[String]) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    terms.iter()
+        .map(|term| find_all(&chars, &term.chars().collect::<Vec<_>>()).len())
+        .sum()
+}
+
+/// 围绕第一个命中位置截取摘要，并标出窗口内的所有命中
+fn build_snippet(text: &str, terms: &[String]) -> Option<Vec<SnippetSegment>> {
+    let chars: Vec<char> = text.chars().collect();
+    let (first, first_len) = find_first_term(text, terms)?;
+
+    let start = first.saturating_sub(SNIPPET_CONTEXT);
+    let end = (first + first_len + SNIPPET_CONTEXT).min(chars.len());
+
+    let mut mask = vec![false; chars.len()];
+    for term in terms {
+        let needle: Vec<char> = term.chars().collect();
+        for offset in find_all(&chars, &needle) {
+            mask[offset..offset + needle.len()].iter_mut().for_each(|m| *m = true);
+        }
+    }
+
+    let mut segments = Vec::new();
+    if start > 0 {
+        segments.push(SnippetSegment { text: "…".to_string(), highlighted: false });
+    }
+    for i in start..end {
+        match segments.last_mut() {
+            Some(SnippetSegment { text, highlighted }) if *highlighted == mask[i] && !(i == start && start > 0) => {
+                text.push(chars[i]);
+            }
+            _ => segments.push(SnippetSegment { text: chars[i].to_string(), highlighted: mask[i] }),
+        }
+    }
+    if end < chars.len() {
+        segments.push(SnippetSegment { text: "…".to_string(), highlighted: false });
+    }
+
+    Some(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Database, i64) {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("青云志").unwrap();
+        let first = db.create_chapter(novel.id, "第一章 入门", None).unwrap();
+        db.update_chapter_content(first.id, "林风走进了青云宗的大门，心中满是忐忑。").unwrap();
+        let second = db.create_chapter(novel.id, "第二章 试炼", None).unwrap();
+        db.update_chapter_content(second.id, "试炼开始了。青云宗的长老看着林风，林风握紧了剑。").unwrap();
+        (db, novel.id)
+    }
+
+    fn highlighted_text(segments: &[SnippetSegment]) -> Vec<String> {
+        segments.iter().filter(|s| s.highlighted).map(|s| s.text.clone()).collect()
+    }
+
+    #[test]
+    fn test_indexed_chinese_search_with_snippet() {
+        let (db, novel_id) = setup();
+        let hits = db.search_chapters(Some(novel_id), "青云宗", 10).unwrap();
+
+        assert_eq!(hits.len(), 2);
+        for hit in &hits {
+            assert_eq!(highlighted_text(&hit.snippet), vec!["青云宗".to_string()]);
+            assert_eq!(hit.match_len, 3);
+        }
+        assert_eq!(hits.iter().find(|h| h.title == "第一章 入门").unwrap().match_offset, Some(5));
+    }
+
+    #[test]
+    fn test_short_terms_fall_back_to_scan_and_rank_by_frequency() {
+        let (db, novel_id) = setup();
+        let hits = db.search_chapters(Some(novel_id), "林风", 10).unwrap();
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].title, "第二章 试炼");
+        assert!(highlighted_text(&hits[0].snippet).iter().all(|t| t == "林风"));
+        assert_eq!(hits[1].match_offset, Some(0));
+    }
+
+    #[test]
+    fn test_index_follows_updates_and_deletes() {
+        let (db, novel_id) = setup();
+        let chapters = db.get_chapters_by_novel(novel_id).unwrap();
+
+        db.update_chapter_content(chapters[0].id, "改写后的内容，没有宗门了。").unwrap();
+        db.delete_chapter(chapters[1].id).unwrap();
+
+        assert!(db.search_chapters(Some(novel_id), "青云宗", 10).unwrap().is_empty());
+        assert_eq!(db.search_chapters(None, "改写后", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_inspirations() {
+        let (db, novel_id) = setup();
+        db.create_inspiration(novel_id, "反派设定", "魔教教主曾是青云宗弃徒").unwrap();
+        db.create_inspiration(novel_id, "地图", "东海之滨").unwrap();
+
+        let hits = db.search_inspiration_hits(Some(novel_id), "青云宗", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "反派设定");
+
+        let inspirations = db.search_inspirations(novel_id, "东海").unwrap();
+        assert_eq!(inspirations.len(), 1);
+        assert_eq!(inspirations[0].title, "地图");
+    }
+
+    #[test]
+    fn test_query_with_quotes_and_like_wildcards() {
+        let (db, novel_id) = setup();
+        assert!(db.search_chapters(Some(novel_id), "\"青云", 10).unwrap().is_empty());
+        assert!(db.search_chapters(Some(novel_id), "%", 10).unwrap().is_empty());
+    }
+}
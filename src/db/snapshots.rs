@@ -0,0 +1,113 @@
+// 全书快照
+//
+// 快照由 `novel_snapshots` 中的一条记录和 `novel_snapshot_chapters` 中每个章节的一行组成（见迁移 0010），
+// 两者在同一事务中写入。章节内容不复制，只记录版本ID，由版本历史重建。
+
+use super::{parse_timestamp, ChapterType, Database, NovelSnapshot, SnapshotChapter};
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+const SNAPSHOT_QUERY: &str =
+    "SELECT s.id, s.novel_id, s.name, s.note,
+            (SELECT COUNT(*) FROM novel_snapshot_chapters c WHERE c.snapshot_id = s.id),
+            s.word_count, s.created_at
+     FROM novel_snapshots s";
+
+impl Database {
+    /// 写入快照及其章节，返回带新ID的快照
+    pub fn create_novel_snapshot(&self, snapshot: &NovelSnapshot, chapters: &[SnapshotChapter]) -> Result<NovelSnapshot> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            conn.execute(
+                "INSERT INTO novel_snapshots (novel_id, name, note, word_count, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![snapshot.novel_id, snapshot.name, snapshot.note, snapshot.word_count, snapshot.created_at.to_rfc3339()],
+            )?;
+            let id = conn.last_insert_rowid();
+            let mut stmt = conn.prepare(
+                "INSERT INTO novel_snapshot_chapters
+                 (snapshot_id, chapter_id, parent_id, version_id, title, chapter_type, sort_path, word_count, is_archived)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+            )?;
+            for chapter in chapters {
+                stmt.execute(params![
+                    id,
+                    chapter.chapter_id,
+                    chapter.parent_id,
+                    chapter.version_id,
+                    chapter.title,
+                    chapter.chapter_type,
+                    chapter.sort_path,
+                    chapter.word_count,
+                    chapter.is_archived,
+                ])?;
+            }
+            Ok(NovelSnapshot { id, chapter_count: chapters.len(), ..snapshot.clone() })
+        })
+    }
+
+    /// 获取小说的全部快照，最新的在前
+    pub fn get_novel_snapshots(&self, novel_id: i64) -> Result<Vec<NovelSnapshot>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE s.novel_id = ?1 ORDER BY s.created_at DESC, s.id DESC",
+            SNAPSHOT_QUERY
+        ))?;
+        let snapshots = stmt.query_map([novel_id], snapshot_from_row)?;
+        snapshots.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+    }
+
+    pub fn get_novel_snapshot(&self, snapshot_id: i64) -> Result<NovelSnapshot> {
+        let conn = self.conn()?;
+        get_snapshot(&conn, snapshot_id)
+    }
+
+    /// 获取快照中的章节，按 `sort_path` 排序
+    pub fn get_snapshot_chapters(&self, snapshot_id: i64) -> Result<Vec<SnapshotChapter>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT chapter_id, parent_id, version_id, title, chapter_type, sort_path, word_count, is_archived
+             FROM novel_snapshot_chapters WHERE snapshot_id = ?1 ORDER BY sort_path, chapter_id"
+        )?;
+        let chapters = stmt.query_map([snapshot_id], |row| {
+            Ok(SnapshotChapter {
+                chapter_id: row.get(0)?,
+                parent_id: row.get(1)?,
+                version_id: row.get(2)?,
+                title: row.get(3)?,
+                chapter_type: ChapterType::from_str(&row.get::<_, String>(4)?),
+                sort_path: row.get(5)?,
+                word_count: row.get(6)?,
+                is_archived: row.get(7)?,
+            })
+        })?;
+        chapters.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+    }
+
+    /// 删除快照，快照引用的版本不受影响
+    pub fn delete_novel_snapshot(&self, snapshot_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM novel_snapshots WHERE id = ?1", [snapshot_id])?;
+        Ok(())
+    }
+}
+
+fn get_snapshot(conn: &Connection, snapshot_id: i64) -> Result<NovelSnapshot> {
+    conn.query_row(
+        &format!("{} WHERE s.id = ?1", SNAPSHOT_QUERY),
+        [snapshot_id],
+        snapshot_from_row,
+    ).optional()?.ok_or_else(|| anyhow::anyhow!("快照不存在: {}", snapshot_id))
+}
+
+fn snapshot_from_row(row: &rusqlite::Row) -> rusqlite::Result<NovelSnapshot> {
+    Ok(NovelSnapshot {
+        id: row.get(0)?,
+        novel_id: row.get(1)?,
+        name: row.get(2)?,
+        note: row.get(3)?,
+        chapter_count: row.get::<_, i64>(4)? as usize,
+        word_count: row.get(5)?,
+        created_at: parse_timestamp(&row.get::<_, String>(6)?)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e)))?,
+    })
+}
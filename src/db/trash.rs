@@ -0,0 +1,358 @@
+// 回收站
+//
+// 小说、章节子树和灵感被删除时只写入 `trash_id`（见迁移 0008），列表查询会跳过这些记录。
+// 恢复时清除标记；彻底删除时才真正删除记录，章节的版本历史随之级联删除。
+
+use super::{parse_timestamp, Database, TrashEntry, TrashItemType};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// 灵感条目概要中保留的正文字符数
+const SUMMARY_PREVIEW_CHARS: usize = 40;
+
+impl Database {
+    /// 把小说移入回收站，章节和灵感随小说一起隐藏
+    pub fn trash_novel(&self, novel_id: i64) -> Result<TrashEntry> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            let title: String = conn.query_row(
+                "SELECT title FROM novels WHERE id = ?1 AND trash_id IS NULL",
+                [novel_id],
+                |row| row.get(0),
+            ).optional()?.ok_or_else(|| anyhow::anyhow!("小说不存在或已在回收站中: {}", novel_id))?;
+            let (chapters, words): (i64, i64) = conn.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(word_count), 0) FROM chapters WHERE novel_id = ?1 AND trash_id IS NULL",
+                [novel_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            let inspirations: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM inspirations WHERE novel_id = ?1 AND trash_id IS NULL",
+                [novel_id],
+                |row| row.get(0),
+            )?;
+
+            let summary = format!("{} 个章节，{} 字，{} 条灵感", chapters, words, inspirations);
+            let entry = insert_entry(&conn, TrashItemType::Novel, novel_id, novel_id, &title, &summary)?;
+            conn.execute("UPDATE novels SET trash_id = ?1 WHERE id = ?2", params![entry.id, novel_id])?;
+            Ok(entry)
+        })
+    }
+
+    /// 把章节连同其子章节移入回收站
+    pub fn trash_chapter(&self, chapter_id: i64) -> Result<TrashEntry> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            let (novel_id, title): (i64, String) = conn.query_row(
+                "SELECT novel_id, title FROM chapters WHERE id = ?1 AND trash_id IS NULL",
+                [chapter_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            ).optional()?.ok_or_else(|| anyhow::anyhow!("章节不存在或已在回收站中: {}", chapter_id))?;
+
+            let subtree = live_subtree(&conn, chapter_id)?;
+            let words: i64 = subtree.iter().map(|(_, words)| words).sum();
+            let summary = if subtree.len() > 1 {
+                format!("含 {} 个子章节，共 {} 字", subtree.len() - 1, words)
+            } else {
+                format!("{} 字", words)
+            };
+
+            let entry = insert_entry(&conn, TrashItemType::Chapter, chapter_id, novel_id, &title, &summary)?;
+            for (id, _) in &subtree {
+                conn.execute("UPDATE chapters SET trash_id = ?1 WHERE id = ?2", params![entry.id, id])?;
+            }
+            Ok(entry)
+        })
+    }
+
+    /// 把灵感移入回收站
+    pub fn trash_inspiration(&self, inspiration_id: i64) -> Result<TrashEntry> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            let (novel_id, title, content): (i64, String, String) = conn.query_row(
+                "SELECT novel_id, title, content FROM inspirations WHERE id = ?1 AND trash_id IS NULL",
+                [inspiration_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            ).optional()?.ok_or_else(|| anyhow::anyhow!("灵感不存在或已在回收站中: {}", inspiration_id))?;
+
+            let mut summary: String = content.chars().take(SUMMARY_PREVIEW_CHARS).collect();
+            if content.chars().count() > SUMMARY_PREVIEW_CHARS {
+                summary.push('…');
+            }
+            let entry = insert_entry(&conn, TrashItemType::Inspiration, inspiration_id, novel_id, &title, &summary)?;
+            conn.execute("UPDATE inspirations SET trash_id = ?1 WHERE id = ?2", params![entry.id, inspiration_id])?;
+            Ok(entry)
+        })
+    }
+
+    /// 回收站中的全部条目，最近删除的在前
+    pub fn get_trash_entries(&self) -> Result<Vec<TrashEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, item_type, item_id, novel_id, title, summary, deleted_at FROM trash ORDER BY id DESC"
+        )?;
+        let entries = stmt.query_map([], trash_entry_from_row)?;
+        entries.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+    }
+
+    /// 章节所在的回收站条目，章节不在回收站中或已被彻底删除时返回 `None`
+    pub fn get_chapter_trash_id(&self, chapter_id: i64) -> Result<Option<i64>> {
+        let conn = self.conn()?;
+        let trash_id: Option<Option<i64>> = conn.query_row(
+            "SELECT trash_id FROM chapters WHERE id = ?1",
+            [chapter_id],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(trash_id.flatten())
+    }
+
+    /// 恢复回收站条目到原来的位置
+    ///
+    /// 章节的上级章节仍在回收站中或已被彻底删除时，挂到最近的未删除祖先下（没有则放到顶层）。
+    pub fn restore_trash_entry(&self, entry_id: i64) -> Result<TrashEntry> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            let entry = get_entry(&conn, entry_id)?;
+
+            if entry.item_type != TrashItemType::Novel {
+                let novel_trashed: bool = conn.query_row(
+                    "SELECT trash_id IS NOT NULL FROM novels WHERE id = ?1",
+                    [entry.novel_id],
+                    |row| row.get(0),
+                ).optional()?.unwrap_or(true);
+                if novel_trashed {
+                    return Err(anyhow::anyhow!("「{}」所属的小说在回收站中，请先恢复小说", entry.title));
+                }
+            }
+
+            match entry.item_type {
+                TrashItemType::Novel => {
+                    conn.execute("UPDATE novels SET trash_id = NULL WHERE id = ?1", [entry.item_id])?;
+                },
+                TrashItemType::Chapter => {
+                    let parent_id: Option<i64> = conn.query_row(
+                        "SELECT parent_id FROM chapters WHERE id = ?1",
+                        [entry.item_id],
+                        |row| row.get(0),
+                    )?;
+                    let live_parent = nearest_live_ancestor(&conn, parent_id)?;
+                    if live_parent != parent_id {
+                        conn.execute("UPDATE chapters SET parent_id = ?1 WHERE id = ?2", params![live_parent, entry.item_id])?;
+                    }
+                    conn.execute("UPDATE chapters SET trash_id = NULL WHERE trash_id = ?1", [entry.id])?;
+                },
+                TrashItemType::Inspiration => {
+                    conn.execute("UPDATE inspirations SET trash_id = NULL WHERE id = ?1", [entry.item_id])?;
+                },
+            }
+            conn.execute("DELETE FROM trash WHERE id = ?1", [entry.id])?;
+            Ok(entry)
+        })
+    }
+
+    /// 彻底删除回收站条目
+    ///
+    /// 彻底删除小说时，该小说在回收站中的章节和灵感条目一并删除。
+    pub fn purge_trash_entry(&self, entry_id: i64) -> Result<()> {
+        self.transaction(|tx| {
+            let conn = tx.conn()?;
+            let entry = get_entry(&conn, entry_id)?;
+            match entry.item_type {
+                TrashItemType::Novel => {
+                    conn.execute("DELETE FROM novels WHERE id = ?1", [entry.item_id])?;
+                    conn.execute("DELETE FROM trash WHERE novel_id = ?1", [entry.item_id])?;
+                },
+                TrashItemType::Chapter => {
+                    conn.execute("DELETE FROM chapters WHERE trash_id = ?1", [entry.id])?;
+                },
+                TrashItemType::Inspiration => {
+                    conn.execute("DELETE FROM inspirations WHERE id = ?1", [entry.item_id])?;
+                },
+            }
+            conn.execute("DELETE FROM trash WHERE id = ?1", [entry.id])?;
+            Ok(())
+        })
+    }
+
+    /// 彻底删除在 `cutoff` 之前移入回收站的条目，返回删除的条目数
+    pub fn purge_trash_before(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let expired: Vec<i64> = self.get_trash_entries()?
+            .into_iter()
+            .filter(|entry| entry.deleted_at < cutoff)
+            .map(|entry| entry.id)
+            .collect();
+
+        let mut purged = 0;
+        for entry_id in expired {
+            // 彻底删除小说时会顺带删除它的其他条目
+            if self.conn()?.query_row("SELECT 1 FROM trash WHERE id = ?1", [entry_id], |_| Ok(())).optional()?.is_some() {
+                self.purge_trash_entry(entry_id)?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+}
+
+fn insert_entry(
+    conn: &Connection,
+    item_type: TrashItemType,
+    item_id: i64,
+    novel_id: i64,
+    title: &str,
+    summary: &str,
+) -> Result<TrashEntry> {
+    let deleted_at = Utc::now();
+    conn.execute(
+        "INSERT INTO trash (item_type, item_id, novel_id, title, summary, deleted_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![item_type, item_id, novel_id, title, summary, deleted_at.to_rfc3339()],
+    )?;
+    Ok(TrashEntry {
+        id: conn.last_insert_rowid(),
+        item_type,
+        item_id,
+        novel_id,
+        title: title.to_string(),
+        summary: summary.to_string(),
+        deleted_at,
+    })
+}
+
+fn get_entry(conn: &Connection, entry_id: i64) -> Result<TrashEntry> {
+    conn.query_row(
+        "SELECT id, item_type, item_id, novel_id, title, summary, deleted_at FROM trash WHERE id = ?1",
+        [entry_id],
+        trash_entry_from_row,
+    ).optional()?.ok_or_else(|| anyhow::anyhow!("回收站条目不存在: {}", entry_id))
+}
+
+fn trash_entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<TrashEntry> {
+    Ok(TrashEntry {
+        id: row.get(0)?,
+        item_type: TrashItemType::from_str(&row.get::<_, String>(1)?),
+        item_id: row.get(2)?,
+        novel_id: row.get(3)?,
+        title: row.get(4)?,
+        summary: row.get(5)?,
+        deleted_at: parse_timestamp(&row.get::<_, String>(6)?)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e)))?,
+    })
+}
+
+/// 章节及其未删除的全部子孙章节的 (id, 字数)
+pub(super) fn live_subtree(conn: &Connection, chapter_id: i64) -> Result<Vec<(i64, i64)>> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE subtree(id) AS (
+            SELECT ?1
+            UNION
+            SELECT c.id FROM chapters c JOIN subtree s ON c.parent_id = s.id WHERE c.trash_id IS NULL
+         )
+         SELECT c.id, COALESCE(c.word_count, 0) FROM chapters c JOIN subtree s ON c.id = s.id"
+    )?;
+    let rows = stmt.query_map([chapter_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+}
+
+/// 沿上级链找到第一个不在回收站中的章节
+fn nearest_live_ancestor(conn: &Connection, mut parent_id: Option<i64>) -> Result<Option<i64>> {
+    let mut visited = Vec::new();
+    while let Some(id) = parent_id {
+        if visited.contains(&id) {
+            return Ok(None);
+        }
+        visited.push(id);
+        let row: Option<(Option<i64>, bool)> = conn.query_row(
+            "SELECT parent_id, trash_id IS NOT NULL FROM chapters WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional()?;
+        match row {
+            Some((_, false)) => return Ok(Some(id)),
+            Some((grandparent, true)) => parent_id = grandparent,
+            None => return Ok(None),
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_restore_and_purge_chapters() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("回收站").unwrap();
+        let volume = db.create_chapter(novel.id, "第一卷", None).unwrap();
+        let chapter = db.create_chapter(novel.id, "第一章", Some(volume.id)).unwrap();
+        let scene = db.create_chapter(novel.id, "场景", Some(chapter.id)).unwrap();
+        db.update_chapter_content(chapter.id, "一二三").unwrap();
+        db.update_chapter_content(scene.id, "四五").unwrap();
+        db.create_chapter_version(crate::db::ChapterVersion {
+            id: 0,
+            chapter_id: scene.id,
+            parent_version_id: None,
+            version_type: crate::db::VersionType::Snapshot,
+            content: "四五".to_string(),
+            diff_data: None,
+            word_count: 2,
+            created_at: Utc::now(),
+            commit_message: String::new(),
+            is_auto_save: false,
+            branch_id: None,
+            merge_parent_version_id: None,
+        }).unwrap();
+
+        // 先单独删除场景，再删除它所在的章节
+        let scene_entry = db.trash_chapter(scene.id).unwrap();
+        assert_eq!(scene_entry.summary, "2 字");
+        let chapter_entry = db.trash_chapter(chapter.id).unwrap();
+        assert_eq!(chapter_entry.summary, "3 字");
+        assert_eq!(db.get_chapters_by_novel(novel.id).unwrap().len(), 1);
+        assert_eq!(db.get_novel_by_id(novel.id).unwrap().unwrap().word_count, 0);
+        assert_eq!(db.get_trash_entries().unwrap().len(), 2);
+
+        // 上级章节仍在回收站中，场景恢复到卷下
+        db.restore_trash_entry(scene_entry.id).unwrap();
+        assert_eq!(db.get_chapter(scene.id).unwrap().parent_id, Some(volume.id));
+        assert_eq!(db.get_novel_by_id(novel.id).unwrap().unwrap().word_count, 2);
+
+        db.purge_trash_entry(chapter_entry.id).unwrap();
+        assert!(db.get_trash_entries().unwrap().is_empty());
+        assert!(db.get_chapter(chapter.id).is_err());
+        assert_eq!(db.get_chapter_versions(scene.id).unwrap().len(), 1);
+
+        // 整棵子树一起删除和恢复
+        let volume_entry = db.trash_chapter(volume.id).unwrap();
+        assert_eq!(volume_entry.summary, "含 1 个子章节，共 2 字");
+        db.restore_trash_entry(volume_entry.id).unwrap();
+        assert_eq!(db.get_chapters_by_novel(novel.id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_trash_novels_and_inspirations() {
+        let db = Database::open_in_memory().unwrap();
+        let novel = db.create_novel("旧作").unwrap();
+        db.create_chapter(novel.id, "第一章", None).unwrap();
+        let inspiration = db.create_inspiration(novel.id, "点子", "一个关于时间旅行的故事").unwrap();
+
+        let inspiration_entry = db.trash_inspiration(inspiration.id).unwrap();
+        assert!(db.get_inspirations_by_novel(novel.id).unwrap().is_empty());
+        let novel_entry = db.trash_novel(novel.id).unwrap();
+        assert_eq!(novel_entry.summary, "1 个章节，0 字，0 条灵感");
+        assert!(db.get_all_novels().unwrap().is_empty());
+
+        let error = db.restore_trash_entry(inspiration_entry.id).unwrap_err();
+        assert!(error.to_string().contains("请先恢复小说"));
+        db.restore_trash_entry(novel_entry.id).unwrap();
+        db.restore_trash_entry(inspiration_entry.id).unwrap();
+        assert_eq!(db.get_inspirations_by_novel(novel.id).unwrap().len(), 1);
+
+        db.trash_inspiration(inspiration.id).unwrap();
+        db.trash_novel(novel.id).unwrap();
+        assert_eq!(db.purge_trash_before(Utc::now() - chrono::Duration::days(1)).unwrap(), 0);
+        assert_eq!(db.purge_trash_before(Utc::now() + chrono::Duration::seconds(1)).unwrap(), 1);
+        assert!(db.get_trash_entries().unwrap().is_empty());
+        assert!(db.get_chapters_by_novel(novel.id).unwrap().is_empty());
+    }
+}
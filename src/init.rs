@@ -1,7 +1,7 @@
 /// Application initialization module
 /// Handles database setup and application bootstrapping
 
-use crate::db;
+use crate::config::AppConfig;
 use log::{info, warn, error};
 
 /// Initialize the application
@@ -44,11 +44,20 @@ pub fn initialize_app() -> Result<(), Box<dyn std::error::Error>> {
 
 /// Initialize the database
 /// 
-/// Creates the database file if it doesn't exist and runs migrations
+/// Resolves the library path from the command line, environment or `AppConfig`,
+/// creates the database file if it doesn't exist and runs migrations
 fn init_database() -> Result<(), Box<dyn std::error::Error>> {
-    match crate::db::init_database() {
+    let mut config = AppConfig::load()?;
+    let path = config.resolve_database_path();
+    info!("Using library: {}", path);
+    
+    match crate::db::init_database(&path) {
         Ok(()) => {
             info!("Database migration completed successfully");
+            config.remember_library(&path);
+            if let Err(e) = config.save() {
+                warn!("Failed to save recent libraries: {}", e);
+            }
             Ok(())
         },
         Err(e) => {
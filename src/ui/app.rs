@@ -1,18 +1,29 @@
 use dioxus::prelude::*;
 use crate::db;
 use web_sys;
-use super::components::{Header, Sidebar, NovelForm, StatusBar, ChapterManagement};
+use super::components::{Header, Sidebar, NovelForm, StatusBar, ChapterManagement, SearchPanel, RecoveryPrompt};
+use crate::core::auto_save::AutoSaveManager;
+use crate::core::backup_manager::BackupManager;
+use crate::core::integrity_checker::{IntegrityCheck, IntegrityChecker};
+use crate::core::recovery_journal::RecoveryEntry;
+use crate::core::search_manager::{SearchJump, SearchManager, SearchResults};
 use super::components::novel_management::NovelManagement;
 use super::components::inspiration_management::InspirationManagement;
 use super::components::inspiration_stats::InspirationStatsView;
 use super::components::stats_view::StatsView;
 use super::components::settings_view::SettingsView;
+use super::components::integrity_report::IntegrityReportPanel;
+use super::components::trash_view::TrashView;
+use crate::core::trash_manager::TrashManager;
 
 #[component]
 pub fn App() -> Element {
-    let current_view = use_signal(|| "novels".to_string());
+    let mut current_view = use_signal(|| "novels".to_string());
     let mut current_novel_id = use_signal(|| None::<i64>);
     let search_query = use_signal(|| "".to_string());
+    let mut search_results = use_signal(|| None::<SearchResults>);
+    let mut searching = use_signal(|| false);
+    let mut pending_jump = use_signal(|| None::<SearchJump>);
     let mut novels = use_signal(|| Vec::<db::Novel>::new());
     let mut novel_title = use_signal(|| "".to_string());
     let mut novel_author = use_signal(|| "".to_string());
@@ -27,35 +38,73 @@ pub fn App() -> Element {
     
     // 写作统计相关信号
     let mut writing_report = use_signal(|| None::<crate::core::stats_manager::WritingReport>);
+    let mut stats_refresh = use_signal(|| 0u32);
     
-    // 加载小说列表
+    // 上次运行留下的未保存内容
+    let mut recovery_entries = use_signal(Vec::<RecoveryEntry>::new);
+    let mut load_recovery_entries = move || {
+        spawn(async move {
+            match AutoSaveManager::new().pending_recoveries().await {
+                Ok(entries) => recovery_entries.set(entries),
+                Err(e) => log::error!("读取恢复日志失败: {}", e),
+            }
+        });
+    };
+    use_hook(move || load_recovery_entries());
+    
+    // 启动时的数据库完整性检查，有问题时显示报告
+    let mut integrity_check = use_signal(|| None::<IntegrityCheck>);
+    
+    // 清理回收站中超过保留天数的条目
+    use_hook(|| spawn(async {
+        if let Err(e) = TrashManager::new().auto_purge().await {
+            log::error!("清理回收站失败: {}", e);
+        }
+    }));
+    
+    // 定时备份书库，关闭窗口时再备份一次
+    use_hook(|| spawn(async { BackupManager::new().run_schedule().await }));
+    use_drop(|| BackupManager::new().backup_on_exit());
+    
+    // 进入统计视图时加载当前小说的写作报告，目标变化后重新加载
+    use_effect(move || {
+        let _ = stats_refresh();
+        if current_view() != "stats" {
+            return;
+        }
+        let Some(novel_id) = current_novel_id() else { return };
+        writing_report.set(None);
+        spawn(async move {
+            match crate::core::stats_manager::StatsManager::new().generate_writing_report(novel_id).await {
+                Ok(report) => writing_report.set(Some(report)),
+                Err(e) => log::error!("生成写作报告失败: {}", e),
+            }
+        });
+    });
+    
+    // 加载小说列表（在后台线程查询，避免阻塞界面）
     use_effect(move || {
         log::debug!("开始加载小说列表");
-        match db::get_database() {
-            Ok(db) => {
-                match db.get_all_novels() {
-                    Ok(mut novels_list) => {
-                        log::debug!("成功加载{}部小说", novels_list.len());
-                        
-                        // 按更新时间倒序排序
-                        novels_list.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
-                        novels.set(novels_list.clone());
-                        
-                        // 自动选择最新的小说
-                        if let Some(latest_novel) = novels_list.first() {
-                            log::debug!("自动选择最新小说: ID={}, title='{}'", latest_novel.id, latest_novel.title);
-                            current_novel_id.set(Some(latest_novel.id));
-                        }
-                    },
-                    Err(e) => {
-                        log::error!("加载小说列表失败: {}", e);
+        spawn(async move {
+            match db::with_database(|db| db.get_all_novels()).await {
+                Ok(mut novels_list) => {
+                    log::debug!("成功加载{}部小说", novels_list.len());
+                    
+                    // 按更新时间倒序排序
+                    novels_list.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+                    novels.set(novels_list.clone());
+                    
+                    // 自动选择最新的小说
+                    if let Some(latest_novel) = novels_list.first() {
+                        log::debug!("自动选择最新小说: ID={}, title='{}'", latest_novel.id, latest_novel.title);
+                        current_novel_id.set(Some(latest_novel.id));
                     }
+                },
+                Err(e) => {
+                    log::error!("加载小说列表失败: {}", e);
                 }
-            },
-            Err(e) => {
-                log::error!("获取数据库连接失败: {}", e);
             }
-        }
+        });
     });
     
     // 编辑小说
@@ -72,108 +121,79 @@ pub fn App() -> Element {
     // 处理小说表单提交
     let handle_novel_submit = move |_| {
         log::debug!("开始处理小说表单提交");
-        match db::get_database() {
-            Ok(db) => {
-                if !novel_title().is_empty() {
-                    if let Some(editing) = editing_novel() {
-                        // 更新现有小说
-                        let mut updated_novel = editing.clone();
-                        updated_novel.title = novel_title();
-                        updated_novel.author = novel_author();
-                        updated_novel.description = novel_description();
-                        updated_novel.updated_at = chrono::Utc::now();
-                        
-                        log::debug!("更新小说ID: {}", updated_novel.id);
-                        match db.update_novel(&updated_novel) {
-                            Ok(()) => {
-                                log::debug!("小说更新成功");
-                                // 重新加载列表
-                                if let Ok(novels_list) = db.get_all_novels() {
-                                    log::debug!("更新后加载{}部小说", novels_list.len());
-                                    novels.set(novels_list);
-                                }
-                                
-                                // 重置表单
-                                novel_title.set("".to_string());
-                                novel_author.set("".to_string());
-                                novel_description.set("".to_string());
-                                show_novel_form.set(false);
-                                editing_novel.set(None);
-                            },
-                            Err(e) => {
-                                log::error!("更新小说失败: {}", e);
-                            }
-                        }
-                    } else {
-                        // 创建新小说
-                        log::debug!("创建新小说: {}", novel_title());
-                        match db.create_novel(&novel_title()) {
-                            Ok(novel) => {
-                                log::debug!("小说创建成功，ID: {}", novel.id);
-                                // 更新作者和描述
-                                let mut updated_novel = novel.clone();
-                                updated_novel.author = novel_author();
-                                updated_novel.description = novel_description();
-                                
-                                match db.update_novel(&updated_novel) {
-                                    Ok(()) => log::debug!("小说详情更新成功"),
-                                    Err(e) => log::error!("更新小说详情失败: {}", e)
-                                }
-                                
-                                // 重新加载列表
-                                if let Ok(novels_list) = db.get_all_novels() {
-                                    log::debug!("创建后加载{}部小说", novels_list.len());
-                                    novels.set(novels_list);
-                                }
-                                
-                                // 重置表单
-                                novel_title.set("".to_string());
-                                novel_author.set("".to_string());
-                                novel_description.set("".to_string());
-                                show_novel_form.set(false);
-                                editing_novel.set(None);
-                            },
-                            Err(e) => {
-                                log::error!("创建小说失败: {}", e);
-                            }
-                        }
+        if novel_title().is_empty() {
+            return;
+        }
+        let (title, author, description) = (novel_title(), novel_author(), novel_description());
+        let editing = editing_novel();
+        spawn(async move {
+            let result = db::with_database(move |db| {
+                if let Some(mut updated_novel) = editing {
+                    // 更新现有小说
+                    updated_novel.title = title;
+                    updated_novel.author = author;
+                    updated_novel.description = description;
+                    updated_novel.updated_at = chrono::Utc::now();
+                    log::debug!("更新小说ID: {}", updated_novel.id);
+                    db.update_novel(&updated_novel)?;
+                } else {
+                    // 创建新小说
+                    log::debug!("创建新小说: {}", title);
+                    let mut novel = db.create_novel(&title)?;
+                    log::debug!("小说创建成功，ID: {}", novel.id);
+                    // 更新作者和描述
+                    novel.author = author;
+                    novel.description = description;
+                    if let Err(e) = db.update_novel(&novel) {
+                        log::error!("更新小说详情失败: {}", e);
                     }
                 }
-            },
-            Err(e) => {
-                log::error!("获取数据库连接失败: {}", e);
+                db.get_all_novels()
+            }).await;
+            
+            match result {
+                Ok(novels_list) => {
+                    log::debug!("保存后加载{}部小说", novels_list.len());
+                    novels.set(novels_list);
+                    
+                    // 重置表单
+                    novel_title.set("".to_string());
+                    novel_author.set("".to_string());
+                    novel_description.set("".to_string());
+                    show_novel_form.set(false);
+                    editing_novel.set(None);
+                },
+                Err(e) => {
+                    log::error!("保存小说失败: {}", e);
+                }
             }
-        }
+        });
     };
     
     // 删除小说
     let delete_novel = move |novel_id: i64| {
         log::debug!("删除小说ID: {}", novel_id);
-        match db::get_database() {
-            Ok(db) => {
-                match db.delete_novel(novel_id) {
-                    Ok(()) => {
-                        log::debug!("小说删除成功");
-                        // 重新加载列表
-                        if let Ok(novels_list) = db.get_all_novels() {
-                            log::debug!("删除后加载{}部小说", novels_list.len());
-                            novels.set(novels_list);
-                        }
-                        
-                        // 如果删除的是当前选中的小说，清空选择
-                        if current_novel_id() == Some(novel_id) {
-                            current_novel_id.set(None);
-                        }
-                    },
-                    Err(e) => {
-                        log::error!("删除小说失败: {}", e);
+        spawn(async move {
+            let result = db::with_database(move |db| {
+                db.trash_novel(novel_id)?;
+                db.get_all_novels()
+            }).await;
+            
+            match result {
+                Ok(novels_list) => {
+                    log::debug!("小说已移入回收站，剩余{}部小说", novels_list.len());
+                    novels.set(novels_list);
+                    
+                    // 如果删除的是当前选中的小说，清空选择
+                    if current_novel_id() == Some(novel_id) {
+                        current_novel_id.set(None);
                     }
+                },
+                Err(e) => {
+                    log::error!("删除小说失败: {}", e);
                 }
-            },
-            Err(e) => {
-                log::error!("获取数据库连接失败: {}", e);
             }
-        }
+        });
     };
     
     // 选择小说
@@ -204,73 +224,148 @@ pub fn App() -> Element {
         let (novel_id, current_title) = args;
         log::debug!("切换小说置顶状态，ID: {}", novel_id);
         
-        // 在Web环境中，我们需要创建一个新的异步任务来处理这个操作
-        // 由于在组件内部使用spawn_local可能会导致运行时错误，我们使用简单的方法处理
-        match db::get_database() {
-            Ok(db) => {
-                // 直接在主线程中处理置顶逻辑（简化版本）
-                if let Some(mut novel) = match db.get_novel_by_id(novel_id) {
-                    Ok(novel) => novel,
-                    Err(e) => {
-                        log::error!("获取小说失败: {}", e);
-                        None
+        spawn(async move {
+            let result = db::with_database(move |db| {
+                let Some(mut novel) = db.get_novel_by_id(novel_id)? else {
+                    return Err(anyhow::anyhow!("小说不存在"));
+                };
+                
+                if !novel.is_pinned {
+                    // 检查当前置顶小说数量
+                    let pinned_count = db.get_all_novels()?.iter().filter(|n| n.is_pinned).count();
+                    if pinned_count >= 3 {
+                        return Err(anyhow::anyhow!("最多只能置顶3本小说"));
                     }
-                } {
-                    let current_pinned = novel.is_pinned;
                     
-                    if !current_pinned {
-                        // 检查当前置顶小说数量
-                        if let Ok(all_novels) = db.get_all_novels() {
-                            let pinned_count = all_novels.iter().filter(|n| n.is_pinned).count();
-                            
-                            if pinned_count >= 3 {
-                                log::warn!("置顶数量已达上限");
-                                if let Some(window) = web_sys::window() {
-                                    window.alert_with_message("最多只能置顶3本小说").unwrap_or(());
-                                }
-                                return;
-                            }
-                            
-                            // 设置为置顶
-                            novel.is_pinned = true;
-                            novel.pinned_order = Some((pinned_count + 1) as i32);
-                            log::debug!("置顶小说，ID: {}, 顺序: {}", novel_id, pinned_count + 1);
-                        }
+                    // 设置为置顶
+                    novel.is_pinned = true;
+                    novel.pinned_order = Some((pinned_count + 1) as i32);
+                    log::debug!("置顶小说，ID: {}, 顺序: {}", novel_id, pinned_count + 1);
+                } else {
+                    // 取消置顶
+                    novel.is_pinned = false;
+                    novel.pinned_order = None;
+                    log::debug!("取消置顶小说，ID: {}", novel_id);
+                }
+                
+                novel.updated_at = chrono::Utc::now();
+                db.update_novel(&novel)?;
+                Ok((novel.is_pinned, db.get_all_novels()?))
+            }).await;
+            
+            // 显示操作结果
+            let message = match result {
+                Ok((is_pinned, novels_list)) => {
+                    log::debug!("小说置顶状态更新成功");
+                    novels.set(novels_list);
+                    if is_pinned {
+                        format!("已置顶《{}》", current_title)
                     } else {
-                        // 取消置顶
-                        novel.is_pinned = false;
-                        novel.pinned_order = None;
-                        log::debug!("取消置顶小说，ID: {}", novel_id);
+                        format!("已取消《{}》的置顶", current_title)
                     }
-                    
-                    novel.updated_at = chrono::Utc::now();
-                    
-                    // 更新数据库
-                    if let Ok(()) = db.update_novel(&novel) {
-                        log::debug!("小说置顶状态更新成功");
-                        
-                        // 重新加载小说列表
-                        if let Ok(novels_list) = db.get_all_novels() {
-                            novels.set(novels_list);
-                        }
-                        
-                        // 显示操作结果
-                        if let Some(window) = web_sys::window() {
-                            if novel.is_pinned {
-                                window.alert_with_message(&format!("已置顶《{}》", current_title)).unwrap_or(())
-                            } else {
-                                window.alert_with_message(&format!("已取消《{}》的置顶", current_title)).unwrap_or(())
-                            }
-                        }
-                    } else {
-                        log::error!("更新小说置顶状态失败");
+                },
+                Err(e) => {
+                    log::warn!("切换小说置顶状态失败: {}", e);
+                    e.to_string()
+                }
+            };
+            if let Some(window) = web_sys::window() {
+                window.alert_with_message(&message).unwrap_or(());
+            }
+        });
+    };
+    
+    // 在后台线程重新加载小说列表，`select` 决定加载后选中哪部小说
+    let load_novels = move |select: fn(&[db::Novel], Option<i64>) -> Option<i64>| {
+        spawn(async move {
+            match db::with_database(|db| db.get_all_novels()).await {
+                Ok(novels_list) => {
+                    current_novel_id.set(select(&novels_list, current_novel_id()));
+                    novels.set(novels_list);
+                },
+                Err(e) => {
+                    log::error!("加载小说列表失败: {}", e);
+                    novels.set(Vec::new());
+                }
+            }
+        });
+    };
+    
+    // 导入小说后刷新列表并选中导入到的小说
+    let novel_imported = move |novel_id: i64| {
+        current_novel_id.set(Some(novel_id));
+        load_novels(|_, current| current);
+    };
+    
+    // 从回收站恢复或彻底删除后重新加载小说列表，当前小说不在列表中时改选最新的一部
+    let refresh_novels = move |_| {
+        load_novels(|novels_list, current| match current {
+            Some(id) if novels_list.iter().any(|n| n.id == id) => Some(id),
+            _ => novels_list.first().map(|n| n.id),
+        });
+    };
+    
+    // 切换书库后重新加载小说列表
+    let mut reload_library = move |_| {
+        log::debug!("书库已切换，重新加载小说列表");
+        current_novel_id.set(None);
+        load_recovery_entries();
+        load_novels(|novels_list, _| novels_list.first().map(|n| n.id));
+    };
+    
+    use_hook(move || {
+        spawn(async move {
+            match IntegrityChecker::new().startup_check().await {
+                Ok(Some(check)) if !check.report.is_clean() => {
+                    if check.report.repaired {
+                        reload_library(());
                     }
+                    integrity_check.set(Some(check));
+                },
+                Ok(_) => {},
+                Err(e) => log::error!("数据库完整性检查失败: {}", e),
+            }
+        });
+    });
+    
+    // 全文搜索
+    let run_search = move |query: String| {
+        current_view.set("search".to_string());
+        searching.set(true);
+        spawn(async move {
+            match SearchManager::new().search(None, &query).await {
+                Ok(results) => search_results.set(Some(results)),
+                Err(e) => {
+                    log::error!("搜索失败: {}", e);
+                    search_results.set(None);
                 }
-            },
-            Err(e) => {
-                log::error!("获取数据库连接失败: {}", e);
             }
-        }
+            searching.set(false);
+        });
+    };
+    
+    // 打开搜索命中的小说、章节或灵感
+    let open_search_novel = move |novel_id: i64| {
+        current_novel_id.set(Some(novel_id));
+        current_view.set("novels".to_string());
+    };
+    
+    let open_search_chapter = move |jump: SearchJump| {
+        current_novel_id.set(Some(jump.novel_id));
+        pending_jump.set(Some(jump));
+        current_view.set("chapters".to_string());
+    };
+    
+    // 恢复未保存内容后打开该章节
+    let open_recovered_chapter = move |(novel_id, chapter_id): (i64, i64)| {
+        current_novel_id.set(Some(novel_id));
+        pending_jump.set(Some(SearchJump { novel_id, chapter_id, match_offset: None, match_len: 0 }));
+        current_view.set("chapters".to_string());
+    };
+    
+    let open_search_inspiration = move |novel_id: i64| {
+        current_novel_id.set(Some(novel_id));
+        current_view.set("inspirations".to_string());
     };
     
     // 取消表单
@@ -289,6 +384,7 @@ pub fn App() -> Element {
             // 顶部导航栏
             Header {
                 search_query: search_query.clone(),
+                on_search: run_search,
             }
             
             // 主内容区域
@@ -340,11 +436,13 @@ pub fn App() -> Element {
                         on_cancel_form: cancel_form,
                         on_add_novel: show_new_novel_form,
                         on_toggle_pin: toggle_novel_pin,
+                        on_imported: novel_imported,
                     }
                     } else if current_view() == "chapters" {
                         ChapterManagement {
                             current_novel_id: current_novel_id.clone(),
                             novels: novels.clone(),
+                            pending_jump: pending_jump,
                         }
                     } else if current_view() == "inspirations" {
                         if current_novel_id().is_some() {
@@ -365,7 +463,11 @@ pub fn App() -> Element {
                                     class: "stats-container",
                                     div { 
                                         class: "stats-section writing-stats",
-                                        StatsView { writing_report: writing_report.clone() }
+                                        StatsView {
+                                            novel_id: novel_id,
+                                            writing_report: writing_report.clone(),
+                                            on_goals_changed: move |_| stats_refresh += 1,
+                                        }
                                     }
                                     
                                     div { 
@@ -386,14 +488,38 @@ pub fn App() -> Element {
                                 p { "请先从左侧选择一部小说" }
                             }
                         }
+                    } else if current_view() == "search" {
+                        SearchPanel {
+                            results: search_results,
+                            searching: searching,
+                            novels: novels.clone(),
+                            on_open_novel: open_search_novel,
+                            on_open_chapter: open_search_chapter,
+                            on_open_inspiration: open_search_inspiration,
+                        }
+                    } else if current_view() == "trash" {
+                        TrashView { novels: novels, on_changed: refresh_novels }
                     } else if current_view() == "settings" {
-                        SettingsView {}
+                        SettingsView { on_library_changed: reload_library }
                     } else {
                         div { "未知视图" }
                     }
                 }
             }
             
+            // 崩溃恢复提示
+            RecoveryPrompt {
+                entries: recovery_entries,
+                novels: novels,
+                on_restored: open_recovered_chapter,
+            }
+            
+            // 数据库检查报告
+            IntegrityReportPanel {
+                result: integrity_check,
+                on_repaired: reload_library,
+            }
+            
             // 底部状态栏
             StatusBar {
                 current_novel_id: current_novel_id.clone(),
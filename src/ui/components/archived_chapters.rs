@@ -0,0 +1,163 @@
+/// 已归档章节面板
+use dioxus::prelude::*;
+use crate::db::{self, Chapter};
+use log::{error, info};
+use std::collections::HashMap;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ArchivedChaptersProps {
+    pub novel_id: i64,
+    pub on_close: EventHandler<()>,
+    /// 取消归档后通知上层重新加载章节列表
+    pub on_changed: EventHandler<()>,
+}
+
+/// 已归档章节及其在章节树中的位置
+#[derive(Debug, Clone, PartialEq)]
+struct ArchivedItem {
+    chapter: Chapter,
+    /// 上级章节标题，从卷开始以“ / ”连接
+    location: String,
+    has_archived_children: bool,
+}
+
+fn load_archived(novel_id: i64) -> anyhow::Result<Vec<ArchivedItem>> {
+    let db = db::get_database()?;
+    let all = db.get_all_chapters_by_novel(novel_id)?;
+    let by_id: HashMap<i64, &Chapter> = all.iter().map(|c| (c.id, c)).collect();
+
+    Ok(db.get_archived_chapters(novel_id)?
+        .into_iter()
+        .map(|chapter| {
+            let mut titles = Vec::new();
+            let mut parent_id = chapter.parent_id;
+            while let Some(parent) = parent_id.and_then(|id| by_id.get(&id)) {
+                if titles.len() > all.len() {
+                    break;
+                }
+                titles.push(parent.title.clone());
+                parent_id = parent.parent_id;
+            }
+            titles.reverse();
+            let has_archived_children = all.iter().any(|c| c.parent_id == Some(chapter.id) && c.is_archived);
+            ArchivedItem { location: titles.join(" / "), has_archived_children, chapter }
+        })
+        .collect())
+}
+
+#[component]
+pub fn ArchivedChapters(props: ArchivedChaptersProps) -> Element {
+    let novel_id = props.novel_id;
+    let on_close = props.on_close;
+    let on_changed = props.on_changed;
+    let mut items = use_signal(Vec::<ArchivedItem>::new);
+    let mut message = use_signal(|| None::<String>);
+
+    let mut load_items = move || {
+        match load_archived(novel_id) {
+            Ok(list) => items.set(list),
+            Err(e) => error!("读取已归档章节失败: {}", e),
+        }
+    };
+    use_hook(load_items);
+
+    let mut unarchive = move |chapter: Chapter, with_children: bool| {
+        let result = db::get_database().and_then(|db| {
+            if with_children { db.unarchive_subtree(chapter.id) } else { db.unarchive_chapter(chapter.id) }
+        });
+        match result {
+            Ok(count) => {
+                info!("已取消归档 {} 个章节: root={}", count, chapter.id);
+                message.set(Some(if count > 1 {
+                    format!("「{}」等 {} 个章节已恢复到章节列表", chapter.title, count)
+                } else {
+                    format!("「{}」已恢复到章节列表", chapter.title)
+                }));
+                on_changed.call(());
+            },
+            Err(e) => {
+                error!("取消归档失败: {}", e);
+                message.set(Some(format!("取消归档失败: {}", e)));
+            }
+        }
+        load_items();
+    };
+
+    rsx! {
+        div {
+            class: "chapter-form-overlay",
+            onclick: move |_| on_close.call(()),
+
+            div {
+                class: "chapter-form archived-chapters",
+                onclick: move |e: Event<MouseData>| e.stop_propagation(),
+
+                h3 { "🗄️ 已归档章节" }
+                p {
+                    class: "setting-hint",
+                    "归档的章节不显示在章节列表中，不计入字数和搜索；导出时可以选择是否包含。取消归档时，已归档的上级章节也会一并恢复。"
+                }
+
+                if let Some(text) = message() {
+                    p { class: "export-message", "{text}" }
+                }
+
+                if items().is_empty() {
+                    div { class: "empty-state", "没有已归档的章节" }
+                } else {
+                    ul {
+                        class: "archived-list",
+                        for item in items() {
+                            li {
+                                key: "{item.chapter.id}",
+                                class: "archived-item",
+                                div {
+                                    class: "archived-item-info",
+                                    strong { "{item.chapter.title}" }
+                                    span {
+                                        class: "archived-item-meta",
+                                        if item.location.is_empty() {
+                                            "顶层 · {item.chapter.word_count} 字"
+                                        } else {
+                                            "{item.location} · {item.chapter.word_count} 字"
+                                        }
+                                    }
+                                }
+                                div {
+                                    class: "archived-item-actions",
+                                    button {
+                                        class: "btn btn-secondary",
+                                        onclick: {
+                                            let chapter = item.chapter.clone();
+                                            move |_| unarchive(chapter.clone(), false)
+                                        },
+                                        "取消归档"
+                                    }
+                                    if item.has_archived_children {
+                                        button {
+                                            class: "btn btn-secondary",
+                                            onclick: {
+                                                let chapter = item.chapter.clone();
+                                                move |_| unarchive(chapter.clone(), true)
+                                            },
+                                            "连同子章节取消归档"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div {
+                    class: "form-actions",
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| on_close.call(()),
+                        "关闭"
+                    }
+                }
+            }
+        }
+    }
+}
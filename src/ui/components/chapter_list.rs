@@ -1,4 +1,4 @@
-/// 章节列表组件 - 支持树状结构显示
+/// 章节列表组件 - 支持树状结构显示和拖放排序
 use dioxus::prelude::*;
 use crate::db::{Chapter, ChapterType};
 use crate::core::chapter_manager::{ChapterManager, ChapterTree};
@@ -11,17 +11,99 @@ pub struct ChapterListProps {
     pub on_edit_chapter: EventHandler<Chapter>,
     pub on_delete_chapter: EventHandler<i64>,
     pub on_add_subchapter: EventHandler<i64>,
+    pub on_archive_chapter: EventHandler<i64>,
+    /// 拖放结束时给出 `(章节, 新的上级章节, 在新同级中的位置)`，位置不计被移动的章节本身
+    pub on_move_chapter: EventHandler<(i64, Option<i64>, usize)>,
+}
+
+/// 拖放的落点
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DropTarget {
+    /// 放在某个章节之前
+    Before(i64),
+    /// 作为某个章节的最后一个子章节
+    Into(i64),
+    /// 放在某一组同级章节的末尾，`None` 为顶层
+    End(Option<i64>),
+}
+
+/// 渲染树节点时共用的状态和回调
+#[derive(Clone, Copy)]
+struct TreeContext {
+    current_chapter_id: Signal<Option<i64>>,
+    dragging: Signal<Option<i64>>,
+    drop_target: Signal<Option<DropTarget>>,
+    on_select: EventHandler<i64>,
+    on_edit: EventHandler<Chapter>,
+    on_delete: EventHandler<i64>,
+    on_add_sub: EventHandler<i64>,
+    on_archive: EventHandler<i64>,
+    on_move: EventHandler<(i64, Option<i64>, usize)>,
+}
+
+impl TreeContext {
+    /// 当前拖动的章节放到 `target` 时的新位置，不能放到自己或自己的子孙中时为 `None`
+    fn resolve(&self, tree: &ChapterTree, target: DropTarget) -> Option<(i64, Option<i64>, usize)> {
+        let dragged = (self.dragging)()?;
+        let (parent_id, anchor) = match target {
+            DropTarget::Before(id) => (tree.nodes.get(&id)?.chapter.parent_id, Some(id)),
+            DropTarget::Into(id) => (Some(id), None),
+            DropTarget::End(parent_id) => (parent_id, None),
+        };
+        if anchor == Some(dragged) || parent_id.is_some_and(|id| is_within(tree, id, dragged)) {
+            return None;
+        }
+
+        let siblings: Vec<i64> = match parent_id {
+            Some(id) => tree.nodes.get(&id)?.children.clone(),
+            None => tree.root_nodes.clone(),
+        };
+        let siblings: Vec<i64> = siblings.into_iter().filter(|id| *id != dragged).collect();
+        let position = match anchor {
+            Some(anchor) => siblings.iter().position(|id| *id == anchor)?,
+            None => siblings.len(),
+        };
+        Some((dragged, parent_id, position))
+    }
+
+    fn finish_drag(mut self) {
+        self.dragging.set(None);
+        self.drop_target.set(None);
+    }
+}
+
+/// `node_id` 是否就是 `ancestor_id` 或在它的子树中
+fn is_within(tree: &ChapterTree, node_id: i64, ancestor_id: i64) -> bool {
+    let mut current = Some(node_id);
+    let mut steps = 0;
+    while let Some(id) = current {
+        if id == ancestor_id {
+            return true;
+        }
+        steps += 1;
+        if steps > tree.nodes.len() {
+            return false;
+        }
+        current = tree.nodes.get(&id).and_then(|node| node.chapter.parent_id);
+    }
+    false
 }
 
 #[component]
 pub fn ChapterList(props: ChapterListProps) -> Element {
     let chapters = props.chapters;
-    let current_chapter_id = props.current_chapter_id;
-    let on_select_chapter = props.on_select_chapter;
-    let on_edit_chapter = props.on_edit_chapter;
-    let on_delete_chapter = props.on_delete_chapter;
-    let on_add_subchapter = props.on_add_subchapter;
-    
+    let ctx = TreeContext {
+        current_chapter_id: props.current_chapter_id,
+        dragging: use_signal(|| None::<i64>),
+        drop_target: use_signal(|| None::<DropTarget>),
+        on_select: props.on_select_chapter,
+        on_edit: props.on_edit_chapter,
+        on_delete: props.on_delete_chapter,
+        on_add_sub: props.on_add_subchapter,
+        on_archive: props.on_archive_chapter,
+        on_move: props.on_move_chapter,
+    };
+
     rsx! {
         div {
             class: "chapter-list",
@@ -36,7 +118,7 @@ pub fn ChapterList(props: ChapterListProps) -> Element {
                     }
                 }
             } else {
-                { 
+                {
                     let manager = ChapterManager::new();
                     let chapter_tree = manager.build_chapter_tree(chapters());
                     let root_nodes = chapter_tree.root_nodes.clone();
@@ -44,16 +126,9 @@ pub fn ChapterList(props: ChapterListProps) -> Element {
                         div {
                             class: "chapter-tree",
                             for root_id in root_nodes {
-                                { render_chapter_node(
-                                &chapter_tree,
-                                root_id,
-                                current_chapter_id,
-                                on_select_chapter.clone(),
-                                on_edit_chapter.clone(),
-                                on_delete_chapter.clone(),
-                                on_add_subchapter.clone()
-                            ) }
+                                { render_chapter_node(&chapter_tree, root_id, ctx) }
                             }
+                            { render_end_zone(&chapter_tree, None, ctx) }
                         }
                     }
                 }
@@ -62,15 +137,42 @@ pub fn ChapterList(props: ChapterListProps) -> Element {
     }
 }
 
-fn render_children(
-    tree: &ChapterTree,
-    children: Vec<i64>,
-    current_chapter_id: Signal<Option<i64>>,
-    on_select: EventHandler<i64>,
-    on_edit: EventHandler<Chapter>,
-    on_delete: EventHandler<i64>,
-    on_add_sub: EventHandler<i64>,
-) -> Element {
+/// 拖动时显示的落点，`target` 无效（例如拖到自己的子孙中）时不接受放下
+fn render_drop_zone(tree: &ChapterTree, target: DropTarget, ctx: TreeContext, class: &str) -> Element {
+    if (ctx.dragging)().is_none() {
+        return rsx! {};
+    }
+    let resolved = ctx.resolve(tree, target);
+    let active = resolved.is_some() && (ctx.drop_target)() == Some(target);
+    let mut drop_target = ctx.drop_target;
+    rsx! {
+        div {
+            class: if active { format!("{} active", class) } else { class.to_string() },
+            ondragover: move |e: Event<DragData>| {
+                if resolved.is_some() {
+                    e.prevent_default();
+                    if *drop_target.peek() != Some(target) {
+                        drop_target.set(Some(target));
+                    }
+                }
+            },
+            ondrop: move |e: Event<DragData>| {
+                e.stop_propagation();
+                e.prevent_default();
+                if let Some(chapter_move) = resolved {
+                    ctx.on_move.call(chapter_move);
+                }
+                ctx.finish_drag();
+            },
+        }
+    }
+}
+
+fn render_end_zone(tree: &ChapterTree, parent_id: Option<i64>, ctx: TreeContext) -> Element {
+    render_drop_zone(tree, DropTarget::End(parent_id), ctx, "chapter-drop-zone end")
+}
+
+fn render_children(tree: &ChapterTree, parent_id: i64, children: Vec<i64>, ctx: TreeContext) -> Element {
     if children.is_empty() {
         rsx! { div {} }
     } else {
@@ -78,30 +180,15 @@ fn render_children(
             div {
                 class: "chapter-children",
                 for child_id in children {
-                    { render_chapter_node(
-                        tree,
-                        child_id,
-                        current_chapter_id.clone(),
-                        on_select.clone(),
-                        on_edit.clone(),
-                        on_delete.clone(),
-                        on_add_sub.clone()
-                    ) }
+                    { render_chapter_node(tree, child_id, ctx) }
                 }
+                { render_end_zone(tree, Some(parent_id), ctx) }
             }
         }
     }
 }
 
-fn render_chapter_node(
-    tree: &ChapterTree,
-    node_id: i64,
-    current_chapter_id: Signal<Option<i64>>,
-    on_select: EventHandler<i64>,
-    on_edit: EventHandler<Chapter>,
-    on_delete: EventHandler<i64>,
-    on_add_sub: EventHandler<i64>,
-) -> Element {
+fn render_chapter_node(tree: &ChapterTree, node_id: i64, ctx: TreeContext) -> Element {
     if let Some(node) = tree.nodes.get(&node_id) {
         let chapter = node.chapter.clone();
         let depth = node.depth;
@@ -110,36 +197,77 @@ fn render_chapter_node(
         let chapter_title = chapter.title.clone();
         let chapter_word_count = chapter.word_count;
         let chapter_type_icon = get_chapter_type_icon(&chapter.chapter_type);
-        let is_selected = current_chapter_id() == Some(chapter_id);
+        let is_selected = (ctx.current_chapter_id)() == Some(chapter_id);
         let children = node.children.clone();
-        
+        let (on_select, on_edit, on_delete, on_add_sub, on_archive) =
+            (ctx.on_select, ctx.on_edit, ctx.on_delete, ctx.on_add_sub, ctx.on_archive);
+
+        // 拖动到章节上时作为它的最后一个子章节
+        let into = DropTarget::Into(chapter_id);
+        let into_move = ctx.resolve(tree, into);
+        let is_drop_into = into_move.is_some() && (ctx.drop_target)() == Some(into);
+        let is_dragged = (ctx.dragging)() == Some(chapter_id);
+        let mut dragging = ctx.dragging;
+        let mut drop_target = ctx.drop_target;
+
         // 改进缩进计算，提供更好的视觉层次感
         let indent_style = format!("margin-left: {}px", depth * 24);
-        
+        let mut node_class = String::from("chapter-node");
+        if is_selected {
+            node_class.push_str(" selected");
+        }
+        if is_dragged {
+            node_class.push_str(" dragging");
+        }
+        if is_drop_into {
+            node_class.push_str(" drop-into");
+        }
+
         rsx! {
             div {
                 key: "{chapter_id}",
-                class: if is_selected { "chapter-node selected" } else { "chapter-node" },
+                class: "{node_class}",
                 style: "{indent_style}",
-                
+
+                { render_drop_zone(tree, DropTarget::Before(chapter_id), ctx, "chapter-drop-zone") }
+
                 div {
                     class: "chapter-node-content",
+                    draggable: "true",
                     onclick: {
                         let id = chapter_id;
                         move |_| on_select.call(id)
                     },
-                    
+                    ondragstart: move |_| dragging.set(Some(chapter_id)),
+                    ondragend: move |_| ctx.finish_drag(),
+                    ondragover: move |e: Event<DragData>| {
+                        if into_move.is_some() {
+                            e.prevent_default();
+                            if *drop_target.peek() != Some(into) {
+                                drop_target.set(Some(into));
+                            }
+                        }
+                    },
+                    ondrop: move |e: Event<DragData>| {
+                        e.stop_propagation();
+                        e.prevent_default();
+                        if let Some(chapter_move) = into_move {
+                            ctx.on_move.call(chapter_move);
+                        }
+                        ctx.finish_drag();
+                    },
+
                     // 章节信息区域
                     div {
-                        span { 
-                            class: "chapter-icon", 
+                        span {
+                            class: "chapter-icon",
                             "{chapter_type_icon}"
                         }
-                        span { 
-                            class: "chapter-title", 
+                        span {
+                            class: "chapter-title",
                             "{chapter_title}"
                         }
-                        
+
                         // 子章节数量指示器（如果有子章节）
                         {if has_children {
                             rsx! {
@@ -152,18 +280,18 @@ fn render_chapter_node(
                         } else {
                             rsx! { div {} }
                         }}
-                        
+
                         // 字数统计
-                        span { 
-                            class: "chapter-word-count", 
+                        span {
+                            class: "chapter-word-count",
                             "{chapter_word_count} 字"
                         }
                     }
-                    
+
                     // 操作按钮区域
-                    div {            
+                    div {
                         class: "chapter-actions",
-                        
+
                         // 常规模式下的操作按钮
                         button {
                             class: "action-btn",
@@ -189,6 +317,18 @@ fn render_chapter_node(
                             },
                             "✏️"
                         }
+                        button {
+                            class: "action-btn",
+                            title: if has_children { "连同子章节归档" } else { "归档" },
+                            onclick: {
+                                let id = chapter_id;
+                                move |e: Event<MouseData>| {
+                                    e.stop_propagation();
+                                    on_archive.call(id);
+                                }
+                            },
+                            "🗄️"
+                        }
                         button {
                             class: "action-btn danger",
                             title: "删除",
@@ -203,10 +343,9 @@ fn render_chapter_node(
                         }
                     }
                 }
-                
+
                 // 递归渲染子章节
-                {render_children(tree, children, current_chapter_id, on_select.clone(), 
-                               on_edit.clone(), on_delete.clone(), on_add_sub.clone())}
+                {render_children(tree, chapter_id, children, ctx)}
             }
         }
     } else {
@@ -220,4 +359,4 @@ fn get_chapter_type_icon(chapter_type: &ChapterType) -> &'static str {
         ChapterType::Chapter => "📄",
         ChapterType::Scene => "🎬",
     }
-}
\ No newline at end of file
+}
@@ -2,7 +2,18 @@
 use dioxus::prelude::*;
 use crate::db::{self, Chapter, ChapterType};
 use crate::core::chapter_manager::ChapterManager;
+use crate::core::search_manager::SearchJump;
+use crate::core::writing_session::WritingSession;
+use crate::core::edit_history::EditHistoryStore;
+use crate::core::auto_save::{AutoSaveManager, AutoSaveTimer, AUTO_SAVE_DEBOUNCE_SECS, JOURNAL_DEBOUNCE_MS};
+use crate::core::recovery_journal::RecoveryEntry;
+use crate::config::AppConfig;
 use super::{ChapterList, ChapterForm, Editor};
+use super::find_replace_panel::FindReplacePanel;
+use super::export_panel::ExportPanel;
+use super::archived_chapters::ArchivedChapters;
+use super::version_timeline::VersionTimeline;
+use super::snapshot_panel::SnapshotPanel;
 use log::{info, warn, error};
 use tokio::task::spawn_local;
 
@@ -10,18 +21,29 @@ use tokio::task::spawn_local;
 pub struct ChapterManagementProps {
     pub current_novel_id: Signal<Option<i64>>,
     pub novels: Signal<Vec<db::Novel>>,
+    /// 从搜索结果跳转过来时要打开的章节
+    pub pending_jump: Signal<Option<SearchJump>>,
 }
 
 #[component]
 pub fn ChapterManagement(props: ChapterManagementProps) -> Element {
     let current_novel_id = props.current_novel_id;
     let novels = props.novels;
+    let mut pending_jump = props.pending_jump;
     
     // 章节相关状态
     let mut chapters = use_signal(|| Vec::<Chapter>::new());
     let mut current_selected_chapter_id = use_signal(|| None::<i64>);
     let mut current_chapter = use_signal(|| None::<Chapter>);
     let mut chapter_content = use_signal(|| String::new());
+    let mut editor_highlight = use_signal(|| None::<(usize, usize)>);
+    let mut writing_session = use_signal(|| None::<WritingSession>);
+    let mut edit_history = use_signal(EditHistoryStore::new);
+    
+    // 自动保存与恢复日志
+    let mut auto_save_timer = use_signal(|| AutoSaveTimer::from_config(&AppConfig::load().unwrap_or_default()));
+    let mut journal_generation = use_signal(|| 0u64);
+    let mut save_status = use_signal(|| None::<String>);
     
     // 表单状态
     let mut show_chapter_form = use_signal(|| false);
@@ -30,6 +52,19 @@ pub fn ChapterManagement(props: ChapterManagementProps) -> Element {
     let mut chapter_title = use_signal(|| String::new());
     let mut chapter_type = use_signal(|| ChapterType::Chapter);
     
+    // 查找替换面板
+    let mut show_find_replace = use_signal(|| false);
+    
+    // 导出面板
+    let mut show_export = use_signal(|| false);
+    
+    // 已归档章节面板
+    let mut show_archived = use_signal(|| false);
+    
+    // 版本时间线面板
+    let mut show_versions = use_signal(|| false);
+    let mut show_snapshots = use_signal(|| false);
+    
     // 删除确认状态
     let mut show_delete_confirm = use_signal(|| false);
     let mut chapter_to_delete = use_signal(|| None::<i64>);
@@ -49,13 +84,160 @@ pub fn ChapterManagement(props: ChapterManagementProps) -> Element {
         }
     });
     
+    // 结束当前写作会话并写入统计
+    let mut end_writing_session = move || {
+        if let Some(session) = writing_session.write().take() {
+            match db::get_database() {
+                Ok(db) => {
+                    if let Err(e) = session.finish(&db) {
+                        error!("保存写作会话失败: {}", e);
+                    }
+                },
+                Err(e) => error!("获取数据库连接失败: {}", e),
+            }
+        }
+    };
+    
+    // 为当前章节开始新的写作会话
+    let mut start_writing_session = move || {
+        end_writing_session();
+        if let Some(chapter) = current_chapter.peek().as_ref() {
+            writing_session.set(Some(WritingSession::start(
+                chapter.novel_id,
+                chapter.id,
+                &chapter_content.peek(),
+                chrono::Utc::now(),
+            )));
+        }
+    };
+    
+    // 把当前正文写入恢复日志
+    let write_journal = move || {
+        let Some(chapter) = current_chapter.peek().clone() else { return };
+        let entry = RecoveryEntry {
+            novel_id: chapter.novel_id,
+            chapter_id: chapter.id,
+            chapter_title: chapter.title.clone(),
+            content: chapter_content.peek().clone(),
+            written_at: chrono::Utc::now(),
+        };
+        if let Err(e) = AutoSaveManager::new().journal(&entry) {
+            error!("写入恢复日志失败: {}", e);
+        }
+    };
+    
+    // 自动保存当前章节，保存在后台线程进行，期间的新输入留给下一次自动保存
+    let mut auto_save_now = move || {
+        let Some(chapter) = current_chapter.peek().clone() else { return };
+        let content = chapter_content.peek().clone();
+        auto_save_timer.write().mark_saved();
+        let saving = AutoSaveManager::new().auto_save(chapter.id, content.clone());
+        spawn(async move {
+            match saving.await {
+                Ok(updated_chapter) => {
+                    // 保存期间又有输入时不标记为已保存
+                    if let Some(history) = edit_history.write().get_mut(chapter.id).filter(|h| h.current() == content) {
+                        history.mark_saved();
+                    }
+                    let still_open = current_chapter.peek().as_ref().map(|c| c.id) == Some(chapter.id);
+                    if let Some(listed) = chapters.write().iter_mut().find(|c| c.id == chapter.id) {
+                        *listed = updated_chapter.clone();
+                    }
+                    if still_open {
+                        current_chapter.set(Some(updated_chapter));
+                        save_status.set(Some(format!("已自动保存 {}", chrono::Local::now().format("%H:%M:%S"))));
+                    }
+                },
+                Err(e) => {
+                    error!("自动保存失败: {}", e);
+                    // 保留未保存状态，离开章节时会再次尝试
+                    auto_save_timer.write().record_edit(chrono::Utc::now());
+                    save_status.set(Some(format!("自动保存失败: {}", e)));
+                }
+            }
+        });
+    };
+    
+    // 离开当前章节前处理未保存的修改：开启自动保存时立即保存，否则保证恢复日志是最新的
+    let mut flush_pending_edits = move || {
+        journal_generation += 1;
+        let (enabled, dirty) = {
+            let timer = auto_save_timer.peek();
+            (timer.enabled, timer.is_dirty())
+        };
+        if dirty {
+            if enabled {
+                auto_save_now();
+            } else {
+                write_journal();
+            }
+        }
+        auto_save_timer.write().mark_saved();
+        save_status.set(None);
+    };
+    
+    use_drop(move || {
+        flush_pending_edits();
+        end_writing_session();
+    });
+    
+    // 章节列表加载后处理搜索跳转
+    use_effect(move || {
+        let Some(jump) = pending_jump() else { return };
+        if current_novel_id() != Some(jump.novel_id) {
+            return;
+        }
+        if let Some(listed) = chapters().iter().find(|c| c.id == jump.chapter_id) {
+            flush_pending_edits();
+            // 列表可能落后于数据库（例如刚从恢复日志写回），以数据库为准
+            let chapter = db::get_database()
+                .and_then(|db| db.get_chapter(listed.id))
+                .unwrap_or_else(|_| listed.clone());
+            current_selected_chapter_id.set(Some(chapter.id));
+            current_chapter.set(Some(chapter.clone()));
+            chapter_content.set(chapter.content.clone());
+            edit_history.write().open(chapter.id, &chapter.content, chrono::Utc::now());
+            editor_highlight.set(jump.match_offset.map(|offset| (offset, jump.match_len)));
+            pending_jump.set(None);
+            start_writing_session();
+        }
+    });
+    
     // 选择章节
     let select_chapter = move |chapter_id: i64| {
+        flush_pending_edits();
         current_selected_chapter_id.set(Some(chapter_id));
         if let Some(chapter) = chapters().iter().find(|c| c.id == chapter_id) {
             current_chapter.set(Some(chapter.clone()));
             chapter_content.set(chapter.content.clone());
+            edit_history.write().open(chapter.id, &chapter.content, chrono::Utc::now());
         }
+        start_writing_session();
+    };
+    
+    // 编辑器输入时累计写作会话
+    let record_edit = move |_| {
+        let now = chrono::Utc::now();
+        if let Some(session) = writing_session.write().as_mut() {
+            session.record_edit(&chapter_content.peek(), now);
+        }
+        auto_save_timer.write().record_edit(now);
+        
+        // 停止输入片刻后先写恢复日志，再按计时自动保存
+        journal_generation += 1;
+        let generation = *journal_generation.peek();
+        spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(JOURNAL_DEBOUNCE_MS as u64)).await;
+            if *journal_generation.peek() == generation {
+                write_journal();
+            }
+        });
+        spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(AUTO_SAVE_DEBOUNCE_SECS as u64)).await;
+            if auto_save_timer.peek().is_due(chrono::Utc::now()) {
+                auto_save_now();
+            }
+        });
     };
     
     // 新建章节
@@ -91,26 +273,39 @@ pub fn ChapterManagement(props: ChapterManagementProps) -> Element {
         show_delete_confirm.set(true);
     };
     
+    // 重新加载章节列表，当前章节已不在列表中（被删除或归档）时清空选择
+    let mut reload_chapter_list = move || {
+        if let Some(novel_id) = current_novel_id() {
+            match db::get_database().and_then(|db| db.get_chapters_by_novel(novel_id)) {
+                Ok(chapters_list) => chapters.set(chapters_list),
+                Err(e) => error!("加载章节列表失败: {}", e),
+            }
+        }
+        
+        let removed_selection = current_selected_chapter_id()
+            .filter(|id| !chapters().iter().any(|c| c.id == *id));
+        if let Some(selected_id) = removed_selection {
+            journal_generation += 1;
+            auto_save_timer.write().mark_saved();
+            if let Err(e) = AutoSaveManager::new().clear_journal(selected_id) {
+                warn!("清除恢复日志失败: {}", e);
+            }
+            end_writing_session();
+            current_selected_chapter_id.set(None);
+            current_chapter.set(None);
+            chapter_content.set(String::new());
+        }
+    };
+    
     // 执行删除
     let execute_delete = move |_| {
         if let Some(chapter_id) = chapter_to_delete() {
             info!("执行章节删除: id={}", chapter_id);
             if let Ok(db) = db::get_database() {
-                if let Ok(()) = db.delete_chapter(chapter_id) {
-                    info!("章节删除成功: id={}", chapter_id);
-                    // 重新加载章节列表
-                    if let Some(novel_id) = current_novel_id() {
-                        if let Ok(chapters_list) = db.get_chapters_by_novel(novel_id) {
-                            chapters.set(chapters_list);
-                        }
-                    }
-                    
-                    // 如果删除的是当前章节,清空选择
-                    if current_selected_chapter_id() == Some(chapter_id) {
-                        current_selected_chapter_id.set(None);
-                        current_chapter.set(None);
-                        chapter_content.set(String::new());
-                    }
+                if db.trash_chapter(chapter_id).is_ok() {
+                    info!("章节已移入回收站: id={}", chapter_id);
+                    edit_history.write().remove(chapter_id);
+                    reload_chapter_list();
                 } else {
                     error!("章节删除失败: id={}", chapter_id);
                 }
@@ -120,7 +315,71 @@ pub fn ChapterManagement(props: ChapterManagementProps) -> Element {
         chapter_to_delete.set(None);
     };
     
-    // 移动章节功能已移除
+    // 归档章节，有子章节时连同子章节一起归档
+    let archive_chapter = move |chapter_id: i64| {
+        let manager = ChapterManager::new();
+        let tree = manager.build_chapter_tree(chapters());
+        let subtree: Vec<Chapter> = manager.collect_subtree(&tree, chapter_id);
+        let Some(chapter) = subtree.first() else { return };
+        let prompt = if subtree.len() > 1 {
+            format!("确定要归档「{}」及其 {} 个子章节吗？归档后不再显示在章节列表中，也不计入字数，可以在“归档”中取消归档。", chapter.title, subtree.len() - 1)
+        } else {
+            format!("确定要归档「{}」吗？归档后不再显示在章节列表中，也不计入字数，可以在“归档”中取消归档。", chapter.title)
+        };
+        let confirmed = web_sys::window()
+            .and_then(|window| window.confirm_with_message(&prompt).ok())
+            .unwrap_or(false);
+        if !confirmed {
+            return;
+        }
+        
+        // 正在编辑的章节被归档时先保存修改
+        if current_selected_chapter_id().is_some_and(|id| subtree.iter().any(|c| c.id == id)) && auto_save_timer.peek().is_dirty() {
+            auto_save_now();
+        }
+        let result = db::get_database().and_then(|db| {
+            if subtree.len() > 1 { db.archive_subtree(chapter_id) } else { db.archive_chapter(chapter_id) }
+        });
+        match result {
+            Ok(count) => {
+                info!("已归档 {} 个章节: root={}", count, chapter_id);
+                reload_chapter_list();
+            },
+            Err(e) => error!("归档章节失败: {}", e),
+        }
+    };
+    
+    // 拖放移动章节
+    let move_chapter = move |(chapter_id, new_parent_id, new_position): (i64, Option<i64>, usize)| {
+        spawn(async move {
+            match ChapterManager::new().move_chapter(chapter_id, new_parent_id, new_position).await {
+                Ok(moved) => {
+                    if let Some(listed) = chapters.write().iter_mut().find(|c| c.id == moved.id) {
+                        *listed = moved.clone();
+                    }
+                    if current_selected_chapter_id() == Some(moved.id) {
+                        current_chapter.set(Some(moved));
+                    }
+                },
+                Err(e) => error!("移动章节失败: {}", e),
+            }
+        });
+    };
+    
+    // 按当前顺序重新编号全部章节的排序键
+    let renumber_chapters = move |_| {
+        let Some(novel_id) = current_novel_id() else { return };
+        spawn(async move {
+            match ChapterManager::new().renumber_novel(novel_id).await {
+                Ok(_) => {
+                    if let Ok(chapters_list) = db::get_database().and_then(|db| db.get_chapters_by_novel(novel_id)) {
+                        chapters.set(chapters_list);
+                    }
+                },
+                Err(e) => error!("重新编号章节失败: {}", e),
+            }
+        });
+    };
     
     // 取消删除
     let cancel_delete = move |_| {
@@ -189,10 +448,22 @@ pub fn ChapterManagement(props: ChapterManagementProps) -> Element {
         if let Some(chapter) = current_chapter() {
             if let Ok(db) = db::get_database() {
                 if let Ok(()) = db.update_chapter_content(chapter.id, &chapter_content()) {
+                    if let Some(history) = edit_history.write().get_mut(chapter.id) {
+                        history.mark_saved();
+                    }
+                    journal_generation += 1;
+                    auto_save_timer.write().mark_saved();
+                    save_status.set(None);
+                    if let Err(e) = AutoSaveManager::new().clear_journal(chapter.id) {
+                        warn!("清除恢复日志失败: {}", e);
+                    }
                     // 更新章节信息
                     if let Ok(updated_chapter) = db.get_chapter(chapter.id) {
                         current_chapter.set(Some(updated_chapter.clone()));
                         
+                        // 保存时结算写作会话，避免异常退出丢失统计
+                        start_writing_session();
+                        
                         // 更新列表中的章节
                         if let Some(novel_id) = current_novel_id() {
                             if let Ok(chapters_list) = db.get_chapters_by_novel(novel_id) {
@@ -205,6 +476,51 @@ pub fn ChapterManagement(props: ChapterManagementProps) -> Element {
         }
     };
     
+    // 查找替换或切换版本分支修改正文后重新加载章节
+    let mut reload_after_replace = move |_| {
+        if let Ok(db) = db::get_database() {
+            if let Some(novel_id) = current_novel_id() {
+                if let Ok(chapters_list) = db.get_chapters_by_novel(novel_id) {
+                    chapters.set(chapters_list);
+                }
+            }
+            if let Some(chapter) = current_chapter() {
+                if let Ok(updated_chapter) = db.get_chapter(chapter.id) {
+                    chapter_content.set(updated_chapter.content.clone());
+                    edit_history.write().open(updated_chapter.id, &updated_chapter.content, chrono::Utc::now());
+                    current_chapter.set(Some(updated_chapter));
+                    // 替换带来的字数变化不计入写作量
+                    start_writing_session();
+                }
+            }
+        }
+    };
+    
+    // 打开版本时间线前保存修改，时间线上的当前分支 head 与正文一致
+    let open_versions = move |_| {
+        if current_chapter.peek().is_none() {
+            return;
+        }
+        if auto_save_timer.peek().is_dirty() {
+            auto_save_now();
+        }
+        show_versions.set(true);
+    };
+    
+    // 拍摄快照前保存修改，快照记录的版本与编辑器中的正文一致
+    let open_snapshots = move |_| {
+        if auto_save_timer.peek().is_dirty() {
+            auto_save_now();
+        }
+        show_snapshots.set(true);
+    };
+    
+    // 恢复快照后章节可能被移入回收站或重新创建，先刷新列表再重新加载正文
+    let reload_after_restore = move |_| {
+        reload_chapter_list();
+        reload_after_replace(());
+    };
+    
     rsx! {
         div {
             class: "chapter-management",
@@ -229,6 +545,40 @@ pub fn ChapterManagement(props: ChapterManagementProps) -> Element {
                                     span { class: "material-icons", "add" }
                                     "新建章节"
                                 }
+                                button {
+                                    class: "btn btn-secondary",
+                                    onclick: move |_| show_find_replace.set(true),
+                                    "🔎 查找替换"
+                                }
+                                button {
+                                    class: "btn btn-secondary",
+                                    onclick: move |_| show_export.set(true),
+                                    "📤 导出"
+                                }
+                                button {
+                                    class: "btn btn-secondary",
+                                    onclick: move |_| show_archived.set(true),
+                                    "🗄️ 归档"
+                                }
+                                button {
+                                    class: "btn btn-secondary",
+                                    title: "当前章节的版本分支和里程碑",
+                                    disabled: current_chapter().is_none(),
+                                    onclick: open_versions,
+                                    "🕘 版本"
+                                }
+                                button {
+                                    class: "btn btn-secondary",
+                                    title: "拍摄、比较和恢复全书快照",
+                                    onclick: open_snapshots,
+                                    "📸 快照"
+                                }
+                                button {
+                                    class: "btn btn-secondary",
+                                    title: "按当前顺序重新生成排序键",
+                                    onclick: renumber_chapters,
+                                    "🔢 整理排序"
+                                }
                             }
                             div {
                                 class: "chapter-list",
@@ -240,6 +590,8 @@ pub fn ChapterManagement(props: ChapterManagementProps) -> Element {
                                     on_edit_chapter: edit_chapter,
                                     on_delete_chapter: confirm_delete,
                                     on_add_subchapter: add_subchapter,
+                                    on_archive_chapter: archive_chapter,
+                                    on_move_chapter: move_chapter,
                                 }
                             }
                         }
@@ -251,6 +603,62 @@ pub fn ChapterManagement(props: ChapterManagementProps) -> Element {
                                 current_chapter: current_chapter,
                                 chapter_content: chapter_content,
                                 on_save: save_chapter,
+                                highlight: editor_highlight,
+                                on_edit: record_edit,
+                                history: edit_history,
+                                save_status: save_status,
+                            }
+                        }
+                        
+                        // 查找替换面板
+                        if show_find_replace() {
+                            FindReplacePanel {
+                                novel_id: novel_id,
+                                chapters: chapters,
+                                on_close: move |_| show_find_replace.set(false),
+                                on_changed: reload_after_replace,
+                            }
+                        }
+                        
+                        // 导出面板
+                        if show_export() {
+                            ExportPanel {
+                                novel_id: novel_id,
+                                novel_title: novel.title.clone(),
+                                chapters: chapters,
+                                on_close: move |_| show_export.set(false),
+                            }
+                        }
+                        
+                        // 已归档章节面板
+                        if show_archived() {
+                            ArchivedChapters {
+                                novel_id: novel_id,
+                                on_close: move |_| show_archived.set(false),
+                                on_changed: move |_| reload_chapter_list(),
+                            }
+                        }
+                        
+                        // 版本时间线面板
+                        if show_versions() {
+                            if let Some(chapter) = current_chapter() {
+                                VersionTimeline {
+                                    chapter_id: chapter.id,
+                                    chapter_title: chapter.title.clone(),
+                                    on_close: move |_| show_versions.set(false),
+                                    on_switched: reload_after_replace,
+                                }
+                            }
+                        }
+                        
+                        // 全书快照面板
+                        if show_snapshots() {
+                            SnapshotPanel {
+                                novel_id: novel_id,
+                                novel_title: novel.title.clone(),
+                                chapters: chapters,
+                                on_close: move |_| show_snapshots.set(false),
+                                on_restored: reload_after_restore,
                             }
                         }
                         
@@ -320,7 +728,7 @@ pub fn ChapterManagement(props: ChapterManagementProps) -> Element {
                                     onclick: move |e: Event<MouseData>| e.stop_propagation(),
                                     
                                     h3 { "确认删除章节" }
-                                    div { class: "warning-text", "⚠️ 删除章节后无法恢复，所有子章节也将被删除。确认继续吗？" }
+                                    div { class: "warning-text", "章节连同所有子章节将移入回收站，可以在回收站中恢复。确认继续吗？" }
                                     
                                     div { class: "form-actions",
                                         button {
@@ -0,0 +1,127 @@
+/// 文本对比组件 - 按段落、句子或字词显示两段文本的差异，支持并排和行内两种视图
+use dioxus::prelude::*;
+use crate::utils::diff_utils::{DiffGranularity, DiffHunk, DiffSpan, DiffUtils, SpanKind};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct DiffViewProps {
+    pub old_text: String,
+    pub new_text: String,
+    pub old_label: String,
+    pub new_label: String,
+}
+
+fn span_class(kind: SpanKind) -> &'static str {
+    match kind {
+        SpanKind::Equal => "diff-equal",
+        SpanKind::Insert => "diff-insert",
+        SpanKind::Delete => "diff-delete",
+    }
+}
+
+/// 并排视图中一侧显示的片段：旧文本不显示插入，新文本不显示删除
+fn side_spans(hunk: &DiffHunk, hidden: SpanKind) -> Vec<DiffSpan> {
+    hunk.spans.iter().filter(|s| s.kind != hidden).cloned().collect()
+}
+
+fn render_spans(spans: Vec<DiffSpan>) -> Element {
+    rsx! {
+        for (index, span) in spans.into_iter().enumerate() {
+            span { key: "{index}", class: span_class(span.kind), "{span.text}" }
+        }
+    }
+}
+
+#[component]
+pub fn DiffView(props: DiffViewProps) -> Element {
+    let mut granularity = use_signal(|| DiffGranularity::Word);
+    let mut side_by_side = use_signal(|| true);
+
+    let diff = DiffUtils::structured_diff(&props.old_text, &props.new_text, granularity());
+    let stats = diff.statistics.clone();
+    let old_paragraph_count = props.old_text.split('\n').count();
+
+    rsx! {
+        div {
+            class: "diff-view",
+
+            div {
+                class: "diff-toolbar",
+                select {
+                    value: match granularity() {
+                        DiffGranularity::Paragraph => "paragraph",
+                        DiffGranularity::Sentence => "sentence",
+                        DiffGranularity::Word => "word",
+                    },
+                    onchange: move |e| granularity.set(match e.value().as_str() {
+                        "paragraph" => DiffGranularity::Paragraph,
+                        "sentence" => DiffGranularity::Sentence,
+                        _ => DiffGranularity::Word,
+                    }),
+                    option { value: "paragraph", "按段落" }
+                    option { value: "sentence", "按句子" }
+                    option { value: "word", "按字词" }
+                }
+                button {
+                    class: "btn btn-secondary",
+                    onclick: move |_| side_by_side.set(!side_by_side()),
+                    if side_by_side() { "行内视图" } else { "并排视图" }
+                }
+                span {
+                    class: "diff-stats",
+                    "{diff.hunks.len()} 处修改 · "
+                    span { class: "diff-insert", "+{stats.insertions}" }
+                    " / "
+                    span { class: "diff-delete", "-{stats.deletions}" }
+                    " 字 · {stats.unchanged} 字未变"
+                }
+            }
+
+            if diff.hunks.is_empty() {
+                div { class: "empty-state", "两段文本没有差异" }
+            } else {
+                if side_by_side() {
+                    div {
+                        class: "diff-columns diff-header",
+                        div { "{props.old_label}" }
+                        div { "{props.new_label}" }
+                    }
+                }
+                div {
+                    class: "diff-hunks",
+                    for (index, hunk) in diff.hunks.iter().enumerate() {
+                        div {
+                            key: "{index}",
+                            class: "diff-hunk",
+                            {
+                                // 与上一处修改之间未改动的段落数
+                                let previous_end = if index == 0 { 0 } else { diff.hunks[index - 1].old_paragraphs.end };
+                                let skipped = hunk.old_paragraphs.start - previous_end;
+                                rsx! {
+                                    div {
+                                        class: "diff-hunk-header",
+                                        if skipped > 0 { "⋯ {skipped} 段未改动 ⋯ " }
+                                        "第 {hunk.new_paragraphs.start + 1} 段"
+                                    }
+                                }
+                            }
+                            if side_by_side() {
+                                div {
+                                    class: "diff-columns",
+                                    div { class: "diff-text", { render_spans(side_spans(hunk, SpanKind::Insert)) } }
+                                    div { class: "diff-text", { render_spans(side_spans(hunk, SpanKind::Delete)) } }
+                                }
+                            } else {
+                                div { class: "diff-text", { render_spans(hunk.spans.clone()) } }
+                            }
+                        }
+                    }
+                    if let Some(last) = diff.hunks.last() {
+                        if last.old_paragraphs.end < old_paragraph_count {
+                            div { class: "diff-hunk-header", "⋯ {old_paragraph_count - last.old_paragraphs.end} 段未改动 ⋯" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -2,12 +2,65 @@
 /// 章节编辑器组件
 use dioxus::prelude::*;
 use crate::db::Chapter;
+use crate::core::edit_history::{EditHistoryStore, EditKind};
+use crate::core::formatter::{Formatter, FormatOptions};
+use crate::core::version_manager::{ParagraphAnnotation, ParagraphOrigin, VersionManager};
+use chrono::{DateTime, Utc};
+use log::error;
 
 #[derive(Props, Clone, PartialEq)]
 pub struct EditorProps {
     pub current_chapter: Signal<Option<Chapter>>,
     pub chapter_content: Signal<String>,
     pub on_save: EventHandler<()>,
+    /// 需要选中并滚动到的正文区间（字符偏移, 字符长度），处理后清空
+    pub highlight: Signal<Option<(usize, usize)>>,
+    /// 正文被用户输入、撤销/重做或格式化修改后触发
+    pub on_edit: EventHandler<()>,
+    /// 各章节的撤销历史，由上层在打开章节时初始化
+    pub history: Signal<EditHistoryStore>,
+    /// 自动保存状态提示
+    pub save_status: Signal<Option<String>>,
+}
+
+/// 段落年代的分档：(距今不超过的天数, 样式, 图例)
+const AGE_BUCKETS: &[(i64, &str, &str)] = &[
+    (1, "blame-age-0", "一天内"),
+    (7, "blame-age-1", "一周内"),
+    (30, "blame-age-2", "一个月内"),
+    (180, "blame-age-3", "半年内"),
+    (i64::MAX, "blame-age-4", "更早"),
+];
+
+fn age_class(origin: Option<&ParagraphOrigin>, now: DateTime<Utc>) -> &'static str {
+    let Some(origin) = origin else { return "blame-unsaved" };
+    let days = (now - origin.created_at).num_days();
+    AGE_BUCKETS.iter().find(|(limit, _, _)| days < *limit).map(|(_, class, _)| *class).unwrap_or("blame-age-4")
+}
+
+fn age_label(origin: Option<&ParagraphOrigin>, now: DateTime<Utc>) -> String {
+    let Some(origin) = origin else { return "未保存".to_string() };
+    let elapsed = now - origin.created_at;
+    if elapsed.num_hours() < 1 {
+        format!("{} 分钟前", elapsed.num_minutes().max(0))
+    } else if elapsed.num_days() < 1 {
+        format!("{} 小时前", elapsed.num_hours())
+    } else if elapsed.num_days() < 60 {
+        format!("{} 天前", elapsed.num_days())
+    } else {
+        origin.created_at.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string()
+    }
+}
+
+fn origin_title(origin: Option<&ParagraphOrigin>) -> String {
+    let Some(origin) = origin else { return "尚未保存为版本".to_string() };
+    let kind = if origin.is_auto_save { "自动保存" } else { "手动保存" };
+    let time = origin.created_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+    if origin.commit_message.is_empty() {
+        format!("版本 #{} · {} · {}", origin.version_id, time, kind)
+    } else {
+        format!("版本 #{} · {} · {} · {}", origin.version_id, time, kind, origin.commit_message)
+    }
 }
 
 #[component]
@@ -15,6 +68,94 @@ pub fn Editor(props: EditorProps) -> Element {
     let current_chapter = props.current_chapter;
     let mut chapter_content = props.chapter_content;
     let on_save = props.on_save;
+    let mut highlight = props.highlight;
+    let on_edit = props.on_edit;
+    let mut history = props.history;
+    let save_status = props.save_status;
+    
+    // 下一次输入事件的类型：粘贴事件先于输入事件触发
+    let mut next_edit_kind = use_signal(|| EditKind::Typing);
+    // 段落年代模式：正文只读，每段左侧按最后修改的时间着色
+    let mut blame_mode = use_signal(|| false);
+    let mut annotations = use_signal(Vec::<ParagraphAnnotation>::new);
+    
+    // 打开段落年代模式或切换章节时重新标注
+    use_effect(move || {
+        let chapter = current_chapter();
+        if !blame_mode() {
+            return;
+        }
+        let Some(chapter) = chapter else { return };
+        let content = chapter_content.peek().clone();
+        annotations.set(Vec::new());
+        spawn(async move {
+            match VersionManager::new().annotate_paragraphs(chapter.id, &content).await {
+                Ok(list) => annotations.set(list),
+                Err(e) => error!("标注段落来源失败: {}", e),
+            }
+        });
+    });
+    
+    let chapter_id = move || current_chapter.peek().as_ref().map(|c| c.id);
+    
+    // 记录一次编辑并更新正文
+    let mut apply_edit = move |content: String, kind: EditKind| {
+        if let Some(id) = chapter_id() {
+            if let Some(h) = history.write().get_mut(id) {
+                h.record(&content, kind, chrono::Utc::now());
+            }
+        }
+        chapter_content.set(content);
+        on_edit.call(());
+    };
+    
+    let mut undo = move || {
+        let Some(id) = chapter_id() else { return };
+        let restored = history.write().get_mut(id).and_then(|h| h.undo());
+        if let Some(content) = restored {
+            chapter_content.set(content);
+            on_edit.call(());
+        }
+    };
+    
+    let mut redo = move || {
+        let Some(id) = chapter_id() else { return };
+        let restored = history.write().get_mut(id).and_then(|h| h.redo());
+        if let Some(content) = restored {
+            chapter_content.set(content);
+            on_edit.call(());
+        }
+    };
+    
+    // 对整章应用格式化，作为单独的撤销步骤
+    let mut format_chapter = move || {
+        let formatted = Formatter::new().format_text(&chapter_content.peek(), &FormatOptions::default());
+        apply_edit(formatted, EditKind::Format);
+    };
+    
+    let can_undo = current_chapter().and_then(|c| history.read().get(c.id).map(|h| h.can_undo())).unwrap_or(false);
+    let can_redo = current_chapter().and_then(|c| history.read().get(c.id).map(|h| h.can_redo())).unwrap_or(false);
+    
+    // 选中检索命中的文字并滚动到可见区域
+    use_effect(move || {
+        if let Some((offset, len)) = highlight() {
+            // 浏览器中的选区按UTF-16编码单元计数
+            let content = chapter_content.peek().clone();
+            let start: usize = content.chars().take(offset).map(char::len_utf16).sum();
+            let end: usize = start + content.chars().skip(offset).take(len).map(char::len_utf16).sum::<usize>();
+            document::eval(&format!(
+                r#"setTimeout(() => {{
+                    const textarea = document.querySelector('.editor-textarea');
+                    if (!textarea) return;
+                    textarea.focus();
+                    textarea.setSelectionRange({start}, {end});
+                    const ratio = {start} / Math.max(textarea.value.length, 1);
+                    textarea.scrollTop = Math.max(0, ratio * textarea.scrollHeight - textarea.clientHeight / 2);
+                }}, 0);"#
+            ));
+            highlight.set(None);
+        }
+    });
     
     rsx! {
         div {
@@ -51,30 +192,102 @@ pub fn Editor(props: EditorProps) -> Element {
                             }
                             button {
                                 class: "toolbar-btn",
-                                title: "撤销",
+                                title: "撤销 (Ctrl+Z)",
+                                disabled: !can_undo,
+                                onclick: move |_| undo(),
                                 "↶ 撤销"
                             }
                             button {
                                 class: "toolbar-btn",
-                                title: "重做",
+                                title: "重做 (Ctrl+Shift+Z)",
+                                disabled: !can_redo,
+                                onclick: move |_| redo(),
                                 "↷ 重做"
                             }
+                            button {
+                                class: "toolbar-btn",
+                                title: "格式化全章（可撤销）",
+                                onclick: move |_| format_chapter(),
+                                "🧹 格式化"
+                            }
+                            button {
+                                class: if blame_mode() { "toolbar-btn active" } else { "toolbar-btn" },
+                                title: "按最后修改的时间为每段着色（只读）",
+                                onclick: move |_| blame_mode.set(!blame_mode()),
+                                if blame_mode() { "✏️ 返回编辑" } else { "🕰 段落年代" }
+                            }
                         }
                         
-                        div {
-                            class: "editor-content",
-                            textarea {
-                                class: "editor-textarea",
-                                value: "{chapter_content}",
-                                oninput: move |e| chapter_content.set(e.value()),
-                                placeholder: "开始写作...",
-                                spellcheck: true,
+                        if blame_mode() {
+                            {
+                                let now = Utc::now();
+                                rsx! {
+                                    div {
+                                        class: "editor-content editor-blame",
+                                        div {
+                                            class: "blame-legend",
+                                            span { class: "blame-swatch blame-unsaved", "未保存" }
+                                            for (_, class, label) in AGE_BUCKETS.iter() {
+                                                span { class: "blame-swatch {class}", "{label}" }
+                                            }
+                                        }
+                                        for (index, annotation) in annotations().into_iter().enumerate() {
+                                            div {
+                                                key: "{index}",
+                                                class: "blame-row",
+                                                title: origin_title(annotation.origin.as_ref()),
+                                                div {
+                                                    class: "blame-gutter {age_class(annotation.origin.as_ref(), now)}",
+                                                    if !annotation.text.trim().is_empty() {
+                                                        "{age_label(annotation.origin.as_ref(), now)}"
+                                                    }
+                                                }
+                                                p { class: "blame-text", "{annotation.text}" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        } else {
+                            div {
+                                class: "editor-content",
+                                textarea {
+                                    class: "editor-textarea",
+                                    value: "{chapter_content}",
+                                    oninput: move |e| {
+                                        let kind = next_edit_kind.replace(EditKind::Typing);
+                                        apply_edit(e.value(), kind);
+                                    },
+                                    onpaste: move |_| next_edit_kind.set(EditKind::Paste),
+                                    onkeydown: move |e: KeyboardEvent| {
+                                        let modifiers = e.modifiers();
+                                        if !(modifiers.ctrl() || modifiers.meta()) {
+                                            return;
+                                        }
+                                        match e.key() {
+                                            Key::Character(c) if c.eq_ignore_ascii_case("z") => {
+                                                e.prevent_default();
+                                                if modifiers.shift() { redo() } else { undo() }
+                                            },
+                                            Key::Character(c) if c.eq_ignore_ascii_case("y") => {
+                                                e.prevent_default();
+                                                redo();
+                                            },
+                                            _ => {}
+                                        }
+                                    },
+                                    placeholder: "开始写作...",
+                                    spellcheck: true,
+                                }
                             }
                         }
                         
                         div {
                             class: "editor-footer",
                             span { "当前字数: {chapter_content().chars().filter(|c| !c.is_whitespace()).count()}" }
+                            if let Some(status) = save_status() {
+                                span { class: "editor-save-status", "{status}" }
+                            }
                         }
                     }
                 }
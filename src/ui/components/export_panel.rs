@@ -0,0 +1,358 @@
+/// 小说导出面板
+use dioxus::prelude::*;
+use crate::core::epub_exporter::{EpubExporter, EpubOptions};
+use crate::core::docx_exporter::{DocxExporter, DocxOptions};
+use crate::core::markdown_exchange::{MarkdownExchange, ProjectFormat};
+use crate::core::project_bundle::{BundleManager, BUNDLE_EXTENSION};
+use crate::core::snapshot_manager::SnapshotManager;
+use crate::db::{self, Chapter, ChapterType, NovelSnapshot};
+use crate::utils::export_utils::safe_file_name;
+use log::error;
+use std::path::PathBuf;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ExportPanelProps {
+    pub novel_id: i64,
+    pub novel_title: String,
+    pub chapters: Signal<Vec<Chapter>>,
+    pub on_close: EventHandler<()>,
+    /// 导出快照时的全书而不是当前内容，此时不提供项目包格式
+    pub snapshot: Option<NovelSnapshot>,
+}
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Epub,
+    Docx,
+    Project(ProjectFormat),
+    Bundle,
+}
+
+impl ExportFormat {
+    fn value(&self) -> &'static str {
+        match self {
+            ExportFormat::Epub => "epub",
+            ExportFormat::Docx => "docx",
+            ExportFormat::Project(ProjectFormat::Markdown) => "markdown",
+            ExportFormat::Project(ProjectFormat::PlainText) => "text",
+            ExportFormat::Bundle => BUNDLE_EXTENSION,
+        }
+    }
+
+    fn from_value(value: &str) -> Self {
+        match value {
+            "docx" => ExportFormat::Docx,
+            "markdown" => ExportFormat::Project(ProjectFormat::Markdown),
+            "text" => ExportFormat::Project(ProjectFormat::PlainText),
+            BUNDLE_EXTENSION => ExportFormat::Bundle,
+            _ => ExportFormat::Epub,
+        }
+    }
+}
+
+/// 默认导出到书库所在目录下的 `exports/`
+///
+/// 项目文件夹格式的保存位置是目录，会在其中创建以小说标题命名的文件夹。
+fn default_output_path(title: &str, format: ExportFormat) -> String {
+    let dir = db::current_database_path()
+        .and_then(|p| p.parent().map(|d| d.join("exports")))
+        .unwrap_or_else(|| PathBuf::from("exports"));
+    let path = match format {
+        ExportFormat::Project(_) => dir,
+        _ => dir.join(format!("{}.{}", safe_file_name(title), format.value())),
+    };
+    path.to_string_lossy().to_string()
+}
+
+/// 按快照重建全书后写出，快照不支持项目包格式
+async fn export_snapshot(
+    snapshot_id: i64,
+    format: ExportFormat,
+    epub_options: &EpubOptions,
+    docx_options: &DocxOptions,
+    include_archived: bool,
+    path: PathBuf,
+) -> anyhow::Result<PathBuf> {
+    let data = SnapshotManager::new().export_data(snapshot_id).await?;
+    match format {
+        ExportFormat::Epub => EpubExporter::write(&data, epub_options, &path).map(|_| path),
+        ExportFormat::Docx => DocxExporter::write(&data, docx_options, &path).map(|_| path),
+        ExportFormat::Project(project_format) => MarkdownExchange::new().export_data(data, project_format, include_archived, &path),
+        ExportFormat::Bundle => Err(anyhow::anyhow!("快照不能导出为项目包")),
+    }
+}
+
+#[component]
+pub fn ExportPanel(props: ExportPanelProps) -> Element {
+    let novel_id = props.novel_id;
+    let chapters = props.chapters;
+    let on_close = props.on_close;
+
+    let snapshot_id = props.snapshot.as_ref().map(|s| s.id);
+    let novel_title = match &props.snapshot {
+        Some(snapshot) => format!("{}-{}", props.novel_title, snapshot.name),
+        None => props.novel_title.clone(),
+    };
+    let mut format = use_signal(|| ExportFormat::Epub);
+    let mut output_path = use_signal(|| default_output_path(&novel_title, ExportFormat::Epub));
+    let mut selected_volumes = use_signal(Vec::<i64>::new);
+    let mut include_archived = use_signal(|| false);
+    // EPUB 选项
+    let mut language = use_signal(|| EpubOptions::default().language);
+    let mut vertical_writing = use_signal(|| false);
+    let mut cover_image = use_signal(String::new);
+    // DOCX 选项
+    let mut font_family = use_signal(|| DocxOptions::default().font_family);
+    let mut font_size = use_signal(|| DocxOptions::default().font_size);
+    let mut line_spacing = use_signal(|| DocxOptions::default().line_spacing);
+    let mut surname = use_signal(String::new);
+    let mut exporting = use_signal(|| false);
+    let mut message = use_signal(|| None::<String>);
+
+    let run_export = move |_| {
+        let cover = cover_image().trim().to_string();
+        let epub_options = EpubOptions {
+            volume_ids: selected_volumes(),
+            language: language(),
+            vertical_writing: vertical_writing(),
+            include_archived: include_archived(),
+            cover_image: (!cover.is_empty()).then(|| PathBuf::from(cover)),
+        };
+        let docx_options = DocxOptions {
+            root_ids: selected_volumes(),
+            include_archived: include_archived(),
+            font_family: font_family(),
+            font_size: font_size(),
+            line_spacing: line_spacing(),
+            surname: surname(),
+        };
+        let path = PathBuf::from(output_path().trim());
+        let format = format();
+        exporting.set(true);
+        spawn(async move {
+            let result = match snapshot_id {
+                Some(snapshot_id) => export_snapshot(snapshot_id, format, &epub_options, &docx_options, include_archived(), path).await,
+                None => match format {
+                        ExportFormat::Epub => EpubExporter::new().export(novel_id, &epub_options, &path).await.map(|_| path),
+                    ExportFormat::Docx => DocxExporter::new().export(novel_id, &docx_options, &path).await.map(|_| path),
+                    ExportFormat::Project(project_format) => MarkdownExchange::new().export(novel_id, project_format, include_archived(), &path).await,
+                    ExportFormat::Bundle => BundleManager::new().export(novel_id, &path).await.map(|_| path),
+                },
+            };
+            match result {
+                Ok(path) => message.set(Some(format!("已导出到 {}", path.display()))),
+                Err(e) => {
+                    error!("导出失败: {}", e);
+                    message.set(Some(format!("导出失败: {}", e)));
+                }
+            }
+            exporting.set(false);
+        });
+    };
+
+    // EPUB 按卷选择，DOCX 可以选择任意卷或章节的子树，项目文件夹和快照总是导出全书
+    let volumes: Vec<Chapter> = chapters().into_iter()
+        .filter(|_| snapshot_id.is_none())
+        .filter(|c| match format() {
+            ExportFormat::Epub => c.chapter_type == ChapterType::Volume,
+            ExportFormat::Docx => c.chapter_type != ChapterType::Scene,
+            ExportFormat::Project(_) | ExportFormat::Bundle => false,
+        })
+        .collect();
+
+    rsx! {
+        div {
+            class: "chapter-form-overlay",
+            onclick: move |_| on_close.call(()),
+
+            div {
+                class: "chapter-form export-panel",
+                onclick: move |e: Event<MouseData>| e.stop_propagation(),
+
+                h3 { "📤 导出" }
+                if let Some(snapshot) = &props.snapshot {
+                    p { class: "setting-hint", "导出快照「{snapshot.name}」时的全书，而不是当前内容" }
+                }
+
+                div {
+                    class: "form-group",
+                    label { "格式: " }
+                    select {
+                        value: format().value(),
+                        onchange: move |e| {
+                            let new_format = ExportFormat::from_value(&e.value());
+                            format.set(new_format);
+                            // 项目文件夹默认完整导出，其余格式默认只导出正文
+                            include_archived.set(matches!(new_format, ExportFormat::Project(_)));
+                            selected_volumes.write().clear();
+                            output_path.set(default_output_path(&novel_title, new_format));
+                        },
+                        option { value: "epub", "EPUB 电子书" }
+                        option { value: "docx", "DOCX 投稿稿件" }
+                        option { value: "markdown", "Markdown 项目文件夹" }
+                        option { value: "text", "纯文本项目文件夹" }
+                        if snapshot_id.is_none() {
+                            option { value: BUNDLE_EXTENSION, "项目包（完整备份与迁移）" }
+                        }
+                    }
+                }
+
+                div {
+                    class: "form-group",
+                    label { if matches!(format(), ExportFormat::Project(_)) { "保存到目录: " } else { "保存到: " } }
+                    input {
+                        value: output_path(),
+                        oninput: move |e| output_path.set(e.value()),
+                    }
+                }
+
+                if !volumes.is_empty() {
+                    div {
+                        class: "form-group",
+                        label { "导出范围（不选则导出全书）: " }
+                        div {
+                            class: "export-volumes",
+                            for volume in volumes {
+                                label {
+                                    key: "{volume.id}",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: selected_volumes().contains(&volume.id),
+                                        onchange: move |e| {
+                                            let mut ids = selected_volumes.write();
+                                            ids.retain(|id| *id != volume.id);
+                                            if e.checked() {
+                                                ids.push(volume.id);
+                                            }
+                                        },
+                                    }
+                                    "{volume.title}"
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if format() != ExportFormat::Bundle {
+                    div {
+                        class: "form-group",
+                        label {
+                            input {
+                                r#type: "checkbox",
+                                checked: include_archived(),
+                                onchange: move |e| include_archived.set(e.checked()),
+                            }
+                            "包含已归档章节"
+                        }
+                    }
+                }
+
+                if format() == ExportFormat::Docx {
+                    div {
+                        class: "form-group",
+                        label { "字体: " }
+                        input {
+                            value: font_family(),
+                            oninput: move |e| font_family.set(e.value()),
+                        }
+                        label { "字号: " }
+                        input {
+                            r#type: "number",
+                            step: "0.5",
+                            value: "{font_size}",
+                            oninput: move |e| {
+                                if let Ok(value) = e.value().parse::<f32>() {
+                                    font_size.set(value.clamp(6.0, 72.0));
+                                }
+                            },
+                        }
+                        label { "行距: " }
+                        select {
+                            value: "{line_spacing}",
+                            onchange: move |e| line_spacing.set(e.value().parse().unwrap_or(2.0)),
+                            option { value: "1", "单倍" }
+                            option { value: "1.5", "1.5 倍" }
+                            option { value: "2", "两倍" }
+                        }
+                    }
+                    div {
+                        class: "form-group",
+                        label { "页眉姓氏: " }
+                        input {
+                            placeholder: "留空则取作者名",
+                            value: surname(),
+                            oninput: move |e| surname.set(e.value()),
+                        }
+                    }
+                }
+
+                if format() == ExportFormat::Epub {
+                    div {
+                        class: "form-group",
+                        label { "语言: " }
+                        select {
+                            value: language(),
+                            onchange: move |e| language.set(e.value()),
+                            option { value: "zh-CN", "简体中文" }
+                            option { value: "zh-TW", "繁體中文" }
+                            option { value: "ja", "日本語" }
+                            option { value: "en", "English" }
+                        }
+                        label {
+                            input {
+                                r#type: "checkbox",
+                                checked: vertical_writing(),
+                                onchange: move |e| vertical_writing.set(e.checked()),
+                            }
+                            "竖排"
+                        }
+                    }
+
+                    div {
+                        class: "form-group",
+                        label { "封面图片（可选）: " }
+                        input {
+                            placeholder: "JPG / PNG 文件路径",
+                            value: cover_image(),
+                            oninput: move |e| cover_image.set(e.value()),
+                        }
+                    }
+                }
+
+                if matches!(format(), ExportFormat::Project(_)) {
+                    p {
+                        class: "export-hint",
+                        "每章一个文件，卷为子目录，可以通过「导入」完整恢复；不包含已归档章节时，导入后也不会有这些章节。"
+                    }
+                }
+
+                if format() == ExportFormat::Bundle {
+                    p {
+                        class: "export-hint",
+                        "包含全部章节、历史版本、灵感及标签、章节关联和写作统计，可导入到任意书库。"
+                    }
+                }
+
+                if let Some(text) = message() {
+                    p { class: "export-message", "{text}" }
+                }
+
+                div {
+                    class: "form-actions",
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| on_close.call(()),
+                        "关闭"
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: exporting() || output_path().trim().is_empty(),
+                        onclick: run_export,
+                        if exporting() { "导出中..." } else { "导出" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+/// 全书查找替换面板
+use dioxus::prelude::*;
+use crate::core::find_replace_manager::{FindMatch, FindOptions, FindReplaceManager, MatchMode, ReplaceOutcome};
+use crate::db::{Chapter, ChapterType};
+use log::error;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct FindReplacePanelProps {
+    pub novel_id: i64,
+    pub chapters: Signal<Vec<Chapter>>,
+    pub on_close: EventHandler<()>,
+    /// 替换或撤销后通知上层重新加载章节
+    pub on_changed: EventHandler<()>,
+}
+
+#[component]
+pub fn FindReplacePanel(props: FindReplacePanelProps) -> Element {
+    let novel_id = props.novel_id;
+    let chapters = props.chapters;
+    let on_close = props.on_close;
+    let on_changed = props.on_changed;
+
+    let mut pattern = use_signal(String::new);
+    let mut replacement = use_signal(String::new);
+    let mut mode = use_signal(MatchMode::default);
+    let mut case_sensitive = use_signal(|| false);
+    let mut scope_chapter_id = use_signal(|| None::<i64>);
+
+    let mut matches = use_signal(Vec::<FindMatch>::new);
+    let mut selected = use_signal(Vec::<bool>::new);
+    let mut last_outcome = use_signal(|| None::<ReplaceOutcome>);
+    let mut message = use_signal(|| None::<String>);
+
+    let current_options = move || FindOptions {
+        pattern: pattern(),
+        replacement: replacement(),
+        mode: mode(),
+        case_sensitive: case_sensitive(),
+        scope_chapter_id: scope_chapter_id(),
+    };
+
+    // 查找并生成预览
+    let run_find = move || {
+        let options = current_options();
+        spawn(async move {
+            match FindReplaceManager::new().find(novel_id, &options).await {
+                Ok(found) => {
+                    message.set(Some(format!("找到 {} 处", found.len())));
+                    selected.set(vec![true; found.len()]);
+                    matches.set(found);
+                },
+                Err(e) => {
+                    message.set(Some(e.to_string()));
+                    matches.set(Vec::new());
+                    selected.set(Vec::new());
+                }
+            }
+        });
+    };
+
+    // 替换选中的命中
+    let replace_selected = move |_| {
+        let options = current_options();
+        let chosen: Vec<FindMatch> = matches().into_iter()
+            .zip(selected())
+            .filter(|(_, checked)| *checked)
+            .map(|(m, _)| m)
+            .collect();
+        if chosen.is_empty() {
+            return;
+        }
+
+        spawn(async move {
+            match FindReplaceManager::new().replace(novel_id, &options, &chosen).await {
+                Ok(outcome) => {
+                    message.set(Some(format!(
+                        "已在 {} 个章节中替换 {} 处",
+                        outcome.chapters.len(),
+                        outcome.total_replacements()
+                    )));
+                    last_outcome.set(Some(outcome));
+                    matches.set(Vec::new());
+                    selected.set(Vec::new());
+                    on_changed.call(());
+                },
+                Err(e) => {
+                    error!("查找替换失败: {}", e);
+                    message.set(Some(e.to_string()));
+                }
+            }
+        });
+    };
+
+    // 撤销上一次替换
+    let revert_last = move |_| {
+        let Some(outcome) = last_outcome() else { return };
+        spawn(async move {
+            match FindReplaceManager::new().revert(&outcome).await {
+                Ok(()) => {
+                    message.set(Some("已撤销上一次替换".to_string()));
+                    last_outcome.set(None);
+                    on_changed.call(());
+                },
+                Err(e) => {
+                    error!("撤销替换失败: {}", e);
+                    message.set(Some(e.to_string()));
+                }
+            }
+        });
+    };
+
+    let volumes: Vec<Chapter> = chapters().into_iter()
+        .filter(|c| c.chapter_type == ChapterType::Volume)
+        .collect();
+    let selected_count = selected().iter().filter(|s| **s).count();
+
+    rsx! {
+        div {
+            class: "chapter-form-overlay",
+            onclick: move |_| on_close.call(()),
+
+            div {
+                class: "chapter-form find-replace-panel",
+                onclick: move |e: Event<MouseData>| e.stop_propagation(),
+
+                h3 { "🔎 查找替换" }
+
+                div {
+                    class: "form-group",
+                    label { "查找: " }
+                    input {
+                        placeholder: "要查找的内容",
+                        value: pattern(),
+                        oninput: move |e| pattern.set(e.value()),
+                        onkeydown: move |e: KeyboardEvent| {
+                            if e.key() == Key::Enter {
+                                run_find();
+                            }
+                        },
+                    }
+                }
+                div {
+                    class: "form-group",
+                    label { "替换为: " }
+                    input {
+                        placeholder: "替换后的内容",
+                        value: replacement(),
+                        oninput: move |e| replacement.set(e.value()),
+                    }
+                }
+                div {
+                    class: "find-replace-options",
+                    select {
+                        value: format!("{:?}", mode()),
+                        onchange: move |e| {
+                            mode.set(match e.value().as_str() {
+                                "Regex" => MatchMode::Regex,
+                                "WholeWord" => MatchMode::WholeWord,
+                                _ => MatchMode::Plain,
+                            });
+                        },
+                        option { value: "Plain", "普通文本" }
+                        option { value: "Regex", "正则表达式" }
+                        option { value: "WholeWord", "全词匹配" }
+                    }
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: case_sensitive(),
+                            onchange: move |e| case_sensitive.set(e.checked()),
+                        }
+                        "区分大小写"
+                    }
+                    select {
+                        onchange: move |e| scope_chapter_id.set(e.value().parse::<i64>().ok()),
+                        option { value: "", selected: scope_chapter_id().is_none(), "整部小说" }
+                        for volume in volumes {
+                            option {
+                                value: "{volume.id}",
+                                selected: scope_chapter_id() == Some(volume.id),
+                                "{volume.title}"
+                            }
+                        }
+                    }
+                }
+
+                if let Some(text) = message() {
+                    p { class: "find-replace-message", "{text}" }
+                }
+
+                if !matches().is_empty() {
+                    ul {
+                        class: "find-replace-matches",
+                        for (index, m) in matches().into_iter().enumerate() {
+                            li {
+                                key: "{m.chapter_id}-{m.start}",
+                                label {
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: selected().get(index).copied().unwrap_or(false),
+                                        onchange: move |e| {
+                                            if let Some(flag) = selected.write().get_mut(index) {
+                                                *flag = e.checked();
+                                            }
+                                        },
+                                    }
+                                    span { class: "find-replace-chapter", "{m.chapter_title}" }
+                                    span { "…{m.context_before}" }
+                                    del { "{m.matched}" }
+                                    ins { "{m.replacement}" }
+                                    span { "{m.context_after}…" }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div {
+                    class: "form-actions",
+                    if last_outcome().is_some() {
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: revert_last,
+                            "↶ 撤销替换"
+                        }
+                    }
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| on_close.call(()),
+                        "关闭"
+                    }
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| run_find(),
+                        "查找"
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: selected_count == 0,
+                        onclick: replace_selected,
+                        "替换所选 ({selected_count})"
+                    }
+                }
+            }
+        }
+    }
+}
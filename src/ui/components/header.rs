@@ -3,11 +3,20 @@ use dioxus::prelude::*;
 #[derive(Props, Clone, PartialEq)]
 pub struct HeaderProps {
     pub search_query: Signal<String>,
+    /// 回车或点击搜索按钮时触发
+    pub on_search: EventHandler<String>,
 }
 
 #[component]
 pub fn Header(props: HeaderProps) -> Element {
     let mut search_query = props.search_query;
+    let on_search = props.on_search;
+    let submit = move || {
+        let query = search_query().trim().to_string();
+        if !query.is_empty() {
+            on_search.call(query);
+        }
+    };
     
     rsx! {
         header {
@@ -23,11 +32,19 @@ pub fn Header(props: HeaderProps) -> Element {
                     placeholder: "搜索小说、章节或灵感...",
                     value: "{search_query}",
                     oninput: move |e| search_query.set(e.value()),
+                    onkeydown: move |e: KeyboardEvent| {
+                        if e.key() == Key::Enter {
+                            submit();
+                        }
+                    },
                 }
             }
             div {
                 class: "header-right",
-                button { "🔍 搜索" }
+                button {
+                    onclick: move |_| submit(),
+                    "🔍 搜索"
+                }
                 button { "⚙️ 设置" }
                 button { "❓ 帮助" }
             }
@@ -0,0 +1,559 @@
+/// 导入小说：项目文件夹或单个 TXT 文件
+use dioxus::prelude::*;
+use crate::core::markdown_exchange::{self, ConflictPolicy, ImportConflict, MarkdownExchange, Project};
+use crate::core::project_bundle::{BundleManager, BundleManifest};
+use crate::core::txt_importer::{ProposedChapter, SplitPreview, SplitRules, TxtImporter};
+use crate::db::{ChapterType, Novel};
+use log::error;
+use std::path::PathBuf;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ImportPanelProps {
+    pub novels: Signal<Vec<Novel>>,
+    /// 导入成功后调用，参数为导入到的小说ID
+    pub on_imported: EventHandler<i64>,
+    pub on_close: EventHandler<()>,
+}
+
+/// 导入来源
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ImportSource {
+    ProjectFolder,
+    TextFile,
+    Bundle,
+}
+
+#[component]
+pub fn ImportPanel(props: ImportPanelProps) -> Element {
+    let novels = props.novels;
+    let on_imported = props.on_imported;
+    let on_close = props.on_close;
+    let mut source = use_signal(|| ImportSource::ProjectFolder);
+
+    rsx! {
+        div {
+            class: "chapter-form-overlay",
+            onclick: move |_| on_close.call(()),
+
+            div {
+                class: "chapter-form import-panel",
+                onclick: move |e: Event<MouseData>| e.stop_propagation(),
+
+                h3 { "📥 导入" }
+
+                div {
+                    class: "form-group",
+                    label { "来源: " }
+                    select {
+                        value: match source() {
+                            ImportSource::ProjectFolder => "folder",
+                            ImportSource::TextFile => "txt",
+                            ImportSource::Bundle => "bundle",
+                        },
+                        onchange: move |e| source.set(match e.value().as_str() {
+                            "txt" => ImportSource::TextFile,
+                            "bundle" => ImportSource::Bundle,
+                            _ => ImportSource::ProjectFolder,
+                        }),
+                        option { value: "folder", "项目文件夹（Markdown / 纯文本）" }
+                        option { value: "txt", "单个 TXT 文件（自动拆分章节）" }
+                        option { value: "bundle", "项目包（.nwbundle）" }
+                    }
+                }
+
+                match source() {
+                    ImportSource::TextFile => rsx! { TextFileImport { novels, on_imported, on_close } },
+                    ImportSource::Bundle => rsx! { BundleImport { novels, on_imported, on_close } },
+                    ImportSource::ProjectFolder => rsx! { ProjectFolderImport { novels, on_imported, on_close } },
+                }
+            }
+        }
+    }
+}
+
+/// 新建小说或已有小说的选择框，空值表示新建
+#[component]
+fn TargetSelect(novels: Signal<Vec<Novel>>, target: Signal<Option<i64>>, on_change: EventHandler<()>) -> Element {
+    let mut target = target;
+    rsx! {
+        div {
+            class: "form-group",
+            label { "导入到: " }
+            select {
+                value: target().map(|id| id.to_string()).unwrap_or_default(),
+                onchange: move |e| {
+                    target.set(e.value().parse().ok());
+                    on_change.call(());
+                },
+                option { value: "", "新建小说" }
+                for novel in novels() {
+                    option { key: "{novel.id}", value: "{novel.id}", "{novel.title}" }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ProjectFolderImport(props: ImportPanelProps) -> Element {
+    let novels = props.novels;
+    let on_imported = props.on_imported;
+    let on_close = props.on_close;
+
+    let mut source_path = use_signal(String::new);
+    let mut project = use_signal(|| None::<Project>);
+    // None 表示新建小说
+    let target = use_signal(|| None::<i64>);
+    let mut policy = use_signal(ConflictPolicy::default);
+    let mut conflicts = use_signal(Vec::<ImportConflict>::new);
+    let mut importing = use_signal(|| false);
+    let mut message = use_signal(|| None::<String>);
+
+    let mut check_conflicts = move || {
+        conflicts.write().clear();
+        let (Some(loaded), Some(novel_id)) = (project(), target()) else { return };
+        spawn(async move {
+            match MarkdownExchange::new().check_conflicts(loaded, novel_id).await {
+                Ok(found) => conflicts.set(found),
+                Err(e) => error!("检查导入冲突失败: {}", e),
+            }
+        });
+    };
+
+    let load_project = move |_| {
+        message.set(None);
+        match markdown_exchange::read_project(&PathBuf::from(source_path().trim())) {
+            Ok(loaded) => {
+                project.set(Some(loaded));
+                check_conflicts();
+            },
+            Err(e) => {
+                project.set(None);
+                message.set(Some(format!("读取失败: {}", e)));
+            }
+        }
+    };
+
+    let run_import = move |_| {
+        let Some(loaded) = project() else { return };
+        let (target, policy) = (target(), policy());
+        importing.set(true);
+        spawn(async move {
+            match MarkdownExchange::new().import(loaded, target, policy).await {
+                Ok(report) if report.is_aborted() => {
+                    message.set(Some("存在冲突，请选择处理方式后重试".to_string()));
+                    conflicts.set(report.conflicts);
+                },
+                Ok(report) => {
+                    message.set(Some(format!(
+                        "导入完成：新建 {} 章，合并 {} 章",
+                        report.created_chapters, report.merged_chapters
+                    )));
+                    if let Some(novel_id) = report.novel_id {
+                        on_imported.call(novel_id);
+                    }
+                },
+                Err(e) => {
+                    error!("导入失败: {}", e);
+                    message.set(Some(format!("导入失败: {}", e)));
+                }
+            }
+            importing.set(false);
+        });
+    };
+
+    rsx! {
+        div {
+            class: "form-group",
+            label { "文件夹: " }
+            input {
+                placeholder: "导出的 Markdown 或纯文本项目文件夹",
+                value: source_path(),
+                oninput: move |e| source_path.set(e.value()),
+            }
+            button {
+                class: "btn btn-secondary",
+                disabled: source_path().trim().is_empty(),
+                onclick: load_project,
+                "读取"
+            }
+        }
+
+        if let Some(loaded) = project() {
+            p {
+                class: "import-summary",
+                "《{loaded.novel.title}》 · {loaded.chapter_count()} 个章节"
+            }
+
+            TargetSelect { novels, target, on_change: move |_| check_conflicts() }
+
+            if target().is_some() {
+                div {
+                    class: "form-group",
+                    label { "同名章节: " }
+                    select {
+                        value: match policy() {
+                            ConflictPolicy::Abort => "abort",
+                            ConflictPolicy::Merge => "merge",
+                            ConflictPolicy::KeepBoth => "keep-both",
+                        },
+                        onchange: move |e| policy.set(match e.value().as_str() {
+                            "merge" => ConflictPolicy::Merge,
+                            "keep-both" => ConflictPolicy::KeepBoth,
+                            _ => ConflictPolicy::Abort,
+                        }),
+                        option { value: "abort", "有冲突时不导入" }
+                        option { value: "merge", "合并（保留已有章节，只导入新增内容）" }
+                        option { value: "keep-both", "全部作为新章节导入" }
+                    }
+                }
+            }
+        }
+
+        if !conflicts().is_empty() {
+            div {
+                class: "import-conflicts",
+                p { "发现 {conflicts().len()} 处冲突：" }
+                ul {
+                    for conflict in conflicts() {
+                        li { key: "{conflict.source}", "{conflict.description()}" }
+                    }
+                }
+            }
+        }
+
+        if let Some(text) = message() {
+            p { class: "export-message", "{text}" }
+        }
+
+        div {
+            class: "form-actions",
+            button {
+                class: "btn btn-secondary",
+                onclick: move |_| on_close.call(()),
+                "关闭"
+            }
+            button {
+                class: "btn btn-primary",
+                disabled: importing() || project().is_none(),
+                onclick: run_import,
+                if importing() { "导入中..." } else { "导入" }
+            }
+        }
+    }
+}
+
+#[component]
+fn BundleImport(props: ImportPanelProps) -> Element {
+    let on_imported = props.on_imported;
+    let on_close = props.on_close;
+
+    let mut source_path = use_signal(String::new);
+    let mut manifest = use_signal(|| None::<BundleManifest>);
+    let mut importing = use_signal(|| false);
+    let mut message = use_signal(|| None::<String>);
+
+    let inspect = move |_| {
+        message.set(None);
+        match BundleManager::new().inspect(&PathBuf::from(source_path().trim())) {
+            Ok(found) => manifest.set(Some(found)),
+            Err(e) => {
+                manifest.set(None);
+                message.set(Some(format!("读取失败: {}", e)));
+            }
+        }
+    };
+
+    let run_import = move |_| {
+        let path = PathBuf::from(source_path().trim());
+        importing.set(true);
+        spawn(async move {
+            match BundleManager::new().import(&path).await {
+                Ok(report) => {
+                    message.set(Some(format!(
+                        "导入完成：{} 个章节、{} 个版本、{} 条灵感",
+                        report.chapters, report.versions, report.inspirations
+                    )));
+                    on_imported.call(report.novel_id);
+                },
+                Err(e) => {
+                    error!("导入项目包失败: {}", e);
+                    message.set(Some(format!("导入失败: {}", e)));
+                }
+            }
+            importing.set(false);
+        });
+    };
+
+    rsx! {
+        div {
+            class: "form-group",
+            label { "项目包: " }
+            input {
+                placeholder: ".nwbundle 文件路径",
+                value: source_path(),
+                oninput: move |e| {
+                    source_path.set(e.value());
+                    manifest.set(None);
+                },
+            }
+            button {
+                class: "btn btn-secondary",
+                disabled: source_path().trim().is_empty(),
+                onclick: inspect,
+                "校验"
+            }
+        }
+
+        if let Some(found) = manifest() {
+            p {
+                class: "import-summary",
+                {format!(
+                    "《{}》 · {} 个章节 · {} 个版本 · {} 条灵感 · 导出于 {}（校验通过，将导入为新小说）",
+                    found.novel_title,
+                    found.chapter_count,
+                    found.version_count,
+                    found.inspiration_count,
+                    found.created_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                )}
+            }
+        }
+
+        if let Some(text) = message() {
+            p { class: "export-message", "{text}" }
+        }
+
+        div {
+            class: "form-actions",
+            button {
+                class: "btn btn-secondary",
+                onclick: move |_| on_close.call(()),
+                "关闭"
+            }
+            button {
+                class: "btn btn-primary",
+                disabled: importing() || manifest().is_none(),
+                onclick: run_import,
+                if importing() { "导入中..." } else { "导入" }
+            }
+        }
+    }
+}
+
+/// 每行一个正则表达式
+fn pattern_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[component]
+fn TextFileImport(props: ImportPanelProps) -> Element {
+    let novels = props.novels;
+    let on_imported = props.on_imported;
+    let on_close = props.on_close;
+
+    let defaults = SplitRules::default();
+    let mut source_path = use_signal(String::new);
+    let mut novel_title = use_signal(String::new);
+    let mut volume_patterns = use_signal(|| defaults.volume_patterns.join("\n"));
+    let mut chapter_patterns = use_signal(|| defaults.chapter_patterns.join("\n"));
+    let mut special_patterns = use_signal(|| defaults.special_patterns.join("\n"));
+    let mut max_heading_chars = use_signal(|| defaults.max_heading_chars);
+    let mut show_rules = use_signal(|| false);
+    let mut preview = use_signal(|| None::<SplitPreview>);
+    let target = use_signal(|| None::<i64>);
+    let mut importing = use_signal(|| false);
+    let mut message = use_signal(|| None::<String>);
+
+    let run_preview = move |_| {
+        message.set(None);
+        let rules = SplitRules {
+            volume_patterns: pattern_lines(&volume_patterns()),
+            chapter_patterns: pattern_lines(&chapter_patterns()),
+            special_patterns: pattern_lines(&special_patterns()),
+            max_heading_chars: max_heading_chars(),
+        };
+        let path = PathBuf::from(source_path().trim());
+        match TxtImporter::new().preview(&path, &rules) {
+            Ok(result) => {
+                if novel_title().trim().is_empty() {
+                    if let Some(stem) = path.file_stem() {
+                        novel_title.set(stem.to_string_lossy().to_string());
+                    }
+                }
+                preview.set(Some(result));
+            },
+            Err(e) => {
+                preview.set(None);
+                message.set(Some(format!("预览失败: {}", e)));
+            }
+        }
+    };
+
+    let run_import = move |_| {
+        let Some(result) = preview() else { return };
+        let title = novel_title().trim().to_string();
+        let target = target();
+        importing.set(true);
+        spawn(async move {
+            match TxtImporter::new().import(result, target, title).await {
+                Ok(report) => {
+                    message.set(Some(format!("导入完成：新建 {} 个卷和章节", report.created_chapters)));
+                    if let Some(novel_id) = report.novel_id {
+                        on_imported.call(novel_id);
+                    }
+                },
+                Err(e) => {
+                    error!("导入 TXT 失败: {}", e);
+                    message.set(Some(format!("导入失败: {}", e)));
+                }
+            }
+            importing.set(false);
+        });
+    };
+
+    rsx! {
+        div {
+            class: "form-group",
+            label { "TXT 文件: " }
+            input {
+                placeholder: "支持 UTF-8、GBK、UTF-16 编码",
+                value: source_path(),
+                oninput: move |e| source_path.set(e.value()),
+            }
+            button {
+                class: "btn btn-secondary",
+                disabled: source_path().trim().is_empty(),
+                onclick: run_preview,
+                "预览拆分"
+            }
+        }
+
+        div {
+            class: "form-group",
+            button {
+                class: "btn btn-secondary",
+                onclick: move |_| show_rules.toggle(),
+                if show_rules() { "收起拆分规则" } else { "拆分规则..." }
+            }
+        }
+
+        if show_rules() {
+            div {
+                class: "import-rules",
+                label { "卷标题（每行一个正则，可用 (?P<num>...) 标出编号）" }
+                textarea {
+                    rows: "2",
+                    value: volume_patterns(),
+                    oninput: move |e| volume_patterns.set(e.value()),
+                }
+                label { "章节标题" }
+                textarea {
+                    rows: "2",
+                    value: chapter_patterns(),
+                    oninput: move |e| chapter_patterns.set(e.value()),
+                }
+                label { "序章、尾声等特殊章节" }
+                textarea {
+                    rows: "2",
+                    value: special_patterns(),
+                    oninput: move |e| special_patterns.set(e.value()),
+                }
+                label { "标题最多字数: " }
+                input {
+                    r#type: "number",
+                    min: "1",
+                    value: "{max_heading_chars}",
+                    oninput: move |e| {
+                        if let Ok(value) = e.value().parse::<usize>() {
+                            max_heading_chars.set(value.max(1));
+                        }
+                    },
+                }
+            }
+        }
+
+        if let Some(result) = preview() {
+            p {
+                class: "import-summary",
+                "编码 {result.encoding.name()} · {result.chapter_count()} 个卷和章节 · 共 {result.word_count()} 字"
+            }
+
+            if !result.warnings.is_empty() {
+                div {
+                    class: "import-conflicts",
+                    ul {
+                        for warning in result.warnings.iter() {
+                            li { key: "{warning}", "{warning}" }
+                        }
+                    }
+                }
+            }
+
+            ul {
+                class: "import-preview",
+                for chapter in result.chapters.iter() {
+                    ProposedItem { key: "{chapter.line}", chapter: chapter.clone() }
+                }
+            }
+
+            TargetSelect { novels, target, on_change: move |_| {} }
+
+            if target().is_none() {
+                div {
+                    class: "form-group",
+                    label { "小说标题: " }
+                    input {
+                        value: novel_title(),
+                        oninput: move |e| novel_title.set(e.value()),
+                    }
+                }
+            }
+        }
+
+        if let Some(text) = message() {
+            p { class: "export-message", "{text}" }
+        }
+
+        div {
+            class: "form-actions",
+            button {
+                class: "btn btn-secondary",
+                onclick: move |_| on_close.call(()),
+                "关闭"
+            }
+            button {
+                class: "btn btn-primary",
+                disabled: importing() || preview().is_none() || (target().is_none() && novel_title().trim().is_empty()),
+                onclick: run_import,
+                if importing() { "导入中..." } else { "导入" }
+            }
+        }
+    }
+}
+
+/// 预览树中的一项
+#[component]
+fn ProposedItem(chapter: ProposedChapter) -> Element {
+    let icon = match chapter.chapter_type {
+        ChapterType::Volume => "📚",
+        ChapterType::Chapter => "📄",
+        ChapterType::Scene => "🎬",
+    };
+    rsx! {
+        li {
+            span { "{icon} {chapter.title}" }
+            span { class: "import-preview-count", "{chapter.word_count()} 字" }
+            if !chapter.children.is_empty() {
+                ul {
+                    for child in chapter.children.iter() {
+                        ProposedItem { key: "{child.line}", chapter: child.clone() }
+                    }
+                }
+            }
+        }
+    }
+}
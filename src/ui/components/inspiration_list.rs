@@ -38,7 +38,7 @@ impl InspirationList {
     // 删除灵感
     pub fn delete_inspiration(&self, inspiration_id: i64) -> Result<(), Box<dyn std::error::Error>> {
         let db = db::get_database()?;
-        db.delete_inspiration(inspiration_id).map_err(|e| e.into())
+        db.trash_inspiration(inspiration_id).map(|_| ()).map_err(|e| e.into())
     }
     
     // 切换置顶状态
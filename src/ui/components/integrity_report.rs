@@ -0,0 +1,105 @@
+/// 数据库完整性检查报告
+use dioxus::prelude::*;
+use crate::core::integrity_checker::{IntegrityCheck, IntegrityChecker};
+use log::error;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct IntegrityReportPanelProps {
+    /// 最近一次检查结果，关闭面板时置为 `None`
+    pub result: Signal<Option<IntegrityCheck>>,
+    /// 修复完成后通知上层重新加载数据
+    pub on_repaired: EventHandler<()>,
+}
+
+#[component]
+pub fn IntegrityReportPanel(props: IntegrityReportPanelProps) -> Element {
+    let mut result = props.result;
+    let on_repaired = props.on_repaired;
+    let mut repairing = use_signal(|| false);
+    let mut message = use_signal(|| None::<String>);
+
+    let repair = move |_| {
+        repairing.set(true);
+        spawn(async move {
+            match IntegrityChecker::new().check(true).await {
+                Ok(check) => {
+                    result.set(Some(check));
+                    on_repaired.call(());
+                },
+                Err(e) => {
+                    error!("修复数据库失败: {}", e);
+                    message.set(Some(format!("修复失败: {}", e)));
+                }
+            }
+            repairing.set(false);
+        });
+    };
+
+    let Some(check) = result() else {
+        return rsx! {};
+    };
+    let report = check.report;
+
+    rsx! {
+        div {
+            class: "chapter-form-overlay",
+
+            div {
+                class: "chapter-form integrity-report",
+
+                h3 { "🩺 数据库检查" }
+
+                if report.is_clean() {
+                    p { "未发现问题。" }
+                } else if report.repaired {
+                    p { "发现并修复了 {report.issues.len()} 个问题。" }
+                } else if report.is_corrupted() {
+                    p {
+                        class: "warning-text",
+                        "数据库文件已损坏，无法自动修复。请在系统设置的“备份”中从最近的备份恢复。"
+                    }
+                } else {
+                    p { "发现 {report.issues.len()} 个问题，均可自动修复。" }
+                }
+
+                if let Some(backup) = check.backup {
+                    p { class: "setting-hint", "修复前的书库已备份到 {backup.display()}" }
+                }
+
+                if let Some(text) = message() {
+                    p { class: "warning-text", "{text}" }
+                }
+
+                if !report.is_clean() {
+                    ul {
+                        class: "integrity-issues",
+                        for (index, issue) in report.issues.iter().enumerate() {
+                            li {
+                                key: "{index}",
+                                class: if issue.is_repairable() { "integrity-issue" } else { "integrity-issue fatal" },
+                                "{issue.description()}"
+                            }
+                        }
+                    }
+                }
+
+                div {
+                    class: "form-actions",
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| result.set(None),
+                        "关闭"
+                    }
+                    if !report.repaired && report.repairable_count() > 0 && !report.is_corrupted() {
+                        button {
+                            class: "btn btn-primary",
+                            disabled: repairing(),
+                            onclick: repair,
+                            if repairing() { "修复中..." } else { "修复" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+/// 合并视图 - 预览三方合并结果，逐处处理冲突后提交为新版本
+use dioxus::prelude::*;
+use crate::core::version_manager::{VersionManager, VersionMerge};
+use crate::utils::merge::{ConflictResolution, MergeChunk};
+use log::{error, info};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct MergeViewProps {
+    pub chapter_id: i64,
+    /// 要合并到当前分支的版本
+    pub theirs_version_id: i64,
+    pub on_close: EventHandler<()>,
+    /// 合并提交、章节正文改变后通知上层重新加载
+    pub on_merged: EventHandler<()>,
+}
+
+fn resolution_class(current: Option<ConflictResolution>, option: ConflictResolution) -> &'static str {
+    if current == Some(option) { "btn btn-primary" } else { "btn btn-secondary" }
+}
+
+#[component]
+pub fn MergeView(props: MergeViewProps) -> Element {
+    let chapter_id = props.chapter_id;
+    let theirs_version_id = props.theirs_version_id;
+    let on_close = props.on_close;
+    let on_merged = props.on_merged;
+    let mut merge = use_signal(|| None::<VersionMerge>);
+    // 按冲突出现的顺序记录每处的处理方式
+    let mut resolutions = use_signal(Vec::<Option<ConflictResolution>>::new);
+    let mut committing = use_signal(|| false);
+    let mut message = use_signal(|| None::<String>);
+
+    use_hook(move || {
+        spawn(async move {
+            match VersionManager::new().preview_merge(chapter_id, theirs_version_id).await {
+                Ok(preview) => {
+                    resolutions.set(vec![None; preview.result.conflict_count()]);
+                    merge.set(Some(preview));
+                },
+                Err(e) => {
+                    error!("预览合并失败: {}", e);
+                    message.set(Some(format!("无法合并: {}", e)));
+                }
+            }
+        });
+    });
+
+    let commit = move |_| {
+        let Some(preview) = merge() else { return };
+        let chosen: Option<Vec<ConflictResolution>> = resolutions().into_iter().collect();
+        let content = match chosen.map(|chosen| preview.result.resolve(&chosen)) {
+            Some(Ok(content)) => content,
+            Some(Err(e)) => {
+                message.set(Some(format!("合并失败: {}", e)));
+                return;
+            }
+            None => {
+                message.set(Some("还有冲突未处理".to_string()));
+                return;
+            }
+        };
+        committing.set(true);
+        spawn(async move {
+            match VersionManager::new().commit_merge(chapter_id, theirs_version_id, &content).await {
+                Ok(version) => {
+                    info!("Merged version {} into chapter {} as {}", theirs_version_id, chapter_id, version.id);
+                    on_merged.call(());
+                    on_close.call(());
+                },
+                Err(e) => {
+                    error!("提交合并失败: {}", e);
+                    message.set(Some(format!("提交合并失败: {}", e)));
+                }
+            }
+            committing.set(false);
+        });
+    };
+
+    let unresolved = resolutions().iter().filter(|r| r.is_none()).count();
+
+    rsx! {
+        div {
+            class: "merge-view",
+
+            if let Some(preview) = merge() {
+                h4 { "🔀 把{preview.theirs_label}合并到分支「{preview.branch.name}」" }
+                p {
+                    class: "setting-hint",
+                    {match preview.base_version_id {
+                        Some(base) => format!("共同祖先为版本 #{}。", base),
+                        None => "两个版本没有共同祖先，以双方共有的段落为准。".to_string(),
+                    }}
+                    "自动合并 {preview.result.merged_count()} 处修改，{preview.result.conflict_count()} 处冲突"
+                    if unresolved > 0 { "（还有 {unresolved} 处未处理）" }
+                }
+
+                div {
+                    class: "merge-chunks",
+                    {
+                        let chunks = &preview.result.chunks;
+                        chunks.iter().enumerate().map(|(index, chunk)| {
+                            match chunk {
+                                MergeChunk::Unchanged(lines) => rsx! {
+                                    div {
+                                        key: "{index}",
+                                        class: "merge-chunk unchanged",
+                                        for line in lines.iter() {
+                                            p { "{line}" }
+                                        }
+                                    }
+                                },
+                                MergeChunk::Merged(lines) => rsx! {
+                                    div {
+                                        key: "{index}",
+                                        class: "merge-chunk merged",
+                                        title: "已自动合并",
+                                        for line in lines.iter() {
+                                            p { "{line}" }
+                                        }
+                                    }
+                                },
+                                MergeChunk::Conflict(conflict) => {
+                                    // 这是第几处冲突
+                                    let n = chunks[..index].iter().filter(|c| matches!(c, MergeChunk::Conflict(_))).count();
+                                    let current = resolutions().get(n).copied().flatten();
+                                    let mut choose = move |option: ConflictResolution| {
+                                        if let Some(slot) = resolutions.write().get_mut(n) {
+                                            *slot = Some(option);
+                                        }
+                                    };
+                                    rsx! {
+                                        div {
+                                            key: "{index}",
+                                            class: if current.is_some() { "merge-chunk conflict resolved" } else { "merge-chunk conflict" },
+                                            div {
+                                                class: "merge-sides",
+                                                div {
+                                                    class: "merge-side ours",
+                                                    span { class: "merge-side-label", "我方（当前正文）" }
+                                                    for line in conflict.ours.iter() {
+                                                        p { "{line}" }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "merge-side theirs",
+                                                    span { class: "merge-side-label", "对方（{preview.theirs_label}）" }
+                                                    for line in conflict.theirs.iter() {
+                                                        p { "{line}" }
+                                                    }
+                                                }
+                                            }
+                                            div {
+                                                class: "merge-choices",
+                                                button {
+                                                    class: resolution_class(current, ConflictResolution::Ours),
+                                                    onclick: move |_| choose(ConflictResolution::Ours),
+                                                    "采用我方"
+                                                }
+                                                button {
+                                                    class: resolution_class(current, ConflictResolution::Theirs),
+                                                    onclick: move |_| choose(ConflictResolution::Theirs),
+                                                    "采用对方"
+                                                }
+                                                button {
+                                                    class: resolution_class(current, ConflictResolution::Both),
+                                                    onclick: move |_| choose(ConflictResolution::Both),
+                                                    "两者都要"
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        })
+                    }
+                }
+            } else if message().is_none() {
+                div { class: "empty-state", "正在计算合并..." }
+            }
+
+            if let Some(text) = message() {
+                p { class: "export-message", "{text}" }
+            }
+
+            div {
+                class: "form-actions",
+                button {
+                    class: "btn btn-secondary",
+                    onclick: move |_| on_close.call(()),
+                    "取消"
+                }
+                button {
+                    class: "btn btn-primary",
+                    disabled: committing() || merge().is_none() || unresolved > 0,
+                    onclick: commit,
+                    if committing() { "提交中..." } else { "提交合并" }
+                }
+            }
+        }
+    }
+}
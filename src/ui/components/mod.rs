@@ -15,6 +15,18 @@ pub mod stats_view;
 pub mod settings_view;
 pub mod status_bar;
 pub mod inspiration_stats;
+pub mod search_panel;
+pub mod find_replace_panel;
+pub mod recovery_prompt;
+pub mod export_panel;
+pub mod import_panel;
+pub mod integrity_report;
+pub mod trash_view;
+pub mod archived_chapters;
+pub mod version_timeline;
+pub mod snapshot_panel;
+pub mod merge_view;
+pub mod diff_view;
 
 pub use header::Header;
 pub use sidebar::Sidebar;
@@ -26,4 +38,6 @@ pub use chapter_form::ChapterForm;
 pub use chapter_management::ChapterManagement;
 pub use editor::Editor;
 pub use inspiration_stats::InspirationStatsView;
-pub use stats_view::StatsView;
\ No newline at end of file
+pub use stats_view::StatsView;
+pub use search_panel::SearchPanel;
+pub use recovery_prompt::RecoveryPrompt;
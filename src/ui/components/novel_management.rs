@@ -1,5 +1,6 @@
 use dioxus::prelude::*;
 use crate::db;
+use super::import_panel::ImportPanel;
 use std::time::Duration;
 
 #[derive(PartialEq, Clone, Copy)]
@@ -33,7 +34,9 @@ pub fn NovelManagement(
     on_cancel_form: EventHandler<()>,
     on_add_novel: EventHandler<()>,
     on_toggle_pin: EventHandler<(i64, String)>,
+    on_imported: EventHandler<i64>,
 ) -> Element {
+    let mut show_import = use_signal(|| false);
     let mut view_mode = use_signal(|| ViewMode::List);
     let mut search_query = use_signal(|| "".to_string());
     let mut filter_status = use_signal(|| FilterStatus::All);
@@ -69,7 +72,7 @@ pub fn NovelManagement(
     let handle_delete_novel = move |novel_id: i64| {
         // 显示确认对话框
         if let Some(window) = web_sys::window() {
-            if window.confirm_with_message("确定要删除这本小说吗？此操作不可撤销，所有相关章节也将被删除。").unwrap_or(false) {
+            if window.confirm_with_message("确定要删除这本小说吗？小说会连同章节和灵感移入回收站，可以在回收站中恢复。").unwrap_or(false) {
                 simulate_load();
                 on_delete_novel.call(novel_id);
             }
@@ -226,6 +229,12 @@ pub fn NovelManagement(
                             "➕ 新建小说"
                         }
                         
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: move |_| show_import.set(true),
+                            "📥 导入"
+                        }
+                        
                         // 视图切换
                         div {
                             class: "view-switcher",
@@ -245,6 +254,14 @@ pub fn NovelManagement(
                     }
                 }
                 
+                if show_import() {
+                    ImportPanel {
+                        novels,
+                        on_imported: move |novel_id| on_imported.call(novel_id),
+                        on_close: move |_| show_import.set(false),
+                    }
+                }
+                
                 // 错误提示
                 if let Some(err) = error_message() {
                     div {
@@ -0,0 +1,128 @@
+/// 启动时的崩溃恢复提示
+use dioxus::prelude::*;
+use crate::core::auto_save::AutoSaveManager;
+use crate::core::recovery_journal::RecoveryEntry;
+use crate::db::Novel;
+use log::error;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct RecoveryPromptProps {
+    /// 上次运行留下的未保存内容，处理完一条就移除一条
+    pub entries: Signal<Vec<RecoveryEntry>>,
+    pub novels: Signal<Vec<Novel>>,
+    /// 恢复成功后打开对应章节，参数为 (小说ID, 章节ID)
+    pub on_restored: EventHandler<(i64, i64)>,
+}
+
+#[component]
+pub fn RecoveryPrompt(props: RecoveryPromptProps) -> Element {
+    let mut entries = props.entries;
+    let novels = props.novels;
+    let on_restored = props.on_restored;
+    let mut dismissed = use_signal(|| false);
+    let mut message = use_signal(|| None::<String>);
+
+    let mut remove_entry = move |chapter_id: i64| {
+        entries.write().retain(|e| e.chapter_id != chapter_id);
+    };
+
+    let restore = move |entry: RecoveryEntry| {
+        spawn(async move {
+            let (novel_id, chapter_id) = (entry.novel_id, entry.chapter_id);
+            match AutoSaveManager::new().restore(entry).await {
+                Ok(()) => {
+                    remove_entry(chapter_id);
+                    on_restored.call((novel_id, chapter_id));
+                },
+                Err(e) => {
+                    error!("恢复未保存内容失败: {}", e);
+                    message.set(Some(format!("恢复失败: {}", e)));
+                }
+            }
+        });
+    };
+
+    let mut discard = move |entry: RecoveryEntry| {
+        match AutoSaveManager::new().discard(&entry) {
+            Ok(()) => remove_entry(entry.chapter_id),
+            Err(e) => {
+                error!("丢弃恢复日志失败: {}", e);
+                message.set(Some(format!("丢弃失败: {}", e)));
+            }
+        }
+    };
+
+    if dismissed() || entries().is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div {
+            class: "chapter-form-overlay",
+
+            div {
+                class: "chapter-form recovery-prompt",
+
+                h3 { "♻️ 发现未保存的内容" }
+                p { "上次运行时以下章节有尚未保存的修改，可能是程序异常退出。" }
+
+                if let Some(text) = message() {
+                    p { class: "recovery-message", "{text}" }
+                }
+
+                ul {
+                    class: "recovery-entries",
+                    for entry in entries() {
+                        li {
+                            key: "{entry.chapter_id}",
+                            div {
+                                class: "recovery-entry-info",
+                                strong { "{entry.chapter_title}" }
+                                span {
+                                    class: "recovery-entry-meta",
+                                    {
+                                        let novel_title = novels().iter()
+                                            .find(|n| n.id == entry.novel_id)
+                                            .map(|n| n.title.clone())
+                                            .unwrap_or_default();
+                                        let written_at = entry.written_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+                                        format!("{} · {} 字 · {}", novel_title, entry.word_count(), written_at)
+                                    }
+                                }
+                            }
+                            div {
+                                class: "recovery-entry-actions",
+                                button {
+                                    class: "btn btn-secondary",
+                                    onclick: {
+                                        let entry = entry.clone();
+                                        move |_| discard(entry.clone())
+                                    },
+                                    "丢弃"
+                                }
+                                button {
+                                    class: "btn btn-primary",
+                                    onclick: {
+                                        let entry = entry.clone();
+                                        move |_| restore(entry.clone())
+                                    },
+                                    "恢复"
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div {
+                    class: "form-actions",
+                    button {
+                        class: "btn btn-secondary",
+                        title: "下次启动时会再次提示",
+                        onclick: move |_| dismissed.set(true),
+                        "稍后处理"
+                    }
+                }
+            }
+        }
+    }
+}
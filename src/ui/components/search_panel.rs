@@ -0,0 +1,126 @@
+/// 全文检索结果面板
+use dioxus::prelude::*;
+use crate::core::search_manager::{SearchJump, SearchResults};
+use crate::db::{self, SnippetSegment};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct SearchPanelProps {
+    pub results: Signal<Option<SearchResults>>,
+    pub searching: Signal<bool>,
+    pub novels: Signal<Vec<db::Novel>>,
+    pub on_open_novel: EventHandler<i64>,
+    pub on_open_chapter: EventHandler<SearchJump>,
+    pub on_open_inspiration: EventHandler<i64>,
+}
+
+/// 渲染带高亮的摘要
+fn render_snippet(segments: &[SnippetSegment]) -> Element {
+    rsx! {
+        p {
+            class: "search-snippet",
+            for segment in segments.iter() {
+                if segment.highlighted {
+                    mark { "{segment.text}" }
+                } else {
+                    span { "{segment.text}" }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn SearchPanel(props: SearchPanelProps) -> Element {
+    let results = props.results;
+    let searching = props.searching;
+    let novels = props.novels;
+    let on_open_novel = props.on_open_novel;
+    let on_open_chapter = props.on_open_chapter;
+    let on_open_inspiration = props.on_open_inspiration;
+
+    let novel_title = move |novel_id: i64| {
+        novels().iter()
+            .find(|n| n.id == novel_id)
+            .map(|n| n.title.clone())
+            .unwrap_or_default()
+    };
+
+    rsx! {
+        div {
+            class: "search-panel",
+
+            if searching() {
+                p { class: "search-status", "正在搜索..." }
+            } else if let Some(results) = results() {
+                h2 { "🔍 “{results.query}” 的搜索结果（{results.total()}）" }
+
+                if results.is_empty() {
+                    p { class: "search-status", "没有找到匹配的内容" }
+                }
+
+                if !results.novels.is_empty() {
+                    section {
+                        class: "search-section",
+                        h3 { "📚 小说" }
+                        for novel in results.novels.iter().cloned() {
+                            div {
+                                key: "novel-{novel.id}",
+                                class: "search-hit",
+                                onclick: move |_| on_open_novel.call(novel.id),
+                                div { class: "search-hit-title", "{novel.title}" }
+                                if !novel.description.is_empty() {
+                                    p { class: "search-snippet", "{novel.description}" }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if !results.chapters.is_empty() {
+                    section {
+                        class: "search-section",
+                        h3 { "📑 章节" }
+                        for hit in results.chapters.iter().cloned() {
+                            div {
+                                key: "chapter-{hit.chapter_id}",
+                                class: "search-hit",
+                                onclick: {
+                                    let jump = SearchJump::from(&hit);
+                                    move |_| on_open_chapter.call(jump.clone())
+                                },
+                                div {
+                                    class: "search-hit-title",
+                                    "{hit.title}"
+                                    span { class: "search-hit-novel", "《{novel_title(hit.novel_id)}》" }
+                                }
+                                {render_snippet(&hit.snippet)}
+                            }
+                        }
+                    }
+                }
+
+                if !results.inspirations.is_empty() {
+                    section {
+                        class: "search-section",
+                        h3 { "💡 灵感" }
+                        for hit in results.inspirations.iter().cloned() {
+                            div {
+                                key: "inspiration-{hit.inspiration_id}",
+                                class: "search-hit",
+                                onclick: move |_| on_open_inspiration.call(hit.novel_id),
+                                div {
+                                    class: "search-hit-title",
+                                    "{hit.title}"
+                                    span { class: "search-hit-novel", "《{novel_title(hit.novel_id)}》" }
+                                }
+                                {render_snippet(&hit.snippet)}
+                            }
+                        }
+                    }
+                }
+            } else {
+                p { class: "search-status", "在顶部搜索框输入关键词后按回车开始搜索" }
+            }
+        }
+    }
+}
@@ -1,10 +1,146 @@
 use dioxus::prelude::*;
-use crate::config::AppConfig;
-use log::{debug, error, info};
+use crate::config::{AppConfig, BackupConfig, IntegrityConfig, TrashConfig};
+use crate::core::backup_manager::{BackupInfo, BackupManager};
+use crate::core::integrity_checker::{IntegrityCheck, IntegrityChecker};
+use super::integrity_report::IntegrityReportPanel;
+use log::{error, info};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct SettingsViewProps {
+    /// 切换书库后通知上层重新加载数据
+    pub on_library_changed: EventHandler<()>,
+}
 
 #[component]
-pub fn SettingsView() -> Element {
-    let mut auto_save_enabled = use_signal(|| true);
+pub fn SettingsView(props: SettingsViewProps) -> Element {
+    let on_library_changed = props.on_library_changed;
+    
+    // 书库相关状态
+    let mut config = use_signal(|| AppConfig::load().unwrap_or_default());
+    let mut auto_save_enabled = use_signal(|| config.peek().editor.auto_save);
+    let mut auto_save_interval = use_signal(|| config.peek().auto_save_interval);
+    let mut settings_message = use_signal(|| None::<String>);
+    let mut current_library = use_signal(|| {
+        crate::db::current_database_path()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+    let mut new_library_path = use_signal(String::new);
+    let mut library_error = use_signal(|| None::<String>);
+    
+    // 备份相关状态
+    let mut backup_config = use_signal(|| config.peek().backup.clone());
+    let mut backups = use_signal(Vec::<BackupInfo>::new);
+    let mut backup_busy = use_signal(|| false);
+    let mut backup_message = use_signal(|| None::<String>);
+    
+    // 数据库检查相关状态
+    let mut integrity_config = use_signal(|| config.peek().integrity.clone());
+    let mut integrity_check = use_signal(|| None::<IntegrityCheck>);
+    let mut checking = use_signal(|| false);
+    
+    // 回收站相关状态
+    let mut trash_config = use_signal(|| config.peek().trash.clone());
+    
+    let run_integrity_check = move |_| {
+        checking.set(true);
+        spawn(async move {
+            match IntegrityChecker::new().check(false).await {
+                Ok(check) => integrity_check.set(Some(check)),
+                Err(e) => {
+                    error!("数据库检查失败: {}", e);
+                    backup_message.set(Some(format!("数据库检查失败: {}", e)));
+                }
+            }
+            checking.set(false);
+        });
+    };
+    
+    let mut load_backups = move || {
+        match BackupManager::new().list() {
+            Ok(list) => backups.set(list),
+            Err(e) => error!("读取备份列表失败: {}", e),
+        }
+    };
+    use_hook(move || load_backups());
+    
+    let backup_now = move |_| {
+        backup_busy.set(true);
+        spawn(async move {
+            match BackupManager::new().backup_now().await {
+                Ok(backup) => backup_message.set(Some(format!("已备份到 {}", backup.path.display()))),
+                Err(e) => {
+                    error!("备份失败: {}", e);
+                    backup_message.set(Some(format!("备份失败: {}", e)));
+                }
+            }
+            backup_busy.set(false);
+            load_backups();
+        });
+    };
+    
+    let mut restore_backup = move |backup: BackupInfo| {
+        let confirmed = web_sys::window()
+            .and_then(|window| window.confirm_with_message("确定要用这份备份覆盖当前书库吗？当前内容会先另存一份安全备份。").ok())
+            .unwrap_or(false);
+        if !confirmed {
+            return;
+        }
+        backup_busy.set(true);
+        spawn(async move {
+            match BackupManager::new().restore(&backup.path).await {
+                Ok(safety) => {
+                    backup_message.set(Some(format!("已恢复，恢复前的书库保存在 {}", safety.display())));
+                    on_library_changed.call(());
+                },
+                Err(e) => {
+                    error!("恢复备份失败: {}", e);
+                    backup_message.set(Some(format!("恢复备份失败: {}", e)));
+                }
+            }
+            backup_busy.set(false);
+            load_backups();
+        });
+    };
+    
+    // 打开或创建书库
+    let mut open_library = move |path: String| {
+        let path = path.trim().to_string();
+        if path.is_empty() {
+            return;
+        }
+        
+        info!("切换书库: {}", path);
+        match crate::db::switch_database(&path) {
+            Ok(()) => {
+                let mut updated = config();
+                updated.database_path = path.clone();
+                updated.remember_library(&path);
+                if let Err(e) = updated.save() {
+                    error!("保存书库配置失败: {}", e);
+                }
+                config.set(updated);
+                current_library.set(path);
+                new_library_path.set(String::new());
+                library_error.set(None);
+                on_library_changed.call(());
+            },
+            Err(e) => {
+                error!("打开书库失败: {}", e);
+                library_error.set(Some(format!("打开书库失败: {}", e)));
+            }
+        }
+    };
+    
+    // 从最近列表中移除书库
+    let mut forget_library = move |path: String| {
+        let mut updated = config();
+        updated.forget_library(&path);
+        if let Err(e) = updated.save() {
+            error!("保存书库配置失败: {}", e);
+        }
+        config.set(updated);
+    };
     
     // 简化版本，使用静态数据避免类型推断问题
     rsx! {
@@ -15,6 +151,268 @@ pub fn SettingsView() -> Element {
             div {
                 class: "settings-form",
                 
+                // 书库设置
+                div {
+                    class: "settings-section",
+                    h3 { "书库" }
+                    
+                    div {
+                        class: "setting-item",
+                        label { "当前书库:" }
+                        span { class: "library-path", "{current_library}" }
+                    }
+                    
+                    div {
+                        class: "setting-item",
+                        input {
+                            r#type: "text",
+                            class: "form-input",
+                            placeholder: "书库文件路径，例如 data/笔名.db",
+                            value: "{new_library_path}",
+                            oninput: move |evt| new_library_path.set(evt.value()),
+                        }
+                        button {
+                            class: "btn btn-primary",
+                            onclick: move |_| open_library(new_library_path()),
+                            "打开/新建书库"
+                        }
+                    }
+                    
+                    if let Some(message) = library_error() {
+                        div { class: "warning-text", "{message}" }
+                    }
+                    
+                    if !config().recent_libraries.is_empty() {
+                        h4 { "最近使用" }
+                        ul {
+                            class: "recent-libraries",
+                            for path in config().recent_libraries {
+                                li {
+                                    key: "{path}",
+                                    class: if path == current_library() { "recent-library active" } else { "recent-library" },
+                                    span { class: "library-path", "{path}" }
+                                    if path != current_library() {
+                                        button {
+                                            class: "btn btn-secondary",
+                                            onclick: {
+                                                let path = path.clone();
+                                                move |_| open_library(path.clone())
+                                            },
+                                            "切换"
+                                        }
+                                        button {
+                                            class: "action-btn danger",
+                                            title: "从列表中移除",
+                                            onclick: {
+                                                let path = path.clone();
+                                                move |_| forget_library(path.clone())
+                                            },
+                                            "✕"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                
+                // 定时备份
+                div {
+                    class: "settings-section",
+                    h3 { "备份" }
+                    
+                    div {
+                        class: "setting-item",
+                        label {
+                            class: "checkbox-label",
+                            input {
+                                r#type: "checkbox",
+                                checked: backup_config().enabled,
+                                onchange: move |evt| backup_config.write().enabled = evt.checked(),
+                            }
+                            span { "定时备份（退出时也会备份）" }
+                        }
+                    }
+                    
+                    div {
+                        class: "setting-item",
+                        label { "备份间隔（分钟）" }
+                        input {
+                            r#type: "number",
+                            min: "1",
+                            disabled: !backup_config().enabled,
+                            value: "{backup_config().interval_minutes}",
+                            oninput: move |evt| {
+                                if let Ok(value) = evt.value().parse::<u64>() {
+                                    backup_config.write().interval_minutes = value.max(1);
+                                }
+                            },
+                        }
+                    }
+                    
+                    div {
+                        class: "setting-item",
+                        label { "备份目录" }
+                        input {
+                            r#type: "text",
+                            class: "form-input",
+                            placeholder: "留空则使用书库所在目录下的 backups/",
+                            value: "{backup_config().directory}",
+                            oninput: move |evt| backup_config.write().directory = evt.value(),
+                        }
+                    }
+                    
+                    div {
+                        class: "setting-item backup-retention",
+                        label { "保留: 每小时" }
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            value: "{backup_config().keep_hourly}",
+                            oninput: move |evt| {
+                                if let Ok(value) = evt.value().parse::<usize>() {
+                                    backup_config.write().keep_hourly = value;
+                                }
+                            },
+                        }
+                        label { "每天" }
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            value: "{backup_config().keep_daily}",
+                            oninput: move |evt| {
+                                if let Ok(value) = evt.value().parse::<usize>() {
+                                    backup_config.write().keep_daily = value;
+                                }
+                            },
+                        }
+                        label { "每周" }
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            value: "{backup_config().keep_weekly}",
+                            oninput: move |evt| {
+                                if let Ok(value) = evt.value().parse::<usize>() {
+                                    backup_config.write().keep_weekly = value;
+                                }
+                            },
+                        }
+                        p {
+                            class: "setting-hint",
+                            "每个时间段保留最新的一份，最新的备份总是保留。每份备份都会做完整性校验，校验失败的备份会被丢弃。"
+                        }
+                    }
+                    
+                    div {
+                        class: "setting-item",
+                        button {
+                            class: "btn btn-primary",
+                            disabled: backup_busy(),
+                            onclick: backup_now,
+                            if backup_busy() { "处理中..." } else { "立即备份" }
+                        }
+                    }
+                    
+                    if let Some(message) = backup_message() {
+                        div { class: "setting-hint", "{message}" }
+                    }
+                    
+                    if !backups().is_empty() {
+                        h4 { "已有备份" }
+                        ul {
+                            class: "backup-list",
+                            for backup in backups() {
+                                li {
+                                    key: "{backup.path.display()}",
+                                    class: "backup-item",
+                                    span {
+                                        class: "backup-time",
+                                        {backup.created_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string()}
+                                    }
+                                    span { class: "backup-size", "{backup.size / 1024} KB" }
+                                    button {
+                                        class: "btn btn-secondary",
+                                        disabled: backup_busy(),
+                                        onclick: {
+                                            let backup = backup.clone();
+                                            move |_| restore_backup(backup.clone())
+                                        },
+                                        "恢复"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                
+                // 数据库检查
+                div {
+                    class: "settings-section",
+                    h3 { "数据库检查" }
+                    
+                    div {
+                        class: "setting-item",
+                        label {
+                            class: "checkbox-label",
+                            input {
+                                r#type: "checkbox",
+                                checked: integrity_config().check_on_startup,
+                                onchange: move |evt| integrity_config.write().check_on_startup = evt.checked(),
+                            }
+                            span { "启动时检查数据库完整性" }
+                        }
+                        label {
+                            class: "checkbox-label",
+                            input {
+                                r#type: "checkbox",
+                                checked: integrity_config().auto_repair,
+                                disabled: !integrity_config().check_on_startup,
+                                onchange: move |evt| integrity_config.write().auto_repair = evt.checked(),
+                            }
+                            span { "发现问题时自动修复" }
+                        }
+                        p {
+                            class: "setting-hint",
+                            "检查文件完整性、外键、时间戳格式、跨小说或循环的章节层级以及小说字数统计。修复前会先备份书库。"
+                        }
+                    }
+                    
+                    div {
+                        class: "setting-item",
+                        button {
+                            class: "btn btn-primary",
+                            disabled: checking(),
+                            onclick: run_integrity_check,
+                            if checking() { "检查中..." } else { "立即检查" }
+                        }
+                    }
+                }
+                
+                // 回收站
+                div {
+                    class: "settings-section",
+                    h3 { "回收站" }
+                    
+                    div {
+                        class: "setting-item",
+                        label { "保留天数" }
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            value: "{trash_config().retention_days}",
+                            oninput: move |evt| {
+                                if let Ok(value) = evt.value().parse::<u32>() {
+                                    trash_config.write().retention_days = value;
+                                }
+                            },
+                        }
+                        p {
+                            class: "setting-hint",
+                            "超过保留天数的条目会在启动时彻底删除，设为 0 则一直保留。"
+                        }
+                    }
+                }
+                
                 // 简单的主题设置
                 div {
                     class: "settings-section",
@@ -45,16 +443,31 @@ pub fn SettingsView() -> Element {
                             input {
                                 r#type: "checkbox",
                                 checked: auto_save_enabled(),
-                                onchange: move |evt| {
-                                    let new_value = evt.value() == "on";
-                                    auto_save_enabled.set(new_value);
-                                    println!("自动保存已{}", if new_value { "启用" } else { "禁用" });
-                                    // 实际应用中这里应该更新配置
-                                }
+                                onchange: move |evt| auto_save_enabled.set(evt.checked()),
                             }
                             span { "自动保存" }
                         }
                     }
+                    
+                    div {
+                        class: "setting-item",
+                        label { "最长自动保存间隔（秒）" }
+                        input {
+                            r#type: "number",
+                            min: "5",
+                            disabled: !auto_save_enabled(),
+                            value: "{auto_save_interval}",
+                            oninput: move |evt| {
+                                if let Ok(value) = evt.value().parse::<u64>() {
+                                    auto_save_interval.set(value.max(5));
+                                }
+                            },
+                        }
+                        p {
+                            class: "setting-hint",
+                            "停止输入几秒后自动保存；持续输入时最迟每隔该时长保存一次。未保存的内容会写入恢复日志，异常退出后下次启动可以恢复。"
+                        }
+                    }
                 }
                 
                 // 操作按钮
@@ -63,21 +476,49 @@ pub fn SettingsView() -> Element {
                     button {
                         class: "btn btn-secondary",
                         onclick: move |_| {
-                            println!("重置为默认值");
-                            auto_save_enabled.set(true);
-                            // 实际应用中这里应该重置所有设置为默认值
+                            let defaults = AppConfig::default();
+                            auto_save_enabled.set(defaults.editor.auto_save);
+                            auto_save_interval.set(defaults.auto_save_interval);
+                            backup_config.set(BackupConfig::default());
+                            integrity_config.set(IntegrityConfig::default());
+                            trash_config.set(TrashConfig::default());
+                            settings_message.set(None);
                         },
                         "重置为默认值"
                     }
                     button {
                         class: "btn btn-primary",
                         onclick: move |_| {
-                            println!("保存设置: 自动保存 = {}", auto_save_enabled());
-                            // 实际应用中这里应该保存所有设置到配置文件
+                            let mut updated = config();
+                            updated.editor.auto_save = auto_save_enabled();
+                            updated.update_auto_save_interval(auto_save_interval());
+                            updated.backup = backup_config();
+                            updated.integrity = integrity_config();
+                            updated.trash = trash_config();
+                            match updated.save() {
+                                Ok(()) => {
+                                    load_backups();
+                                    info!("设置已保存: 自动保存 = {}, 间隔 = {}s", updated.editor.auto_save, updated.auto_save_interval);
+                                    config.set(updated);
+                                    settings_message.set(Some("设置已保存".to_string()));
+                                },
+                                Err(e) => {
+                                    error!("保存设置失败: {}", e);
+                                    settings_message.set(Some(format!("保存设置失败: {}", e)));
+                                }
+                            }
                         },
                         "保存设置"
                     }
                 }
+                if let Some(message) = settings_message() {
+                    div { class: "setting-hint", "{message}" }
+                }
+            }
+            
+            IntegrityReportPanel {
+                result: integrity_check,
+                on_repaired: move |_| on_library_changed.call(()),
             }
         }
     }
@@ -104,16 +104,26 @@ pub fn Sidebar(props: SidebarProps) -> Element {
                     //         span { "写作统计" }
                     //     }
                     // }
+                    // 回收站导航项
+                    li {
+                        class: "nav-item",
+                        button {
+                            class: if current_view() == "trash" { "nav-link active" } else { "nav-link" },
+                            onclick: move |_| current_view.set("trash".to_string()),
+                            span { "🗑️" }
+                            span { "回收站" }
+                        }
+                    }
                     // 系统设置导航项
-                    // li {
-                    //     class: "nav-item",
-                    //     button {
-                    //         class: if current_view() == "settings" { "nav-link active" } else { "nav-link" },
-                    //         onclick: move |_| current_view.set("settings".to_string()),
-                    //         span { "⚙️" }
-                    //         span { "系统设置" }
-                    //     }
-                    // }
+                    li {
+                        class: "nav-item",
+                        button {
+                            class: if current_view() == "settings" { "nav-link active" } else { "nav-link" },
+                            onclick: move |_| current_view.set("settings".to_string()),
+                            span { "⚙️" }
+                            span { "系统设置" }
+                        }
+                    }
                 }
             }
         }
@@ -0,0 +1,340 @@
+/// 全书快照面板 - 拍摄、比较、恢复和导出整本书的快照
+use dioxus::prelude::*;
+use crate::core::snapshot_manager::{SnapshotChange, SnapshotDiff, SnapshotManager};
+use crate::db::{Chapter, NovelSnapshot};
+use super::export_panel::ExportPanel;
+use log::{error, info};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct SnapshotPanelProps {
+    pub novel_id: i64,
+    pub novel_title: String,
+    pub chapters: Signal<Vec<Chapter>>,
+    pub on_close: EventHandler<()>,
+    /// 恢复快照改变了章节后通知上层重新加载
+    pub on_restored: EventHandler<()>,
+}
+
+fn change_label(change: SnapshotChange) -> (&'static str, &'static str) {
+    match change {
+        SnapshotChange::Added => ("新增", "snapshot-change added"),
+        SnapshotChange::Removed => ("删除", "snapshot-change removed"),
+        SnapshotChange::Modified => ("修改", "snapshot-change modified"),
+        SnapshotChange::Unchanged => ("调整", "snapshot-change"),
+    }
+}
+
+/// 差异文本中每一行的样式，`---`/`+++` 文件头在渲染时已跳过
+fn patch_line_class(line: &str) -> &'static str {
+    if line.starts_with("@@") {
+        "patch-hunk"
+    } else if line.starts_with('+') {
+        "patch-added"
+    } else if line.starts_with('-') {
+        "patch-removed"
+    } else {
+        "patch-context"
+    }
+}
+
+#[component]
+pub fn SnapshotPanel(props: SnapshotPanelProps) -> Element {
+    let novel_id = props.novel_id;
+    let on_close = props.on_close;
+    let on_restored = props.on_restored;
+    let mut snapshots = use_signal(Vec::<NovelSnapshot>::new);
+    let mut name_input = use_signal(String::new);
+    let mut note_input = use_signal(String::new);
+    // 勾选用于比较的快照，最多两个
+    let mut selected = use_signal(Vec::<i64>::new);
+    let mut diff = use_signal(|| None::<SnapshotDiff>);
+    let mut expanded = use_signal(|| None::<i64>);
+    let mut exporting = use_signal(|| None::<NovelSnapshot>);
+    let mut busy = use_signal(|| false);
+    let mut message = use_signal(|| None::<String>);
+
+    let load = move || {
+        spawn(async move {
+            match SnapshotManager::new().list(novel_id).await {
+                Ok(list) => snapshots.set(list),
+                Err(e) => error!("读取快照失败: {}", e),
+            }
+        });
+    };
+    use_hook(move || load());
+
+    let create_snapshot = move |_| {
+        let (name, note) = (name_input(), note_input());
+        busy.set(true);
+        spawn(async move {
+            match SnapshotManager::new().create(novel_id, &name, &note).await {
+                Ok(snapshot) => {
+                    message.set(Some(format!("已拍摄快照「{}」，共 {} 个章节", snapshot.name, snapshot.chapter_count)));
+                    name_input.set(String::new());
+                    note_input.set(String::new());
+                },
+                Err(e) => message.set(Some(format!("拍摄快照失败: {}", e))),
+            }
+            busy.set(false);
+            load();
+        });
+    };
+
+    let mut toggle_selected = move |snapshot_id: i64| {
+        let mut ids = selected.write();
+        if let Some(index) = ids.iter().position(|id| *id == snapshot_id) {
+            ids.remove(index);
+        } else {
+            if ids.len() == 2 {
+                ids.remove(0);
+            }
+            ids.push(snapshot_id);
+        }
+    };
+
+    let compare = move |_| {
+        let ids = selected();
+        let [a, b] = ids[..] else { return };
+        // 列表按时间倒序，ID 较小的即较早的快照
+        let (old_id, new_id) = (a.min(b), a.max(b));
+        spawn(async move {
+            match SnapshotManager::new().diff(old_id, new_id).await {
+                Ok(result) => {
+                    expanded.set(None);
+                    diff.set(Some(result));
+                },
+                Err(e) => message.set(Some(format!("比较快照失败: {}", e))),
+            }
+        });
+    };
+
+    let mut restore = move |snapshot: NovelSnapshot| {
+        let confirmed = web_sys::window()
+            .and_then(|window| window.confirm_with_message(&format!(
+                "确定要把全书恢复到快照「{}」吗？恢复前会自动拍摄一个快照，之后新增的章节会移入回收站。",
+                snapshot.name
+            )).ok())
+            .unwrap_or(false);
+        if !confirmed {
+            return;
+        }
+        busy.set(true);
+        spawn(async move {
+            match SnapshotManager::new().restore(snapshot.id).await {
+                Ok(report) => {
+                    info!("Restored novel {} to snapshot {}", novel_id, snapshot.id);
+                    let mut text = format!(
+                        "已恢复到「{}」：{} 个章节已还原，{} 个从回收站恢复，{} 个重新创建，{} 个移入回收站",
+                        snapshot.name, report.restored, report.untrashed, report.recreated, report.trashed
+                    );
+                    if report.missing_versions > 0 {
+                        text.push_str(&format!("；{} 个章节的版本已被删除，正文保持不变", report.missing_versions));
+                    }
+                    message.set(Some(text));
+                    diff.set(None);
+                    on_restored.call(());
+                },
+                Err(e) => message.set(Some(format!("恢复快照失败: {}", e))),
+            }
+            busy.set(false);
+            load();
+        });
+    };
+
+    let delete = move |snapshot: NovelSnapshot| {
+        let confirmed = web_sys::window()
+            .and_then(|window| window.confirm_with_message(&format!("确定要删除快照「{}」吗？章节和版本历史不受影响。", snapshot.name)).ok())
+            .unwrap_or(false);
+        if !confirmed {
+            return;
+        }
+        spawn(async move {
+            match SnapshotManager::new().delete(snapshot.id).await {
+                Ok(()) => {
+                    selected.write().retain(|id| *id != snapshot.id);
+                    message.set(Some(format!("已删除快照「{}」", snapshot.name)));
+                },
+                Err(e) => message.set(Some(format!("删除快照失败: {}", e))),
+            }
+            load();
+        });
+    };
+
+    if let Some(snapshot) = exporting() {
+        return rsx! {
+            ExportPanel {
+                novel_id: novel_id,
+                novel_title: props.novel_title.clone(),
+                chapters: props.chapters,
+                snapshot: snapshot,
+                on_close: move |_| exporting.set(None),
+            }
+        };
+    }
+
+    rsx! {
+        div {
+            class: "chapter-form-overlay",
+            onclick: move |_| on_close.call(()),
+
+            div {
+                class: "chapter-form snapshot-panel",
+                onclick: move |e: Event<MouseData>| e.stop_propagation(),
+
+                h3 { "📸 全书快照 · {props.novel_title}" }
+                p {
+                    class: "setting-hint",
+                    "快照记录每个章节当时的版本、标题和位置，例如交稿或大改之前。可以比较两个快照，或把全书恢复、导出为快照时的样子。"
+                }
+
+                div {
+                    class: "snapshot-create",
+                    input {
+                        r#type: "text",
+                        placeholder: "快照名称，例如「交给编辑 3 月 3 日」",
+                        value: "{name_input}",
+                        oninput: move |e| name_input.set(e.value()),
+                    }
+                    input {
+                        r#type: "text",
+                        placeholder: "备注（可选）",
+                        value: "{note_input}",
+                        oninput: move |e| note_input.set(e.value()),
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: busy() || name_input().trim().is_empty(),
+                        onclick: create_snapshot,
+                        "📸 拍摄快照"
+                    }
+                }
+
+                if let Some(text) = message() {
+                    p { class: "export-message", "{text}" }
+                }
+
+                if snapshots().is_empty() {
+                    div { class: "empty-state", "还没有快照" }
+                } else {
+                    ul {
+                        class: "snapshot-list",
+                        for snapshot in snapshots() {
+                            li {
+                                key: "{snapshot.id}",
+                                class: if selected().contains(&snapshot.id) { "snapshot-row selected" } else { "snapshot-row" },
+                                input {
+                                    r#type: "checkbox",
+                                    title: "选择两个快照进行比较",
+                                    checked: selected().contains(&snapshot.id),
+                                    onchange: move |_| toggle_selected(snapshot.id),
+                                }
+                                div {
+                                    class: "snapshot-info",
+                                    span { class: "snapshot-name", "{snapshot.name}" }
+                                    span {
+                                        class: "snapshot-meta",
+                                        {snapshot.created_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string()}
+                                        " · {snapshot.chapter_count} 个章节 · {snapshot.word_count} 字"
+                                    }
+                                    if !snapshot.note.is_empty() {
+                                        span { class: "snapshot-note", "{snapshot.note}" }
+                                    }
+                                }
+                                button {
+                                    class: "btn btn-secondary",
+                                    disabled: busy(),
+                                    onclick: {
+                                        let snapshot = snapshot.clone();
+                                        move |_| restore(snapshot.clone())
+                                    },
+                                    "恢复"
+                                }
+                                button {
+                                    class: "btn btn-secondary",
+                                    onclick: {
+                                        let snapshot = snapshot.clone();
+                                        move |_| exporting.set(Some(snapshot.clone()))
+                                    },
+                                    "导出"
+                                }
+                                button {
+                                    class: "btn btn-secondary",
+                                    onclick: {
+                                        let snapshot = snapshot.clone();
+                                        move |_| delete(snapshot.clone())
+                                    },
+                                    "删除"
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if let Some(result) = diff() {
+                    div {
+                        class: "snapshot-diff",
+                        h4 { "「{result.old.name}」→「{result.new.name}」" }
+                        p {
+                            class: "setting-hint",
+                            "{result.chapters.len()} 个章节有变化，{result.unchanged_count} 个章节未变；全书字数 {result.old.word_count} → {result.new.word_count}"
+                        }
+                        for entry in result.chapters.iter().cloned() {
+                            div {
+                                key: "{entry.chapter_id}",
+                                class: "snapshot-diff-chapter",
+                                style: "margin-left: {entry.depth * 16}px",
+                                div {
+                                    class: "snapshot-diff-header",
+                                    onclick: move |_| {
+                                        let id = entry.chapter_id;
+                                        expanded.set(if expanded() == Some(id) { None } else { Some(id) });
+                                    },
+                                    span { class: change_label(entry.change).1, "{change_label(entry.change).0}" }
+                                    span { class: "snapshot-diff-title", "{entry.title}" }
+                                    if let Some(old_title) = &entry.old_title {
+                                        span { class: "snapshot-diff-flag", "原标题「{old_title}」" }
+                                    }
+                                    if entry.moved {
+                                        span { class: "snapshot-diff-flag", "位置变化" }
+                                    }
+                                    if entry.archived_changed {
+                                        span { class: "snapshot-diff-flag", "归档状态变化" }
+                                    }
+                                    span { class: "snapshot-diff-words", "{entry.old_word_count} → {entry.new_word_count} 字" }
+                                    if let Some(stats) = &entry.statistics {
+                                        span { class: "snapshot-diff-words", "+{stats.insertions} / -{stats.deletions}" }
+                                    }
+                                }
+                                if expanded() == Some(entry.chapter_id) {
+                                    if let Some(patch) = &entry.patch {
+                                        pre {
+                                            class: "snapshot-patch",
+                                            for (index, line) in patch.lines().skip(2).enumerate() {
+                                                div { key: "{index}", class: patch_line_class(line), "{line}" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div {
+                    class: "form-actions",
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| on_close.call(()),
+                        "关闭"
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: selected().len() != 2,
+                        onclick: compare,
+                        "比较所选快照"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,61 @@
 use dioxus::prelude::*;
-use crate::core::stats_manager::WritingReport;
+use crate::core::stats_manager::{GoalPace, StatsManager, WritingGoal, WritingReport};
+use crate::db::{GoalStatus, GoalType};
+use chrono::NaiveDate;
 
 #[component]
-pub fn StatsView(writing_report: Signal<Option<WritingReport>>) -> Element {
+pub fn StatsView(
+    novel_id: i64,
+    writing_report: Signal<Option<WritingReport>>,
+    /// 设定或放弃目标后通知上层重新生成报告
+    on_goals_changed: EventHandler<()>,
+) -> Element {
     let mut selected_tab = use_signal(|| "overview".to_string());
+    
+    // 目标表单状态
+    let mut goal_type = use_signal(|| GoalType::TotalWords);
+    let mut goal_target = use_signal(String::new);
+    let mut goal_deadline = use_signal(String::new);
+    let mut goal_error = use_signal(|| None::<String>);
+    
+    let save_goal = move |_| {
+        let Ok(target) = goal_target().trim().parse::<u32>() else {
+            goal_error.set(Some("请输入有效的目标数量".to_string()));
+            return;
+        };
+        let deadline = match goal_deadline().trim() {
+            "" => None,
+            value => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                Ok(date) => Some(date),
+                Err(_) => {
+                    goal_error.set(Some("截止日期格式应为 YYYY-MM-DD".to_string()));
+                    return;
+                }
+            },
+        };
+        let goal = WritingGoal { goal_type: goal_type(), target, deadline };
+        
+        spawn(async move {
+            match StatsManager::new().set_writing_goal(novel_id, goal).await {
+                Ok(_) => {
+                    goal_error.set(None);
+                    goal_target.set(String::new());
+                    goal_deadline.set(String::new());
+                    on_goals_changed.call(());
+                },
+                Err(e) => goal_error.set(Some(e.to_string())),
+            }
+        });
+    };
+    
+    let abandon_goal = move |goal_id: i64| {
+        spawn(async move {
+            match StatsManager::new().abandon_writing_goal(goal_id).await {
+                Ok(()) => on_goals_changed.call(()),
+                Err(e) => goal_error.set(Some(e.to_string())),
+            }
+        });
+    };
 
     rsx! {
         div {
@@ -44,62 +96,79 @@ pub fn StatsView(writing_report: Signal<Option<WritingReport>>) -> Element {
                     div {
                         class: "overview-section",
                         h3 { "📋 小说概览" }
-                        div {
-                            class: "stats-card",
-                            p { class: "stats-label", "总字数" }
-                            p { class: "stats-value", "125,342" }
-                        }
-                        div {
-                            class: "stats-card",
-                            p { class: "stats-label", "已完成章节" }
-                            p { class: "stats-value", "15 章" }
-                        }
-                        div {
-                            class: "stats-card",
-                            p { class: "stats-label", "写作天数" }
-                            p { class: "stats-value", "45 天" }
-                        }
-                        div {
-                            class: "stats-card",
-                            p { class: "stats-label", "平均日产量" }
-                            p { class: "stats-value", "2,785 字" }
-                        }
-                    }
-                }
-                
-                // 写作趋势标签页
-                else if selected_tab() == "trends" {
-                    div {
-                        class: "trends-section",
-                        h3 { "📈 写作趋势" }
-                        div {
-                            class: "chart-placeholder",
-                            p { "📊 最近30天写作趋势图" }
+                        if let Some(report) = writing_report() {
                             div {
-                                class: "trend-bar",
-                                style: "height: 30%; background-color: #4CAF50; width: 10px; margin: 0 2px; display: inline-block;"
+                                class: "stats-card",
+                                p { class: "stats-label", "总字数" }
+                                p { class: "stats-value", "{report.novel_stats.total_words}" }
                             }
                             div {
-                                class: "trend-bar",
-                                style: "height: 50%; background-color: #4CAF50; width: 10px; margin: 0 2px; display: inline-block;"
+                                class: "stats-card",
+                                p { class: "stats-label", "章节数" }
+                                p { class: "stats-value", "{report.novel_stats.total_chapters} 章" }
                             }
                             div {
-                                class: "trend-bar",
-                                style: "height: 70%; background-color: #4CAF50; width: 10px; margin: 0 2px; display: inline-block;"
+                                class: "stats-card",
+                                p { class: "stats-label", "写作天数" }
+                                p { class: "stats-value", "{report.novel_stats.writing_days} 天" }
                             }
                             div {
-                                class: "trend-bar",
-                                style: "height: 45%; background-color: #4CAF50; width: 10px; margin: 0 2px; display: inline-block;"
+                                class: "stats-card",
+                                p { class: "stats-label", "连续写作" }
+                                p { class: "stats-value", "{report.novel_stats.current_streak} 天（最长 {report.novel_stats.longest_streak} 天）" }
                             }
                             div {
-                                class: "trend-bar",
-                                style: "height: 60%; background-color: #4CAF50; width: 10px; margin: 0 2px; display: inline-block;"
+                                class: "stats-card",
+                                p { class: "stats-label", "平均日产量" }
+                                p { class: "stats-value", "{report.novel_stats.average_daily_words:.0} 字" }
                             }
+                        } else {
+                            p { "正在加载统计数据..." }
                         }
-                        div {
-                            class: "best-day-info",
-                            h4 { "🏆 最佳写作日" }
-                            p { "2023-05-15: 5,687 字" }
+                    }
+                }
+                
+                // 写作趋势标签页
+                else if selected_tab() == "trends" {
+                    div {
+                        class: "trends-section",
+                        h3 { "📈 写作趋势" }
+                        if let Some(report) = writing_report() {
+                            {
+                                let trends = report.trends.clone();
+                                let max_words = trends.recent_days.iter().map(|d| d.word_count).max().unwrap_or(0).max(1);
+                                rsx! {
+                                    div {
+                                        class: "chart-placeholder",
+                                        p { "📊 最近{trends.period_days}天写作趋势" }
+                                        if trends.recent_days.is_empty() {
+                                            p { class: "hint", "这段时间还没有写作记录" }
+                                        }
+                                        div {
+                                            class: "trend-chart",
+                                            for day in trends.recent_days.iter().rev() {
+                                                div {
+                                                    key: "{day.date}",
+                                                    class: "trend-bar",
+                                                    title: "{day.date}: +{day.words_added} / -{day.words_deleted} 字，{day.writing_time / 60} 分钟",
+                                                    style: "height: {day.word_count * 100 / max_words}%;",
+                                                }
+                                            }
+                                        }
+                                    }
+                                    p { "近期日均：{trends.recent_daily_average:.0} 字　总体日均：{trends.total_daily_average:.0} 字" }
+                                    p { "写作一致性：{trends.consistency_score:.0}%" }
+                                    if let Some(best_day) = trends.best_day.clone() {
+                                        div {
+                                            class: "best-day-info",
+                                            h4 { "🏆 最佳写作日" }
+                                            p { "{best_day.date}: {best_day.word_count} 字" }
+                                        }
+                                    }
+                                }
+                            }
+                        } else {
+                            p { "正在加载统计数据..." }
                         }
                     }
                 }
@@ -109,18 +178,115 @@ pub fn StatsView(writing_report: Signal<Option<WritingReport>>) -> Element {
                     div {
                         class: "goals-section",
                         h3 { "🎯 目标进度" }
-                        div {
-                            class: "progress-container",
+                        
+                        for progress in writing_report().map(|r| r.goal_progress).unwrap_or_default() {
                             div {
-                                class: "progress-bar",
-                                style: "width: 65%; background-color: #2196F3; height: 24px; border-radius: 12px;"
+                                key: "{progress.goal.id}",
+                                class: "goal-card",
+                                h4 { "{progress.goal.goal_type.display_name()}目标" }
+                                div {
+                                    class: "progress-container",
+                                    div {
+                                        class: "progress-bar",
+                                        style: "width: {progress.progress_percentage:.0}%;",
+                                    }
+                                }
+                                p { class: "progress-text", "{progress.current} / {progress.target}（{progress.progress_percentage:.0}%）" }
+                                p {
+                                    class: "goal-details goal-pace",
+                                    {match progress.pace {
+                                        GoalPace::Achieved => "✅ 已达成",
+                                        GoalPace::OnTrack => "🟢 按计划推进",
+                                        GoalPace::Behind => "🟠 进度落后",
+                                        GoalPace::Overdue => "🔴 已超过截止日期",
+                                        GoalPace::NoDeadline => "⚪ 未设截止日期",
+                                    }}
+                                }
+                                if let Some(deadline) = progress.deadline {
+                                    p { class: "goal-details", "截止日期：{deadline}（剩余 {progress.days_remaining.unwrap_or(0).max(0)} 天）" }
+                                }
+                                if let Some(pace) = progress.required_daily_pace {
+                                    p {
+                                        class: "goal-details",
+                                        if progress.goal.goal_type == GoalType::DailyWords {
+                                            "今天还需：{pace:.0} 字"
+                                        } else if progress.goal.goal_type == GoalType::ChapterCount {
+                                            "所需速度：每天 {pace:.1} 章"
+                                        } else {
+                                            "所需速度：每天 {pace:.0} 字"
+                                        }
+                                    }
+                                }
+                                if let Some(date) = progress.estimated_completion_date {
+                                    p { class: "goal-details", "按当前速度预计完成：{date}" }
+                                }
+                                button {
+                                    class: "btn btn-secondary",
+                                    onclick: move |_| abandon_goal(progress.goal.id),
+                                    "放弃目标"
+                                }
+                            }
+                        }
+                        
+                        // 设定新目标
+                        div {
+                            class: "goal-form",
+                            h4 { "设定目标" }
+                            select {
+                                value: goal_type().as_str(),
+                                onchange: move |e| goal_type.set(GoalType::from_str(&e.value())),
+                                option { value: "total_words", "总字数" }
+                                option { value: "chapter_count", "章节数" }
+                                option { value: "daily_words", "每日字数" }
+                            }
+                            input {
+                                r#type: "number",
+                                min: "1",
+                                placeholder: "目标数量",
+                                value: goal_target(),
+                                oninput: move |e| goal_target.set(e.value()),
+                            }
+                            if goal_type() != GoalType::DailyWords {
+                                input {
+                                    r#type: "date",
+                                    value: goal_deadline(),
+                                    oninput: move |e| goal_deadline.set(e.value()),
+                                }
+                            }
+                            button {
+                                class: "btn btn-primary",
+                                onclick: save_goal,
+                                "保存目标"
+                            }
+                            if let Some(error) = goal_error() {
+                                p { class: "form-error", "{error}" }
+                            }
+                        }
+                        
+                        // 目标历史
+                        if let Some(report) = writing_report() {
+                            if report.goal_history.iter().any(|g| g.status != GoalStatus::Active) {
+                                div {
+                                    class: "goal-history",
+                                    h4 { "目标历史" }
+                                    ul {
+                                        for record in report.goal_history.iter().filter(|g| g.status != GoalStatus::Active) {
+                                            li {
+                                                key: "{record.id}",
+                                                "{record.goal_type.display_name()} {record.target}（{record.start_date} 起）"
+                                                span {
+                                                    class: "goal-history-status",
+                                                    if record.status == GoalStatus::Achieved { "已达成" } else { "已放弃" }
+                                                }
+                                                if let Some(completed_at) = record.completed_at {
+                                                    span { class: "goal-history-date", "{completed_at.format(\"%Y-%m-%d\")}" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
                             }
-                            p { class: "progress-text", "65% 完成" }
                         }
-                        p { class: "goal-details", "目标：200,000 字" }
-                        p { class: "goal-details", "已完成：125,342 字" }
-                        p { class: "goal-details", "剩余：74,658 字" }
-                        p { class: "goal-details", "预计完成日期：2023-12-15" }
                     }
                 }
                 
@@ -131,21 +297,12 @@ pub fn StatsView(writing_report: Signal<Option<WritingReport>>) -> Element {
                         h3 { "改进建议" }
                         ul {
                             class: "recommendations-list",
-                            // 静态建议数据
-                            li {
-                                class: "recommendation-item",
-                                span { class: "recommendation-icon", "💡" }
-                                span { "建议增加角色对话，提升故事生动性" }
-                            }
-                            li {
-                                class: "recommendation-item",
-                                span { class: "recommendation-icon", "💡" }
-                                span { "可以适当增加场景描写，增强画面感" }
-                            }
-                            li {
-                                class: "recommendation-item",
-                                span { class: "recommendation-icon", "💡" }
-                                span { "考虑设定每周写作目标，增强写作动力" }
+                            for recommendation in writing_report().map(|r| r.recommendations).unwrap_or_default() {
+                                li {
+                                    class: "recommendation-item",
+                                    span { class: "recommendation-icon", "💡" }
+                                    span { "{recommendation}" }
+                                }
                             }
                         }
                     }
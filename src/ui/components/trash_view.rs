@@ -0,0 +1,172 @@
+/// 回收站视图
+use dioxus::prelude::*;
+use crate::config::AppConfig;
+use crate::core::trash_manager::TrashManager;
+use crate::db::{Novel, TrashEntry, TrashItemType};
+use log::error;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct TrashViewProps {
+    pub novels: Signal<Vec<Novel>>,
+    /// 恢复或彻底删除后通知上层重新加载数据
+    pub on_changed: EventHandler<()>,
+}
+
+#[component]
+pub fn TrashView(props: TrashViewProps) -> Element {
+    let novels = props.novels;
+    let on_changed = props.on_changed;
+    let mut entries = use_signal(Vec::<TrashEntry>::new);
+    let mut message = use_signal(|| None::<String>);
+    let retention_days = use_signal(|| AppConfig::load().unwrap_or_default().trash.retention_days);
+
+    let load_entries = move || {
+        spawn(async move {
+            match TrashManager::new().list().await {
+                Ok(list) => entries.set(list),
+                Err(e) => error!("读取回收站失败: {}", e),
+            }
+        });
+    };
+    use_hook(move || load_entries());
+
+    let restore = move |entry: TrashEntry| {
+        spawn(async move {
+            match TrashManager::new().restore(entry.id).await {
+                Ok(_) => {
+                    message.set(Some(format!("已恢复「{}」", entry.title)));
+                    on_changed.call(());
+                },
+                Err(e) => {
+                    error!("恢复失败: {}", e);
+                    message.set(Some(format!("恢复失败: {}", e)));
+                }
+            }
+            load_entries();
+        });
+    };
+
+    let purge = move |entry: TrashEntry| {
+        let confirmed = web_sys::window()
+            .and_then(|window| window.confirm_with_message(&format!("确定要彻底删除「{}」吗？此操作不可撤销，相关的历史版本也将被删除。", entry.title)).ok())
+            .unwrap_or(false);
+        if !confirmed {
+            return;
+        }
+        spawn(async move {
+            match TrashManager::new().purge(entry.id).await {
+                Ok(()) => {
+                    message.set(Some(format!("已彻底删除「{}」", entry.title)));
+                    on_changed.call(());
+                },
+                Err(e) => {
+                    error!("彻底删除失败: {}", e);
+                    message.set(Some(format!("彻底删除失败: {}", e)));
+                }
+            }
+            load_entries();
+        });
+    };
+
+    let empty_trash = move |_| {
+        let confirmed = web_sys::window()
+            .and_then(|window| window.confirm_with_message("确定要清空回收站吗？此操作不可撤销。").ok())
+            .unwrap_or(false);
+        if !confirmed {
+            return;
+        }
+        spawn(async move {
+            match TrashManager::new().empty().await {
+                Ok(count) => {
+                    message.set(Some(format!("已彻底删除 {} 个条目", count)));
+                    on_changed.call(());
+                },
+                Err(e) => {
+                    error!("清空回收站失败: {}", e);
+                    message.set(Some(format!("清空回收站失败: {}", e)));
+                }
+            }
+            load_entries();
+        });
+    };
+
+    rsx! {
+        div {
+            class: "trash-view",
+            div {
+                class: "trash-header",
+                h2 { "🗑️ 回收站" }
+                button {
+                    class: "btn btn-danger",
+                    disabled: entries().is_empty(),
+                    onclick: empty_trash,
+                    "清空回收站"
+                }
+            }
+            p {
+                class: "setting-hint",
+                if retention_days() > 0 {
+                    "删除的小说、章节和灵感会在这里保留 {retention_days} 天，之后在启动时自动彻底删除。"
+                } else {
+                    "删除的小说、章节和灵感会一直保留在这里，直到手动彻底删除。"
+                }
+            }
+
+            if let Some(text) = message() {
+                p { class: "export-message", "{text}" }
+            }
+
+            if entries().is_empty() {
+                div { class: "empty-state", "回收站是空的" }
+            } else {
+                ul {
+                    class: "trash-entries",
+                    for entry in entries() {
+                        li {
+                            key: "{entry.id}",
+                            class: "trash-entry",
+                            span { class: "trash-entry-type", "{entry.item_type.display_name()}" }
+                            div {
+                                class: "trash-entry-info",
+                                strong { "{entry.title}" }
+                                span {
+                                    class: "trash-entry-meta",
+                                    {
+                                        let deleted_at = entry.deleted_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+                                        let novel_title = novels().iter()
+                                            .find(|n| n.id == entry.novel_id)
+                                            .map(|n| n.title.clone());
+                                        match (entry.item_type, novel_title) {
+                                            (TrashItemType::Novel, _) => format!("{} · 删除于 {}", entry.summary, deleted_at),
+                                            (_, Some(title)) => format!("《{}》 · {} · 删除于 {}", title, entry.summary, deleted_at),
+                                            (_, None) => format!("所属小说在回收站中 · {} · 删除于 {}", entry.summary, deleted_at),
+                                        }
+                                    }
+                                }
+                            }
+                            div {
+                                class: "trash-entry-actions",
+                                button {
+                                    class: "btn btn-secondary",
+                                    onclick: {
+                                        let entry = entry.clone();
+                                        move |_| restore(entry.clone())
+                                    },
+                                    "恢复"
+                                }
+                                button {
+                                    class: "btn btn-danger",
+                                    onclick: {
+                                        let entry = entry.clone();
+                                        move |_| purge(entry.clone())
+                                    },
+                                    "彻底删除"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
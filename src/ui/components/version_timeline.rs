@@ -0,0 +1,435 @@
+/// 版本时间线面板 - 显示章节的分支图、里程碑，并在分支之间切换正文
+use dioxus::prelude::*;
+use crate::core::version_manager::{VersionManager, VersionTimelineEntry};
+use crate::db::{VersionBranch, VersionTag};
+use super::merge_view::MergeView;
+use super::diff_view::DiffView;
+use crate::db;
+use crate::utils::diff_utils::DiffGranularity;
+use log::{error, info};
+use std::collections::HashMap;
+
+/// 分支图每一行的高度和每条泳道的宽度（像素），与样式表中 `.version-row` 的高度一致
+const ROW_HEIGHT: usize = 44;
+const LANE_WIDTH: usize = 18;
+const LANE_COLORS: &[&str] = &["#3b82f6", "#f59e0b", "#10b981", "#ef4444", "#8b5cf6", "#ec4899"];
+
+#[derive(Props, Clone, PartialEq)]
+pub struct VersionTimelineProps {
+    pub chapter_id: i64,
+    pub chapter_title: String,
+    pub on_close: EventHandler<()>,
+    /// 切换分支改变了章节正文后通知上层重新加载
+    pub on_switched: EventHandler<()>,
+}
+
+fn lane_color(lane: usize) -> &'static str {
+    LANE_COLORS[lane % LANE_COLORS.len()]
+}
+
+fn lane_x(lane: usize) -> usize {
+    lane * LANE_WIDTH + LANE_WIDTH / 2
+}
+
+fn row_y(row: usize) -> usize {
+    row * ROW_HEIGHT + ROW_HEIGHT / 2
+}
+
+/// 分支图中每个版本到父版本的连线，跨泳道时先沿自己的泳道走到父版本上方再折向父版本
+///
+/// 合并版本到合并来源的连线用虚线（第三项为 `true`），颜色取来源所在泳道。
+fn graph_edges(timeline: &[VersionTimelineEntry]) -> Vec<(String, &'static str, bool)> {
+    let rows: HashMap<i64, usize> = timeline.iter().enumerate().map(|(row, e)| (e.version_id, row)).collect();
+    let edge = |row: usize, parent_row: usize, merge: bool| {
+        let (entry, parent) = (&timeline[row], &timeline[parent_row]);
+        let (x1, y1) = (lane_x(entry.lane), row_y(row));
+        let (x2, y2) = (lane_x(parent.lane), row_y(parent_row));
+        let path = if x1 == x2 {
+            format!("M {} {} L {} {}", x1, y1, x2, y2)
+        } else {
+            format!("M {} {} L {} {} L {} {}", x1, y1, x1, y2 - ROW_HEIGHT / 2, x2, y2)
+        };
+        (path, lane_color(if merge { parent.lane } else { entry.lane }), merge)
+    };
+    timeline.iter()
+        .enumerate()
+        .flat_map(|(row, entry)| {
+            let parent = entry.parent_version_id.and_then(|id| rows.get(&id)).map(|&p| edge(row, p, false));
+            let merged = entry.merge_parent_version_id.and_then(|id| rows.get(&id)).map(|&p| edge(row, p, true));
+            parent.into_iter().chain(merged)
+        })
+        .collect()
+}
+
+#[component]
+pub fn VersionTimeline(props: VersionTimelineProps) -> Element {
+    let chapter_id = props.chapter_id;
+    let on_close = props.on_close;
+    let on_switched = props.on_switched;
+    let mut timeline = use_signal(Vec::<VersionTimelineEntry>::new);
+    let mut branches = use_signal(Vec::<VersionBranch>::new);
+    let mut selected_version = use_signal(|| None::<i64>);
+    let mut name_input = use_signal(String::new);
+    let mut message = use_signal(|| None::<String>);
+    // 正在合并到当前分支的版本
+    let mut merging = use_signal(|| None::<i64>);
+    // 正在对比的两段文本：(旧文本, 新文本, 旧文本名称, 新文本名称)
+    let mut comparing = use_signal(|| None::<(String, String, String, String)>);
+
+    let load = move || {
+        spawn(async move {
+            let manager = VersionManager::new();
+            match manager.get_version_timeline(chapter_id).await {
+                Ok(entries) => timeline.set(entries),
+                Err(e) => error!("读取版本时间线失败: {}", e),
+            }
+            match manager.list_branches(chapter_id).await {
+                Ok(list) => branches.set(list),
+                Err(e) => error!("读取版本分支失败: {}", e),
+            }
+        });
+    };
+    use_hook(move || load());
+
+    let switch_branch = move |branch: VersionBranch| {
+        spawn(async move {
+            match VersionManager::new().switch_branch(branch.id).await {
+                Ok(_) => {
+                    info!("Switched chapter {} to branch {}", chapter_id, branch.id);
+                    message.set(Some(format!("正文已切换到分支「{}」", branch.name)));
+                    on_switched.call(());
+                },
+                Err(e) => {
+                    error!("切换分支失败: {}", e);
+                    message.set(Some(format!("切换分支失败: {}", e)));
+                }
+            }
+            load();
+        });
+    };
+
+    let delete_branch = move |branch: VersionBranch| {
+        let confirmed = web_sys::window()
+            .and_then(|window| window.confirm_with_message(&format!("确定要删除分支「{}」吗？分支上的版本仍会保留在时间线中。", branch.name)).ok())
+            .unwrap_or(false);
+        if !confirmed {
+            return;
+        }
+        spawn(async move {
+            match VersionManager::new().delete_branch(branch.id).await {
+                Ok(()) => message.set(Some(format!("已删除分支「{}」", branch.name))),
+                Err(e) => message.set(Some(format!("删除分支失败: {}", e))),
+            }
+            load();
+        });
+    };
+
+    let create_branch = move |_| {
+        let Some(version_id) = selected_version() else { return };
+        let name = name_input();
+        spawn(async move {
+            match VersionManager::new().create_branch(version_id, &name).await {
+                Ok(branch) => {
+                    message.set(Some(format!("已从版本 #{} 创建分支「{}」", version_id, branch.name)));
+                    name_input.set(String::new());
+                },
+                Err(e) => message.set(Some(format!("创建分支失败: {}", e))),
+            }
+            load();
+        });
+    };
+
+    let add_milestone = move |_| {
+        let Some(version_id) = selected_version() else { return };
+        let name = name_input();
+        spawn(async move {
+            match VersionManager::new().add_milestone(version_id, &name, "").await {
+                Ok(tag) => {
+                    message.set(Some(format!("已在版本 #{} 上标记里程碑「{}」", version_id, tag.name)));
+                    name_input.set(String::new());
+                },
+                Err(e) => message.set(Some(format!("标记里程碑失败: {}", e))),
+            }
+            load();
+        });
+    };
+
+    // 与父版本对比，或与章节当前正文对比
+    let compare = move |(version_id, against_current): (i64, bool)| {
+        let parent_id = timeline.peek().iter()
+            .find(|e| e.version_id == version_id)
+            .and_then(|e| e.parent_version_id);
+        spawn(async move {
+            let manager = VersionManager::new();
+            let texts = if against_current {
+                manager.restore_to_version(version_id).await.and_then(|content| {
+                    let chapter = db::get_database()?.get_chapter(chapter_id)?;
+                    Ok((content, chapter.content, format!("版本 #{}", version_id), "当前正文".to_string()))
+                })
+            } else {
+                match parent_id {
+                    Some(parent_id) => manager.compare_versions(parent_id, version_id, DiffGranularity::Word).await
+                        .map(|c| (c.content1, c.content2, format!("版本 #{}", parent_id), format!("版本 #{}", version_id))),
+                    None => manager.restore_to_version(version_id).await
+                        .map(|content| (String::new(), content, "（空）".to_string(), format!("版本 #{}", version_id))),
+                }
+            };
+            match texts {
+                Ok(texts) => comparing.set(Some(texts)),
+                Err(e) => message.set(Some(format!("对比版本失败: {}", e))),
+            }
+        });
+    };
+
+    let remove_milestone = move |tag: VersionTag| {
+        spawn(async move {
+            match VersionManager::new().remove_milestone(tag.id).await {
+                Ok(()) => message.set(Some(format!("已移除里程碑「{}」", tag.name))),
+                Err(e) => message.set(Some(format!("移除里程碑失败: {}", e))),
+            }
+            load();
+        });
+    };
+
+    if let Some(theirs_version_id) = merging() {
+        return rsx! {
+            div {
+                class: "chapter-form-overlay",
+                onclick: move |_| merging.set(None),
+
+                div {
+                    class: "chapter-form version-timeline",
+                    onclick: move |e: Event<MouseData>| e.stop_propagation(),
+
+                    h3 { "🔀 合并版本 · {props.chapter_title}" }
+                    MergeView {
+                        chapter_id: chapter_id,
+                        theirs_version_id: theirs_version_id,
+                        on_close: move |_| merging.set(None),
+                        on_merged: move |_| {
+                            message.set(Some(format!("已把版本 #{} 合并到当前分支", theirs_version_id)));
+                            on_switched.call(());
+                            load();
+                        },
+                    }
+                }
+            }
+        };
+    }
+
+    if let Some((old_text, new_text, old_label, new_label)) = comparing() {
+        return rsx! {
+            div {
+                class: "chapter-form-overlay",
+                onclick: move |_| comparing.set(None),
+
+                div {
+                    class: "chapter-form version-timeline version-compare",
+                    onclick: move |e: Event<MouseData>| e.stop_propagation(),
+
+                    h3 { "🔍 {old_label} → {new_label}" }
+                    DiffView { old_text, new_text, old_label, new_label }
+                    div {
+                        class: "form-actions",
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: move |_| comparing.set(None),
+                            "返回"
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    let entries = timeline();
+    let lane_count = entries.iter().map(|e| e.lane + 1).max().unwrap_or(1);
+    let graph_width = lane_count * LANE_WIDTH;
+    let graph_height = entries.len() * ROW_HEIGHT;
+    let edges = graph_edges(&entries);
+
+    rsx! {
+        div {
+            class: "chapter-form-overlay",
+            onclick: move |_| on_close.call(()),
+
+            div {
+                class: "chapter-form version-timeline",
+                onclick: move |e: Event<MouseData>| e.stop_propagation(),
+
+                h3 { "🕘 版本历史 · {props.chapter_title}" }
+                p {
+                    class: "setting-hint",
+                    "新版本保存在当前分支上。可以从任意版本创建分支来尝试不同的写法，切换分支时未保存为版本的修改会先保存到原分支。"
+                }
+
+                if let Some(text) = message() {
+                    p { class: "export-message", "{text}" }
+                }
+
+                div {
+                    class: "version-branches",
+                    for branch in branches() {
+                        div {
+                            key: "{branch.id}",
+                            class: if branch.is_current { "version-branch current" } else { "version-branch" },
+                            span { class: "version-branch-name", "🌿 {branch.name}" }
+                            if branch.is_current {
+                                span { class: "version-badge", "当前" }
+                            } else {
+                                button {
+                                    class: "btn btn-secondary",
+                                    disabled: branch.head_version_id.is_none(),
+                                    onclick: {
+                                        let branch = branch.clone();
+                                        move |_| switch_branch(branch.clone())
+                                    },
+                                    "切换"
+                                }
+                                button {
+                                    class: "btn btn-secondary",
+                                    onclick: {
+                                        let branch = branch.clone();
+                                        move |_| delete_branch(branch.clone())
+                                    },
+                                    "删除"
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if let Some(version_id) = selected_version() {
+                    div {
+                        class: "version-actions",
+                        span { "版本 #{version_id}" }
+                        input {
+                            r#type: "text",
+                            placeholder: "分支或里程碑名称",
+                            value: "{name_input}",
+                            oninput: move |e| name_input.set(e.value()),
+                        }
+                        button {
+                            class: "btn btn-primary",
+                            disabled: name_input().trim().is_empty(),
+                            onclick: create_branch,
+                            "🌿 创建分支"
+                        }
+                        button {
+                            class: "btn btn-secondary",
+                            disabled: name_input().trim().is_empty(),
+                            onclick: add_milestone,
+                            "🏁 标记里程碑"
+                        }
+                        button {
+                            class: "btn btn-secondary",
+                            title: "把这个版本三方合并到当前分支",
+                            onclick: move |_| merging.set(Some(version_id)),
+                            "🔀 合并到当前分支"
+                        }
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: move |_| compare((version_id, false)),
+                            "🔍 与上一版本对比"
+                        }
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: move |_| compare((version_id, true)),
+                            "🔍 与当前正文对比"
+                        }
+                    }
+                }
+
+                if entries.is_empty() {
+                    div { class: "empty-state", "这个章节还没有保存过版本" }
+                } else {
+                    div {
+                        class: "version-graph",
+                        svg {
+                            class: "version-graph-lines",
+                            width: "{graph_width}",
+                            height: "{graph_height}",
+                            for (d, color, merge) in edges {
+                                path {
+                                    d: "{d}",
+                                    stroke: "{color}",
+                                    stroke_width: "2",
+                                    stroke_dasharray: if merge { "4 3" } else { "none" },
+                                    fill: "none",
+                                }
+                            }
+                            for (row, entry) in entries.iter().enumerate() {
+                                circle {
+                                    cx: "{lane_x(entry.lane)}",
+                                    cy: "{row_y(row)}",
+                                    r: if entry.milestones.is_empty() { "5" } else { "7" },
+                                    fill: if entry.is_auto_save { "#ffffff" } else { lane_color(entry.lane) },
+                                    stroke: "{lane_color(entry.lane)}",
+                                    stroke_width: "2",
+                                }
+                            }
+                        }
+                        ul {
+                            class: "version-rows",
+                            style: "margin-left: {graph_width + 8}px",
+                            for entry in entries.iter().cloned() {
+                                li {
+                                    key: "{entry.version_id}",
+                                    class: if selected_version() == Some(entry.version_id) { "version-row selected" } else { "version-row" },
+                                    onclick: move |_| selected_version.set(Some(entry.version_id)),
+                                    span { class: "version-id", "#{entry.version_id}" }
+                                    span {
+                                        class: "version-time",
+                                        {entry.created_at.with_timezone(&chrono::Local).format("%m-%d %H:%M").to_string()}
+                                    }
+                                    span {
+                                        class: "version-message",
+                                        if entry.commit_message.is_empty() {
+                                            if entry.is_auto_save { "自动保存" } else { "手动保存" }
+                                        } else {
+                                            "{entry.commit_message}"
+                                        }
+                                    }
+                                    span { class: "version-word-count", "{entry.word_count} 字" }
+                                    for branch in entry.head_of.iter() {
+                                        span {
+                                            key: "head-{branch.id}",
+                                            class: if branch.is_current { "version-badge current" } else { "version-badge" },
+                                            "🌿 {branch.name}"
+                                        }
+                                    }
+                                    for tag in entry.milestones.iter().cloned() {
+                                        span {
+                                            key: "tag-{tag.id}",
+                                            class: "version-milestone",
+                                            title: "{tag.note}",
+                                            "🏁 {tag.name}"
+                                            button {
+                                                class: "version-milestone-remove",
+                                                title: "移除里程碑",
+                                                onclick: move |e: Event<MouseData>| {
+                                                    e.stop_propagation();
+                                                    remove_milestone(tag.clone());
+                                                },
+                                                "×"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div {
+                    class: "form-actions",
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| on_close.call(()),
+                        "关闭"
+                    }
+                }
+            }
+        }
+    }
+}
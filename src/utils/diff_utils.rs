@@ -1,4 +1,9 @@
-use similar::{TextDiff, ChangeTag};
+use similar::{capture_diff_slices, Algorithm, ChangeTag, DiffOp, TextDiff};
+use anyhow::Result;
+use std::ops::Range;
+
+/// 增量编码格式的版本头
+const DELTA_HEADER: &str = "d1";
 
 pub struct DiffUtils;
 
@@ -82,6 +87,40 @@ impl DiffUtils {
         1.0 - (changes as f64 / total_chars as f64)
     }
     
+    /// 计算可应用的增量数据（按字符的保留/删除/插入操作序列）
+    pub fn create_delta(old_text: &str, new_text: &str) -> String {
+        let diff = TextDiff::from_chars(old_text, new_text);
+        let mut ops: Vec<DeltaOp> = Vec::new();
+        
+        for change in diff.iter_all_changes() {
+            let value = change.value();
+            let len = value.chars().count();
+            match (change.tag(), ops.last_mut()) {
+                (ChangeTag::Equal, Some(DeltaOp::Retain(n))) => *n += len,
+                (ChangeTag::Delete, Some(DeltaOp::Delete(n))) => *n += len,
+                (ChangeTag::Insert, Some(DeltaOp::Insert(text))) => text.push_str(value),
+                (ChangeTag::Equal, _) => ops.push(DeltaOp::Retain(len)),
+                (ChangeTag::Delete, _) => ops.push(DeltaOp::Delete(len)),
+                (ChangeTag::Insert, _) => ops.push(DeltaOp::Insert(value.to_string())),
+            }
+        }
+        
+        TextDelta {
+            base_len: old_text.chars().count(),
+            ops,
+        }.encode()
+    }
+    
+    /// 将增量数据应用到基础文本上，重建新文本
+    pub fn apply_delta(base_text: &str, delta: &str) -> Result<String> {
+        TextDelta::decode(delta)?.apply(base_text)
+    }
+    
+    /// 判断字符串是否为 `create_delta` 生成的增量数据
+    pub fn is_delta(data: &str) -> bool {
+        data.starts_with(DELTA_HEADER) && data[DELTA_HEADER.len()..].starts_with('|')
+    }
+    
     pub fn create_patch(old_text: &str, new_text: &str) -> String {
         let diff = TextDiff::from_lines(old_text, new_text);
         diff.unified_diff()
@@ -89,6 +128,195 @@ impl DiffUtils {
             .header("old", "new")
             .to_string()
     }
+    
+    /// 按段落定位修改，再按所选粒度对齐每处修改中的文字
+    ///
+    /// 段落以 `\n` 分隔。统计中的插入、删除字数按所选粒度计算：按段落比较时整段计入。
+    pub fn structured_diff(old_text: &str, new_text: &str, granularity: DiffGranularity) -> StructuredDiff {
+        let old_paragraphs: Vec<&str> = old_text.split('\n').collect();
+        let new_paragraphs: Vec<&str> = new_text.split('\n').collect();
+        
+        let mut ranges: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+        for op in capture_diff_slices(Algorithm::Myers, &old_paragraphs, &new_paragraphs) {
+            if let DiffOp::Equal { .. } = op {
+                continue;
+            }
+            let (old, new) = (op.old_range(), op.new_range());
+            match ranges.last_mut() {
+                // 相邻的删除和插入属于同一处修改
+                Some((last_old, last_new)) if last_old.end == old.start && last_new.end == new.start => {
+                    last_old.end = old.end;
+                    last_new.end = new.end;
+                }
+                _ => ranges.push((old, new)),
+            }
+        }
+        
+        let mut statistics = ChangeStats::default();
+        let hunks: Vec<DiffHunk> = ranges.into_iter()
+            .map(|(old_range, new_range)| {
+                let old_segment = old_paragraphs[old_range.clone()].join("\n");
+                let new_segment = new_paragraphs[new_range.clone()].join("\n");
+                let spans = match granularity {
+                    DiffGranularity::Paragraph => [(SpanKind::Delete, old_segment), (SpanKind::Insert, new_segment)]
+                        .into_iter()
+                        .filter(|(_, text)| !text.is_empty())
+                        .map(|(kind, text)| DiffSpan { kind, text })
+                        .collect(),
+                    DiffGranularity::Sentence => diff_tokens(&split_sentences(&old_segment), &split_sentences(&new_segment)),
+                    DiffGranularity::Word => diff_tokens(&split_words(&old_segment), &split_words(&new_segment)),
+                };
+                for span in &spans {
+                    match span.kind {
+                        SpanKind::Insert => statistics.insertions += span.text.chars().count(),
+                        SpanKind::Delete => statistics.deletions += span.text.chars().count(),
+                        SpanKind::Equal => {}
+                    }
+                }
+                DiffHunk { old_paragraphs: old_range, new_paragraphs: new_range, spans }
+            })
+            .collect();
+        
+        statistics.unchanged = old_text.chars().count().saturating_sub(statistics.deletions);
+        statistics.total_changes = statistics.insertions + statistics.deletions;
+        StructuredDiff { granularity, hunks, statistics }
+    }
+}
+
+/// 结构化差异的粒度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffGranularity {
+    #[default]
+    Paragraph,
+    /// 按句子，句子以 `。！？…` 或换行结束
+    Sentence,
+    /// 按词：中日韩文字逐字，拉丁字母和数字按单词
+    Word,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffSpan {
+    pub kind: SpanKind,
+    pub text: String,
+}
+
+/// 一处修改：新旧文本中相邻的若干段落，以及对齐后的片段
+///
+/// 旧文本由 `Equal` 和 `Delete` 片段依次拼成，新文本由 `Equal` 和 `Insert` 片段依次拼成。
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffHunk {
+    /// 旧文本中被修改的段落（从 0 开始）
+    pub old_paragraphs: Range<usize>,
+    /// 新文本中对应的段落
+    pub new_paragraphs: Range<usize>,
+    pub spans: Vec<DiffSpan>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StructuredDiff {
+    pub granularity: DiffGranularity,
+    pub hunks: Vec<DiffHunk>,
+    pub statistics: ChangeStats,
+}
+
+/// 句末标点，其后紧跟的句末标点和右引号、右括号仍属于同一句
+const SENTENCE_TERMINATORS: &[char] = &['。', '！', '？', '…', '!', '?'];
+const CLOSING_MARKS: &[char] = &['”', '’', '」', '』', '）', ')', '"', '\''];
+
+/// 把文本切分为句子，切分结果依次拼接即为原文
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut ended = false;
+    for (index, c) in text.char_indices() {
+        if ended && !SENTENCE_TERMINATORS.contains(&c) && !CLOSING_MARKS.contains(&c) {
+            sentences.push(&text[start..index]);
+            start = index;
+            ended = false;
+        }
+        if c == '\n' {
+            sentences.push(&text[start..index + 1]);
+            start = index + 1;
+        } else if SENTENCE_TERMINATORS.contains(&c) {
+            ended = true;
+        }
+    }
+    if start < text.len() {
+        sentences.push(&text[start..]);
+    }
+    sentences
+}
+
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'      // 平假名、片假名
+        | '\u{3400}'..='\u{4DBF}'    // 扩展 A
+        | '\u{4E00}'..='\u{9FFF}'    // 基本区
+        | '\u{AC00}'..='\u{D7AF}'    // 谚文
+        | '\u{F900}'..='\u{FAFF}'    // 兼容汉字
+        | '\u{20000}'..='\u{2FA1F}'  // 扩展 B 及以后
+    )
+}
+
+/// 把文本切分为词：中日韩文字和标点逐字，连续的字母数字、连续的空白各为一个词
+pub fn split_words(text: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum Run { Word, Space, Single }
+    
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<Run> = None;
+    for (index, c) in text.char_indices() {
+        let run = if c.is_whitespace() {
+            Run::Space
+        } else if c.is_alphanumeric() && !is_cjk(c) || c == '_' {
+            Run::Word
+        } else {
+            Run::Single
+        };
+        if index > start && (run == Run::Single || previous.as_ref() != Some(&run)) {
+            words.push(&text[start..index]);
+            start = index;
+        }
+        previous = Some(run);
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+fn diff_tokens(old: &[&str], new: &[&str]) -> Vec<DiffSpan> {
+    let mut spans: Vec<DiffSpan> = Vec::new();
+    let mut push = |kind: SpanKind, tokens: &[&str]| {
+        if tokens.is_empty() {
+            return;
+        }
+        match spans.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(&tokens.concat()),
+            _ => spans.push(DiffSpan { kind, text: tokens.concat() }),
+        }
+    };
+    for op in capture_diff_slices(Algorithm::Myers, old, new) {
+        let (old_range, new_range) = (op.old_range(), op.new_range());
+        match op {
+            DiffOp::Equal { .. } => push(SpanKind::Equal, &old[old_range]),
+            DiffOp::Delete { .. } => push(SpanKind::Delete, &old[old_range]),
+            DiffOp::Insert { .. } => push(SpanKind::Insert, &new[new_range]),
+            DiffOp::Replace { .. } => {
+                push(SpanKind::Delete, &old[old_range]);
+                push(SpanKind::Insert, &new[new_range]);
+            }
+        }
+    }
+    spans
 }
 
 #[derive(Debug, Clone, Default)]
@@ -106,4 +334,242 @@ pub struct SimilarChunk {
     pub similarity: f64,
     pub old_start: usize,
     pub new_start: usize,
-}
\ No newline at end of file
+}
+
+/// 单个增量操作，偏移量均以字符（而非字节）计
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeltaOp {
+    /// 保留基础文本中接下来的 N 个字符
+    Retain(usize),
+    /// 跳过基础文本中接下来的 N 个字符
+    Delete(usize),
+    /// 插入新文本
+    Insert(String),
+}
+
+/// 文本增量：记录基础文本长度和按顺序执行的操作
+///
+/// 编码格式为 `d1|<基础长度>|` 后接操作序列：
+/// `=N` 保留、`-N` 删除、`+N:文本` 插入（N 为插入文本的字符数，因此无需转义）。
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextDelta {
+    pub base_len: usize,
+    pub ops: Vec<DeltaOp>,
+}
+
+impl TextDelta {
+    pub fn encode(&self) -> String {
+        let mut result = format!("{}|{}|", DELTA_HEADER, self.base_len);
+        for op in &self.ops {
+            match op {
+                DeltaOp::Retain(n) => result.push_str(&format!("={}", n)),
+                DeltaOp::Delete(n) => result.push_str(&format!("-{}", n)),
+                DeltaOp::Insert(text) => {
+                    result.push_str(&format!("+{}:", text.chars().count()));
+                    result.push_str(text);
+                }
+            }
+        }
+        result
+    }
+    
+    pub fn decode(data: &str) -> Result<Self> {
+        if !DiffUtils::is_delta(data) {
+            return Err(anyhow::anyhow!("无法识别的增量数据格式"));
+        }
+        
+        let body = &data[DELTA_HEADER.len() + 1..];
+        let (base_len, mut rest) = body.split_once('|')
+            .ok_or_else(|| anyhow::anyhow!("增量数据缺少基础长度"))?;
+        let base_len: usize = base_len.parse()
+            .map_err(|_| anyhow::anyhow!("增量数据基础长度无效: {}", base_len))?;
+        
+        let mut ops = Vec::new();
+        while let Some(kind) = rest.chars().next() {
+            rest = &rest[kind.len_utf8()..];
+            let digits_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let count: usize = rest[..digits_end].parse()
+                .map_err(|_| anyhow::anyhow!("增量操作长度无效"))?;
+            rest = &rest[digits_end..];
+            
+            match kind {
+                '=' => ops.push(DeltaOp::Retain(count)),
+                '-' => ops.push(DeltaOp::Delete(count)),
+                '+' => {
+                    rest = rest.strip_prefix(':')
+                        .ok_or_else(|| anyhow::anyhow!("插入操作缺少分隔符"))?;
+                    let byte_len = rest.char_indices()
+                        .nth(count)
+                        .map(|(i, _)| i)
+                        .unwrap_or(rest.len());
+                    if rest[..byte_len].chars().count() != count {
+                        return Err(anyhow::anyhow!("插入操作内容被截断"));
+                    }
+                    ops.push(DeltaOp::Insert(rest[..byte_len].to_string()));
+                    rest = &rest[byte_len..];
+                }
+                other => return Err(anyhow::anyhow!("未知的增量操作: {}", other)),
+            }
+        }
+        
+        Ok(Self { base_len, ops })
+    }
+    
+    pub fn apply(&self, base_text: &str) -> Result<String> {
+        let actual_len = base_text.chars().count();
+        if actual_len != self.base_len {
+            return Err(anyhow::anyhow!(
+                "基础文本长度不匹配: 期望 {} 个字符，实际 {} 个字符",
+                self.base_len, actual_len
+            ));
+        }
+        
+        let mut chars = base_text.chars();
+        let mut result = String::with_capacity(base_text.len());
+        
+        for op in &self.ops {
+            match op {
+                DeltaOp::Retain(n) => {
+                    let mut taken = 0;
+                    for c in chars.by_ref().take(*n) {
+                        result.push(c);
+                        taken += 1;
+                    }
+                    if taken != *n {
+                        return Err(anyhow::anyhow!("保留操作超出基础文本范围"));
+                    }
+                }
+                DeltaOp::Delete(n) => {
+                    if chars.by_ref().take(*n).count() != *n {
+                        return Err(anyhow::anyhow!("删除操作超出基础文本范围"));
+                    }
+                }
+                DeltaOp::Insert(text) => result.push_str(text),
+            }
+        }
+        
+        if chars.next().is_some() {
+            return Err(anyhow::anyhow!("增量数据未覆盖完整的基础文本"));
+        }
+        
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_delta_round_trip() {
+        let cases = [
+            ("", ""),
+            ("", "第一章"),
+            ("第一章 开端", ""),
+            ("他走进房间。", "她走进了房间。"),
+            ("Hello world", "Hello, brave new world!"),
+            ("行1\n行2\n行3", "行1\n行2改\n行3\n行4"),
+            ("emoji 😀 test", "emoji 😀😀 text"),
+        ];
+        
+        for (old, new) in cases {
+            let delta = DiffUtils::create_delta(old, new);
+            assert!(DiffUtils::is_delta(&delta));
+            assert_eq!(DiffUtils::apply_delta(old, &delta).unwrap(), new);
+        }
+    }
+    
+    #[test]
+    fn test_delta_insert_containing_op_markers() {
+        let old = "abc";
+        let new = "a+3:=1-2|d1|bc";
+        let delta = DiffUtils::create_delta(old, new);
+        assert_eq!(DiffUtils::apply_delta(old, &delta).unwrap(), new);
+    }
+    
+    #[test]
+    fn test_delta_rejects_wrong_base() {
+        let delta = DiffUtils::create_delta("原始文本", "修改后的文本");
+        assert!(DiffUtils::apply_delta("另一段文本内容", &delta).is_err());
+    }
+    
+    #[test]
+    fn test_delta_rejects_corrupt_ops() {
+        // 被手工改坏的数据：操作位置上是多字节字符
+        assert!(TextDelta::decode("d1|2|章2").is_err());
+        assert!(TextDelta::decode("d1|2|=1章").is_err());
+        assert!(TextDelta::decode("d1|2|+2:章").is_err());
+    }
+    
+    #[test]
+    fn test_legacy_markup_is_not_delta() {
+        let legacy = DiffUtils::calculate_diff("旧", "新");
+        assert!(!DiffUtils::is_delta(&legacy));
+        assert!(DiffUtils::apply_delta("旧", &legacy).is_err());
+    }
+    
+    /// 由片段拼回新旧文本
+    fn sides(hunk: &DiffHunk) -> (String, String) {
+        let mut old = String::new();
+        let mut new = String::new();
+        for span in &hunk.spans {
+            if span.kind != SpanKind::Insert {
+                old.push_str(&span.text);
+            }
+            if span.kind != SpanKind::Delete {
+                new.push_str(&span.text);
+            }
+        }
+        (old, new)
+    }
+    
+    #[test]
+    fn test_split_sentences_and_words() {
+        assert_eq!(
+            split_sentences("他说：“走吧。”她没动……\n雨停了！真的？"),
+            vec!["他说：“走吧。”", "她没动……", "\n", "雨停了！", "真的？"]
+        );
+        assert_eq!(split_sentences(""), Vec::<&str>::new());
+        assert_eq!(
+            split_words("他说 Hello, world_2 好的"),
+            vec!["他", "说", " ", "Hello", ",", " ", "world_2", " ", "好", "的"]
+        );
+    }
+    
+    #[test]
+    fn test_structured_diff_granularities() {
+        let old = "第一段不变。\n她走进房间。窗外下着雨。\nThe quick fox.\n最后一段。";
+        let new = "第一段不变。\n她走进了房间。窗外下着雨。\nThe slow fox.\n最后一段。\n新的一段。";
+        
+        let paragraph = DiffUtils::structured_diff(old, new, DiffGranularity::Paragraph);
+        assert_eq!(paragraph.hunks.len(), 2);
+        assert_eq!((paragraph.hunks[0].old_paragraphs.clone(), paragraph.hunks[0].new_paragraphs.clone()), (1..3, 1..3));
+        assert_eq!((paragraph.hunks[1].old_paragraphs.clone(), paragraph.hunks[1].new_paragraphs.clone()), (4..4, 4..5));
+        assert_eq!(paragraph.hunks[1].spans, vec![DiffSpan { kind: SpanKind::Insert, text: "新的一段。".to_string() }]);
+        
+        let sentence = DiffUtils::structured_diff(old, new, DiffGranularity::Sentence);
+        let kinds: Vec<(SpanKind, &str)> = sentence.hunks[0].spans.iter().map(|s| (s.kind, s.text.as_str())).collect();
+        assert_eq!(kinds, vec![
+            (SpanKind::Delete, "她走进房间。"),
+            (SpanKind::Insert, "她走进了房间。"),
+            (SpanKind::Equal, "窗外下着雨。\n"),
+            (SpanKind::Delete, "The quick fox."),
+            (SpanKind::Insert, "The slow fox."),
+        ]);
+        
+        let word = DiffUtils::structured_diff(old, new, DiffGranularity::Word);
+        let changed: Vec<(SpanKind, &str)> = word.hunks[0].spans.iter()
+            .filter(|s| s.kind != SpanKind::Equal)
+            .map(|s| (s.kind, s.text.as_str()))
+            .collect();
+        assert_eq!(changed, vec![(SpanKind::Insert, "了"), (SpanKind::Delete, "quick"), (SpanKind::Insert, "slow")]);
+        assert_eq!((word.statistics.insertions, word.statistics.deletions), (1 + 4 + 5, 5));
+        
+        for diff in [&paragraph, &sentence, &word] {
+            let (old_side, new_side) = sides(&diff.hunks[0]);
+            assert_eq!(old_side, "她走进房间。窗外下着雨。\nThe quick fox.");
+            assert_eq!(new_side, "她走进了房间。窗外下着雨。\nThe slow fox.");
+        }
+        assert!(DiffUtils::structured_diff(old, old, DiffGranularity::Word).hunks.is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+/// 导出各种格式时共用的文本处理
+
+use anyhow::Result;
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+/// 转义 XML/XHTML 文本和属性值中的特殊字符
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // XML 1.0 不允许的控制字符直接丢弃
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 把正文拆成段落：每个非空行一段，去掉行首的缩进（包括全角空格）
+pub fn paragraphs(content: &str) -> Vec<&str> {
+    content.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// 把标题转换成可用作文件名的字符串
+pub fn safe_file_name(title: &str) -> String {
+    let name: String = title.trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim_matches(|c: char| c == '.' || c.is_whitespace()).to_string();
+    if name.is_empty() { "untitled".to_string() } else { name }
+}
+
+/// 读取 ZIP 压缩包中的一个文件
+pub fn read_zip_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<Vec<u8>> {
+    let mut entry = archive.by_name(name)
+        .map_err(|_| anyhow::anyhow!("压缩包中缺少 {}", name))?;
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// 测试用：以文本读取压缩包中的文件，缺少时直接失败
+#[cfg(test)]
+pub(crate) fn read_zip_text<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> String {
+    String::from_utf8(read_zip_entry(archive, name).unwrap()).unwrap_or_else(|_| panic!("{} 不是 UTF-8 文本", name))
+}
+
+/// 测试用：导出器共用的示例小说
+///
+/// 两卷：第一卷下的第一章含两个场景，另有空的第二章和已归档的“废稿”；
+/// 第二卷下是第三章和已归档的“番外草稿”。
+#[cfg(test)]
+pub(crate) fn sample_export_data() -> crate::core::novel_manager::NovelExportData {
+    use crate::core::novel_manager::NovelManager;
+    use crate::db::{ChapterType, Database};
+
+    let db = Database::open_in_memory().unwrap();
+    let set_type = |id: i64, chapter_type: ChapterType| {
+        let mut chapter = db.get_chapter(id).unwrap();
+        chapter.chapter_type = chapter_type;
+        db.update_chapter(&chapter).unwrap();
+    };
+
+    let mut novel = db.create_novel("山海 & <归途>").unwrap();
+    novel.author = "李青山".to_string();
+    novel.description = "一个关于 & 的故事".to_string();
+    db.update_novel(&novel).unwrap();
+
+    let volume_one = db.create_chapter(novel.id, "第一卷", None).unwrap();
+    set_type(volume_one.id, ChapterType::Volume);
+    let first = db.create_chapter(novel.id, "第一章", Some(volume_one.id)).unwrap();
+    db.update_chapter_content(first.id, "　　山路很长。\n\n　　他走了很久。").unwrap();
+    let scene_one = db.create_chapter(novel.id, "场景一", Some(first.id)).unwrap();
+    set_type(scene_one.id, ChapterType::Scene);
+    db.update_chapter_content(scene_one.id, "天黑了。").unwrap();
+    let scene_two = db.create_chapter(novel.id, "场景二", Some(first.id)).unwrap();
+    set_type(scene_two.id, ChapterType::Scene);
+    db.update_chapter_content(scene_two.id, "天亮了。").unwrap();
+    db.create_chapter(novel.id, "第二章", Some(volume_one.id)).unwrap();
+    let discarded = db.create_chapter(novel.id, "废稿", Some(volume_one.id)).unwrap();
+    db.update_chapter_content(discarded.id, "不要的内容").unwrap();
+    db.archive_chapter(discarded.id).unwrap();
+
+    let volume_two = db.create_chapter(novel.id, "第二卷", None).unwrap();
+    set_type(volume_two.id, ChapterType::Volume);
+    db.create_chapter(novel.id, "第三章", Some(volume_two.id)).unwrap();
+    let draft = db.create_chapter(novel.id, "番外草稿", Some(volume_two.id)).unwrap();
+    db.archive_chapter(draft.id).unwrap();
+
+    NovelManager::export_novel_data_with(&db, novel.id).unwrap()
+}
+
+/// 测试用：检查导出的 XML 中标签是否成对闭合
+#[cfg(test)]
+pub(crate) fn assert_well_formed(name: &str, xml: &str) {
+    let tag = regex::Regex::new(r"<(/?)([A-Za-z][\w:.-]*)[^>]*?(/?)>").unwrap();
+    let mut stack = Vec::new();
+    for caps in tag.captures_iter(xml) {
+        let tag_name = caps[2].to_string();
+        if &caps[1] == "/" {
+            assert_eq!(stack.pop().as_deref(), Some(tag_name.as_str()), "{} 中标签未正确闭合", name);
+        } else if &caps[3] != "/" {
+            stack.push(tag_name);
+        }
+    }
+    assert!(stack.is_empty(), "{} 中有未闭合的标签: {:?}", name, stack);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_and_paragraphs() {
+        assert_eq!(escape_xml("<a & \"b\">\u{1}"), "&lt;a &amp; &quot;b&quot;&gt;");
+        assert_eq!(paragraphs("　　第一段\n\n  第二段  \n"), vec!["第一段", "第二段"]);
+        assert_eq!(safe_file_name("第一卷：风起/云涌?"), "第一卷：风起_云涌_");
+        assert_eq!(safe_file_name(" .. "), "untitled");
+    }
+}
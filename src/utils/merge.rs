@@ -0,0 +1,247 @@
+//! 按段落的三方合并
+//!
+//! 正文按 `\n` 切分为段落，分别计算共同祖先到双方的段落差异。只有一方修改的区域直接采用该方的内容，
+//! 双方修改了同一区域且结果不同时产生冲突。一方在某段之前插入新段落、另一方修改这一段不算冲突。
+
+use anyhow::Result;
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+/// 合并结果中的一段
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeChunk {
+    /// 双方都没有修改的段落
+    Unchanged(Vec<String>),
+    /// 只有一方修改或双方修改相同、已自动合并的段落
+    Merged(Vec<String>),
+    Conflict(MergeConflict),
+}
+
+/// 双方修改了同一区域且结果不同
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    pub base: Vec<String>,
+    pub ours: Vec<String>,
+    pub theirs: Vec<String>,
+}
+
+/// 冲突的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+    /// 先我方、后对方
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeResult {
+    pub chunks: Vec<MergeChunk>,
+}
+
+impl MergeResult {
+    pub fn conflict_count(&self) -> usize {
+        self.chunks.iter().filter(|c| matches!(c, MergeChunk::Conflict(_))).count()
+    }
+
+    /// 自动合并的修改处数
+    pub fn merged_count(&self) -> usize {
+        self.chunks.iter().filter(|c| matches!(c, MergeChunk::Merged(_))).count()
+    }
+
+    /// 按顺序给出每个冲突的处理方式，生成合并后的正文
+    pub fn resolve(&self, resolutions: &[ConflictResolution]) -> Result<String> {
+        if resolutions.len() != self.conflict_count() {
+            return Err(anyhow::anyhow!("还有 {} 处冲突未处理", self.conflict_count().saturating_sub(resolutions.len())));
+        }
+        let mut resolutions = resolutions.iter();
+        let mut paragraphs: Vec<&str> = Vec::new();
+        for chunk in &self.chunks {
+            match chunk {
+                MergeChunk::Unchanged(lines) | MergeChunk::Merged(lines) => paragraphs.extend(lines.iter().map(String::as_str)),
+                MergeChunk::Conflict(conflict) => {
+                    let (first, second): (&[String], &[String]) = match resolutions.next() {
+                        Some(ConflictResolution::Ours) => (&conflict.ours, &[]),
+                        Some(ConflictResolution::Theirs) => (&conflict.theirs, &[]),
+                        Some(ConflictResolution::Both) | None => (&conflict.ours, &conflict.theirs),
+                    };
+                    paragraphs.extend(first.iter().chain(second).map(String::as_str));
+                }
+            }
+        }
+        Ok(paragraphs.join("\n"))
+    }
+}
+
+/// 一方相对共同祖先的一处修改：祖先中 `base` 范围的段落被替换为该方 `new` 范围的段落
+#[derive(Debug, Clone, Copy)]
+struct Hunk {
+    ours: bool,
+    base: (usize, usize),
+    new: (usize, usize),
+}
+
+fn paragraphs(text: &str) -> Vec<&str> {
+    text.split('\n').collect()
+}
+
+fn hunks(base: &[&str], side: &[&str], ours: bool) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, base, side) {
+        if let DiffOp::Equal { .. } = op {
+            continue;
+        }
+        let (old, new) = (op.old_range(), op.new_range());
+        match hunks.last_mut() {
+            // 相邻的删除和插入合并为一处替换
+            Some(last) if last.base.1 == old.start && last.new.1 == new.start => {
+                last.base.1 = old.end;
+                last.new.1 = new.end;
+            }
+            _ => hunks.push(Hunk { ours, base: (old.start, old.end), new: (new.start, new.end) }),
+        }
+    }
+    hunks
+}
+
+/// 两处修改是否落在同一区域；纯插入只与同一位置的插入或包含该位置的修改重叠
+fn overlaps(a: (usize, usize), b: (usize, usize)) -> bool {
+    match (a.0 == a.1, b.0 == b.1) {
+        (true, true) => a.0 == b.0,
+        (true, false) => b.0 < a.0 && a.0 < b.1,
+        (false, true) => a.0 < b.0 && b.0 < a.1,
+        (false, false) => a.0 < b.1 && b.0 < a.1,
+    }
+}
+
+/// 一方在祖先 `span` 范围内的内容
+fn side_text(base: &[&str], side: &[&str], span: (usize, usize), hunks: &[Hunk]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut position = span.0;
+    for hunk in hunks {
+        lines.extend(base[position..hunk.base.0].iter().map(|s| s.to_string()));
+        lines.extend(side[hunk.new.0..hunk.new.1].iter().map(|s| s.to_string()));
+        position = hunk.base.1;
+    }
+    lines.extend(base[position..span.1].iter().map(|s| s.to_string()));
+    lines
+}
+
+/// 以 `base` 为共同祖先合并 `ours` 和 `theirs`
+pub fn merge_paragraphs(base: &str, ours: &str, theirs: &str) -> MergeResult {
+    let (base, ours, theirs) = (paragraphs(base), paragraphs(ours), paragraphs(theirs));
+    let mut all: Vec<Hunk> = hunks(&base, &ours, true);
+    all.extend(hunks(&base, &theirs, false));
+    all.sort_by_key(|h| (h.base.0, h.base.1, !h.ours));
+
+    // 按祖先中的位置把重叠的修改分组，每组要么自动合并，要么是一处冲突
+    let mut groups: Vec<((usize, usize), Vec<Hunk>)> = Vec::new();
+    for hunk in all {
+        match groups.last_mut() {
+            Some((span, members)) if overlaps(*span, hunk.base) => {
+                span.1 = span.1.max(hunk.base.1);
+                members.push(hunk);
+            }
+            _ => groups.push((hunk.base, vec![hunk])),
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let mut position = 0;
+    for (span, members) in groups {
+        if position < span.0 {
+            chunks.push(MergeChunk::Unchanged(base[position..span.0].iter().map(|s| s.to_string()).collect()));
+        }
+        let (ours_hunks, theirs_hunks): (Vec<Hunk>, Vec<Hunk>) = members.into_iter().partition(|h| h.ours);
+        let ours_text = side_text(&base, &ours, span, &ours_hunks);
+        let theirs_text = side_text(&base, &theirs, span, &theirs_hunks);
+        let chunk = if theirs_hunks.is_empty() || ours_text == theirs_text {
+            MergeChunk::Merged(ours_text)
+        } else if ours_hunks.is_empty() {
+            MergeChunk::Merged(theirs_text)
+        } else {
+            MergeChunk::Conflict(MergeConflict {
+                base: base[span.0..span.1].iter().map(|s| s.to_string()).collect(),
+                ours: ours_text,
+                theirs: theirs_text,
+            })
+        };
+        chunks.push(chunk);
+        position = span.1;
+    }
+    if position < base.len() {
+        chunks.push(MergeChunk::Unchanged(base[position..].iter().map(|s| s.to_string()).collect()));
+    }
+    MergeResult { chunks }
+}
+
+/// 没有共同祖先时（例如合作者的独立副本）以双方共有的段落作为祖先
+pub fn common_paragraphs(ours: &str, theirs: &str) -> String {
+    let (ours, theirs) = (paragraphs(ours), paragraphs(theirs));
+    capture_diff_slices(Algorithm::Myers, &ours, &theirs)
+        .into_iter()
+        .filter_map(|op| match op {
+            DiffOp::Equal { old_index, len, .. } => Some(&ours[old_index..old_index + len]),
+            _ => None,
+        })
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "第一段。\n第二段。\n第三段。\n第四段。";
+
+    #[test]
+    fn test_non_overlapping_changes_merge_cleanly() {
+        let ours = "第一段，改。\n第二段。\n第三段。\n第四段。";
+        let theirs = "第一段。\n第二段。\n第三段，也改了。\n新增一段。\n第四段。";
+        let result = merge_paragraphs(BASE, ours, theirs);
+        assert_eq!(result.conflict_count(), 0);
+        assert_eq!(result.merged_count(), 2);
+        assert_eq!(result.resolve(&[]).unwrap(), "第一段，改。\n第二段。\n第三段，也改了。\n新增一段。\n第四段。");
+
+        // 相邻段落的修改、在被修改的段落前插入都不算冲突
+        let ours = "第一段。\n第二段，我改的。\n第三段。\n第四段。";
+        let theirs = "第一段。\n插入的段落。\n第二段。\n第三段，对方改的。\n第四段。";
+        let result = merge_paragraphs(BASE, ours, theirs);
+        assert_eq!(result.resolve(&[]).unwrap(), "第一段。\n插入的段落。\n第二段，我改的。\n第三段，对方改的。\n第四段。");
+
+        // 双方做了相同的修改
+        let same = "第一段。\n第二段。\n第三段。";
+        assert_eq!(merge_paragraphs(BASE, same, same).resolve(&[]).unwrap(), same);
+    }
+
+    #[test]
+    fn test_conflicts_resolve_per_hunk() {
+        let ours = "第一段。\n我的第二段。\n第三段。\n我的第四段。";
+        let theirs = "第一段。\n对方的第二段。\n第三段。\n对方的第四段。";
+        let result = merge_paragraphs(BASE, ours, theirs);
+        assert_eq!(result.conflict_count(), 2);
+        let MergeChunk::Conflict(conflict) = &result.chunks[1] else { panic!("应为冲突") };
+        assert_eq!(conflict.base, vec!["第二段。"]);
+        assert_eq!(conflict.theirs, vec!["对方的第二段。"]);
+
+        assert!(result.resolve(&[ConflictResolution::Ours]).is_err());
+        let merged = result.resolve(&[ConflictResolution::Theirs, ConflictResolution::Both]).unwrap();
+        assert_eq!(merged, "第一段。\n对方的第二段。\n第三段。\n我的第四段。\n对方的第四段。");
+
+        // 一方删除、另一方修改同一段也是冲突
+        let deleted = "第一段。\n第三段。\n第四段。";
+        let result = merge_paragraphs(BASE, deleted, theirs);
+        assert_eq!(result.conflict_count(), 1);
+        assert_eq!(result.resolve(&[ConflictResolution::Ours]).unwrap(), "第一段。\n第三段。\n对方的第四段。");
+    }
+
+    #[test]
+    fn test_common_paragraphs_as_base() {
+        let ours = "开头。\n我加的。\n结尾。";
+        let theirs = "开头。\n结尾。\n对方加的。";
+        assert_eq!(common_paragraphs(ours, theirs), "开头。\n结尾。");
+        let result = merge_paragraphs(&common_paragraphs(ours, theirs), ours, theirs);
+        assert_eq!(result.resolve(&[]).unwrap(), "开头。\n我加的。\n结尾。\n对方加的。");
+    }
+}
@@ -1 +1,4 @@
-pub mod diff_utils;
\ No newline at end of file
+pub mod diff_utils;
+pub mod export_utils;
+pub mod merge;
+pub mod sort_key;
\ No newline at end of file
@@ -0,0 +1,178 @@
+//! 章节排序键
+//!
+//! 排序键是 base62 字符串（`0-9A-Za-z`，按 ASCII 顺序即数值顺序），看作 `0.d1d2d3…` 这样的小数。
+//! 生成的键不以 `0` 结尾，因此任意两个不同的键之间总能插入新键，移动章节时不需要改动其他章节。
+//! 旧版本写入的 20 位数字时间戳也是合法的键，可以与新键一起比较。
+
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const BASE: u32 = 62;
+
+fn digit_value(byte: u8) -> Option<u32> {
+    match byte {
+        b'0'..=b'9' => Some((byte - b'0') as u32),
+        b'A'..=b'Z' => Some((byte - b'A') as u32 + 10),
+        b'a'..=b'z' => Some((byte - b'a') as u32 + 36),
+        _ => None,
+    }
+}
+
+fn decode(key: &str) -> Option<Vec<u32>> {
+    key.bytes().map(digit_value).collect()
+}
+
+fn encode(digits: &[u32]) -> String {
+    digits.iter().map(|&d| DIGITS[d as usize] as char).collect()
+}
+
+/// 生成排在 `lower` 之后、`upper` 之前的键，`None` 表示该侧没有相邻章节
+///
+/// 两个键之间没有空隙（相等、顺序颠倒）或含有无法识别的字符时返回 `None`，此时应先重新编号同级章节。
+pub fn key_between(lower: Option<&str>, upper: Option<&str>) -> Option<String> {
+    let lower = match lower {
+        Some(key) => decode(key)?,
+        None => Vec::new(),
+    };
+    let upper = match upper {
+        Some(key) => Some(decode(key)?),
+        None => None,
+    };
+
+    let Some(upper) = upper else {
+        return Some(encode(&increment(&lower)));
+    };
+
+    // 逐位比较：相同的前缀照抄，出现间隔至少为 2 的数位时取中间值；
+    // 间隔为 1 时取下界的数位，之后只需要大于下界的剩余部分
+    let mut result = Vec::new();
+    let mut bounded = true;
+    for i in 0.. {
+        let low = lower.get(i).copied().unwrap_or(0);
+        let high = if bounded {
+            if i >= lower.len() && i >= upper.len() {
+                return None;
+            }
+            upper.get(i).copied().unwrap_or(0)
+        } else {
+            BASE
+        };
+        if low > high {
+            return None;
+        }
+        if high - low > 1 {
+            result.push((low + high) / 2);
+            return Some(encode(&result));
+        }
+        result.push(low);
+        if high - low == 1 {
+            bounded = false;
+        }
+    }
+    unreachable!()
+}
+
+/// 追加到末尾时使用的键：在第一个不是 `z` 的数位上加一，保持键尽量短
+fn increment(lower: &[u32]) -> Vec<u32> {
+    if lower.is_empty() {
+        return vec![BASE / 2];
+    }
+    let position = lower.iter().position(|&d| d < BASE - 1).unwrap_or(lower.len());
+    let mut result = lower[..position].to_vec();
+    result.push(lower.get(position).copied().unwrap_or(0) + 1);
+    result
+}
+
+/// 追加到 `last` 之后的键；`last` 含有无法识别的字符时在其后追加一位，仍然保证排在它之后
+pub fn key_after(last: Option<&str>) -> String {
+    key_between(last, None).unwrap_or_else(|| format!("{}{}", last.unwrap_or_default(), DIGITS[BASE as usize / 2] as char))
+}
+
+/// 为 `count` 个同级章节生成均匀分布的键，用于重新编号
+pub fn spread_keys(count: usize) -> Vec<String> {
+    let slots = count as u64 + 1;
+    let mut width = 1;
+    let mut range = BASE as u64;
+    // 相邻两个键之间至少留出一位的空间
+    while range < slots * BASE as u64 {
+        width += 1;
+        range *= BASE as u64;
+    }
+    let step = range / slots;
+    (1..slots)
+        .map(|i| {
+            let mut value = i * step;
+            let mut digits = vec![0; width];
+            for digit in digits.iter_mut().rev() {
+                *digit = (value % BASE as u64) as u32;
+                value /= BASE as u64;
+            }
+            while digits.last() == Some(&0) {
+                digits.pop();
+            }
+            encode(&digits)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn between(lower: Option<&str>, upper: Option<&str>) -> String {
+        let key = key_between(lower, upper).unwrap();
+        if let Some(lower) = lower {
+            assert!(lower < key.as_str(), "{} < {}", lower, key);
+        }
+        if let Some(upper) = upper {
+            assert!(key.as_str() < upper, "{} < {}", key, upper);
+        }
+        assert!(!key.ends_with('0'));
+        key
+    }
+
+    #[test]
+    fn test_keys_between_neighbours() {
+        assert_eq!(between(None, None), "V");
+        assert_eq!(between(Some("V"), None), "W");
+        assert_eq!(between(Some("z"), None), "z1");
+        assert_eq!(between(Some("A"), Some("C")), "B");
+        assert_eq!(between(Some("A"), Some("B")), "AV");
+        assert_eq!(between(None, Some("1")), "0V");
+        between(Some("A"), Some("A05"));
+        between(Some("Az"), Some("B"));
+
+        // 反复插入到同一位置，键只会缓慢变长
+        let (mut lower, upper) = ("A".to_string(), "B".to_string());
+        for _ in 0..100 {
+            lower = between(Some(&lower), Some(&upper));
+        }
+        let mut upper = "B".to_string();
+        for _ in 0..100 {
+            upper = between(Some("A"), Some(&upper));
+        }
+        assert!(lower.len() < 30 && upper.len() < 30);
+
+        // 与旧版本的时间戳键混用
+        between(Some("00000001700000000000"), None);
+        between(Some("00000001700000000000"), Some("00000001700000000001"));
+
+        assert_eq!(key_between(Some("B"), Some("B")), None);
+        assert_eq!(key_between(Some("C"), Some("B")), None);
+        assert_eq!(key_between(Some("A"), Some("A0")), None);
+        assert_eq!(key_between(Some("000001_1700000000000"), None), None);
+        assert!(key_after(Some("000001_1700000000000")).as_str() > "000001_1700000000000");
+    }
+
+    #[test]
+    fn test_spread_keys_are_ordered_with_gaps() {
+        for count in [0, 1, 2, 61, 62, 500, 5000] {
+            let keys = spread_keys(count);
+            assert_eq!(keys.len(), count);
+            for pair in keys.windows(2) {
+                assert!(pair[0] < pair[1]);
+                between(Some(&pair[0]), Some(&pair[1]));
+            }
+            assert!(keys.iter().all(|k| !k.is_empty() && !k.ends_with('0')));
+        }
+        assert_eq!(spread_keys(1), vec!["V".to_string()]);
+    }
+}